@@ -3,14 +3,31 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cw20::{AllowanceResponse, BalanceResponse};
+use cw20::{
+    AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse,
+    DownloadLogoResponse, MarketingInfoResponse, TokenInfoResponse,
+};
 // use cw20_bondcamp::msg::{
 //     ClaimsResponse, ExecuteMsg, InstantiateMsg, InvestmentResponse, QueryMsg,
 // };
 
-use cw20_bondcamp::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use cw20_bondcamp::query::{ClaimsResponse, InvestmentResponse, TokenInfoResponseWithMeta};
-use cw20_bonding::msg::CurveInfoResponse;
+use cw20_bondcamp::msg::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, SudoMsg, TradeHookMsg,
+};
+use cw20_bondcamp::query::{
+    AllClaimsResponse, BuyCommitmentResponse, BuyOrdersResponse, CapabilitiesResponse,
+    CheckRoyaltiesResponse, ClaimEstimatesResponse, ClaimableRewardsResponse, ClaimsResponse,
+    CreatorEscrowResponse, CreatorResponse, CreatorVestingResponse, CurveInfoResponse,
+    DcaPlansResponse, DelegationsResponse, FeeExemptionsResponse, FrozenResponse,
+    FundingProgressResponse, HealthResponse, HooksResponse, InvestmentResponse, LaunchResponse,
+    LiquidBufferResponse, NftReceiptsResponse, OwnerFeesResponse, PatronTierResponse,
+    PatronTiersResponse, PausedResponse, PayeesResponse, PollResponse, PollsResponse,
+    PresaleResponse, ProtocolFeesResponse, RateHistoryResponse, ReinvestPreviewResponse,
+    RoyaltyInfoResponse, SellOrdersResponse, SpotPriceAtHeightResponse, StatsResponse,
+    TokenInfoResponseWithMeta, TotalClaimsResponse, TotalSupplyResponse, TreasuryResponse,
+    TwapResponse, UnbondCapacityResponse, UnbondEpochResponse, ValidatorChangeResponse,
+    ValidatorHealthResponse, WindDownResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -22,12 +39,65 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(TradeHookMsg), &out_dir);
+    export_schema(&schema_for!(HooksResponse), &out_dir);
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
+    export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
+    export_schema(&schema_for!(AllAccountsResponse), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
+    export_schema(&schema_for!(AllClaimsResponse), &out_dir);
+    export_schema(&schema_for!(TotalClaimsResponse), &out_dir);
+    export_schema(&schema_for!(UnbondEpochResponse), &out_dir);
+    export_schema(&schema_for!(TotalSupplyResponse), &out_dir);
+    export_schema(&schema_for!(PollResponse), &out_dir);
+    export_schema(&schema_for!(PollsResponse), &out_dir);
     export_schema(&schema_for!(CurveInfoResponse), &out_dir);
+    export_schema(&schema_for!(TokenInfoResponse), &out_dir);
     export_schema(&schema_for!(TokenInfoResponseWithMeta), &out_dir);
+    export_schema(&schema_for!(MarketingInfoResponse), &out_dir);
+    export_schema(&schema_for!(DownloadLogoResponse), &out_dir);
+    export_schema(&schema_for!(CreatorResponse), &out_dir);
+    export_schema(&schema_for!(TreasuryResponse), &out_dir);
+    export_schema(&schema_for!(BuyCommitmentResponse), &out_dir);
+    export_schema(&schema_for!(LaunchResponse), &out_dir);
+    export_schema(&schema_for!(BuyOrdersResponse), &out_dir);
+    export_schema(&schema_for!(SellOrdersResponse), &out_dir);
+    export_schema(&schema_for!(DcaPlansResponse), &out_dir);
+    export_schema(&schema_for!(CreatorEscrowResponse), &out_dir);
+    export_schema(&schema_for!(CreatorVestingResponse), &out_dir);
+    export_schema(&schema_for!(OwnerFeesResponse), &out_dir);
+    export_schema(&schema_for!(PayeesResponse), &out_dir);
+    export_schema(&schema_for!(ProtocolFeesResponse), &out_dir);
+    export_schema(&schema_for!(FeeExemptionsResponse), &out_dir);
+    export_schema(&schema_for!(FrozenResponse), &out_dir);
+    export_schema(&schema_for!(SpotPriceAtHeightResponse), &out_dir);
+    export_schema(&schema_for!(StatsResponse), &out_dir);
+    export_schema(&schema_for!(NftReceiptsResponse), &out_dir);
+    export_schema(&schema_for!(PatronTierResponse), &out_dir);
+    export_schema(&schema_for!(PatronTiersResponse), &out_dir);
+    export_schema(&schema_for!(FundingProgressResponse), &out_dir);
+    export_schema(&schema_for!(HealthResponse), &out_dir);
+    export_schema(&schema_for!(CapabilitiesResponse), &out_dir);
+    export_schema(&schema_for!(PausedResponse), &out_dir);
+    export_schema(&schema_for!(CheckRoyaltiesResponse), &out_dir);
+    export_schema(&schema_for!(RoyaltyInfoResponse), &out_dir);
+    export_schema(&schema_for!(UnbondCapacityResponse), &out_dir);
+    export_schema(&schema_for!(WindDownResponse), &out_dir);
+    export_schema(&schema_for!(ClaimEstimatesResponse), &out_dir);
+    export_schema(&schema_for!(ClaimableRewardsResponse), &out_dir);
+    export_schema(&schema_for!(PresaleResponse), &out_dir);
+    export_schema(&schema_for!(LiquidBufferResponse), &out_dir);
+    export_schema(&schema_for!(ReinvestPreviewResponse), &out_dir);
+    export_schema(&schema_for!(RateHistoryResponse), &out_dir);
+    export_schema(&schema_for!(TwapResponse), &out_dir);
 
     // staking
     export_schema(&schema_for!(ClaimsResponse), &out_dir);
     export_schema(&schema_for!(InvestmentResponse), &out_dir);
+    export_schema(&schema_for!(DelegationsResponse), &out_dir);
+    export_schema(&schema_for!(ValidatorChangeResponse), &out_dir);
+    export_schema(&schema_for!(ValidatorHealthResponse), &out_dir);
 }
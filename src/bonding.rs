@@ -1,26 +1,194 @@
 use cosmwasm_std::{
-    attr, coins, Addr, BankMsg, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    attr, coins, from_binary, to_binary, Addr, Attribute, BankMsg, BlockInfo, CosmosMsg, Decimal,
+    DepsMut, Empty, Env, Event, Fraction, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
 };
 
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw20_base::allowances::deduct_allowance;
 
 use cw20_base::state::BALANCES;
 
 use crate::error::ContractError;
 
-use crate::state::{CURVE_STATE, TOKEN_INFO_WITH_META};
-use cw0::{must_pay, nonpayable};
+use crate::cooldown::{assert_cooled_down, start_cooldown};
+use crate::fees::{fee_rate, take_protocol_fee};
+use crate::freeze::assert_not_frozen;
+use crate::hooks::trade_hooks;
+use crate::launch::assert_launched;
+use crate::milestones::{creator_share, take_creator_share};
+use crate::msg::{CustomMsg, ExecuteMsg, ReceiveMsg, RouterExecuteMsg, TradeDirection};
+use crate::pause::assert_not_paused;
+use crate::pause::{assert_buys_open, assert_sells_open};
+use crate::state::{
+    CurveState, PriceObservation, CONTRIBUTIONS, CURVE_STATE, FUNDING_GOAL, PRICE_OBSERVATIONS,
+    RESERVE_TOKEN, SALE_END, SALE_START, SPOT_PRICES, SPREAD, SWAP_ROUTER, TOKEN_INFO_WITH_META,
+};
+use cw0::{must_pay, nonpayable, one_coin};
+use cw_storage_plus::Bound;
+
+use crate::curves::CurveFn;
+use crate::patrons::record_support;
+use crate::presale::spend_presale_allowance;
+use crate::receipts::receipt_msg;
+use crate::rewards::settle_rewards;
+use crate::snapshot::{snapshot_balance, snapshot_supply};
+use crate::staking::{add_to_backing, backing, to_nominal};
+use crate::stats::record_trade;
+use crate::treasury::receive_cw20;
+use cw20_bonding::curves::Curve;
+
+/// checkpoint the spot price at this height, so it can be looked up after the fact.
+/// a later change in the same block overwrites it, leaving the closing price.
+/// The price that held until now is also added to the running sum used for TWAPs
+pub fn checkpoint_spot_price(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    spot_price: Decimal,
+) -> StdResult<()> {
+    SPOT_PRICES.save(storage, block.height.into(), &spot_price)?;
+    let now = block.time.seconds();
+    let cumulative = cumulative_price_at(storage, now)?.unwrap_or_default();
+    let observation = PriceObservation {
+        cumulative,
+        spot_price,
+    };
+    PRICE_OBSERVATIONS.save(storage, now.into(), &observation)
+}
+
+/// the running sum of spot price times seconds, up to time. None before the first trade
+pub fn cumulative_price_at(storage: &dyn Storage, time: u64) -> StdResult<Option<Uint128>> {
+    let latest = PRICE_OBSERVATIONS
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive_int(time)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?;
+    let cumulative = latest.map(|(key, observation)| {
+        let mut time_bytes = [0u8; 8];
+        time_bytes.copy_from_slice(&key);
+        let elapsed = time - u64::from_be_bytes(time_bytes);
+        observation.cumulative
+            + Uint128::new(observation.spot_price.numerator()) * Uint128::from(elapsed)
+    });
+    Ok(cumulative)
+}
 
-use cw20_bonding::msg::CurveFn;
+/// all-or-nothing: once the deadline passes short of the goal, the campaign only refunds
+pub fn is_refunding(storage: &dyn Storage, block: &BlockInfo, reserve: Uint128) -> StdResult<bool> {
+    let refunding = match FUNDING_GOAL.may_load(storage)? {
+        Some(goal) => {
+            reserve < goal.amount && matches!(goal.deadline, Some(d) if d.is_expired(block))
+        }
+        None => false,
+    };
+    Ok(refunding)
+}
+
+/// the curve only sells inside the sale window, never in refund mode,
+/// not before a launch auction is settled, and in crowdfund mode only until the reserve
+/// reaches the goal
+pub fn assert_curve_open(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    reserve: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(start) = SALE_START.may_load(storage)? {
+        if !start.is_expired(block) {
+            return Err(ContractError::SaleNotActive {});
+        }
+    }
+    if let Some(end) = SALE_END.may_load(storage)? {
+        if end.is_expired(block) {
+            return Err(ContractError::SaleNotActive {});
+        }
+    }
+    if is_refunding(storage, block, reserve)? {
+        return Err(ContractError::FundingRefund {});
+    }
+    assert_launched(storage)?;
+    match FUNDING_GOAL.may_load(storage)? {
+        Some(goal) if goal.closes_curve.unwrap_or_default() && reserve >= goal.amount => {
+            Err(ContractError::FundingGoalReached {})
+        }
+        _ => Ok(()),
+    }
+}
+
+/// the share of each buy and sell kept in the reserve, none unless configured
+pub fn spread(storage: &dyn Storage) -> StdResult<Decimal> {
+    Ok(SPREAD.may_load(storage)?.unwrap_or_else(Decimal::zero))
+}
+
+/// add payment to buyer's cost basis
+pub fn record_contribution(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    payment: Uint128,
+) -> StdResult<()> {
+    CONTRIBUTIONS.update(storage, buyer, |c| -> StdResult<_> {
+        Ok(c.unwrap_or_default() + payment)
+    })?;
+    Ok(())
+}
+
+/// take back the cost basis of a bond whose delegation failed
+pub fn unrecord_contribution(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    payment: Uint128,
+) -> StdResult<()> {
+    CONTRIBUTIONS.update(storage, buyer, |c| -> StdResult<_> {
+        Ok(c.unwrap_or_default().checked_sub(payment)?)
+    })?;
+    Ok(())
+}
+
+/// take the share of holder's cost basis that goes with amount out of their balance,
+/// returning it. The cost basis stays with the buyer when tokens are transferred
+pub fn take_contribution(
+    storage: &mut dyn Storage,
+    holder: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let balance = BALANCES.may_load(storage, holder)?.unwrap_or_default();
+    let contribution = CONTRIBUTIONS.may_load(storage, holder)?.unwrap_or_default();
+    if balance.is_zero() || contribution.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let share = contribution.multiply_ratio(amount.min(balance), balance);
+    CONTRIBUTIONS.save(storage, holder, &(contribution - share))?;
+    Ok(share)
+}
+
+/// an event for the buy (or bond) that took the reserve from before up past the goal
+pub fn goal_reached_event(
+    storage: &dyn Storage,
+    before: Uint128,
+    after: Uint128,
+) -> StdResult<Option<Event>> {
+    let event = FUNDING_GOAL
+        .may_load(storage)?
+        .filter(|goal| before < goal.amount && after >= goal.amount)
+        .map(|goal| {
+            Event::new("goal_reached")
+                .add_attribute("goal", goal.amount)
+                .add_attribute("raised", after)
+        });
+    Ok(event)
+}
 
 // the-frey: this is again a slight change to the one defined in cw20-base
 // as we have different types and so stuff goes askew
-pub fn execute_burn(
+pub fn execute_burn<C: CustomMsg>(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+) -> Result<Response<C>, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::Base(
             cw20_base::ContractError::InvalidZeroAmount {},
@@ -28,6 +196,7 @@ pub fn execute_burn(
     }
 
     // lower balance
+    settle_rewards(deps.storage, &env.contract.address, &info.sender)?;
     BALANCES.update(
         deps.storage,
         &info.sender,
@@ -40,6 +209,8 @@ pub fn execute_burn(
         info.token_info.total_supply = info.token_info.total_supply.checked_sub(amount)?;
         Ok(info)
     })?;
+    snapshot_balance(deps.storage, &info.sender, env.block.height)?;
+    snapshot_supply(deps.storage, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -50,13 +221,13 @@ pub fn execute_burn(
 
 // the-frey: this is again a slight change to the one defined in cw20-base
 // as we have different types and so stuff goes askew
-pub fn execute_mint(
+pub fn execute_mint<C: CustomMsg>(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+) -> Result<Response<C>, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::Base(
             cw20_base::ContractError::InvalidZeroAmount {},
@@ -74,20 +245,21 @@ pub fn execute_mint(
     config.token_info.total_supply += amount;
     if let Some(limit) = config.token_info.get_cap() {
         if config.token_info.total_supply > limit {
-            return Err(ContractError::Base(
-                cw20_base::ContractError::CannotExceedCap {},
-            ));
+            return Err(ContractError::CannotExceedCap {});
         }
     }
     TOKEN_INFO_WITH_META.save(deps.storage, &config)?;
 
     // add amount to recipient balance
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    settle_rewards(deps.storage, &env.contract.address, &rcpt_addr)?;
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
+    snapshot_balance(deps.storage, &rcpt_addr, env.block.height)?;
+    snapshot_supply(deps.storage, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "mint")
@@ -98,76 +270,478 @@ pub fn execute_mint(
 
 // the-frey:
 // this is verbatim from cw20-bonding, we should probably refactor out
-pub fn execute_buy(
+pub fn execute_buy<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    min_supply_out: Option<Uint128>,
+    recipient: Option<String>,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let state = CURVE_STATE.load(deps.storage)?;
+    let payment = must_pay(&info, &state.reserve_denom)?;
+    assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+    let buyer = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+    do_buy(deps, env, buyer, payment, curve_fn, min_supply_out)
+}
+
+/// buys exactly tokens, sending back whatever was paid beyond their cost
+pub fn execute_buy_exact<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    tokens: Uint128,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    if tokens.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let state = CURVE_STATE.load(deps.storage)?;
+    let sent = must_pay(&info, &state.reserve_denom)?;
+    let curve = curve_fn(state.decimals);
+    let cost = buy_cost(deps.storage, &info.sender, &state, curve.as_ref(), tokens)?;
+    if cost > sent {
+        return Err(ContractError::BuyExactUnderpaid { cost });
+    }
+
+    let res = do_buy(deps, env, info.sender.clone(), cost, curve_fn, Some(tokens))?;
+    let change = sent - cost;
+    if change.is_zero() {
+        return Ok(res);
+    }
+    let res = res
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(change.u128(), state.reserve_denom),
+        })
+        .add_attribute("change", change);
+    Ok(res)
+}
+
+// the reserve a buy of tokens needs: what the curve charges, grossed up for the spread,
+// the creator's share and the protocol fee. Each step rounds up so the buy falls no short
+fn buy_cost(
+    storage: &dyn Storage,
+    buyer: &Addr,
+    state: &CurveState,
+    curve: &dyn Curve,
+    tokens: Uint128,
+) -> StdResult<Uint128> {
+    let nominal = curve.reserve(state.supply + tokens);
+    let backing = backing(storage)?;
+    let mut reserve = nominal.multiply_ratio(backing.numerator(), backing.denominator());
+    if to_nominal(reserve, backing) < nominal {
+        reserve += Uint128::new(1);
+    }
+    let on_curve = reserve.saturating_sub(state.curve_reserve);
+    let cost = gross_up(on_curve, spread(storage)?);
+    let cost = gross_up(cost, creator_share(storage)?);
+    Ok(gross_up(
+        cost,
+        fee_rate(storage, buyer, TradeDirection::Buy)?,
+    ))
+}
+
+// the least amount that still leaves net once rate of it is taken off
+fn gross_up(net: Uint128, rate: Decimal) -> Uint128 {
+    let whole = rate.denominator();
+    let kept = whole - rate.numerator();
+    let gross = net.multiply_ratio(whole, kept);
+    if gross - gross * rate < net {
+        gross + Uint128::new(1)
+    } else {
+        gross
+    }
+}
+
+/// a cw20 reserve is bought with here. Any other cw20 is a donation to the treasury
+pub fn execute_receive<C: CustomMsg>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     curve_fn: CurveFn,
-) -> Result<Response, ContractError> {
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response<C>, ContractError> {
+    let reserve_token = RESERVE_TOKEN.may_load(deps.storage)?;
+    if reserve_token.as_ref() != Some(&info.sender) {
+        return receive_cw20(deps, env, info, wrapper);
+    }
+    nonpayable(&info)?;
+    assert_not_paused(deps.storage)?;
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Buy {
+            min_supply_out,
+            recipient,
+        } => {
+            let buyer = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => deps.api.addr_validate(&wrapper.sender)?,
+            };
+            do_buy(deps, env, buyer, wrapper.amount, curve_fn, min_supply_out)
+        }
+    }
+}
+
+/// errors if the reserve is a cw20, for everything that moves native reserve or stakes it
+pub fn assert_native_reserve(storage: &dyn Storage) -> Result<(), ContractError> {
+    if RESERVE_TOKEN.may_load(storage)?.is_some() {
+        return Err(ContractError::Cw20Reserve {});
+    }
+    Ok(())
+}
+
+/// pays amount of reserve to recipient, with a cw20 transfer if that is what the reserve is
+pub fn send_reserve<C: CustomMsg>(
+    storage: &dyn Storage,
+    recipient: String,
+    amount: Uint128,
+    reserve_denom: String,
+) -> StdResult<CosmosMsg<C>> {
+    let msg = match RESERVE_TOKEN.may_load(storage)? {
+        Some(token) => WasmMsg::Execute {
+            contract_addr: token.into_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }
+        .into(),
+        None => BankMsg::Send {
+            to_address: recipient,
+            amount: coins(amount.u128(), reserve_denom),
+        }
+        .into(),
+    };
+    Ok(msg)
+}
+
+/// Swaps whatever was sent into the reserve denom through the configured router,
+/// then buys with the proceeds in a callback to ourself
+pub fn execute_buy_with_swap<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_reserve_out: Option<Uint128>,
+    min_tokens_out: Uint128,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let router = SWAP_ROUTER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSwapRouter {})?;
+    let state = CURVE_STATE.load(deps.storage)?;
+    let offer = one_coin(&info)?;
+    if offer.denom == state.reserve_denom {
+        return Err(ContractError::SwapNotNeeded { denom: offer.denom });
+    }
+
+    // whatever our reserve balance grows by is what the swap returned
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &state.reserve_denom)?
+        .amount;
+    let swap = to_binary(&RouterExecuteMsg::Swap {
+        ask_denom: state.reserve_denom,
+        min_return: min_reserve_out,
+        to: None,
+    })?;
+    let callback = to_binary(&ExecuteMsg::_BuySwapped {
+        buyer: info.sender.to_string(),
+        balance_before,
+        min_tokens_out,
+    })?;
+
+    let res = Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: router.to_string(),
+            msg: swap,
+            funds: vec![offer.clone()],
+        })
+        .add_message(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: callback,
+            funds: vec![],
+        })
+        .add_attribute("action", "buy_with_swap")
+        .add_attribute("from", info.sender)
+        .add_attribute("offer", offer.to_string());
+    Ok(res)
+}
+
+pub fn _buy_swapped<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    buyer: String,
+    balance_before: Uint128,
+    min_tokens_out: Uint128,
+) -> Result<Response<C>, ContractError> {
+    // this is just meant as a call-back to ourself
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let state = CURVE_STATE.load(deps.storage)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &state.reserve_denom)?
+        .amount;
+    let payment = balance
+        .checked_sub(balance_before)
+        .map_err(StdError::overflow)?;
+    let buyer = deps.api.addr_validate(&buyer)?;
+    do_buy(deps, env, buyer, payment, curve_fn, Some(min_tokens_out))
+}
+
+pub fn do_buy<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    // buyer is the one receiving the minted tokens
+    buyer: Addr,
+    payment: Uint128,
+    curve_fn: CurveFn,
+    min_supply_out: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
+    assert_not_frozen(deps.storage, deps.api, &[buyer.to_string()])?;
+    assert_buys_open(deps.storage)?;
     let mut state = CURVE_STATE.load(deps.storage)?;
+    assert_curve_open(deps.storage, &env.block, state.curve_reserve)?;
+    // the protocol fee comes off the top
+    let fee = take_protocol_fee(deps.storage, &buyer, payment, TradeDirection::Buy)?;
+    // as does the creator's share, if it is escrowed
+    let escrowed = take_creator_share(deps.storage, payment - fee)?;
+    let payment = payment - fee - escrowed;
+    let goal_reached = goal_reached_event(
+        deps.storage,
+        state.curve_reserve,
+        state.curve_reserve + payment,
+    )?;
 
-    let payment = must_pay(&info, &state.reserve_denom)?;
+    // the spread stays behind for the holders, the rest buys on the curve
+    let kept = payment * spread(deps.storage)?;
+    let backing = backing(deps.storage)?;
 
     // calculate how many tokens can be purchased with this and mint them
     let curve = curve_fn(state.decimals);
-    state.reserve += payment;
+    state.curve_reserve += payment - kept;
 
     // curve.supply() calculates native -> CW20
-    let new_supply = curve.supply(state.reserve);
+    let new_supply = curve.supply(to_nominal(state.curve_reserve, backing));
     let minted = new_supply
         .checked_sub(state.supply)
         .map_err(StdError::overflow)?;
+    if let Some(min) = min_supply_out {
+        if minted < min {
+            return Err(ContractError::SlippageExceeded {
+                amount: minted,
+                min,
+            });
+        }
+    }
     state.supply = new_supply;
+    if !kept.is_zero() {
+        add_to_backing(deps.storage, state.curve_reserve, kept)?;
+        state.curve_reserve += kept;
+    }
     CURVE_STATE.save(deps.storage, &state)?;
+    checkpoint_spot_price(deps.storage, &env.block, curve.spot_price(state.supply))?;
+    spend_presale_allowance(deps.storage, &env.block, &buyer, payment)?;
+    record_trade(deps.storage, &buyer, TradeDirection::Buy, payment)?;
+    record_support(deps.storage, &buyer, payment)?;
+    record_contribution(deps.storage, &buyer, payment)?;
+    start_cooldown(deps.storage, &env.block, &buyer)?;
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &buyer,
+        minted,
+        TradeDirection::Buy,
+    )?;
 
     // call into cw20-base to mint the token, call as self as no one else is allowed
     let sub_info = MessageInfo {
         sender: env.contract.address.clone(),
         funds: vec![],
     };
-    execute_mint(deps, env, sub_info, info.sender.to_string(), minted)?;
+    let receipt = receipt_msg(deps.storage, &buyer, payment, minted)?;
+    let mint = execute_mint::<C>(deps, env, sub_info, buyer.to_string(), minted)?;
 
     // bond them to the validator
-    let res = Response::new()
+    let mut res = Response::new()
+        .add_submessages(hooks)
+        .add_submessages(receipt)
+        .add_events(goal_reached)
         .add_attribute("action", "buy")
-        .add_attribute("from", info.sender)
+        .add_attribute("from", buyer)
         .add_attribute("reserve", payment)
-        .add_attribute("supply", minted);
+        .add_attribute("supply", minted)
+        .add_attributes(mint.attributes);
+    if !kept.is_zero() {
+        res = res.add_attribute("spread", kept);
+    }
+    if !fee.is_zero() {
+        res = res.add_attribute("protocol_fee", fee);
+    }
     Ok(res)
 }
 
 // the-frey:
 // this is verbatim from cw20-bonding, we should probably refactor out
-pub fn execute_sell(
+pub fn execute_sell<C: CustomMsg>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     curve_fn: CurveFn,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+    min_reserve_out: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
     nonpayable(&info)?;
+    let reserve = CURVE_STATE.load(deps.storage)?.curve_reserve;
+    if is_refunding(deps.storage, &env.block, reserve)? {
+        return execute_refund(deps, env, info, amount, min_reserve_out);
+    }
     let receiver = info.sender.clone();
     // do all the work
-    let mut res = do_sell(deps, env, info, curve_fn, receiver, amount)?;
+    do_sell(deps, env, info, curve_fn, receiver, amount, min_reserve_out)
+}
 
-    // add our custom attributes
-    res.attributes.push(attr("action", "burn"));
+/// sells however many tokens it takes to release reserve_out, as long as that's no more
+/// than max_supply_in
+pub fn execute_sell_exact_reserve<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    reserve_out: Uint128,
+    max_supply_in: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    if reserve_out.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let state = CURVE_STATE.load(deps.storage)?;
+    let curve = curve_fn(state.decimals);
+    let amount = sell_cost(
+        deps.storage,
+        &info.sender,
+        &state,
+        curve.as_ref(),
+        reserve_out,
+    )?;
+    if amount > max_supply_in {
+        return Err(ContractError::SupplyInExceeded {
+            amount,
+            max: max_supply_in,
+        });
+    }
+    let receiver = info.sender.clone();
+    do_sell(
+        deps,
+        env,
+        info,
+        curve_fn,
+        receiver,
+        amount,
+        Some(reserve_out),
+    )
+}
+
+// the tokens a sell must burn to pay out reserve_out, once the protocol fee and the
+// spread are taken. Rounds so the sell releases no less
+fn sell_cost(
+    storage: &dyn Storage,
+    seller: &Addr,
+    state: &CurveState,
+    curve: &dyn Curve,
+    reserve_out: Uint128,
+) -> Result<Uint128, ContractError> {
+    let released = gross_up(
+        reserve_out,
+        fee_rate(storage, seller, TradeDirection::Sell)?,
+    );
+    let released = gross_up(released, spread(storage)?);
+    let left = state.curve_reserve.checked_sub(released).map_err(|_| {
+        ContractError::InsufficientReserve {
+            available: state.curve_reserve,
+        }
+    })?;
+    let supply_left = curve.supply(to_nominal(left, backing(storage)?));
+    Ok(state.supply.saturating_sub(supply_left))
+}
+
+/// in refund mode Burn hands back what the holder paid for amount rather than the curve price
+fn execute_refund<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    min_reserve_out: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
+    let refund = take_contribution(deps.storage, &info.sender, amount)?;
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &info.sender,
+        amount,
+        TradeDirection::Sell,
+    )?;
+    let burn = execute_burn::<C>(deps.branch(), env, info.clone(), amount)?;
+
+    let mut state = CURVE_STATE.load(deps.storage)?;
+    let refund = refund.min(state.curve_reserve);
+    if let Some(min) = min_reserve_out {
+        if refund < min {
+            return Err(ContractError::SlippageExceeded {
+                amount: refund,
+                min,
+            });
+        }
+    }
+    state.supply = state
+        .supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    state.curve_reserve -= refund;
+    CURVE_STATE.save(deps.storage, &state)?;
+    record_trade(deps.storage, &info.sender, TradeDirection::Sell, refund)?;
+
+    let msg = send_reserve(
+        deps.storage,
+        info.sender.to_string(),
+        refund,
+        state.reserve_denom,
+    )?;
+    let res = Response::new()
+        .add_message(msg)
+        .add_submessages(hooks)
+        .add_attribute("action", "refund")
+        .add_attribute("from", info.sender)
+        .add_attribute("supply", amount)
+        .add_attribute("reserve", refund)
+        .add_attributes(burn.attributes);
     Ok(res)
 }
 
 // the-frey: even though this is the default impl
 // not convinced it does exactly what we want here. TBC
-pub fn execute_sell_from(
+pub fn execute_sell_from<C: CustomMsg>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     curve_fn: CurveFn,
     owner: String,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+    min_reserve_out: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
     nonpayable(&info)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
     let spender_addr = info.sender.clone();
+    // the spender is paid the reserve, so must not be frozen either
+    assert_not_frozen(deps.storage, deps.api, &[spender_addr.to_string()])?;
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &spender_addr, &env.block, amount)?;
@@ -185,15 +759,19 @@ pub fn execute_sell_from(
         curve_fn,
         receiver_addr.clone(),
         amount,
+        min_reserve_out,
     )?;
 
-    // add our custom attributes
-    res.attributes.push(attr("action", "burn_from"));
-    res.attributes.push(attr("by", receiver_addr));
-    Ok(res)
+    // cw20-base reports a burn on the owner's behalf as burn_from, by the spender
+    for attr in res.attributes.iter_mut() {
+        if attr.key == "action" && attr.value == "burn" {
+            attr.value = "burn_from".into();
+        }
+    }
+    Ok(res.add_attribute("by", receiver_addr))
 }
 
-fn do_sell(
+fn do_sell<C: CustomMsg>(
     mut deps: DepsMut,
     env: Env,
     // info.sender is the one burning tokens
@@ -202,12 +780,62 @@ fn do_sell(
     // receiver is the one who gains (same for execute_sell, diff for execute_sell_from)
     receiver: Addr,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+    min_reserve_out: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &info.sender,
+        amount,
+        TradeDirection::Sell,
+    )?;
+    let (released, reserve_denom, burn) =
+        sell_on_curve(deps.branch(), env, info.clone(), curve_fn, amount)?;
+    if let Some(min) = min_reserve_out {
+        if released < min {
+            return Err(ContractError::SlippageExceeded {
+                amount: released,
+                min,
+            });
+        }
+    }
+
+    // now send the tokens to the sender (TODO: for sell_from we do something else, right???)
+    let msg = send_reserve(deps.storage, receiver.to_string(), released, reserve_denom)?;
+    let res = Response::new()
+        .add_message(msg)
+        .add_submessages(hooks)
+        .add_attribute("from", info.sender)
+        .add_attribute("supply", amount)
+        .add_attribute("reserve", released)
+        .add_attributes(burn);
+    Ok(res)
+}
+
+// sell_on_curve burns amount from info.sender and moves the curve down,
+// returning how much reserve was released (and in what denom) for the caller to pay out,
+// along with the burn's attributes
+pub fn sell_on_curve(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+) -> Result<(Uint128, String, Vec<Attribute>), ContractError> {
+    let mut state = CURVE_STATE.load(deps.storage)?;
+    if is_refunding(deps.storage, &env.block, state.curve_reserve)? {
+        return Err(ContractError::FundingRefund {});
+    }
+    assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+    assert_sells_open(deps.storage)?;
+    assert_cooled_down(deps.storage, &env.block, &info.sender)?;
+    take_contribution(deps.storage, &info.sender, amount)?;
+    let seller = info.sender.clone();
+
     // burn from the caller, this ensures there are tokens to cover this
-    execute_burn(deps.branch(), env, info.clone(), amount)?;
+    let burn = execute_burn::<Empty>(deps.branch(), env.clone(), info, amount)?;
 
     // calculate how many tokens can be purchased with this and mint them
-    let mut state = CURVE_STATE.load(deps.storage)?;
     let curve = curve_fn(state.decimals);
     state.supply = state
         .supply
@@ -215,23 +843,82 @@ fn do_sell(
         .map_err(StdError::overflow)?;
 
     // curve.reserve() calculates CW20 -> native
-    let new_reserve = curve.reserve(state.supply);
-    let released = state
-        .reserve
+    let new_reserve = curve.reserve(state.supply) * backing(deps.storage)?;
+    let mut released = state
+        .curve_reserve
         .checked_sub(new_reserve)
         .map_err(StdError::overflow)?;
-    state.reserve = new_reserve;
+    // the spread stays behind for the holders left, if there are any
+    let mut attributes = burn.attributes;
+    if !new_reserve.is_zero() {
+        let kept = released * spread(deps.storage)?;
+        if !kept.is_zero() {
+            add_to_backing(deps.storage, new_reserve, kept)?;
+            released -= kept;
+            attributes.push(attr("spread", kept));
+        }
+    }
+    state.curve_reserve -= released;
+    let fee = take_protocol_fee(deps.storage, &seller, released, TradeDirection::Sell)?;
+    if !fee.is_zero() {
+        released -= fee;
+        attributes.push(attr("protocol_fee", fee));
+    }
     CURVE_STATE.save(deps.storage, &state)?;
+    checkpoint_spot_price(deps.storage, &env.block, curve.spot_price(state.supply))?;
+    record_trade(deps.storage, &seller, TradeDirection::Sell, released)?;
 
-    // now send the tokens to the sender (TODO: for sell_from we do something else, right???)
-    let msg = BankMsg::Send {
-        to_address: receiver.to_string(),
-        amount: coins(released.u128(), state.reserve_denom),
-    };
+    Ok((released, state.reserve_denom, attributes))
+}
+
+/// Sells on the curve like Burn, but swaps the released reserve into target_denom
+/// through the configured router, which pays the seller directly
+pub fn execute_burn_to_denom<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+    target_denom: String,
+    min_out: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_native_reserve(deps.storage)?;
+    let router = SWAP_ROUTER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSwapRouter {})?;
+    if target_denom == CURVE_STATE.load(deps.storage)?.reserve_denom {
+        return Err(ContractError::SwapNotNeeded {
+            denom: target_denom,
+        });
+    }
+    let seller = info.sender.clone();
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &seller,
+        amount,
+        TradeDirection::Sell,
+    )?;
+    let (released, reserve_denom, burn) = sell_on_curve(deps, env, info, curve_fn, amount)?;
+
+    let swap = to_binary(&RouterExecuteMsg::Swap {
+        ask_denom: target_denom.clone(),
+        min_return: Some(min_out),
+        to: Some(seller.to_string()),
+    })?;
     let res = Response::new()
-        .add_message(msg)
-        .add_attribute("from", info.sender)
+        .add_message(WasmMsg::Execute {
+            contract_addr: router.to_string(),
+            msg: swap,
+            funds: coins(released.u128(), reserve_denom),
+        })
+        .add_submessages(hooks)
+        .add_attribute("action", "burn_to_denom")
+        .add_attribute("from", seller)
         .add_attribute("supply", amount)
-        .add_attribute("reserve", released);
+        .add_attribute("reserve", released)
+        .add_attribute("target_denom", target_denom)
+        .add_attributes(burn);
     Ok(res)
 }
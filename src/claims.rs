@@ -0,0 +1,417 @@
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Timestamp, Uint128,
+};
+use cw0::{nonpayable, Duration, Expiration};
+use cw_controllers::Claim;
+use cw_storage_plus::{Bound, PrimaryKey, U64Key};
+
+use crate::error::ContractError;
+use crate::freeze::assert_not_frozen;
+use crate::msg::CustomMsg;
+use crate::query::{
+    AddressClaims, AllClaimsResponse, ClaimEstimate, ClaimEstimatesResponse, ClaimsResponse,
+    QueuedClaim, TotalClaimsResponse,
+};
+use crate::staking::{query_chain_unbonding_time, query_unbonding_entries};
+use crate::state::{
+    UnbondEpoch, CLAIMS, CLAIM_COUNT, CURVE_STATE, INVESTMENT, LEGACY_CLAIMS, UNBOND_EPOCH,
+};
+
+// how many claims Claims, or claimants AllClaims, returns per page
+const DEFAULT_CLAIMS_LIMIT: u32 = 10;
+const MAX_CLAIMS_LIMIT: u32 = 30;
+
+// how many claims TotalClaims looks at before giving up on the breakdown
+const MAX_CLAIM_SCAN: usize = 100;
+
+/// queues amount for owner, payable once release_at has passed
+pub fn create_claim(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    amount: Uint128,
+    release_at: Expiration,
+) -> StdResult<()> {
+    let id = CLAIM_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    CLAIM_COUNT.save(storage, &id)?;
+    CLAIMS.save(storage, (owner, id.into()), &Claim { amount, release_at })
+}
+
+/// removes owner's matured claims, oldest first, as long as they fit under cap,
+/// and returns how much they come to
+pub fn claim_tokens(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    block: &BlockInfo,
+    cap: Option<Uint128>,
+) -> StdResult<Uint128> {
+    let queued = CLAIMS
+        .prefix(owner)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut to_send = Uint128::zero();
+    for (id, claim) in queued {
+        if !claim.release_at.is_expired(block) {
+            continue;
+        }
+        if let Some(limit) = cap {
+            if to_send + claim.amount > limit {
+                continue;
+            }
+        }
+        to_send += claim.amount;
+        CLAIMS.remove(storage, (owner, parse_id(&id)?.into()));
+    }
+    Ok(to_send)
+}
+
+/// matured claims expire the same way they mature, by time or by height
+pub fn validate_claim_expiry(
+    expiry: &Duration,
+    unbonding_period: &Duration,
+) -> Result<(), ContractError> {
+    match (expiry, unbonding_period) {
+        (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_)) => {
+            Ok(())
+        }
+        _ => Err(ContractError::InvalidClaimExpiry {}),
+    }
+}
+
+/// removes up to limit claims, of anyone, left unclaimed for expiry after maturing,
+/// returning how much they were for
+pub fn remove_expired_claims(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    expiry: &Duration,
+    limit: usize,
+) -> StdResult<Uint128> {
+    let expired = CLAIMS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, claim)) => claim_expired(&claim.release_at, expiry, block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut removed = Uint128::zero();
+    for (key, claim) in expired {
+        let (owner, id) = parse_key(&key)?;
+        CLAIMS.remove(storage, (&owner, id.into()));
+        removed += claim.amount;
+    }
+    Ok(removed)
+}
+
+fn claim_expired(release_at: &Expiration, expiry: &Duration, block: &BlockInfo) -> bool {
+    match (release_at, expiry) {
+        (Expiration::AtTime(time), Duration::Time(seconds)) => {
+            block.time >= time.plus_seconds(*seconds)
+        }
+        (Expiration::AtHeight(height), Duration::Height(blocks)) => block.height >= height + blocks,
+        _ => false,
+    }
+}
+
+/// hands amount of the sender's claims to recipient, each part keeping its release time.
+/// Claims released last go first, splitting the last one touched if need be
+pub fn transfer_claim<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let accounts = [info.sender.to_string(), recipient.clone()];
+    assert_not_frozen(deps.storage, deps.api, &accounts)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let queued = CLAIMS
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Descending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let available = queued.iter().map(|(_, c)| c.amount).sum::<Uint128>();
+    if available < amount {
+        return Err(ContractError::InsufficientClaims { available });
+    }
+
+    let mut left = amount;
+    for (id, mut claim) in queued {
+        if left.is_zero() {
+            break;
+        }
+        let key = (&info.sender, parse_id(&id)?.into());
+        let moved = claim.amount.min(left);
+        if moved == claim.amount {
+            CLAIMS.remove(deps.storage, key);
+        } else {
+            claim.amount -= moved;
+            CLAIMS.save(deps.storage, key, &claim)?;
+        }
+        create_claim(deps.storage, &recipient, moved, claim.release_at)?;
+        left -= moved;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "transfer_claim")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+/// up to limit addresses after start_after with a matured claim, in storage order,
+/// looking at no more than MAX_CLAIM_SCAN claims. Also returns where to carry on from,
+/// unless every claim has been looked at
+pub fn due_claimants(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    start_after: Option<Addr>,
+    limit: usize,
+) -> StdResult<(Vec<Addr>, Option<Addr>)> {
+    let start = start_after.map(|s| {
+        let last = (&s, U64Key::new(u64::MAX));
+        Bound::exclusive(last.joined_key())
+    });
+
+    let mut due: Vec<Addr> = vec![];
+    // the last address whose claims have all been looked at, and the one being looked at
+    let mut done: Option<Addr> = None;
+    let mut current: Option<Addr> = None;
+    let mut entries = CLAIMS.range(storage, start, None, Order::Ascending);
+    for item in entries.by_ref().take(MAX_CLAIM_SCAN) {
+        let (key, claim) = item?;
+        let (owner, _) = parse_key(&key)?;
+        if current.as_ref() != Some(&owner) {
+            if due.len() == limit {
+                return Ok((due, current));
+            }
+            done = current.replace(owner.clone());
+        }
+        if claim.release_at.is_expired(block) && due.last() != Some(&owner) {
+            due.push(owner);
+        }
+    }
+    if entries.next().is_none() {
+        return Ok((due, None));
+    }
+    // an address with more claims than we scan still has to move us on
+    Ok((due, done.or(current)))
+}
+
+/// moves claims kept as one list per address into their own entries. Anything
+/// already moved is gone from the old storage, so this can run on every migration
+pub fn migrate_legacy_claims(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_CLAIMS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (owner, claims) in legacy {
+        let owner = Addr::unchecked(String::from_utf8(owner)?);
+        for claim in claims {
+            create_claim(storage, &owner, claim.amount, claim.release_at)?;
+        }
+        LEGACY_CLAIMS.remove(storage, &owner);
+    }
+    Ok(())
+}
+
+// claim keys are the length-prefixed owner followed by the id
+fn parse_key(key: &[u8]) -> StdResult<(Addr, u64)> {
+    let corrupt = || StdError::generic_err("corrupt claim key");
+    if key.len() < 2 {
+        return Err(corrupt());
+    }
+    let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if key.len() != 2 + len + 8 {
+        return Err(corrupt());
+    }
+    let owner = String::from_utf8(key[2..2 + len].to_vec())?;
+    Ok((Addr::unchecked(owner), parse_id(&key[2 + len..])?))
+}
+
+fn parse_id(key: &[u8]) -> StdResult<u64> {
+    let mut id_bytes = [0u8; 8];
+    if key.len() != id_bytes.len() {
+        return Err(StdError::generic_err("corrupt claim id"));
+    }
+    id_bytes.copy_from_slice(key);
+    Ok(u64::from_be_bytes(id_bytes))
+}
+
+/// address's claims, oldest first. only_mature leaves out those still unbonding
+pub fn query_claims(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    only_mature: Option<bool>,
+) -> StdResult<ClaimsResponse> {
+    let owner = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_CLAIMS_LIMIT).min(MAX_CLAIMS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+    let only_mature = only_mature.unwrap_or(false);
+
+    let claims = CLAIMS
+        .prefix(&owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, claim)) => !only_mature || claim.release_at.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (id, claim) = item?;
+            Ok(QueuedClaim {
+                id: parse_id(&id)?,
+                amount: claim.amount,
+                release_at: claim.release_at,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ClaimsResponse { claims })
+}
+
+/// address's claims, each with a best guess at when it will really pay out. A claim
+/// cannot be paid before the chain hands back the undelegation behind it, so that is
+/// looked up among the contract's undelegations still in flight
+pub fn query_claim_estimates(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ClaimEstimatesResponse> {
+    let queued = query_claims(deps, env.clone(), address, start_after, limit, None)?.claims;
+    let period = match INVESTMENT.load(deps.storage)?.unbonding_period {
+        Duration::Time(seconds) => Some(seconds),
+        Duration::Height(_) => None,
+    };
+    let chain_time = query_chain_unbonding_time(&deps.querier)?;
+    let entries =
+        query_unbonding_entries(&deps.querier, &env.contract.address)?.unwrap_or_default();
+    let epoch = UNBOND_EPOCH.may_load(deps.storage)?.unwrap_or_default();
+
+    let claims = queued
+        .into_iter()
+        .map(|claim| {
+            let completes = match (claim.release_at, period, chain_time) {
+                (Expiration::AtTime(release), Some(period), Some(chain_time)) => {
+                    undelegation_completes(
+                        release,
+                        period,
+                        chain_time,
+                        &entries,
+                        &epoch,
+                        env.block.time,
+                    )
+                }
+                _ => None,
+            };
+            let estimated_payout = match (claim.release_at, completes) {
+                (Expiration::AtTime(release), Some(completes)) if completes > release => {
+                    Expiration::AtTime(completes)
+                }
+                (release_at, _) => release_at,
+            };
+            ClaimEstimate {
+                id: claim.id,
+                amount: claim.amount,
+                release_at: claim.release_at,
+                undelegation_completes: completes,
+                estimated_payout,
+            }
+        })
+        .collect();
+    Ok(ClaimEstimatesResponse { claims })
+}
+
+// a claim released at release was undelegated no earlier than the unbonding period
+// before. Still batched in the epoch, it goes out once the epoch is processed; otherwise
+// it is the first undelegation in flight that can have started since. None once it is back
+fn undelegation_completes(
+    release: Timestamp,
+    period: u64,
+    chain_time: u64,
+    entries: &[(Timestamp, Uint128)],
+    epoch: &UnbondEpoch,
+    now: Timestamp,
+) -> Option<Timestamp> {
+    if release.seconds() < period {
+        return None;
+    }
+    let undelegated = release.minus_seconds(period);
+    if !epoch.pending.is_zero() && epoch.ends == Expiration::AtTime(undelegated) {
+        return Some(undelegated.max(now).plus_seconds(chain_time));
+    }
+    let earliest = undelegated.plus_seconds(chain_time);
+    entries
+        .iter()
+        .map(|(completes, _)| *completes)
+        .find(|completes| *completes >= earliest)
+}
+
+/// all claims outstanding, split by whether they can be paid out yet. Only the first
+/// MAX_CLAIM_SCAN claims are looked at, so a big queue reports complete: false and
+/// counts the rest as locked
+pub fn query_total_claims(deps: Deps, env: Env) -> StdResult<TotalClaimsResponse> {
+    let claims = CURVE_STATE.load(deps.storage)?.claims;
+
+    let mut matured = Uint128::zero();
+    let mut scanned = 0;
+    let mut entries = CLAIMS.range(deps.storage, None, None, Order::Ascending);
+    for item in entries.by_ref().take(MAX_CLAIM_SCAN) {
+        let (_, claim) = item?;
+        if claim.release_at.is_expired(&env.block) {
+            matured += claim.amount;
+        }
+        scanned += 1;
+    }
+    let complete = scanned < MAX_CLAIM_SCAN || entries.next().is_none();
+
+    Ok(TotalClaimsResponse {
+        claims,
+        matured,
+        locked: claims.saturating_sub(matured),
+        complete,
+    })
+}
+
+/// every claimant and their queued claims, in storage order (shorter addresses first)
+pub fn query_all_claims(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllClaimsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_CLAIMS_LIMIT).min(MAX_CLAIMS_LIMIT) as usize;
+    // skip past every claim of start_after, whatever its id
+    let start = start_after.map(|s| {
+        let last = (&Addr::unchecked(s), U64Key::new(u64::MAX));
+        Bound::exclusive(last.joined_key())
+    });
+
+    let mut claims: Vec<AddressClaims> = vec![];
+    for item in CLAIMS.range(deps.storage, start, None, Order::Ascending) {
+        let (key, claim) = item?;
+        let (owner, _) = parse_key(&key)?;
+        if let Some(last) = claims.last_mut() {
+            if last.address == owner.as_str() {
+                last.claims.push(claim);
+                continue;
+            }
+        }
+        if claims.len() == limit {
+            break;
+        }
+        claims.push(AddressClaims {
+            address: owner.into_string(),
+            claims: vec![claim],
+        });
+    }
+    Ok(AllClaimsResponse { claims })
+}
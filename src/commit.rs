@@ -0,0 +1,137 @@
+use cosmwasm_std::{
+    coins, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+};
+use cw0::{must_pay, nonpayable};
+use sha2::{Digest, Sha256};
+
+use crate::bonding::{assert_native_reserve, do_buy};
+use crate::curves::CurveFn;
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::BuyCommitmentResponse;
+use crate::state::{BuyCommitment, BUY_COMMITMENTS, CURVE_STATE, ESCROWED_RESERVE};
+
+/// what CommitBuy takes as the hash, binding the salt to the buyer and the least
+/// they will take for it, kept hidden until the reveal
+pub fn commitment_hash(buyer: &Addr, min_supply_out: Uint128, salt: &[u8]) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(buyer.as_bytes());
+    hasher.update(min_supply_out.u128().to_be_bytes());
+    hasher.update(salt);
+    Binary::from(hasher.finalize().to_vec())
+}
+
+/// escrows the reserve sent against a commitment_hash. Nothing is bought until RevealBuy,
+/// and that is held to the minimum committed to, so there's no buy to sandwich
+pub fn commit_buy<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    hash: Binary,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let reserve = must_pay(&info, &reserve_denom)?;
+    if hash.len() != 32 {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    if BUY_COMMITMENTS.has(deps.storage, &info.sender) {
+        return Err(ContractError::CommitmentExists {});
+    }
+
+    BUY_COMMITMENTS.save(
+        deps.storage,
+        &info.sender,
+        &BuyCommitment {
+            hash,
+            reserve,
+            height: env.block.height,
+        },
+    )?;
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed + reserve)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "commit_buy")
+        .add_attribute("from", info.sender)
+        .add_attribute("reserve", reserve);
+    Ok(res)
+}
+
+/// buys with the escrowed reserve at the curve as it is now, as long as that gets the
+/// min_supply_out committed to. The salt must match the commitment, which must be from
+/// an earlier block
+pub fn reveal_buy<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    salt: Binary,
+    min_supply_out: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let commitment = BUY_COMMITMENTS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoCommitment {})?;
+    if env.block.height <= commitment.height {
+        return Err(ContractError::RevealTooSoon {});
+    }
+    if commitment_hash(&info.sender, min_supply_out, &salt) != commitment.hash {
+        return Err(ContractError::CommitmentMismatch {});
+    }
+
+    BUY_COMMITMENTS.remove(deps.storage, &info.sender);
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(commitment.reserve)?)
+    })?;
+    let min_supply_out = Some(min_supply_out);
+    let res = do_buy(
+        deps,
+        env,
+        info.sender,
+        commitment.reserve,
+        curve_fn,
+        min_supply_out,
+    )?
+    .add_attribute("revealed", "true");
+    Ok(res)
+}
+
+/// refunds the escrowed reserve of a commitment that won't be revealed
+pub fn cancel_buy_commitment<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let commitment = BUY_COMMITMENTS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoCommitment {})?;
+    BUY_COMMITMENTS.remove(deps.storage, &info.sender);
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(commitment.reserve)?)
+    })?;
+
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(commitment.reserve.u128(), reserve_denom),
+        })
+        .add_attribute("action", "cancel_buy_commitment")
+        .add_attribute("from", info.sender)
+        .add_attribute("reserve", commitment.reserve);
+    Ok(res)
+}
+
+pub fn query_buy_commitment(deps: Deps, address: String) -> StdResult<BuyCommitmentResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let commitment = BUY_COMMITMENTS.may_load(deps.storage, &address)?;
+    Ok(BuyCommitmentResponse {
+        reserve: commitment
+            .as_ref()
+            .map(|c| c.reserve)
+            .unwrap_or_else(Uint128::zero),
+        height: commitment.map(|c| c.height),
+    })
+}
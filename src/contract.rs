@@ -1,32 +1,106 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    to_binary, Api, Binary, ContractResult, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo,
+    Order, QuerierWrapper, Reply, Response, StdError, StdResult, Uint128,
 };
 
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20_base::allowances::{
     execute_decrease_allowance, execute_increase_allowance, execute_send_from,
     execute_transfer_from, query_allowance,
 };
-use cw20_base::contract::{execute_send, execute_transfer, query_balance};
-use cw20_base::state::{MinterData, TokenInfo};
+use cw20_base::contract::{
+    execute_send, execute_transfer, execute_update_marketing, execute_upload_logo, query_balance,
+    query_download_logo, query_marketing_info,
+};
+use cw20_base::enumerable::{query_all_accounts, query_all_allowances};
+use cw20_base::state::{MinterData, TokenInfo, MARKETING_INFO};
 
+use crate::claims::{
+    migrate_legacy_claims, query_all_claims, query_claim_estimates, query_claims,
+    query_total_claims, transfer_claim, validate_claim_expiry,
+};
+use crate::commit::{cancel_buy_commitment, commit_buy, query_buy_commitment, reveal_buy};
+use crate::creator::{
+    accept_creator, lock_metadata, query_creator, transfer_creator, update_metadata,
+};
+use crate::curves::CurveFn;
+use crate::dca::{cancel_dca, create_dca, execute_dca, query_dca_plans};
+use crate::epoch::{process_epoch, query_unbond_epoch, validate_unbond_epoch};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::query::{CurveInfoResponse, TokenInfoResponseWithMeta};
+use crate::fees::{
+    collect_fees, query_fee_exemptions, query_protocol_fees, update_fee_exemptions,
+    validate_fee_config,
+};
+use crate::freeze::{assert_not_frozen, freeze, query_frozen, unfreeze};
+use crate::hooks::{add_hook, query_hooks, remove_hook};
+use crate::ibc::query_denom_trace;
+use crate::launch::{claim_launch_tokens, launch_bid, query_launch, settle_launch, start_launch};
+use crate::milestones::{query_creator_escrow, release_milestone, start_creator_escrow};
+use crate::msg::{
+    lift_response, CurveType, CustomMsg, ExecuteMsg, ExitTaxPayout, InstantiateMsg, MigrateMsg,
+    QueryMsg, RewardStrategy, SudoMsg,
+};
+use crate::orders::{
+    cancel_buy_order, cancel_sell_order, default_keeper_fee, execute_buy_order, execute_sell_order,
+    place_buy_order, place_sell_order, query_buy_orders, query_sell_orders,
+};
+use crate::patrons::{query_patron_tier, query_patron_tiers, set_patron_tiers};
+use crate::pause::{assert_not_paused, is_pausable, pause, query_paused, set_halts, unpause};
+use crate::polls::{cast_vote, create_poll, query_poll, query_polls};
+use crate::presale::{query_presale, set_presale};
+use crate::query::{
+    CapabilitiesResponse, CurveInfoResponse, FundingPhase, FundingProgressResponse,
+    SpotPriceAtHeightResponse, TokenInfoResponseWithMeta, TwapResponse,
+};
+use crate::receipts::{
+    query_nft_receipts, receipt_failed, set_nft_receipts, validate_nft_receipts, RECEIPT_REPLY_ID,
+};
+use crate::rewards::{
+    claim_rewards, query_claimable_rewards, settle_accounts, validate_reward_strategy,
+};
+use crate::snapshot::{query_balance_at, query_total_supply_at, seed_supply, snapshot_balances};
 use crate::state::{
-    CurveState, InvestmentInfo, TokenInfoWithMeta, CLAIMS, CURVE_STATE, CURVE_TYPE, INVESTMENT,
-    TOKEN_INFO_WITH_META,
+    Approvers, CurveState, InvestmentInfo, TokenInfoWithMeta, CREATOR, CURVE_STATE, CURVE_TYPE,
+    DELEGATIONS, ESCROWED_RESERVE, FEE_CONFIG, FUNDING_GOAL, INVESTMENT, INVESTMENT_V0_1,
+    KEEPER_FEE, NFT_RECEIPTS, PRESALE_END, PRICE_OBSERVATIONS, PURCHASE_ONLY, RESERVE_DONATIONS,
+    RESERVE_TOKEN, RESERVE_TRACE, ROYALTY_BPS, SALE_END, SALE_START, SPOT_PRICES, SPREAD,
+    SWAP_ROUTER, TOKEN_INFO_WITH_META, TRADE_COOLDOWN, UNBOND_LIMITS, VALIDATOR_APPROVERS,
+    WIND_DOWN,
+};
+use crate::stats::query_stats;
+use crate::tax::{query_owner_fees, query_payees, update_payees, withdraw_owner_fees};
+use crate::treasury::{
+    donate, query_treasury, recover_cw20, sweep, withdraw_cw20_donation, withdraw_native_donation,
 };
+use crate::unbond_limits::{query_unbond_capacity, validate_unbond_limits};
+use crate::vesting::{claim_vested, query_creator_vesting, start_creator_vesting};
+use crate::wind_down::{initiate_wind_down, query_wind_down, settle};
 use cw0::nonpayable;
-use cw20::TokenInfoResponse;
-use cw20_bonding::msg::CurveFn;
+use cw20::{MarketingInfoResponse, TokenInfoResponse};
 
 use cw20_bonding::curves::DecimalPlaces;
+use cw_storage_plus::Bound;
 
-use crate::bonding::{execute_buy, execute_sell, execute_sell_from};
-use crate::staking::{_bond_all_tokens, bond, claim, query_investment, reinvest, unbond};
+use crate::bonding::{
+    _buy_swapped, checkpoint_spot_price, cumulative_price_at, execute_burn_to_denom, execute_buy,
+    execute_buy_exact, execute_buy_with_swap, execute_receive, execute_sell,
+    execute_sell_exact_reserve, execute_sell_from,
+};
+use crate::registry::register_with;
+use crate::royalty::{query_check_royalties, query_royalty_info, set_royalty, validate_royalty};
+use crate::staking::{
+    _bond_all_tokens, accept_ownership, approve_validator_change, assert_split_purchases, bond,
+    bond_failed, burn_without_refund, claim, claim_for, donate_to_reserve, failover, get_bonded,
+    instant_unbond, migrate_staked_balance, propose_validator_change, purchase, query_delegations,
+    query_health, query_investment, query_liquid_buffer, query_rate_history,
+    query_reinvest_preview, query_validator_change, query_validator_health, rebalance,
+    rebond_all_tokens, reconcile, recycle_expired_claims, reinvest, set_reward_withdraw_address,
+    staked_balance, sudo_switch_validator, sweep_claims, transfer_ownership, unbond, unbond_from,
+    update_staking_params, validate_unbonding_period, withdraw_funding_pool, withdraw_rewards_only,
+    BOND_REPLY_ID,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "cw20-bondcamp";
@@ -39,19 +113,155 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    // default implementation checks the curve in InstantiateMsg and stores it as an enum,
+    // a derived contract can pass a checker that accepts its own curve to do_instantiate
+    do_instantiate(deps, env, info, msg, check_curve_type)
+}
+
+/// Checks the curve_type an InstantiateMsg asks for, returning the curve to price the
+/// opening and any launch auction with
+pub type CurveChecker =
+    for<'a> fn(&CurveType, &dyn Api, QuerierWrapper<'a>) -> Result<CurveFn<'a>, ContractError>;
+
+/// the default CurveChecker, for the curves CurveType describes
+pub fn check_curve_type<'a>(
+    curve_type: &CurveType,
+    api: &dyn Api,
+    querier: QuerierWrapper<'a>,
+) -> Result<CurveFn<'a>, ContractError> {
+    curve_type.validate()?;
+    if let CurveType::External { contract } = curve_type {
+        api.addr_validate(contract)?;
+    }
+    Ok(curve_type.to_curve_fn(querier))
+}
+
+/// We pull out logic here, so we can import this from another contract with a different
+/// Curve, as with do_execute and do_query, checking the curve with curve_checker
+pub fn do_instantiate<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+    curve_checker: CurveChecker,
+) -> Result<Response<C>, ContractError> {
     nonpayable(&info)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // ensure the validator is registered
+    // ensure the validator is registered, along with any others bonders may choose
+    // or we may fail over to
     let vals = deps.querier.query_all_validators()?;
-    if !vals
-        .iter()
-        .any(|v| v.address == msg.staking_params.validator)
+    let allowed_validators = msg.staking_params.allowed_validators.unwrap_or_default();
+    let fallback_validators = msg.staking_params.fallback_validators.unwrap_or_default();
+    for validator in std::iter::once(&msg.staking_params.validator)
+        .chain(&allowed_validators)
+        .chain(&fallback_validators)
     {
-        return Err(ContractError::NotInValidatorSet {
-            validator: msg.staking_params.validator,
+        if !vals.iter().any(|v| &v.address == validator) {
+            return Err(ContractError::NotInValidatorSet {
+                validator: validator.clone(),
+            });
+        }
+    }
+
+    // stake can only be weighted towards validators bonders are allowed to use,
+    // each once and with something to weigh
+    let target_weights = msg.staking_params.target_weights.unwrap_or_default();
+    for (i, target_weight) in target_weights.iter().enumerate() {
+        if target_weight.weight == 0
+            || target_weights[..i]
+                .iter()
+                .any(|w| w.validator == target_weight.validator)
+        {
+            return Err(ContractError::InvalidTargetWeights {});
+        }
+        if target_weight.validator != msg.staking_params.validator
+            && !allowed_validators.contains(&target_weight.validator)
+        {
+            return Err(ContractError::ValidatorNotAllowed {
+                validator: target_weight.validator.clone(),
+            });
+        }
+    }
+
+    let reward_skim = msg.staking_params.reward_skim.unwrap_or_else(Decimal::zero);
+    if reward_skim > Decimal::one() {
+        return Err(ContractError::InvalidRewardSkim {});
+    }
+
+    let max_exit_tax = msg
+        .staking_params
+        .max_exit_tax
+        .unwrap_or(msg.staking_params.exit_tax);
+    if max_exit_tax > Decimal::one() {
+        return Err(ContractError::ExitTaxTooHigh {
+            max: Decimal::one(),
         });
     }
+    if msg.staking_params.exit_tax > max_exit_tax {
+        return Err(ContractError::ExitTaxTooHigh { max: max_exit_tax });
+    }
+
+    let instant_unbond_penalty = msg
+        .staking_params
+        .instant_unbond_penalty
+        .unwrap_or_else(|| Decimal::percent(5));
+    if instant_unbond_penalty > Decimal::one() {
+        return Err(ContractError::InvalidInstantUnbondPenalty {});
+    }
+
+    let claim_incentive = msg
+        .staking_params
+        .claim_incentive
+        .unwrap_or_else(Decimal::zero);
+    if claim_incentive > Decimal::one() {
+        return Err(ContractError::InvalidClaimIncentive {});
+    }
+
+    let liquid_ratio = msg
+        .staking_params
+        .liquid_ratio
+        .unwrap_or_else(Decimal::zero);
+    if liquid_ratio > Decimal::one() {
+        return Err(ContractError::InvalidLiquidRatio {});
+    }
+    let reward_strategy = validate_reward_strategy(msg.staking_params.reward_strategy)?;
+
+    // by default the owner alone can switch validators, and an approver named twice
+    // still only approves once
+    let mut approvers = match msg.staking_params.validator_approvers {
+        Some(addrs) => addrs
+            .iter()
+            .map(|a| deps.api.addr_validate(a))
+            .collect::<StdResult<Vec<_>>>()?,
+        None => vec![info.sender.clone()],
+    };
+    approvers.sort();
+    approvers.dedup();
+    let threshold = msg
+        .staking_params
+        .validator_approval_threshold
+        .unwrap_or(approvers.len() as u64);
+    if threshold == 0 || threshold > approvers.len() as u64 {
+        return Err(ContractError::InvalidApprovalThreshold {});
+    }
+    VALIDATOR_APPROVERS.save(
+        deps.storage,
+        &Approvers {
+            addrs: approvers,
+            threshold,
+        },
+    )?;
+
+    // brackets must be sorted so each one covers a distinct slice of the unbond
+    let exit_tax_brackets = msg.staking_params.exit_tax_brackets.unwrap_or_default();
+    if exit_tax_brackets
+        .windows(2)
+        .any(|pair| pair[0].threshold >= pair[1].threshold)
+        || exit_tax_brackets.iter().any(|b| b.rate > Decimal::one())
+    {
+        return Err(ContractError::InvalidTaxBracket {});
+    }
 
     // store token info using nested cw20-base format
     let data = TokenInfoWithMeta {
@@ -67,13 +277,59 @@ pub fn instantiate(
             total_supply: Uint128::new(0),
             // set self as minter, so we can properly execute mint and burn
             mint: Some(MinterData {
-                minter: env.contract.address,
-                cap: None,
+                minter: env.contract.address.clone(),
+                cap: msg.max_supply,
             }),
         },
     };
     TOKEN_INFO_WITH_META.save(deps.storage, &data)?;
 
+    let creator = match msg.creator_address {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender.clone(),
+    };
+    CREATOR.save(deps.storage, &creator)?;
+
+    // the creator looks after marketing unless someone else is named
+    let (project, description, marketing, logo) = match msg.marketing {
+        Some(m) => (m.project, m.description, m.marketing, m.logo),
+        None => (None, None, None, None),
+    };
+    let marketing = match marketing {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => creator,
+    };
+    MARKETING_INFO.save(
+        deps.storage,
+        &MarketingInfoResponse {
+            project,
+            description,
+            marketing: Some(marketing.clone()),
+            logo: None,
+        },
+    )?;
+    if let Some(logo) = logo {
+        // uploading as the marketing address validates the logo as cw20-base does
+        let sub_info = MessageInfo {
+            sender: marketing,
+            funds: vec![],
+        };
+        execute_upload_logo(deps.branch(), env.clone(), sub_info, logo)?;
+    }
+
+    validate_unbonding_period(&deps.querier, &msg.staking_params.unbonding_period)?;
+    if let Some(epoch) = &msg.staking_params.unbond_epoch {
+        validate_unbond_epoch(epoch, &msg.staking_params.unbonding_period)?;
+    }
+    if let Some(expiry) = &msg.staking_params.claim_expiry {
+        validate_claim_expiry(expiry, &msg.staking_params.unbonding_period)?;
+    }
+    let claim_recovery = msg
+        .staking_params
+        .claim_recovery_address
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
     // marshal data for investment info
     // this denom should be the reserve denom
     let reserve_denom = deps.querier.query_bonded_denom()?;
@@ -81,20 +337,122 @@ pub fn instantiate(
     let investment_info = InvestmentInfo {
         owner: info.sender,
         exit_tax: msg.staking_params.exit_tax,
+        exit_tax_brackets,
         unbonding_period: msg.staking_params.unbonding_period,
         bond_denom: String::from(&reserve_denom),
         validator: msg.staking_params.validator,
+        allowed_validators,
+        target_weights,
         min_withdrawal: msg.staking_params.min_withdrawal,
+        reward_skim,
+        bond_tolerance: msg.staking_params.bond_tolerance.unwrap_or_default(),
+        max_exit_tax,
+        instant_unbond_penalty,
+        liquid_ratio,
+        claim_incentive,
+        fallback_validators,
+        unbond_epoch: msg.staking_params.unbond_epoch,
+        exit_tax_payout: msg
+            .staking_params
+            .exit_tax_payout
+            .unwrap_or(ExitTaxPayout::Mint {}),
+        reward_strategy,
+        claim_expiry: msg.staking_params.claim_expiry,
+        claim_recovery,
     };
     INVESTMENT.save(deps.storage, &investment_info)?;
 
+    // a cw20 reserve is tracked by its address where the native denom would be
+    let reserve_denom = match (msg.reserve_token, msg.reserve_denom) {
+        (Some(token), _) => {
+            let token = deps.api.addr_validate(&token)?;
+            RESERVE_TOKEN.save(deps.storage, &token)?;
+            token.into_string()
+        }
+        (None, Some(denom)) => {
+            if let Some(trace) = query_denom_trace(&deps.querier, &denom)? {
+                RESERVE_TRACE.save(deps.storage, &trace)?;
+            }
+            denom
+        }
+        (None, None) => reserve_denom,
+    };
     let places = DecimalPlaces::new(msg.decimals, msg.reserve_decimals);
     let supply = CurveState::new(reserve_denom, places);
     CURVE_STATE.save(deps.storage, &supply)?;
 
+    // the opening price, so heights before the first trade can be looked up
+    let curve = curve_checker(&msg.curve_type, deps.api, deps.querier)?(places);
+    checkpoint_spot_price(deps.storage, &env.block, curve.spot_price(supply.supply))?;
     CURVE_TYPE.save(deps.storage, &msg.curve_type)?;
+    if let Some(auction) = msg.launch {
+        start_launch(deps.storage, &env.block, auction, curve.as_ref())?;
+    }
+
+    let keeper_fee = msg.keeper_fee.unwrap_or_else(default_keeper_fee);
+    if keeper_fee > Decimal::one() {
+        return Err(ContractError::InvalidKeeperFee {});
+    }
+    KEEPER_FEE.save(deps.storage, &keeper_fee)?;
+    if let Some(fee_config) = msg.fee_config {
+        let fee_config = validate_fee_config(deps.as_ref(), fee_config)?;
+        FEE_CONFIG.save(deps.storage, &fee_config)?;
+    }
+    if let Some(config) = msg.creator_escrow {
+        start_creator_escrow(deps.storage, config)?;
+    }
+    if let Some(allocation) = msg.creator_allocation {
+        start_creator_vesting(deps.branch(), &env, allocation)?;
+    }
+    if let Some(spread) = msg.spread {
+        if spread >= Decimal::one() {
+            return Err(ContractError::InvalidSpread {});
+        }
+        SPREAD.save(deps.storage, &spread)?;
+    }
+    if let Some(cooldown) = msg.trade_cooldown {
+        TRADE_COOLDOWN.save(deps.storage, &cooldown)?;
+    }
+    if let Some(limits) = msg.unbond_limits {
+        validate_unbond_limits(&limits)?;
+        UNBOND_LIMITS.save(deps.storage, &limits)?;
+    }
+    if let Some(purchase_only) = msg.purchase_only {
+        PURCHASE_ONLY.save(deps.storage, &purchase_only)?;
+    }
+    if let Some(bps) = msg.royalty_bps {
+        validate_royalty(bps)?;
+        ROYALTY_BPS.save(deps.storage, &bps)?;
+    }
+    ESCROWED_RESERVE.save(deps.storage, &Uint128::zero())?;
+    RESERVE_DONATIONS.save(deps.storage, &Uint128::zero())?;
+
+    if let Some(start) = msg.sale_start {
+        SALE_START.save(deps.storage, &start)?;
+    }
+    if let Some(end) = msg.sale_end {
+        SALE_END.save(deps.storage, &end)?;
+    }
+    if let Some(goal) = msg.funding_goal {
+        if goal.amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        FUNDING_GOAL.save(deps.storage, &goal)?;
+    }
+
+    if let Some(router) = msg.swap_router {
+        SWAP_ROUTER.save(deps.storage, &deps.api.addr_validate(&router)?)?;
+    }
+    if let Some(receipts) = msg.nft_receipts {
+        NFT_RECEIPTS.save(deps.storage, &validate_nft_receipts(deps.api, receipts)?)?;
+    }
 
-    Ok(Response::default())
+    let mut res = Response::default();
+    if let Some(registry) = msg.registry {
+        let registry = deps.api.addr_validate(&registry)?;
+        res = res.add_message(register_with(deps.storage, &env, &registry)?);
+    }
+    Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -107,74 +465,337 @@ pub fn execute(
     // default implementation stores curve info as enum, you can do something else in a derived
     // contract and just pass in your custom curve to do_execute
     let curve_type = CURVE_TYPE.load(deps.storage)?;
-    let curve_fn = curve_type.to_curve_fn();
+    let curve_fn = curve_type.to_curve_fn(deps.querier);
     do_execute(deps, env, info, msg, curve_fn)
 }
 
 /// We pull out logic here, so we can import this from another contract and set a different Curve.
 /// This contacts sets a curve with an enum in InstantiateMsg and stored in state, but you may want
 /// to use custom math not included - make this easily reusable
-pub fn do_execute(
-    deps: DepsMut,
+pub fn do_execute<C: CustomMsg>(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
     curve_fn: CurveFn,
-) -> Result<Response, ContractError> {
+) -> Result<Response<C>, ContractError> {
+    if is_pausable(&msg) {
+        assert_not_paused(deps.storage)?;
+    }
+
     match msg {
         // we override these from cw20
         // they are defined in bonding.rs
-        ExecuteMsg::Buy {} => execute_buy(deps, env, info, curve_fn),
-        ExecuteMsg::Burn { amount } => Ok(execute_sell(deps, env, info, curve_fn, amount)?),
-        ExecuteMsg::BurnFrom { owner, amount } => {
-            Ok(execute_sell_from(deps, env, info, curve_fn, owner, amount)?)
+        ExecuteMsg::Buy {
+            min_supply_out,
+            recipient,
+        } => {
+            assert_split_purchases(deps.storage)?;
+            execute_buy(deps, env, info, curve_fn, min_supply_out, recipient)
         }
+        ExecuteMsg::BuyExact { tokens } => execute_buy_exact(deps, env, info, curve_fn, tokens),
+        ExecuteMsg::BuyWithSwap {
+            min_reserve_out,
+            min_tokens_out,
+        } => execute_buy_with_swap(deps, env, info, min_reserve_out, min_tokens_out),
+        ExecuteMsg::_BuySwapped {
+            buyer,
+            balance_before,
+            min_tokens_out,
+        } => _buy_swapped(
+            deps,
+            env,
+            info,
+            curve_fn,
+            buyer,
+            balance_before,
+            min_tokens_out,
+        ),
+        ExecuteMsg::SellExactReserve {
+            reserve_out,
+            max_supply_in,
+        } => execute_sell_exact_reserve(deps, env, info, curve_fn, reserve_out, max_supply_in),
+        ExecuteMsg::Burn {
+            amount,
+            min_reserve_out,
+        } => Ok(execute_sell(
+            deps,
+            env,
+            info,
+            curve_fn,
+            amount,
+            min_reserve_out,
+        )?),
+        ExecuteMsg::BurnFrom {
+            owner,
+            amount,
+            min_reserve_out,
+        } => Ok(execute_sell_from(
+            deps,
+            env,
+            info,
+            curve_fn,
+            owner,
+            amount,
+            min_reserve_out,
+        )?),
+        ExecuteMsg::BurnToDenom {
+            amount,
+            target_denom,
+            min_out,
+        } => execute_burn_to_denom(deps, env, info, curve_fn, amount, target_denom, min_out),
 
         // this is the staking logic
-        ExecuteMsg::Bond {} => bond(deps, env, info, curve_fn),
+        ExecuteMsg::Purchase {} => purchase(deps, env, info, curve_fn),
+        ExecuteMsg::Bond {
+            validator,
+            recipient,
+        } => {
+            assert_split_purchases(deps.storage)?;
+            bond(deps, env, info, curve_fn, validator, recipient)
+        }
         ExecuteMsg::Unbond { amount } => unbond(deps, env, info, curve_fn, amount),
+        ExecuteMsg::UnbondFrom {
+            owner,
+            amount,
+            claim_to_spender,
+        } => unbond_from(
+            deps,
+            env,
+            info,
+            curve_fn,
+            owner,
+            amount,
+            claim_to_spender.unwrap_or(false),
+        ),
+        ExecuteMsg::Reconcile {} => reconcile(deps, env, info),
         ExecuteMsg::Claim {} => claim(deps, env, info),
-        ExecuteMsg::Reinvest {} => reinvest(deps, env, info),
+        ExecuteMsg::ClaimFor { address } => claim_for(deps, env, info, address),
+        ExecuteMsg::ClaimRewards {} => claim_rewards(deps, env, info),
+        ExecuteMsg::SweepClaims { limit, start_after } => {
+            sweep_claims(deps, env, info, limit, start_after)
+        }
+        ExecuteMsg::InitiateWindDown {} => initiate_wind_down(deps, env, info),
+        ExecuteMsg::Settle {} => settle(deps, env, info),
+        ExecuteMsg::RecycleExpiredClaims { limit } => {
+            recycle_expired_claims(deps, env, info, limit, curve_fn)
+        }
+        ExecuteMsg::ProcessEpoch {} => process_epoch(deps, env, info),
+        ExecuteMsg::TransferClaim { recipient, amount } => {
+            transfer_claim(deps, info, recipient, amount)
+        }
+        ExecuteMsg::Rebalance {} => rebalance(deps, env, info),
+        ExecuteMsg::Reinvest {} => reinvest(deps, env),
+        ExecuteMsg::SetRewardWithdrawAddress { address } => {
+            set_reward_withdraw_address(deps, env, info, address)
+        }
+        ExecuteMsg::RebondAllTokens { validator_address } => {
+            rebond_all_tokens(deps, env, info, validator_address)
+        }
+        ExecuteMsg::Failover {} => failover(deps, env, info),
+        ExecuteMsg::WithdrawRewardsOnly {} => withdraw_rewards_only(deps, info),
+        ExecuteMsg::WithdrawFundingPool { amount } => withdraw_funding_pool(deps, info, amount),
+        ExecuteMsg::UpdateMetadata {
+            external_permalink_uri,
+            creator,
+            description,
+            asset_uri,
+        } => update_metadata(
+            deps,
+            info,
+            external_permalink_uri,
+            creator,
+            description,
+            asset_uri,
+        ),
+        ExecuteMsg::LockMetadata {} => lock_metadata(deps, info),
+        ExecuteMsg::SetRoyalty { bps } => set_royalty(deps, info, bps),
+        ExecuteMsg::PlaceBuyOrder { max_spot_price } => place_buy_order(deps, info, max_spot_price),
+        ExecuteMsg::CancelBuyOrder { id } => cancel_buy_order(deps, info, id),
+        ExecuteMsg::ExecuteBuyOrder { id } => execute_buy_order(deps, env, info, curve_fn, id),
+        ExecuteMsg::CreateDca {
+            amount_per_period,
+            period,
+            deposits,
+        } => create_dca(deps, env, info, amount_per_period, period, deposits),
+        ExecuteMsg::ExecuteDca { id } => execute_dca(deps, env, info, curve_fn, id),
+        ExecuteMsg::CancelDca { id } => cancel_dca(deps, info, id),
+        ExecuteMsg::CollectFees {} => collect_fees(deps, info),
+        ExecuteMsg::UpdateFeeExemptions { add, remove } => {
+            update_fee_exemptions(deps, info, add, remove)
+        }
+        ExecuteMsg::Freeze { address } => freeze(deps, info, address),
+        ExecuteMsg::Unfreeze { address } => unfreeze(deps, info, address),
+        ExecuteMsg::ReleaseMilestone { milestone, poll_id } => {
+            release_milestone(deps, env, info, milestone, poll_id)
+        }
+        ExecuteMsg::WithdrawOwnerFees {} => withdraw_owner_fees(deps, env, info),
+        ExecuteMsg::UpdatePayees { payees } => update_payees(deps, info, payees),
+        ExecuteMsg::ClaimVested {} => claim_vested(deps, env, info),
+        ExecuteMsg::CommitBuy { hash } => commit_buy(deps, env, info, hash),
+        ExecuteMsg::RevealBuy {
+            salt,
+            min_supply_out,
+        } => reveal_buy(deps, env, info, curve_fn, salt, min_supply_out),
+        ExecuteMsg::CancelBuyCommitment {} => cancel_buy_commitment(deps, info),
+        ExecuteMsg::LaunchBid {} => launch_bid(deps, env, info),
+        ExecuteMsg::SettleLaunch {} => settle_launch(deps, env, info, curve_fn),
+        ExecuteMsg::ClaimLaunchTokens {} => claim_launch_tokens(deps, env, info),
+        ExecuteMsg::PlaceSellOrder {
+            amount,
+            min_spot_price,
+            expires,
+        } => place_sell_order(deps, env, info, amount, min_spot_price, expires),
+        ExecuteMsg::CancelSellOrder { id } => cancel_sell_order(deps, env, info, id),
+        ExecuteMsg::ExecuteSellOrder { id } => execute_sell_order(deps, env, info, curve_fn, id),
+        ExecuteMsg::Donate {} => donate(deps, info),
+        ExecuteMsg::BurnWithoutRefund { amount } => {
+            burn_without_refund(deps, env, info, curve_fn, amount)
+        }
+        ExecuteMsg::DonateToReserve {} => donate_to_reserve(deps, env, info, curve_fn),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, curve_fn, msg),
+        ExecuteMsg::WithdrawNativeDonation { denom, amount } => {
+            withdraw_native_donation(deps, env, info, denom, amount)
+        }
+        ExecuteMsg::WithdrawCw20Donation { token, amount } => {
+            withdraw_cw20_donation(deps, info, token, amount)
+        }
+        ExecuteMsg::Sweep { denom } => sweep(deps, env, info, denom),
+        ExecuteMsg::RecoverCw20 {
+            token,
+            amount,
+            recipient,
+        } => recover_cw20(deps, env, info, token, amount, recipient),
+        ExecuteMsg::UpdateMarketing {
+            project,
+            description,
+            marketing,
+        } => Ok(lift_response(execute_update_marketing(
+            deps,
+            env,
+            info,
+            project,
+            description,
+            marketing,
+        )?)?),
+        ExecuteMsg::UploadLogo(logo) => {
+            Ok(lift_response(execute_upload_logo(deps, env, info, logo)?)?)
+        }
+        ExecuteMsg::TransferCreator { new_creator } => transfer_creator(deps, info, new_creator),
+        ExecuteMsg::AcceptCreator {} => accept_creator(deps, info),
+        ExecuteMsg::UpdateStakingParams {
+            exit_tax,
+            min_withdrawal,
+        } => update_staking_params(deps, info, exit_tax, min_withdrawal),
+        ExecuteMsg::TransferOwnership { new_owner } => transfer_ownership(deps, info, new_owner),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::InstantUnbond {
+            amount,
+            max_penalty,
+        } => instant_unbond(deps, env, info, curve_fn, amount, max_penalty),
+        ExecuteMsg::Pause {} => pause(deps, info),
+        ExecuteMsg::Unpause {} => unpause(deps, info),
+        ExecuteMsg::SetHalts { buys, sells } => set_halts(deps, info, buys, sells),
+        ExecuteMsg::SetPresale { end, whitelist } => set_presale(deps, info, end, whitelist),
+        ExecuteMsg::CreatePoll {
+            description,
+            options,
+            voting_period,
+            milestone,
+        } => create_poll(
+            deps,
+            env,
+            info,
+            description,
+            options,
+            voting_period,
+            milestone,
+        ),
+        ExecuteMsg::CastVote { poll_id, option } => cast_vote(deps, env, info, poll_id, option),
+        ExecuteMsg::SetPatronTiers { tiers } => set_patron_tiers(deps, info, tiers),
+        ExecuteMsg::SetNftReceipts { receipts } => set_nft_receipts(deps, info, receipts),
+        ExecuteMsg::AddHook { addr } => add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => remove_hook(deps, info, addr),
+        ExecuteMsg::ProposeValidatorChange { validator } => {
+            propose_validator_change(deps, env, info, validator)
+        }
+        ExecuteMsg::ApproveValidatorChange { validator } => {
+            approve_validator_change(deps, env, info, validator)
+        }
         ExecuteMsg::_BondAllTokens {} => _bond_all_tokens(deps, env, info, curve_fn),
 
         // these all come from cw20-base to implement the cw20 standard
+        // and we keep the balance snapshots in step with what they move
         ExecuteMsg::Transfer { recipient, amount } => {
-            Ok(execute_transfer(deps, env, info, recipient, amount)?)
+            let accounts = [info.sender.to_string(), recipient.clone()];
+            assert_not_frozen(deps.storage, deps.api, &accounts)?;
+            settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+            let res = execute_transfer(deps.branch(), env.clone(), info, recipient, amount)?;
+            snapshot_balances(deps.storage, deps.api, env.block.height, &accounts)?;
+            Ok(lift_response(res)?)
         }
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
-        } => Ok(execute_send(deps, env, info, contract, amount, msg)?),
+        } => {
+            let accounts = [info.sender.to_string(), contract.clone()];
+            assert_not_frozen(deps.storage, deps.api, &accounts)?;
+            settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+            let res = execute_send(deps.branch(), env.clone(), info, contract, amount, msg)?;
+            snapshot_balances(deps.storage, deps.api, env.block.height, &accounts)?;
+            Ok(lift_response(res)?)
+        }
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
             expires,
-        } => Ok(execute_increase_allowance(
+        } => Ok(lift_response(execute_increase_allowance(
             deps, env, info, spender, amount, expires,
-        )?),
+        )?)?),
         ExecuteMsg::DecreaseAllowance {
             spender,
             amount,
             expires,
-        } => Ok(execute_decrease_allowance(
+        } => Ok(lift_response(execute_decrease_allowance(
             deps, env, info, spender, amount, expires,
-        )?),
+        )?)?),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
             amount,
-        } => Ok(execute_transfer_from(
-            deps, env, info, owner, recipient, amount,
-        )?),
+        } => {
+            let accounts = [owner.clone(), recipient.clone()];
+            assert_not_frozen(deps.storage, deps.api, &accounts)?;
+            assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+            settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+            let res =
+                execute_transfer_from(deps.branch(), env.clone(), info, owner, recipient, amount)?;
+            snapshot_balances(deps.storage, deps.api, env.block.height, &accounts)?;
+            Ok(lift_response(res)?)
+        }
         ExecuteMsg::SendFrom {
             owner,
             contract,
             amount,
             msg,
-        } => Ok(execute_send_from(
-            deps, env, info, owner, contract, amount, msg,
-        )?),
+        } => {
+            let accounts = [owner.clone(), contract.clone()];
+            assert_not_frozen(deps.storage, deps.api, &accounts)?;
+            assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+            settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+            let res = execute_send_from(
+                deps.branch(),
+                env.clone(),
+                info,
+                owner,
+                contract,
+                amount,
+                msg,
+            )?;
+            snapshot_balances(deps.storage, deps.api, env.block.height, &accounts)?;
+            Ok(lift_response(res)?)
+        }
     }
 }
 
@@ -183,10 +804,22 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     // default implementation stores curve info as enum, you can do something else in a derived
     // contract and just pass in your custom curve to do_execute
     let curve_type = CURVE_TYPE.load(deps.storage)?;
-    let curve_fn = curve_type.to_curve_fn();
+    let curve_fn = curve_type.to_curve_fn(deps.querier);
     do_query(deps, env, msg, curve_fn)
 }
 
+/// the plain cw20 shape, for clients that know nothing of the metadata
+pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
+    let info = TOKEN_INFO_WITH_META.load(deps.storage)?.token_info;
+    let res = TokenInfoResponse {
+        name: info.name,
+        symbol: info.symbol,
+        decimals: info.decimals,
+        total_supply: info.total_supply,
+    };
+    Ok(res)
+}
+
 /// a light adaptation of the code in cw20-base to include meta
 pub fn query_token_info_with_meta(deps: Deps) -> StdResult<TokenInfoResponseWithMeta> {
     let info = TOKEN_INFO_WITH_META.load(deps.storage)?;
@@ -212,27 +845,270 @@ pub fn query_token_info_with_meta(deps: Deps) -> StdResult<TokenInfoResponseWith
 /// We pull out logic here, so we can import this from another contract and set a different Curve.
 /// This contacts sets a curve with an enum in InstantitateMsg and stored in state, but you may want
 /// to use custom math not included - make this easily reusable
-pub fn do_query(deps: Deps, _env: Env, msg: QueryMsg, curve_fn: CurveFn) -> StdResult<Binary> {
+pub fn do_query(deps: Deps, env: Env, msg: QueryMsg, curve_fn: CurveFn) -> StdResult<Binary> {
     match msg {
         // // custom queries for staking
-        QueryMsg::Claims { address } => {
-            to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
+        QueryMsg::Claims {
+            address,
+            start_after,
+            limit,
+            only_mature,
+        } => to_binary(&query_claims(
+            deps,
+            env,
+            address,
+            start_after,
+            limit,
+            only_mature,
+        )?),
+        QueryMsg::ClaimEstimates {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_claim_estimates(
+            deps,
+            env,
+            address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Investment {} => to_binary(&query_investment(deps, curve_fn)?),
+        QueryMsg::Delegations {} => to_binary(&query_delegations(deps)?),
+        QueryMsg::LiquidBuffer {} => to_binary(&query_liquid_buffer(deps, env)?),
+        QueryMsg::ClaimableRewards { address } => {
+            to_binary(&query_claimable_rewards(deps, env, address)?)
+        }
+        QueryMsg::TotalClaims {} => to_binary(&query_total_claims(deps, env)?),
+        QueryMsg::UnbondEpoch {} => to_binary(&query_unbond_epoch(deps, env)?),
+        QueryMsg::UnbondCapacity {} => to_binary(&query_unbond_capacity(deps, env)?),
+        QueryMsg::AllClaims { start_after, limit } => {
+            to_binary(&query_all_claims(deps, start_after, limit)?)
+        }
+        QueryMsg::ReinvestPreview {} => to_binary(&query_reinvest_preview(deps, env)?),
+        QueryMsg::ValidatorChange {} => to_binary(&query_validator_change(deps)?),
+        QueryMsg::ValidatorHealth {} => to_binary(&query_validator_health(deps)?),
+        QueryMsg::Health {} => to_binary(&query_health(deps, env)?),
+        QueryMsg::BuyCommitment { address } => to_binary(&query_buy_commitment(deps, address)?),
+        QueryMsg::Launch {} => to_binary(&query_launch(deps, env)?),
+        QueryMsg::BuyOrders { start_after, limit } => {
+            to_binary(&query_buy_orders(deps, start_after, limit)?)
+        }
+        QueryMsg::SellOrders { start_after, limit } => {
+            to_binary(&query_sell_orders(deps, start_after, limit)?)
+        }
+        QueryMsg::DcaPlans {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_dca_plans(deps, owner, start_after, limit)?),
+        QueryMsg::CreatorEscrow {} => to_binary(&query_creator_escrow(deps)?),
+        QueryMsg::OwnerFees {} => to_binary(&query_owner_fees(deps, env)?),
+        QueryMsg::Payees {} => to_binary(&query_payees(deps)?),
+        QueryMsg::CreatorVesting {} => to_binary(&query_creator_vesting(deps, env)?),
+        QueryMsg::ProtocolFees {} => to_binary(&query_protocol_fees(deps)?),
+        QueryMsg::FeeExemptions { start_after, limit } => {
+            to_binary(&query_fee_exemptions(deps, start_after, limit)?)
+        }
+        QueryMsg::Frozen { start_after, limit } => {
+            to_binary(&query_frozen(deps, start_after, limit)?)
+        }
+        QueryMsg::Treasury {} => to_binary(&query_treasury(deps, env)?),
+        QueryMsg::Creator {} => to_binary(&query_creator(deps)?),
+        QueryMsg::RoyaltyInfo { sale_price } => to_binary(&query_royalty_info(deps, sale_price)?),
+        QueryMsg::CheckRoyalties {} => to_binary(&query_check_royalties(deps)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
+        QueryMsg::WindDown {} => to_binary(&query_wind_down(deps, env)?),
+        QueryMsg::Presale { address } => to_binary(&query_presale(deps, env, address)?),
+        QueryMsg::Poll { poll_id } => to_binary(&query_poll(deps, env, poll_id)?),
+        QueryMsg::Polls { start_after, limit } => {
+            to_binary(&query_polls(deps, env, start_after, limit)?)
         }
-        QueryMsg::Investment {} => to_binary(&query_investment(deps)?),
+        QueryMsg::Hooks {} => to_binary(&query_hooks(deps)?),
         // custom queries for bonding
         QueryMsg::CurveInfo {} => to_binary(&query_curve_info(deps, curve_fn)?),
+        QueryMsg::SpotPriceAtHeight { height } => {
+            to_binary(&query_spot_price_at_height(deps, height)?)
+        }
+        QueryMsg::Twap { window_seconds } => to_binary(&query_twap(deps, env, window_seconds)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::PatronTier { address } => to_binary(&query_patron_tier(deps, address)?),
+        QueryMsg::PatronTiers {} => to_binary(&query_patron_tiers(deps)?),
+        QueryMsg::NftReceipts {} => to_binary(&query_nft_receipts(deps)?),
+        QueryMsg::RateHistory { limit } => to_binary(&query_rate_history(deps, limit)?),
+        QueryMsg::FundingProgress {} => to_binary(&query_funding_progress(deps, env)?),
+        QueryMsg::Capabilities {} => to_binary(&query_capabilities(deps, env)?),
         // inherited from cw20-base
-        QueryMsg::TokenInfo {} => to_binary(&query_token_info_with_meta(deps)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::TokenInfoWithMeta {} => to_binary(&query_token_info_with_meta(deps)?),
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::BalanceAt { address, height } => {
+            to_binary(&query_balance_at(deps, address, height)?)
+        }
+        QueryMsg::TotalSupplyAt { height } => to_binary(&query_total_supply_at(deps, height)?),
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
+        QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let curve_type = CURVE_TYPE.load(deps.storage)?;
+    let curve_fn = curve_type.to_curve_fn(deps.querier);
+    do_reply(deps, env, msg, curve_fn)
+}
+
+/// reply handling for a derived contract whose responses carry its chain's custom messages,
+/// priced with the same curve it passes to do_execute
+pub fn do_reply<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+    curve_fn: CurveFn,
+) -> Result<Response<C>, ContractError> {
+    match (msg.id, msg.result) {
+        (BOND_REPLY_ID, ContractResult::Err(err)) => bond_failed(deps, env, err, curve_fn),
+        (RECEIPT_REPLY_ID, ContractResult::Err(err)) => Ok(receipt_failed(err)),
+        (id, _) => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    do_sudo(deps, env, msg)
+}
+
+/// as do_reply, for sudo
+pub fn do_sudo<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<C>, ContractError> {
+    match msg {
+        SudoMsg::Reinvest {} => reinvest(deps, env),
+        SudoMsg::SwitchValidator { validator } => sudo_switch_validator(deps, env, validator),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    do_migrate(deps, env, msg)
+}
+
+/// as do_reply, for migrate
+pub fn do_migrate<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response<C>, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            contract: stored.contract,
+            version: stored.version,
+        });
+    }
+    match stored.version.as_str() {
+        CONTRACT_VERSION => {}
+        "0.1.0" => migrate_from_v0_1(deps.branch(), &env)?,
+        _ => {
+            return Err(ContractError::CannotMigrate {
+                contract: stored.contract,
+                version: stored.version,
+            })
+        }
     }
+    // claims used to be one list per address, whichever version we came from
+    migrate_legacy_claims(deps.storage)?;
+    // the staked balance used to be read off the reserve
+    migrate_staked_balance(deps.storage)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+    Ok(res)
+}
+
+// v0.1.0 bonded everything to one validator and had none of the later optional features,
+// so everything added since is filled in as if instantiated without them
+fn migrate_from_v0_1(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    let old = INVESTMENT_V0_1.load(deps.storage)?;
+    let invest = InvestmentInfo {
+        owner: old.owner.clone(),
+        bond_denom: old.bond_denom,
+        unbonding_period: old.unbonding_period,
+        exit_tax: old.exit_tax,
+        exit_tax_brackets: vec![],
+        validator: old.validator.clone(),
+        allowed_validators: vec![],
+        target_weights: vec![],
+        min_withdrawal: old.min_withdrawal,
+        reward_skim: Decimal::zero(),
+        bond_tolerance: Uint128::zero(),
+        max_exit_tax: old.exit_tax,
+        instant_unbond_penalty: Decimal::percent(5),
+        liquid_ratio: Decimal::zero(),
+        claim_incentive: Decimal::zero(),
+        fallback_validators: vec![],
+        unbond_epoch: None,
+        exit_tax_payout: ExitTaxPayout::Mint {},
+        reward_strategy: RewardStrategy::Compound {},
+        claim_expiry: None,
+        claim_recovery: None,
+    };
+    INVESTMENT.save(deps.storage, &invest)?;
+
+    // all stake so far sits with the one validator
+    let bonded = get_bonded(&deps.querier, &env.contract.address)?;
+    DELEGATIONS.save(deps.storage, &old.validator, &bonded)?;
+
+    VALIDATOR_APPROVERS.save(
+        deps.storage,
+        &Approvers {
+            addrs: vec![old.owner.clone()],
+            threshold: 1,
+        },
+    )?;
+    CREATOR.save(deps.storage, &old.owner)?;
+    MARKETING_INFO.save(
+        deps.storage,
+        &MarketingInfoResponse {
+            marketing: Some(old.owner.clone()),
+            ..MarketingInfoResponse::default()
+        },
+    )?;
+    KEEPER_FEE.save(deps.storage, &default_keeper_fee())?;
+    ESCROWED_RESERVE.save(deps.storage, &Uint128::zero())?;
+    RESERVE_DONATIONS.save(deps.storage, &Uint128::zero())?;
+    // balances are seeded as they are first touched
+    seed_supply(deps.storage)?;
+
+    // start the price history from here
+    let curve_state = CURVE_STATE.load(deps.storage)?;
+    let curve = CURVE_TYPE.load(deps.storage)?.to_curve_fn(deps.querier)(curve_state.decimals);
+    checkpoint_spot_price(
+        deps.storage,
+        &env.block,
+        curve.spot_price(curve_state.supply),
+    )?;
+    Ok(())
 }
 
 pub fn query_curve_info(deps: Deps, curve_fn: CurveFn) -> StdResult<CurveInfoResponse> {
     let CurveState {
-        reserve,
+        curve_reserve: reserve,
         supply,
         reserve_denom,
         decimals,
@@ -249,6 +1125,129 @@ pub fn query_curve_info(deps: Deps, curve_fn: CurveFn) -> StdResult<CurveInfoRes
         spot_price,
         reserve_denom,
         claims,
+        staked_balance: staked_balance(deps.storage)?,
+        reserve_trace: RESERVE_TRACE.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_capabilities(deps: Deps, env: Env) -> StdResult<CapabilitiesResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let approvers = VALIDATOR_APPROVERS.load(deps.storage)?;
+    let cw20_reserve = RESERVE_TOKEN.may_load(deps.storage)?.is_some();
+    let winding_down = WIND_DOWN.may_load(deps.storage)?.is_some();
+    let presale_end = PRESALE_END.may_load(deps.storage)?;
+    let staked_reserve =
+        !cw20_reserve && CURVE_STATE.load(deps.storage)?.reserve_denom == invest.bond_denom;
+    // the marketing address always defaults to the creator, so only count what was filled in
+    let marketing = matches!(
+        MARKETING_INFO.may_load(deps.storage)?,
+        Some(m) if m.project.is_some() || m.description.is_some() || m.logo.is_some()
+    );
+    Ok(CapabilitiesResponse {
+        multi_validator: !invest.allowed_validators.is_empty(),
+        rebalance: !invest.target_weights.is_empty(),
+        reward_skim: !invest.reward_skim.is_zero(),
+        validator_quorum: approvers.threshold > 1,
+        exit_tax_brackets: !invest.exit_tax_brackets.is_empty(),
+        swap_router: SWAP_ROUTER.may_load(deps.storage)?.is_some(),
+        funding_goal: FUNDING_GOAL.may_load(deps.storage)?.is_some(),
+        // buy orders escrow a native reserve
+        limit_orders: !cw20_reserve && !winding_down,
+        donations: !winding_down,
+        marketing,
+        hooks: !query_hooks(deps)?.hooks.is_empty(),
+        presale: matches!(presale_end, Some(end) if !end.is_expired(&env.block)),
+        instant_unbond: staked_reserve && invest.instant_unbond_penalty < Decimal::one(),
+        cw20_reserve,
+    })
+}
+
+pub fn query_funding_progress(deps: Deps, env: Env) -> StdResult<FundingProgressResponse> {
+    let raised = CURVE_STATE.load(deps.storage)?.curve_reserve;
+    let res = match FUNDING_GOAL.may_load(deps.storage)? {
+        Some(goal) => {
+            let phase = if raised >= goal.amount && goal.closes_curve.unwrap_or_default() {
+                FundingPhase::Closed
+            } else if raised >= goal.amount {
+                FundingPhase::Open
+            } else if matches!(goal.deadline, Some(d) if d.is_expired(&env.block)) {
+                FundingPhase::Refund
+            } else {
+                FundingPhase::Hatch
+            };
+            FundingProgressResponse {
+                goal: Some(goal.amount),
+                raised,
+                percent_complete: Some(Decimal::from_ratio(raised, goal.amount)),
+                deadline: goal.deadline,
+                phase,
+            }
+        }
+        None => FundingProgressResponse {
+            goal: None,
+            raised,
+            percent_complete: None,
+            deadline: None,
+            phase: FundingPhase::Open,
+        },
+    };
+    Ok(res)
+}
+
+pub fn query_spot_price_at_height(deps: Deps, height: u64) -> StdResult<SpotPriceAtHeightResponse> {
+    // the latest checkpoint at or before height is the price that held then
+    let (key, spot_price) = SPOT_PRICES
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive_int(height)),
+            Order::Descending,
+        )
+        .next()
+        .ok_or_else(|| StdError::not_found("spot price"))??;
+    let mut height_bytes = [0u8; 8];
+    height_bytes.copy_from_slice(&key);
+    Ok(SpotPriceAtHeightResponse {
+        height: u64::from_be_bytes(height_bytes),
+        spot_price,
+    })
+}
+
+pub fn query_twap(deps: Deps, env: Env, window_seconds: u64) -> StdResult<TwapResponse> {
+    let now = env.block.time.seconds();
+    let (key, first) = PRICE_OBSERVATIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .ok_or_else(|| StdError::not_found("price observation"))??;
+    let mut time_bytes = [0u8; 8];
+    time_bytes.copy_from_slice(&key);
+    let start = now
+        .saturating_sub(window_seconds)
+        .max(u64::from_be_bytes(time_bytes));
+
+    // nothing to average over yet, so the current price is all there is
+    if start >= now {
+        let latest = PRICE_OBSERVATIONS
+            .range(deps.storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(first.spot_price, |(_, observation)| observation.spot_price);
+        return Ok(TwapResponse {
+            twap: latest,
+            window_seconds: 0,
+        });
+    }
+
+    let cumulative_now = cumulative_price_at(deps.storage, now)?.unwrap_or_default();
+    let cumulative_start = cumulative_price_at(deps.storage, start)?.unwrap_or_default();
+    let elapsed = now - start;
+    let twap = Decimal::from_ratio(
+        cumulative_now - cumulative_start,
+        Uint128::from(elapsed) * Uint128::new(Decimal::one().denominator()),
+    );
+    Ok(TwapResponse {
+        twap,
+        window_seconds: elapsed,
     })
 }
 
@@ -256,21 +1255,46 @@ pub fn query_curve_info(deps: Deps, curve_fn: CurveFn) -> StdResult<CurveInfoRes
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::msg::StakingParams;
-    //use cw20_base::contract::query_token_info;
-    use cw_controllers::Claim;
+    use crate::commit::commitment_hash;
+    use crate::msg::{
+        BondcampRegistration, CreatorAllocation, CreatorEscrowConfig, Cw721ExecuteMsg, FeeConfig,
+        FundingGoal, LaunchAuction, Milestone, MilestoneUnlock, NftReceipts, PatronTier, Payee,
+        ReceiptMetadata, ReceiptMintMsg, ReceiveMsg, RegistryExecuteMsg, RouterExecuteMsg,
+        StakingParams, TaxBracket, TradeDirection, TradeHookMsg, UnbondLimits, ValidatorWeight,
+        VestingSchedule, WhitelistEntry,
+    };
+    use crate::proto::{encode_bytes, encode_varint};
+    use crate::query::{
+        CheckRoyaltiesResponse, ClaimEstimate, ClaimableRewardsResponse, DenomTrace,
+        InvestmentResponse, LiquidBufferResponse, PausedResponse, PresaleResponse, RateSnapshot,
+        ReinvestPreviewResponse, RoyaltyInfoResponse, StatsResponse, UnbondCapacityResponse,
+        UnbondEpochResponse, ValidatorDelegation, ValidatorHealthResponse,
+    };
+    use crate::state::{InvestmentInfoV0_1, CLAIMS, HOLDER_REWARDS, LEGACY_CLAIMS};
+    use cw_controllers::{Claim, HookError};
     use std::str::FromStr;
 
-    use crate::msg::CurveType;
+    use crate::msg::{
+        BaseCurveType, CurveQueryMsg, CurveReserveResponse, CurveSegment, CurveSpotPriceResponse,
+        CurveSupplyResponse, CurveType,
+    };
 
     use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_info, MockQuerier, MOCK_CONTRACT_ADDR,
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
     };
     use cosmwasm_std::{
-        coin, coins, Addr, BankMsg, Coin, CosmosMsg, Decimal, FullDelegation, OverflowError,
-        OverflowOperation, StakingMsg, StdError, SubMsg, Validator,
+        attr, coin, coins, from_binary, from_slice, Addr, BankMsg, Coin, ContractResult, CosmosMsg,
+        Decimal, DistributionMsg, Empty, Event, FullDelegation, OverflowError, OverflowOperation,
+        OwnedDeps, Querier, QuerierResult, QueryRequest, ReplyOn, StakingMsg, SubMsg, SystemResult,
+        Timestamp, Validator, WasmMsg, WasmQuery,
+    };
+    use cw0::{Duration, Expiration, PaymentError, DAY, HOUR};
+    use cw20::{
+        AllAccountsResponse, AllAllowancesResponse, Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg, Logo,
+        LogoInfo,
     };
-    use cw0::{Duration, PaymentError, DAY, HOUR};
+    use cw20_base::msg::InstantiateMarketingInfo;
 
     // DENOM = native token
     const DENOM: &str = "ustake";
@@ -298,14 +1322,51 @@ mod tests {
             name: "Windscale2Coin".to_string(),
             symbol: "WIND".to_string(),
             decimals,
-            // reserve_denom: DENOM.to_string(),
+            reserve_denom: None,
             reserve_decimals,
             curve_type,
+            swap_router: None,
+            registry: None,
+            marketing: None,
+            creator_address: None,
+            keeper_fee: None,
+            funding_goal: None,
+            max_supply: None,
+            sale_start: None,
+            sale_end: None,
+            reserve_token: None,
+            launch: None,
+            nft_receipts: None,
+            spread: None,
+            trade_cooldown: None,
+            unbond_limits: None,
+            purchase_only: None,
+            fee_config: None,
+            creator_escrow: None,
+            creator_allocation: None,
+            royalty_bps: None,
             staking_params: StakingParams {
                 validator: String::from(DEFAULT_VALIDATOR),
                 unbonding_period: DAY * 3,
                 exit_tax: Decimal::percent(tax_percent),
                 min_withdrawal: Uint128::new(min_withdrawal),
+                allowed_validators: None,
+                target_weights: None,
+                reward_skim: None,
+                validator_approvers: None,
+                validator_approval_threshold: None,
+                exit_tax_brackets: None,
+                bond_tolerance: None,
+                max_exit_tax: None,
+                instant_unbond_penalty: None,
+                liquid_ratio: None,
+                claim_incentive: None,
+                fallback_validators: None,
+                unbond_epoch: None,
+                exit_tax_payout: None,
+                reward_strategy: None,
+                claim_expiry: None,
+                claim_recovery_address: None,
             },
         }
     }
@@ -314,6 +1375,11 @@ mod tests {
         query_balance(deps, addr.into()).unwrap().balance
     }
 
+    fn get_investment(deps: Deps) -> StdResult<InvestmentResponse> {
+        let curve_fn = CURVE_TYPE.load(deps.storage)?.to_curve_fn(deps.querier);
+        query_investment(deps, curve_fn)
+    }
+
     fn setup_test(
         deps: DepsMut,
         asset_uri: Option<String>,
@@ -369,7 +1435,8 @@ mod tests {
         assert_eq!(token.token_info_response.total_supply, Uint128::new(0));
 
         // curve state is sensible
-        let state = query_curve_info(deps.as_ref(), curve_type.to_curve_fn()).unwrap();
+        let state =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
         assert_eq!(state.reserve, Uint128::new(0));
         assert_eq!(state.supply, Uint128::new(0));
         assert_eq!(state.reserve_denom.as_str(), DENOM);
@@ -415,7 +1482,8 @@ mod tests {
         assert_eq!(token.token_info_response.total_supply, Uint128::new(0));
 
         // curve state is sensible
-        let state = query_curve_info(deps.as_ref(), curve_type.to_curve_fn()).unwrap();
+        let state =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
         assert_eq!(state.reserve, Uint128::new(0));
         assert_eq!(state.supply, Uint128::new(0));
         assert_eq!(state.reserve_denom.as_str(), DENOM);
@@ -448,7 +1516,10 @@ mod tests {
 
         // succeeds with proper token (5 BTC = 5*10^8 satoshi)
         let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
-        let buy = ExecuteMsg::Buy {};
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
         execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
 
         // bob got 1000 EPOXY (10.00)
@@ -476,7 +1547,8 @@ mod tests {
         assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(1000));
 
         // check curve info updated
-        let curve = query_curve_info(deps.as_ref(), curve_type.to_curve_fn()).unwrap();
+        let curve =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
         assert_eq!(curve.reserve, Uint128::new(2_000_000_000));
         assert_eq!(curve.supply, Uint128::new(2000));
         assert_eq!(curve.spot_price, Decimal::percent(200));
@@ -506,7 +1578,10 @@ mod tests {
 
         // fails when no tokens sent
         let info = mock_info(INVESTOR, &[]);
-        let buy = ExecuteMsg::Buy {};
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
         let err = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap_err();
         assert_eq!(err, PaymentError::NoFunds {}.into());
 
@@ -540,7 +1615,10 @@ mod tests {
 
         // succeeds with proper token (20 BTC = 20*10^8 satoshi)
         let info = mock_info(INVESTOR, &coins(2_000_000_000, DENOM));
-        let buy = ExecuteMsg::Buy {};
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
         execute(deps.as_mut(), mock_env(), info, buy).unwrap();
 
         // bob got 2000 EPOXY (20.00)
@@ -550,6 +1628,7 @@ mod tests {
         let info = mock_info(INVESTOR, &[]);
         let burn = ExecuteMsg::Burn {
             amount: Uint128::new(3000),
+            min_reserve_out: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, burn).unwrap_err();
         assert_eq!(
@@ -565,6 +1644,7 @@ mod tests {
         let info = mock_info(INVESTOR, &[]);
         let burn = ExecuteMsg::Burn {
             amount: Uint128::new(1000),
+            min_reserve_out: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, burn).unwrap();
 
@@ -582,7 +1662,8 @@ mod tests {
         );
 
         // check curve info updated
-        let curve = query_curve_info(deps.as_ref(), curve_type.to_curve_fn()).unwrap();
+        let curve =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
         assert_eq!(curve.reserve, Uint128::new(500_000_000));
         assert_eq!(curve.supply, Uint128::new(1000));
         assert_eq!(curve.spot_price, Decimal::percent(100));
@@ -593,70 +1674,6517 @@ mod tests {
         assert_eq!(token.token_info_response.total_supply, Uint128::new(1000));
     }
 
-    //
-    //  ---- staking starts here ----
-    //
+    #[test]
+    fn buy_respects_min_supply_out() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
 
-    fn sample_validator(addr: &str) -> Validator {
-        Validator {
-            address: addr.into(),
-            commission: Decimal::percent(3),
-            max_commission: Decimal::percent(10),
-            max_change_rate: Decimal::percent(1),
-        }
-    }
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
 
-    fn set_validator(querier: &mut MockQuerier) {
-        querier.update_staking("ustake", &[sample_validator(DEFAULT_VALIDATOR)], &[]);
-    }
+        // 500_000_000 reserve mints 1000 tokens at the start of the curve
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let greedy = ExecuteMsg::Buy {
+            min_supply_out: Some(Uint128::new(1001)),
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), greedy).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SlippageExceeded {
+                amount: Uint128::new(1000),
+                min: Uint128::new(1001)
+            }
+        );
 
-    fn sample_delegation(addr: &str, amount: Coin) -> FullDelegation {
-        let can_redelegate = amount.clone();
-        let accumulated_rewards = coins(0, &amount.denom);
-        FullDelegation {
-            validator: addr.into(),
-            delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
-            amount,
-            can_redelegate,
-            accumulated_rewards,
-        }
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: Some(Uint128::new(1000)),
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(1000));
     }
 
-    fn set_delegation(querier: &mut MockQuerier, amount: u128, denom: &str) {
-        querier.update_staking(
-            "ustake",
-            &[sample_validator(DEFAULT_VALIDATOR)],
-            &[sample_delegation(DEFAULT_VALIDATOR, coin(amount, denom))],
+    #[test]
+    fn buy_mints_to_recipient() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // the investor pays, the buyer gets the tokens
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let gift = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: Some(BUYER.to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), info, gift).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::zero());
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(1000));
+    }
+
+    #[test]
+    fn piecewise_curve_flattens_after_breakpoint() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        // 2 reserve per token for the first 10 tokens, then 1
+        let steep = CurveSegment {
+            start_supply: Uint128::zero(),
+            curve: BaseCurveType::Constant {
+                value: Uint128::new(2),
+                scale: 0,
+            },
+        };
+        let flat = CurveSegment {
+            start_supply: Uint128::new(1000),
+            curve: BaseCurveType::Constant {
+                value: Uint128::new(1),
+                scale: 0,
+            },
+        };
+
+        let unsorted = CurveType::Piecewise {
+            segments: vec![flat.clone(), steep.clone()],
+        };
+        let msg = default_instantiate(None, 2, 8, unsorted, 2, 50);
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPiecewiseCurve {});
+
+        let curve_type = CurveType::Piecewise {
+            segments: vec![steep, flat],
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // 20 reserve buys the first 10 tokens, the other 10 buy 10 more
+        let info = mock_info(INVESTOR, &coins(3_000_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(2000));
+
+        let curve =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
+        assert_eq!(curve.reserve, Uint128::new(3_000_000_000));
+        assert_eq!(curve.spot_price, Decimal::one());
+    }
+
+    #[test]
+    fn sigmoid_curve_levels_off() {
+        let curve_type = CurveType::Sigmoid {
+            max_price: Uint128::new(2),
+            midpoint: Uint128::new(10),
+            steepness: Uint128::new(1),
+            scale: 0,
+        };
+        let deps = mock_dependencies(&[]);
+        let curve = curve_type.to_curve_fn(deps.as_ref().querier)(DecimalPlaces::new(2, 8));
+
+        // half the max price at the midpoint, slow to start and capped at the top
+        assert_eq!(curve.spot_price(Uint128::new(1000)), Decimal::one());
+        assert!(curve.spot_price(Uint128::zero()) < Decimal::percent(1));
+        assert!(curve.spot_price(Uint128::new(100_000)) > Decimal::percent(199));
+        assert!(curve.spot_price(Uint128::new(100_000)) < Decimal::percent(200));
+
+        // supply and reserve are inverses, give or take a supply unit of rounding
+        let supply = curve.supply(Uint128::new(500_000_000));
+        let reserve = curve.reserve(supply);
+        assert!(reserve <= Uint128::new(500_000_000));
+        assert!(reserve > Uint128::new(495_000_000));
+
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), supply);
+    }
+
+    #[test]
+    fn reserve_ratio_curve_matches_bancor() {
+        let deps = mock_dependencies(&[]);
+        let places = DecimalPlaces::new(2, 8);
+
+        // a 50% ratio is the same as a linear curve
+        let bancor = CurveType::ReserveRatio {
+            ratio: Uint128::new(5),
+            price: Uint128::new(10),
+            scale: 1,
+        }
+        .to_curve_fn(deps.as_ref().querier)(places);
+        let linear = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 0,
+        }
+        .to_curve_fn(deps.as_ref().querier)(places);
+        let supply = bancor.supply(Uint128::new(500_000_000));
+        assert_eq!(supply, linear.supply(Uint128::new(500_000_000)));
+        assert_eq!(bancor.reserve(supply), linear.reserve(supply));
+        assert_eq!(bancor.spot_price(supply), linear.spot_price(supply));
+
+        // price = reserve / (supply * ratio)
+        let bancor = CurveType::ReserveRatio {
+            ratio: Uint128::new(2),
+            price: Uint128::new(10),
+            scale: 1,
+        }
+        .to_curve_fn(deps.as_ref().querier)(places);
+        let supply = Uint128::new(300);
+        let reserve = bancor.reserve(supply);
+        assert_eq!(reserve, Uint128::new(4_860_000_000));
+        assert_eq!(bancor.spot_price(supply), Decimal::from_str("81").unwrap());
+
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let invalid = CurveType::ReserveRatio {
+            ratio: Uint128::new(11),
+            price: Uint128::new(10),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, invalid, 2, 50);
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidReserveRatio {});
+    }
+
+    // answers CurveQueryMsg with a constant price of 1, and everything else like MockQuerier
+    struct ExternalCurveQuerier {
+        base: MockQuerier,
+    }
+
+    impl Querier for ExternalCurveQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            let msg = match request {
+                QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => msg,
+                _ => return self.base.raw_query(bin_request),
+            };
+            let res = match from_binary(&msg).unwrap() {
+                CurveQueryMsg::SpotPrice { .. } => to_binary(&CurveSpotPriceResponse {
+                    spot_price: Decimal::one(),
+                }),
+                CurveQueryMsg::Reserve { supply, decimals } => {
+                    let factor = 10u128.pow(decimals.reserve - decimals.supply);
+                    to_binary(&CurveReserveResponse {
+                        reserve: Uint128::new(supply.u128() * factor),
+                    })
+                }
+                CurveQueryMsg::Supply { reserve, decimals } => {
+                    let factor = 10u128.pow(decimals.reserve - decimals.supply);
+                    to_binary(&CurveSupplyResponse {
+                        supply: Uint128::new(reserve.u128() / factor),
+                    })
+                }
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        }
+    }
+
+    #[test]
+    fn external_curve_is_queried_for_prices() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: ExternalCurveQuerier { base },
+        };
+
+        let curve_type = CurveType::External {
+            contract: "pricing".into(),
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+
+        let curve =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier)).unwrap();
+        assert_eq!(curve.reserve, Uint128::new(500_000_000));
+        assert_eq!(curve.spot_price, Decimal::one());
+    }
+
+    #[test]
+    fn burn_respects_min_reserve_out() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+
+        // selling all 1000 tokens releases the whole reserve, and no more
+        let info = mock_info(INVESTOR, &[]);
+        let greedy = ExecuteMsg::Burn {
+            amount: Uint128::new(1000),
+            min_reserve_out: Some(Uint128::new(500_000_001)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, greedy).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SlippageExceeded {
+                amount: Uint128::new(500_000_000),
+                min: Uint128::new(500_000_001)
+            }
+        );
+    }
+
+    #[test]
+    fn buy_with_swap_routes_through_router() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.swap_router = Some("router".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // paying in the reserve denom should just use buy
+        let swap_buy = ExecuteMsg::BuyWithSwap {
+            min_reserve_out: Some(Uint128::new(500_000_000)),
+            min_tokens_out: Uint128::new(1000),
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, swap_buy.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SwapNotNeeded {
+                denom: DENOM.into()
+            }
+        );
+
+        // any other denom is swapped first, then bought with in a callback
+        let info = mock_info(INVESTOR, &coins(1234, "uosmo"));
+        let res = execute(deps.as_mut(), mock_env(), info, swap_buy).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "router".into(),
+                msg: to_binary(&RouterExecuteMsg::Swap {
+                    ask_denom: DENOM.into(),
+                    min_return: Some(Uint128::new(500_000_000)),
+                    to: None,
+                })
+                .unwrap(),
+                funds: coins(1234, "uosmo"),
+            })
+        );
+        let callback = ExecuteMsg::_BuySwapped {
+            buyer: INVESTOR.into(),
+            balance_before: Uint128::zero(),
+            min_tokens_out: Uint128::new(1000),
+        };
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.into(),
+                msg: to_binary(&callback).unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // only we can call back
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(500_000_000, DENOM));
+        let info = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, callback.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the buy is bounded too
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let greedy = ExecuteMsg::_BuySwapped {
+            buyer: INVESTOR.into(),
+            balance_before: Uint128::zero(),
+            min_tokens_out: Uint128::new(1001),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), greedy).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SlippageExceeded {
+                amount: Uint128::new(1000),
+                min: Uint128::new(1001)
+            }
+        );
+
+        execute(deps.as_mut(), mock_env(), info, callback).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(1000));
+    }
+
+    #[test]
+    fn burn_to_denom_swaps_released_reserve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.swap_router = Some("router".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+
+        // selling into the reserve denom should just use burn
+        let info = mock_info(INVESTOR, &[]);
+        let burn = ExecuteMsg::BurnToDenom {
+            amount: Uint128::new(1000),
+            target_denom: DENOM.into(),
+            min_out: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), burn).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SwapNotNeeded {
+                denom: DENOM.into()
+            }
+        );
+
+        // the released reserve goes to the router, which pays the seller
+        let burn = ExecuteMsg::BurnToDenom {
+            amount: Uint128::new(1000),
+            target_denom: "uosmo".into(),
+            min_out: Uint128::new(1234),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, burn).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "router".into(),
+                msg: to_binary(&RouterExecuteMsg::Swap {
+                    ask_denom: "uosmo".into(),
+                    min_return: Some(Uint128::new(1234)),
+                    to: Some(INVESTOR.into()),
+                })
+                .unwrap(),
+                funds: coins(500_000_000, DENOM),
+            })
+        );
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::zero());
+    }
+
+    #[test]
+    fn creator_role_is_separate_from_owner() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.creator_address = Some("artist".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // the owner can't touch the metadata
+        let update = ExecuteMsg::UpdateMetadata {
+            external_permalink_uri: None,
+            creator: None,
+            description: Some("Remastered from the original tapes".into()),
+            asset_uri: None,
+        };
+        let info = mock_info(CREATOR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, update.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info("artist", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), update).unwrap();
+        let token = query_token_info_with_meta(deps.as_ref()).unwrap();
+        assert_eq!(token.description, "Remastered from the original tapes");
+        assert_eq!(token.work, "Feed Me Weird Things (Remaster)");
+
+        // handing over takes two steps
+        let transfer = ExecuteMsg::TransferCreator {
+            new_creator: "label".into(),
+        };
+        execute(deps.as_mut(), mock_env(), info, transfer).unwrap();
+        let role = query_creator(deps.as_ref()).unwrap();
+        assert_eq!(role.creator, "artist");
+        assert_eq!(role.pending_creator, Some("label".into()));
+
+        let info = mock_info(CREATOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptCreator {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info("label", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AcceptCreator {},
+        )
+        .unwrap();
+        let role = query_creator(deps.as_ref()).unwrap();
+        assert_eq!(role.creator, "label");
+        assert_eq!(role.pending_creator, None);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptCreator {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingCreator {});
+    }
+
+    #[test]
+    fn locked_metadata_cannot_change() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(CREATOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::LockMetadata {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::LockMetadata {},
+        )
+        .unwrap();
+
+        let update = ExecuteMsg::UpdateMetadata {
+            external_permalink_uri: None,
+            creator: None,
+            description: Some("Remastered from the original tapes".into()),
+            asset_uri: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, update).unwrap_err();
+        assert_eq!(err, ContractError::MetadataLocked {});
+    }
+
+    #[test]
+    fn keeper_fills_buy_order_once_limit_reached() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        msg.keeper_fee = Some(Decimal::percent(1));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+        let spot_price =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier))
+                .unwrap()
+                .spot_price;
+
+        // bob wants in at half the current price
+        let limit = spot_price / Uint128::new(2);
+        let info = mock_info("bob", &coins(100_000_000, DENOM));
+        let place = ExecuteMsg::PlaceBuyOrder {
+            max_spot_price: limit,
+        };
+        execute(deps.as_mut(), mock_env(), info, place).unwrap();
+        let orders = query_buy_orders(deps.as_ref(), None, None).unwrap().orders;
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].owner, "bob");
+        assert_eq!(orders[0].reserve, Uint128::new(100_000_000));
+        let id = orders[0].id;
+
+        let info = mock_info("keeper", &[]);
+        let fill = ExecuteMsg::ExecuteBuyOrder { id };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), fill.clone()).unwrap_err();
+        assert_eq!(err, ContractError::LimitNotReached { spot_price, limit });
+
+        // only bob can cancel
+        let cancel = ExecuteMsg::CancelBuyOrder { id };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), cancel).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // once the price halves, the keeper buys for bob and keeps 1%
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(500),
+            min_reserve_out: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), burn).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, fill).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper".into(),
+                amount: coins(1_000_000, DENOM),
+            })
+        );
+        assert!(get_balance(deps.as_ref(), "bob") > Uint128::zero());
+        let orders = query_buy_orders(deps.as_ref(), None, None).unwrap().orders;
+        assert_eq!(orders, vec![]);
+    }
+
+    #[test]
+    fn spread_stays_with_the_holders() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        msg.spread = Some(Decimal::one());
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidSpread {});
+        msg.spread = Some(Decimal::percent(1));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // 1% of each buy is kept back, so 99 tokens for 100
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        for buyer in [BUYER, INVESTOR] {
+            let info = mock_info(buyer, &coins(100_000_000, DENOM));
+            execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        }
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(99));
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(98));
+
+        // a round trip loses the spread both ways, which the one holding on gains
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(98),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), burn).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(98_502_514, DENOM),
+            })
+        );
+        // the last holder out pays no spread, as there's no one left to keep it for
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(99),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(BUYER, &[]), burn).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BUYER.into(),
+                amount: coins(101_497_486, DENOM),
+            })
+        );
+        assert_eq!(
+            CURVE_STATE.load(&deps.storage).unwrap().curve_reserve,
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn protocol_fees_accrue_to_the_collector() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        let fee_config = FeeConfig {
+            collector: "launchpad".into(),
+            buy_fee_bps: 100,
+            sell_fee_bps: 10_000,
+        };
+        msg.fee_config = Some(fee_config.clone());
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFeeConfig {});
+        let fee_config = FeeConfig {
+            sell_fee_bps: 200,
+            ..fee_config
+        };
+        msg.fee_config = Some(fee_config.clone());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // 1% of the buy and 2% of what the sell releases
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(99));
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(99),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), burn).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(97_020_000, DENOM),
+            })
+        );
+        let fees = query_protocol_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.fee_config, Some(fee_config));
+        assert_eq!(fees.accrued, Uint128::new(2_980_000));
+
+        let collect = ExecuteMsg::CollectFees {};
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), collect.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "launchpad".into(),
+                amount: coins(2_980_000, DENOM),
+            })
+        );
+        let fees = query_protocol_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued, Uint128::zero());
+        assert_eq!(fees.collected, Uint128::new(2_980_000));
+        let err = execute(deps.as_mut(), mock_env(), info, collect).unwrap_err();
+        assert_eq!(err, ContractError::NoFeesAccrued {});
+    }
+
+    #[test]
+    fn native_exit_tax_is_withdrawn_by_owner() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.staking_params.exit_tax_payout = Some(ExitTaxPayout::Native {});
+        let mut env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(200_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 200_000_000, DENOM);
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(INVESTOR, &[]), unbond).unwrap();
+
+        // the whole unbond is undelegated, and the owner's 2% comes out of it, not the supply
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Undelegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(100_000_000, DENOM),
+            })
+        );
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::zero());
+        let curve_state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(curve_state.supply, Uint128::new(100));
+        assert_eq!(curve_state.claims, Uint128::new(100_000_000));
+        let claims = query_claims(
+            deps.as_ref(),
+            env.clone(),
+            INVESTOR.into(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(claims.claims[0].amount, Uint128::new(98_000_000));
+        let fees = query_owner_fees(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(fees.tokens_minted, Uint128::zero());
+        assert_eq!(fees.native_accrued, coin(2_000_000, DENOM));
+        assert_eq!(fees.native_unclaimed, coin(2_000_000, DENOM));
+        assert_eq!(fees.native_withdrawable, coin(0, DENOM));
+
+        let withdraw = ExecuteMsg::WithdrawOwnerFees {};
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            withdraw.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let creator = mock_info(CREATOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            withdraw.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        // once the undelegation has matured the owner can take it
+        env.block.time = env.block.time.plus_seconds(3 * 24 * 60 * 60);
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100_000_000, DENOM));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            withdraw.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: CREATOR.into(),
+                amount: coins(2_000_000, DENOM),
+            })
+        );
+        let curve_state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(curve_state.claims, Uint128::new(98_000_000));
+        let fees = query_owner_fees(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(fees.native_accrued, coin(2_000_000, DENOM));
+        assert_eq!(fees.native_unclaimed, coin(0, DENOM));
+        let err = execute(deps.as_mut(), env, creator, withdraw).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
+    #[test]
+    fn payees_split_the_exit_tax() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let payees = query_payees(deps.as_ref()).unwrap().payees;
+        assert_eq!(
+            payees,
+            vec![Payee {
+                address: CREATOR.into(),
+                weight: 1
+            }]
+        );
+
+        let payee = |address: &str, weight| Payee {
+            address: address.into(),
+            weight,
+        };
+        let update = |payees| ExecuteMsg::UpdatePayees { payees };
+        let creator = mock_info(CREATOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            update(vec![payee(INVESTOR, 1)]),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let invalid = [
+            vec![],
+            vec![payee("alice", 1), payee("bob", 0)],
+            vec![payee("alice", 1), payee("alice", 2)],
+        ];
+        for payees in invalid {
+            let err =
+                execute(deps.as_mut(), mock_env(), creator.clone(), update(payees)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidPayees {});
+        }
+        let msg = update(vec![payee("alice", 3), payee("bob", 1)]);
+        execute(deps.as_mut(), mock_env(), creator, msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(400_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 400_000_000, DENOM);
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), unbond).unwrap();
+
+        // the 20 tokens of tax go three to one, and none to the owner
+        assert_eq!(get_balance(deps.as_ref(), "alice"), Uint128::new(15));
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(5));
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::zero());
+        let fees = query_owner_fees(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(fees.tokens_minted, Uint128::new(20));
+    }
+
+    #[test]
+    fn frozen_addresses_cannot_trade() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap();
+        let buyer = mock_info(BUYER, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), buyer, buy.clone()).unwrap();
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: INVESTOR.into(),
+            amount: Uint128::new(10),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(BUYER, &[]), allow).unwrap();
+
+        let freeze = ExecuteMsg::Freeze {
+            address: INVESTOR.into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            freeze.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let creator = mock_info(CREATOR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), creator.clone(), freeze).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("freeze").add_attribute("address", INVESTOR)]
+        );
+        let frozen = query_frozen(deps.as_ref(), None, None).unwrap();
+        assert_eq!(frozen.addresses, vec![INVESTOR.to_string()]);
+
+        // nothing moves in or out of a frozen address
+        let frozen = ContractError::AddressFrozen {
+            address: INVESTOR.into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert_eq!(err, frozen);
+        let transfer = ExecuteMsg::Transfer {
+            recipient: BUYER.into(),
+            amount: Uint128::new(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, frozen);
+        let sell = ExecuteMsg::Burn {
+            amount: Uint128::new(10),
+            min_reserve_out: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), sell).unwrap_err();
+        assert_eq!(err, frozen);
+        // nor through an allowance or by handing on claims
+        let sell_from = ExecuteMsg::BurnFrom {
+            owner: BUYER.into(),
+            amount: Uint128::new(10),
+            min_reserve_out: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            sell_from,
+        )
+        .unwrap_err();
+        assert_eq!(err, frozen);
+        let transfer_claim = ExecuteMsg::TransferClaim {
+            recipient: BUYER.into(),
+            amount: Uint128::new(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            transfer_claim,
+        )
+        .unwrap_err();
+        assert_eq!(err, frozen);
+        let gift = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: Some(INVESTOR.into()),
+        };
+        let info = mock_info(BUYER, &coins(100_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, gift).unwrap_err();
+        assert_eq!(err, frozen);
+
+        let unfreeze = ExecuteMsg::Unfreeze {
+            address: INVESTOR.into(),
+        };
+        execute(deps.as_mut(), mock_env(), creator, unfreeze).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            transfer,
+        )
+        .unwrap();
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(110));
+    }
+    #[test]
+    fn exempt_addresses_skip_exit_tax_and_fees() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.fee_config = Some(FeeConfig {
+            collector: "launchpad".into(),
+            buy_fee_bps: 100,
+            sell_fee_bps: 100,
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let update = ExecuteMsg::UpdateFeeExemptions {
+            add: vec![INVESTOR.into(), "market_maker".into()],
+            remove: vec!["market_maker".into()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap();
+        let exempt = query_fee_exemptions(deps.as_ref(), None, None).unwrap();
+        assert_eq!(exempt.addresses, vec![INVESTOR.to_string()]);
+
+        // no exit tax is minted to the owner
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(200_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 200_000_000, DENOM);
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), unbond).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::zero());
+
+        // nor does the investor's buy pay the protocol fee, unlike bob's
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        for buyer in [INVESTOR, "bob"] {
+            let info = mock_info(buyer, &coins(100_000_000, DENOM));
+            execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        }
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(200));
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(99));
+        let fees = query_protocol_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued, Uint128::new(1_000_000));
+    }
+
+    #[test]
+    fn creator_escrow_releases_by_milestone() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        let mut config = CreatorEscrowConfig {
+            share: Decimal::percent(10),
+            milestones: vec![
+                Milestone {
+                    share: Decimal::percent(50),
+                    unlock: MilestoneUnlock::After {
+                        expiration: Expiration::AtHeight(env.block.height + 100),
+                    },
+                },
+                Milestone {
+                    share: Decimal::percent(40),
+                    unlock: MilestoneUnlock::Vote {
+                        quorum: Decimal::percent(10),
+                        min_voting_period: Duration::Height(10),
+                    },
+                },
+            ],
+        };
+        msg.creator_escrow = Some(config.clone());
+        let creator = mock_info(CREATOR, &[]);
+        let err =
+            instantiate(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InvalidCreatorEscrow {});
+        config.milestones[1].share = Decimal::percent(50);
+        msg.creator_escrow = Some(config);
+        instantiate(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+
+        // a tenth of the buy is escrowed rather than buying on the curve
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(90));
+
+        let release = |milestone, poll_id| ExecuteMsg::ReleaseMilestone { milestone, poll_id };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(0, None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneLocked { milestone: 0 });
+        env.block.height += 100;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(0, None),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: CREATOR.into(),
+                amount: coins(5_000_000, DENOM),
+            })
+        );
+
+        // the second needs holders to approve it, in a poll opened for it
+        let create = |voting_period, milestone| ExecuteMsg::CreatePoll {
+            description: "release the second half?".into(),
+            options: vec!["yes".into(), "no".into()],
+            voting_period,
+            milestone,
+        };
+        for milestone in [0, 1] {
+            let too_short = create(Duration::Height(1), Some(milestone));
+            let err = execute(deps.as_mut(), env.clone(), creator.clone(), too_short).unwrap_err();
+            assert_eq!(err, ContractError::InvalidMilestonePoll { milestone });
+        }
+        let unrelated = create(Duration::Height(10), None);
+        execute(deps.as_mut(), env.clone(), creator.clone(), unrelated).unwrap();
+        let for_milestone = create(Duration::Height(10), Some(1));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            for_milestone.clone(),
+        )
+        .unwrap();
+        // one nobody votes in falls short of the quorum
+        execute(deps.as_mut(), env.clone(), creator.clone(), for_milestone).unwrap();
+        for poll_id in [1, 2] {
+            let vote = ExecuteMsg::CastVote { poll_id, option: 0 };
+            execute(deps.as_mut(), env.clone(), mock_info(INVESTOR, &[]), vote).unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(1, Some(2)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneNotApproved { poll_id: 2 });
+        env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(1, Some(1)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneNotApproved { poll_id: 1 });
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(1, Some(3)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneNotApproved { poll_id: 3 });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(1, Some(2)),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            release(1, None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToRelease {});
+
+        // with every milestone reached, later buys' share can be released as it comes in
+        let info = mock_info("bob", &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy).unwrap();
+        execute(deps.as_mut(), env, creator, release(1, None)).unwrap();
+        let escrow = query_creator_escrow(deps.as_ref()).unwrap();
+        assert_eq!(escrow.accrued, Uint128::new(20_000_000));
+        assert_eq!(escrow.paid, Uint128::new(20_000_000));
+        assert!(escrow.milestones.iter().all(|m| m.released));
+    }
+
+    #[test]
+    fn creator_allocation_vests_linearly() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.creator_allocation = Some(CreatorAllocation {
+            amount: Uint128::new(1000),
+            vesting_schedule: VestingSchedule::Linear {
+                duration: Duration::Height(0),
+            },
+        });
+        let creator = mock_info(CREATOR, &[]);
+        let err =
+            instantiate(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVesting {});
+        msg.creator_allocation = Some(CreatorAllocation {
+            amount: Uint128::new(1000),
+            vesting_schedule: VestingSchedule::Linear {
+                duration: Duration::Height(100),
+            },
+        });
+        instantiate(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+
+        // the whole allocation is minted up front, but held by the contract
+        assert_eq!(
+            get_balance(deps.as_ref(), MOCK_CONTRACT_ADDR),
+            Uint128::new(1000)
+        );
+        let claim = ExecuteMsg::ClaimVested {};
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), claim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingVested {});
+
+        env.block.height += 25;
+        let info = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, claim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), creator.clone(), claim.clone()).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::new(250));
+        let vesting = query_creator_vesting(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(vesting.claimed, Uint128::new(250));
+        assert_eq!(vesting.claimable, Uint128::zero());
+        assert_eq!(vesting.locked, Uint128::new(750));
+
+        // everything has vested once the duration is up
+        env.block.height += 200;
+        execute(deps.as_mut(), env.clone(), creator.clone(), claim.clone()).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::new(1000));
+        assert_eq!(
+            get_balance(deps.as_ref(), MOCK_CONTRACT_ADDR),
+            Uint128::zero()
+        );
+        let vesting = query_creator_vesting(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(vesting.locked, Uint128::zero());
+        let err = execute(deps.as_mut(), env, creator, claim).unwrap_err();
+        assert_eq!(err, ContractError::NothingVested {});
+    }
+
+    #[test]
+    fn keeper_runs_dca_plan_each_period() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.keeper_fee = Some(Decimal::percent(1));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let create = ExecuteMsg::CreateDca {
+            amount_per_period: Uint128::new(100_000_000),
+            period: Duration::Height(10),
+            deposits: 3,
+        };
+        let info = mock_info(INVESTOR, &coins(200_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, create.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DcaFundingMismatch {
+                expected: Uint128::new(300_000_000)
+            }
+        );
+        let info = mock_info(INVESTOR, &coins(300_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, create).unwrap();
+        let plans = query_dca_plans(deps.as_ref(), Some(INVESTOR.into()), None, None)
+            .unwrap()
+            .plans;
+        assert_eq!(plans.len(), 1);
+        let id = plans[0].id;
+
+        // the first buy is due straight away, the keeper keeping 1%
+        let mut env = mock_env();
+        let keeper = mock_info("keeper", &[]);
+        let run = ExecuteMsg::ExecuteDca { id };
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), run.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper".into(),
+                amount: coins(1_000_000, DENOM),
+            })
+        );
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(99));
+        let err = execute(deps.as_mut(), env.clone(), keeper.clone(), run.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DcaNotDue {
+                id,
+                next: Expiration::AtHeight(env.block.height + 10)
+            }
+        );
+
+        env.block.height += 10;
+        execute(deps.as_mut(), env.clone(), keeper.clone(), run).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(198));
+        let plans = query_dca_plans(deps.as_ref(), None, None, None)
+            .unwrap()
+            .plans;
+        assert_eq!(plans[0].deposits_left, 1);
+
+        // only the investor can cancel, getting the last deposit back
+        let cancel = ExecuteMsg::CancelDca { id };
+        let err = execute(deps.as_mut(), env.clone(), keeper, cancel.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env, mock_info(INVESTOR, &[]), cancel).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(100_000_000, DENOM),
+            })
+        );
+        let plans = query_dca_plans(deps.as_ref(), None, None, None)
+            .unwrap()
+            .plans;
+        assert_eq!(plans, vec![]);
+    }
+
+    #[test]
+    fn committed_buys_fill_on_reveal() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bob = Addr::unchecked("bob");
+        let salt = Binary::from(b"pepper".to_vec());
+        let commit = ExecuteMsg::CommitBuy {
+            hash: commitment_hash(&bob, Uint128::new(500), &salt),
+        };
+        let info = mock_info("bob", &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info.clone(), commit.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, commit).unwrap_err();
+        assert_eq!(err, ContractError::CommitmentExists {});
+        let committed = query_buy_commitment(deps.as_ref(), "bob".into()).unwrap();
+        assert_eq!(committed.reserve, Uint128::new(500_000_000));
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::zero());
+
+        let reveal = |salt: &[u8]| ExecuteMsg::RevealBuy {
+            salt: Binary::from(salt),
+            min_supply_out: Uint128::new(500),
+        };
+        let info = mock_info("bob", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), reveal(b"pepper")).unwrap_err();
+        assert_eq!(err, ContractError::RevealTooSoon {});
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), reveal(b"salt")).unwrap_err();
+        assert_eq!(err, ContractError::CommitmentMismatch {});
+        execute(deps.as_mut(), env.clone(), info.clone(), reveal(b"pepper")).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(500));
+        let err = execute(deps.as_mut(), env, info, reveal(b"pepper")).unwrap_err();
+        assert_eq!(err, ContractError::NoCommitment {});
+    }
+
+    #[test]
+    fn revealed_buys_hold_to_the_committed_minimum() {
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let setup = || {
+            let mut deps = mock_dependencies(&[]);
+            set_validator(&mut deps.querier);
+            let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+            instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+            deps
+        };
+
+        // what bob's reserve buys on the untouched curve
+        let mut quote = setup();
+        let info = mock_info("bob", &coins(500_000_000, DENOM));
+        execute(quote.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        let expected = get_balance(quote.as_ref(), "bob");
+
+        let mut deps = setup();
+        let bob = Addr::unchecked("bob");
+        let salt = Binary::from(b"pepper".to_vec());
+        let commit = ExecuteMsg::CommitBuy {
+            hash: commitment_hash(&bob, expected, &salt),
+        };
+        let info = mock_info("bob", &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, commit).unwrap();
+
+        // someone buys in first and moves the price
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        let info = mock_info("bob", &[]);
+        // the minimum can't be lowered at the reveal
+        let reveal = ExecuteMsg::RevealBuy {
+            salt: salt.clone(),
+            min_supply_out: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), reveal).unwrap_err();
+        assert_eq!(err, ContractError::CommitmentMismatch {});
+        let reveal = ExecuteMsg::RevealBuy {
+            salt,
+            min_supply_out: expected,
+        };
+        let err = execute(deps.as_mut(), env, info, reveal).unwrap_err();
+        assert!(matches!(err, ContractError::SlippageExceeded { min, .. } if min == expected));
+    }
+
+    #[test]
+    fn launch_auction_seeds_the_curve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        // 1 reserve per token on the curve, which is 10^6 per base unit
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        let per_unit = |n: u128| Decimal::from_ratio(n * 100_000, 1u128);
+        let auction = LaunchAuction {
+            tranche: Uint128::new(1000),
+            start_price: per_unit(40),
+            end_price: per_unit(5),
+            duration: Duration::Height(100),
+        };
+        msg.launch = Some(auction.clone());
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LaunchBelowCurve {
+                floor: Uint128::new(1_000_000_000)
+            }
+        );
+        msg.launch = Some(LaunchAuction {
+            end_price: per_unit(10),
+            ..auction
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(1_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::LaunchNotSettled {});
+
+        // halfway down, bob bids at 2.5 a token
+        let mut env = mock_env();
+        env.block.height += 50;
+        let bid = mock_info("bob", &coins(1_000_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), bid, ExecuteMsg::LaunchBid {}).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SettleLaunch {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::LaunchNotOver {});
+
+        // at 2.2 a token alice's bid sells out the tranche, and the rest comes back
+        env.block.height += 10;
+        let bid = mock_info("alice", &coins(2_000_000_000, DENOM));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            bid.clone(),
+            ExecuteMsg::LaunchBid {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(800_000_000, DENOM),
+            })
+        );
+        let err = execute(deps.as_mut(), env.clone(), bid, ExecuteMsg::LaunchBid {}).unwrap_err();
+        assert_eq!(err, ContractError::LaunchClosed {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SettleLaunch {},
+        )
+        .unwrap();
+        let launch = query_launch(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(launch.clearing_price, Some(per_unit(22)));
+        assert!(!launch.bidding_open);
+        // the curve has 2200 tokens out for the 2200 raised, 1000 of them sold
+        assert_eq!(get_balance(deps.as_ref(), CREATOR), Uint128::new(1200));
+
+        let claim = ExecuteMsg::ClaimLaunchTokens {};
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(454));
+        assert_eq!(get_balance(deps.as_ref(), "alice"), Uint128::new(545));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), claim).unwrap_err();
+        assert_eq!(err, ContractError::NoLaunchBid {});
+
+        execute(deps.as_mut(), env, info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(1));
+    }
+
+    #[test]
+    fn keeper_fills_sell_order_until_expiry() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        msg.keeper_fee = Some(Decimal::percent(1));
+        let mut env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+        let spot_price =
+            query_curve_info(deps.as_ref(), curve_type.to_curve_fn(deps.as_ref().querier))
+                .unwrap()
+                .spot_price;
+
+        // the investor wants out at double the current price, escrowing half their tokens
+        let limit = spot_price + spot_price;
+        let place = ExecuteMsg::PlaceSellOrder {
+            amount: Uint128::new(500),
+            min_spot_price: limit,
+            expires: Some(Expiration::AtHeight(env.block.height + 100)),
+        };
+        let info = mock_info(INVESTOR, &[]);
+        execute(deps.as_mut(), env.clone(), info, place.clone()).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+        let orders = query_sell_orders(deps.as_ref(), None, None).unwrap().orders;
+        assert_eq!(orders.len(), 1);
+        let id = orders[0].id;
+
+        let keeper = mock_info("keeper", &[]);
+        let fill = ExecuteMsg::ExecuteSellOrder { id };
+        let err = execute(deps.as_mut(), env.clone(), keeper.clone(), fill.clone()).unwrap_err();
+        assert_eq!(err, ContractError::LimitNotReached { spot_price, limit });
+
+        // bob doubles the supply, which doubles the price
+        let info = mock_info("bob", &coins(1_500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), fill).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper".into(),
+                amount: coins(8_750_000, DENOM),
+            })
+        );
+        assert_eq!(
+            get_balance(deps.as_ref(), MOCK_CONTRACT_ADDR),
+            Uint128::zero()
+        );
+
+        // an expired order can't be filled, but anyone can return the tokens
+        let info = mock_info(INVESTOR, &[]);
+        execute(deps.as_mut(), env.clone(), info, place).unwrap();
+        let id = query_sell_orders(deps.as_ref(), None, None).unwrap().orders[0].id;
+        let cancel = ExecuteMsg::CancelSellOrder { id };
+        let err = execute(deps.as_mut(), env.clone(), keeper.clone(), cancel.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        env.block.height += 100;
+        let fill = ExecuteMsg::ExecuteSellOrder { id };
+        let err = execute(deps.as_mut(), env.clone(), keeper.clone(), fill).unwrap_err();
+        assert_eq!(err, ContractError::OrderExpired {});
+        execute(deps.as_mut(), env, keeper, cancel).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+    }
+
+    #[test]
+    fn buys_cannot_exceed_max_supply() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        // 1 whole token per whole reserve token, and an edition of 8 tokens
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.max_supply = Some(Uint128::new(800));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+
+        // the next 5 tokens would take the supply past the cap
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, buy).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+    }
+
+    #[test]
+    fn twap_weights_spot_prices_by_time() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut env = mock_env();
+        let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let mut prices = vec![];
+        for _ in 0..2 {
+            let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap();
+            let curve_fn = curve_type.to_curve_fn(deps.as_ref().querier);
+            prices.push(
+                query_curve_info(deps.as_ref(), curve_fn)
+                    .unwrap()
+                    .spot_price,
+            );
+            env.block.time = env.block.time.plus_seconds(100);
+        }
+
+        // each price held for 100 seconds
+        let twap = query_twap(deps.as_ref(), env.clone(), 200).unwrap();
+        let mean = Decimal::from_ratio(
+            prices[0].numerator() + prices[1].numerator(),
+            2 * prices[0].denominator(),
+        );
+        assert_eq!(
+            twap,
+            TwapResponse {
+                twap: mean,
+                window_seconds: 200
+            }
+        );
+        let twap = query_twap(deps.as_ref(), env.clone(), 100).unwrap();
+        assert_eq!(twap.twap, prices[1]);
+
+        // the window can't reach back before the first trade
+        let twap = query_twap(deps.as_ref(), env.clone(), 1_000).unwrap();
+        assert_eq!(twap.window_seconds, 200);
+        let twap = query_twap(deps.as_ref(), env, 0).unwrap();
+        assert_eq!(
+            twap,
+            TwapResponse {
+                twap: prices[1],
+                window_seconds: 0
+            }
+        );
+    }
+
+    #[test]
+    fn funding_progress_reports_phase() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut env = mock_env();
+        let deadline = Expiration::AtHeight(env.block.height + 100);
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.funding_goal = Some(FundingGoal {
+            amount: Uint128::new(1_000_000_000),
+            deadline: Some(deadline),
+            closes_curve: None,
+        });
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+        let progress = query_funding_progress(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(
+            progress,
+            FundingProgressResponse {
+                goal: Some(Uint128::new(1_000_000_000)),
+                raised: Uint128::new(500_000_000),
+                percent_complete: Some(Decimal::percent(50)),
+                deadline: Some(deadline),
+                phase: FundingPhase::Hatch,
+            }
+        );
+
+        // missing the deadline means refunds
+        env.block.height += 100;
+        let progress = query_funding_progress(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(progress.phase, FundingPhase::Refund);
+
+        // and the curve is shut, so the goal can no longer be reached
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let err = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::FundingRefund {});
+    }
+
+    #[test]
+    fn refunds_burn_at_cost_basis() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.funding_goal = Some(FundingGoal {
+            amount: Uint128::new(2_000_000_000),
+            deadline: Some(Expiration::AtHeight(env.block.height + 100)),
+            closes_curve: None,
+        });
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // the later buyer pays more per token on the way up
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        for buyer in [INVESTOR, BUYER] {
+            let info = mock_info(buyer, &coins(500_000_000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap();
+        }
+        let investor_tokens = get_balance(deps.as_ref(), INVESTOR);
+        let buyer_tokens = get_balance(deps.as_ref(), BUYER);
+        assert!(buyer_tokens < investor_tokens);
+
+        // the deadline passes short of the goal, so both get back what they paid
+        env.block.height += 100;
+        let burn = ExecuteMsg::Burn {
+            amount: buyer_tokens,
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(BUYER, &[]), burn).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: BUYER.into(),
+                amount: coins(500_000_000, DENOM),
+            })]
+        );
+        let burn = ExecuteMsg::Burn {
+            amount: investor_tokens.multiply_ratio(1u128, 2u128),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(INVESTOR, &[]), burn).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(250_000_000, DENOM),
+            })]
+        );
+        let reserve = CURVE_STATE.load(&deps.storage).unwrap().curve_reserve;
+        assert_eq!(reserve, Uint128::new(250_000_000));
+
+        // no more buying
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), env, info, buy).unwrap_err();
+        assert_eq!(err, ContractError::FundingRefund {});
+    }
+
+    #[test]
+    fn refunds_unbond_bonds_at_cost_basis() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.funding_goal = Some(FundingGoal {
+            amount: Uint128::new(2_000_000_000),
+            deadline: Some(Expiration::AtHeight(env.block.height + 100)),
+            closes_curve: None,
+        });
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        for (buyer, staked) in [(INVESTOR, 500_000_000), (BUYER, 1_000_000_000)] {
+            let info = mock_info(buyer, &coins(500_000_000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, bond.clone()).unwrap();
+            set_delegation(&mut deps.querier, staked, DENOM);
+        }
+        let buyer_tokens = get_balance(deps.as_ref(), BUYER);
+
+        // the deadline passes short of the goal. Unbonding pays what was paid, with no
+        // exit tax, and there is no jumping the queue at a penalty
+        env.block.height += 100;
+        let instant = ExecuteMsg::InstantUnbond {
+            amount: buyer_tokens,
+            max_penalty: None,
+        };
+        let info = mock_info(BUYER, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), instant).unwrap_err();
+        assert_eq!(err, ContractError::FundingRefund {});
+        let unbond = ExecuteMsg::Unbond {
+            amount: buyer_tokens,
+        };
+        let res = execute(deps.as_mut(), env, info, unbond).unwrap();
+        assert!(res.attributes.contains(&attr("unbonded", "500000000")));
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::zero());
+        let state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.claims, Uint128::new(500_000_000));
+        assert_eq!(state.curve_reserve, Uint128::new(500_000_000));
+    }
+
+    #[test]
+    fn buys_only_work_inside_the_sale_window() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.sale_start = Some(Expiration::AtHeight(env.block.height + 10));
+        msg.sale_end = Some(Expiration::AtHeight(env.block.height + 20));
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::SaleNotActive {});
+
+        env.block.height += 10;
+        execute(deps.as_mut(), env.clone(), info.clone(), buy.clone()).unwrap();
+
+        env.block.height += 10;
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), buy).unwrap_err();
+        assert_eq!(err, ContractError::SaleNotActive {});
+        let err = execute(deps.as_mut(), env, info, bond).unwrap_err();
+        assert_eq!(err, ContractError::SaleNotActive {});
+    }
+
+    #[test]
+    fn presale_is_limited_to_the_whitelist() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let end = Expiration::AtHeight(env.block.height + 10);
+        let presale = ExecuteMsg::SetPresale {
+            end,
+            whitelist: vec![WhitelistEntry {
+                address: INVESTOR.into(),
+                cap: Uint128::new(800_000_000),
+            }],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            presale.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), presale).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NotWhitelisted {});
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info.clone(), buy.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PresaleCapExceeded {
+                remaining: Uint128::new(300_000_000)
+            }
+        );
+        let presale = query_presale(deps.as_ref(), env.clone(), INVESTOR.into()).unwrap();
+        assert_eq!(
+            presale,
+            PresaleResponse {
+                end: Some(end),
+                active: true,
+                allowance: Some(Uint128::new(300_000_000)),
+            }
+        );
+
+        // then the sale opens to everyone
+        env.block.height += 10;
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), env, info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(500));
+    }
+
+    #[test]
+    fn hooks_hear_of_trades() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let env = mock_env();
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let add = ExecuteMsg::AddHook {
+            addr: "rewards".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(CREATOR, &[]),
+            add.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), add).unwrap_err();
+        assert_eq!(err, HookError::HookAlreadyRegistered {}.into());
+        assert_eq!(
+            query_hooks(deps.as_ref()).unwrap().hooks,
+            vec!["rewards".to_string()]
+        );
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let res = execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap();
+        let hook = TradeHookMsg {
+            token: MOCK_CONTRACT_ADDR.into(),
+            account: INVESTOR.into(),
+            amount: Uint128::new(500),
+            direction: TradeDirection::Buy,
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(hook.into_cosmos_msg("rewards").unwrap())]
+        );
+
+        let sell = ExecuteMsg::Burn {
+            amount: Uint128::new(200),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(INVESTOR, &[]), sell).unwrap();
+        let hook = TradeHookMsg {
+            token: MOCK_CONTRACT_ADDR.into(),
+            account: INVESTOR.into(),
+            amount: Uint128::new(200),
+            direction: TradeDirection::Sell,
+        };
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(hook.into_cosmos_msg("rewards").unwrap())
+        );
+
+        // once removed, trades go on without it
+        let remove = ExecuteMsg::RemoveHook {
+            addr: "rewards".into(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), remove).unwrap();
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let res = execute(deps.as_mut(), env, info, buy).unwrap();
+        assert_eq!(res.messages.len(), 0);
+    }
+
+    #[test]
+    fn cw20_reserve_buys_through_receive() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let env = mock_env();
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.reserve_token = Some("stablecoin".into());
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // native funds can neither buy nor bond
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), buy).unwrap_err();
+        assert_eq!(err, ContractError::Cw20Reserve {});
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, bond).unwrap_err();
+        assert_eq!(err, ContractError::Cw20Reserve {});
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: INVESTOR.into(),
+            amount: Uint128::new(500_000_000),
+            msg: to_binary(&ReceiveMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            })
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stablecoin", &[]),
+            receive,
+        )
+        .unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+
+        // nothing is staked, so reconciling must leave the reserve alone
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(BUYER, &[]),
+            ExecuteMsg::Reconcile {},
+        )
+        .unwrap();
+        let state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.curve_reserve, Uint128::new(500_000_000));
+        assert_eq!(state.reserve_denom, "stablecoin");
+
+        // and selling pays out in the cw20
+        let sell = ExecuteMsg::Burn {
+            amount: Uint128::new(200),
+            min_reserve_out: None,
+        };
+        let res = execute(deps.as_mut(), env, mock_info(INVESTOR, &[]), sell).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "stablecoin".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: INVESTOR.into(),
+                    amount: Uint128::new(200_000_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+    }
+
+    const IBC_DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    /// answers the Stargate queries we make, as a chain with the given unbonding time
+    /// and undelegations in flight (completion in seconds, amount) that knows the one
+    /// ibc voucher
+    struct StargateQuerier {
+        base: MockQuerier,
+        unbonding_time: u64,
+        unbonding_entries: Vec<(u64, u128)>,
+    }
+
+    impl Querier for StargateQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            let (path, data) = match request {
+                QueryRequest::Stargate { path, data } => (path, data),
+                _ => return self.base.raw_query(bin_request),
+            };
+            if path == crate::staking::STAKING_PARAMS_PATH {
+                // Duration { seconds = 1 } as a varint
+                let mut duration = vec![0x08];
+                encode_varint(&mut duration, self.unbonding_time);
+                let res = encode_bytes(1, &encode_bytes(1, &duration));
+                SystemResult::Ok(ContractResult::Ok(res.into()))
+            } else if path == crate::staking::UNBONDING_DELEGATIONS_PATH {
+                let entries: Vec<u8> = self
+                    .unbonding_entries
+                    .iter()
+                    .flat_map(|(completes, amount)| {
+                        let mut time = vec![0x08];
+                        encode_varint(&mut time, *completes);
+                        let entry = [
+                            encode_bytes(2, &time),
+                            encode_bytes(4, amount.to_string().as_bytes()),
+                        ]
+                        .concat();
+                        encode_bytes(3, &entry)
+                    })
+                    .collect();
+                let res = encode_bytes(1, &entries);
+                SystemResult::Ok(ContractResult::Ok(res.into()))
+            } else if data.as_slice().ends_with(&IBC_DENOM.as_bytes()[4..]) {
+                assert_eq!(path, crate::ibc::DENOM_TRACE_PATH);
+                let trace = [
+                    encode_bytes(1, b"transfer/channel-0"),
+                    encode_bytes(2, b"uatom"),
+                ]
+                .concat();
+                let res = encode_bytes(1, &trace);
+                SystemResult::Ok(ContractResult::Ok(res.into()))
+            } else {
+                SystemResult::Ok(ContractResult::Err("denomination trace not found".into()))
+            }
+        }
+    }
+
+    #[test]
+    fn unbonding_period_must_cover_the_chains() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: StargateQuerier {
+                base,
+                unbonding_time: 21 * 24 * 60 * 60,
+                unbonding_entries: vec![],
+            },
+        };
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnbondingPeriodTooShort {
+                min: 21 * 24 * 60 * 60
+            }
+        );
+
+        msg.staking_params.unbonding_period = DAY * 21;
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let invest = INVESTMENT.load(&deps.storage).unwrap();
+        assert_eq!(invest.unbonding_period, DAY * 21);
+
+        // a chain that won't say still can't be given a period in blocks
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        msg.staking_params.unbonding_period = Duration::Height(100);
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::UnbondingPeriodNotTime {});
+    }
+
+    #[test]
+    fn ibc_reserve_is_traced_but_not_staked() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: StargateQuerier {
+                base,
+                unbonding_time: 0,
+                unbonding_entries: vec![],
+            },
+        };
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let env = mock_env();
+        let unknown = "ibc/0000000000000000000000000000000000000000000000000000000000000000";
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.reserve_denom = Some(unknown.into());
+        let err = instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidIbcDenom {
+                denom: unknown.into()
+            }
+        );
+        msg.reserve_denom = Some(IBC_DENOM.into());
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, IBC_DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), env, info, bond).unwrap_err();
+        assert_eq!(err, ContractError::ReserveNotStaked {});
+
+        let curve_type = CURVE_TYPE.load(&deps.storage).unwrap();
+        let curve_fn = curve_type.to_curve_fn(deps.as_ref().querier);
+        let info = query_curve_info(deps.as_ref(), curve_fn).unwrap();
+        assert_eq!(info.reserve_denom, IBC_DENOM);
+        assert_eq!(
+            info.reserve_trace,
+            Some(DenomTrace {
+                path: "transfer/channel-0".into(),
+                base_denom: "uatom".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn balances_are_snapshotted_for_voting() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let start = env.block.height;
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy).unwrap();
+
+        env.block.height += 5;
+        let transfer = ExecuteMsg::Transfer {
+            recipient: BUYER.into(),
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), env, mock_info(INVESTOR, &[]), transfer).unwrap();
+
+        // each height sees the balances from before that block's changes
+        let balance_at = |deps: Deps, address: &str, height: u64| {
+            query_balance_at(deps, address.into(), height)
+                .unwrap()
+                .balance
+                .u128()
+        };
+        assert_eq!(balance_at(deps.as_ref(), INVESTOR, start), 0);
+        assert_eq!(balance_at(deps.as_ref(), INVESTOR, start + 1), 500);
+        assert_eq!(balance_at(deps.as_ref(), INVESTOR, start + 5), 500);
+        assert_eq!(balance_at(deps.as_ref(), INVESTOR, start + 6), 300);
+        assert_eq!(balance_at(deps.as_ref(), BUYER, start + 5), 0);
+        assert_eq!(balance_at(deps.as_ref(), BUYER, start + 6), 200);
+
+        let supply_at = |deps: Deps, height: u64| {
+            query_total_supply_at(deps, height)
+                .unwrap()
+                .total_supply
+                .u128()
+        };
+        assert_eq!(supply_at(deps.as_ref(), start), 0);
+        assert_eq!(supply_at(deps.as_ref(), start + 1), 500);
+        assert_eq!(supply_at(deps.as_ref(), start + 6), 500);
+    }
+
+    #[test]
+    fn holders_vote_on_polls_with_their_balance() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut env = mock_env();
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy.clone()).unwrap();
+
+        env.block.height += 1;
+        let create = ExecuteMsg::CreatePoll {
+            description: "next album?".into(),
+            options: vec!["rock".into(), "jazz".into()],
+            voting_period: Duration::Height(10),
+            milestone: None,
+        };
+        // only the creator asks, and there must be a choice
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            create.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let one_option = ExecuteMsg::CreatePoll {
+            description: "next album?".into(),
+            options: vec!["rock".into()],
+            voting_period: Duration::Height(10),
+            milestone: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(CREATOR, &[]),
+            one_option,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::PollNeedsOptions {});
+        execute(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), create).unwrap();
+
+        // tokens bought once the poll is open carry no weight
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy).unwrap();
+        let vote = |option| ExecuteMsg::CastVote { poll_id: 1, option };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(BUYER, &[]), vote(0)).unwrap_err();
+        assert_eq!(err, ContractError::NoVotingPower {});
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            vote(2),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPollOption { option: 2 });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            vote(1),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            vote(0),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+
+        let poll = query_poll(deps.as_ref(), env.clone(), 1).unwrap();
+        assert_eq!(poll.tallies, vec![Uint128::zero(), Uint128::new(500)]);
+        assert!(poll.open);
+
+        env.block.height += 10;
+        let err = execute(deps.as_mut(), env.clone(), mock_info(BUYER, &[]), vote(0)).unwrap_err();
+        assert_eq!(err, ContractError::PollClosed { id: 1 });
+        let polls = query_polls(deps.as_ref(), env, None, None).unwrap().polls;
+        assert_eq!(polls.len(), 1);
+        assert!(!polls[0].open);
+    }
+
+    #[test]
+    fn crowdfund_closes_the_curve_at_the_goal() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.funding_goal = Some(FundingGoal {
+            amount: Uint128::new(800_000_000),
+            deadline: None,
+            closes_curve: Some(true),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        assert!(res.events.is_empty());
+
+        // the buy that crosses the goal still goes through, and says so
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("goal_reached")
+                .add_attribute("goal", "800000000")
+                .add_attribute("raised", "1000000000")]
+        );
+        let progress = query_funding_progress(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(progress.phase, FundingPhase::Closed);
+
+        // after which neither buying nor bonding is possible
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, buy).unwrap_err();
+        assert_eq!(err, ContractError::FundingGoalReached {});
+        let info = mock_info(BUYER, &coins(500_000_000, DENOM));
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, bond).unwrap_err();
+        assert_eq!(err, ContractError::FundingGoalReached {});
+    }
+
+    #[test]
+    fn owner_sweeps_stray_native_tokens() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.reserve_denom = Some("ujuno".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let funds = vec![coin(42, "uosmo"), coin(100, "ujuno"), coin(100, DENOM)];
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, funds);
+
+        let sweep = |denom: &str| ExecuteMsg::Sweep {
+            denom: denom.into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("fan", &[]),
+            sweep("uosmo"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // neither what backs the curve nor what is staked
+        let info = mock_info(CREATOR, &[]);
+        for denom in ["ujuno", DENOM] {
+            let err = execute(deps.as_mut(), mock_env(), info.clone(), sweep(denom)).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::CannotSweep {
+                    denom: denom.into()
+                }
+            );
+        }
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), sweep("uatom")).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NothingToSweep {
+                denom: "uatom".into()
+            }
+        );
+
+        // what was donated stays with the creator
+        let donate = mock_info("fan", &[coin(10, "uosmo")]);
+        execute(deps.as_mut(), mock_env(), donate, ExecuteMsg::Donate {}).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, sweep("uosmo")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: CREATOR.into(),
+                amount: coins(32, "uosmo"),
+            })
+        );
+    }
+
+    // answers every cw20 balance query with balance, and everything else like MockQuerier
+    struct Cw20BalanceQuerier {
+        base: MockQuerier,
+        balance: Uint128,
+    }
+
+    impl Querier for Cw20BalanceQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            if !matches!(request, QueryRequest::Wasm(WasmQuery::Smart { .. })) {
+                return self.base.raw_query(bin_request);
+            }
+            let res = to_binary(&cw20::BalanceResponse {
+                balance: self.balance,
+            });
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        }
+    }
+
+    #[test]
+    fn owner_recovers_cw20_sent_by_mistake() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        // the contract holds 10 meme-token, 7 of them donated
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Cw20BalanceQuerier {
+                base,
+                balance: Uint128::new(10),
+            },
+        };
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let wrapper = Cw20ReceiveMsg {
+            sender: "fan".into(),
+            amount: Uint128::new(7),
+            msg: Binary::default(),
+        };
+        let info = mock_info("meme-token", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Receive(wrapper),
+        )
+        .unwrap();
+
+        let recover = |token: &str, amount| ExecuteMsg::RecoverCw20 {
+            token: token.into(),
+            amount: Uint128::new(amount),
+            recipient: "fan".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("fan", &[]),
+            recover("meme-token", 3),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info(CREATOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            recover(MOCK_CONTRACT_ADDR, 3),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotRecover {
+                token: MOCK_CONTRACT_ADDR.into()
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            recover("meme-token", 4),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RecoverTooMuch {
+                available: Uint128::new(3)
+            }
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), info, recover("meme-token", 3)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "meme-token".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "fan".into(),
+                    amount: Uint128::new(3),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn donations_are_kept_out_of_the_curve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let funds = vec![coin(1_000_000, DENOM), coin(42, "uosmo")];
+        let info = mock_info("fan", &funds);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Donate {}).unwrap();
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, funds);
+        let wrapper = Cw20ReceiveMsg {
+            sender: "fan".into(),
+            amount: Uint128::new(7),
+            msg: Binary::default(),
+        };
+        let info = mock_info("meme-token", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Receive(wrapper),
+        )
+        .unwrap();
+
+        // donated reserve isn't reinvested
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::_BondAllTokens {},
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let treasury = query_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(
+            treasury.native,
+            vec![coin(42, "uosmo"), coin(1_000_000, DENOM)]
+        );
+        assert_eq!(
+            treasury.cw20,
+            vec![Cw20Coin {
+                address: "meme-token".into(),
+                amount: Uint128::new(7),
+            }]
+        );
+
+        // only the creator withdraws, and only what was donated
+        let withdraw = ExecuteMsg::WithdrawNativeDonation {
+            denom: DENOM.into(),
+            amount: Uint128::new(1_000_001),
+        };
+        let info = mock_info("fan", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(CREATOR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), withdraw).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DonationTooSmall {
+                available: Uint128::new(1_000_000)
+            }
+        );
+
+        let withdraw = ExecuteMsg::WithdrawCw20Donation {
+            token: "meme-token".into(),
+            amount: Uint128::new(7),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "meme-token".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: CREATOR.into(),
+                    amount: Uint128::new(7),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let treasury = query_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.cw20, vec![]);
+    }
+
+    #[test]
+    fn capabilities_reflect_instantiation() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.swap_router = Some("router".into());
+        msg.staking_params.reward_skim = Some(Decimal::percent(10));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let caps = query_capabilities(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(
+            caps,
+            CapabilitiesResponse {
+                multi_validator: false,
+                rebalance: false,
+                reward_skim: true,
+                validator_quorum: false,
+                exit_tax_brackets: false,
+                swap_router: true,
+                funding_goal: false,
+                limit_orders: true,
+                donations: true,
+                marketing: false,
+                hooks: false,
+                presale: false,
+                instant_unbond: true,
+                cw20_reserve: false,
+            }
+        );
+
+        // features switched on after instantiation show up too
+        let info = mock_info(CREATOR, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddHook {
+                addr: "analytics".into(),
+            },
+        )
+        .unwrap();
+        let end = Expiration::AtHeight(mock_env().block.height + 100);
+        let set_presale = ExecuteMsg::SetPresale {
+            end,
+            whitelist: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), set_presale).unwrap();
+        let update = ExecuteMsg::UpdateMarketing {
+            project: None,
+            description: Some("Remastered".into()),
+            marketing: None,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), update).unwrap();
+        let caps = query_capabilities(deps.as_ref(), mock_env()).unwrap();
+        assert!(caps.hooks);
+        assert!(caps.presale);
+        assert!(caps.marketing);
+
+        // and winding down closes orders and donations
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::InitiateWindDown {},
+        )
+        .unwrap();
+        let caps = query_capabilities(deps.as_ref(), mock_env()).unwrap();
+        assert!(!caps.limit_orders);
+        assert!(!caps.donations);
+        let info = mock_info("fan", &coins(10, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Donate {}).unwrap_err();
+        assert_eq!(err, ContractError::WindingDown {});
+
+        // a cw20 reserve has no limit orders, and nothing staked to unbond instantly
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.reserve_token = Some("stablecoin".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let caps = query_capabilities(deps.as_ref(), mock_env()).unwrap();
+        assert!(caps.cw20_reserve);
+        assert!(!caps.limit_orders);
+        assert!(!caps.instant_unbond);
+        assert!(caps.donations);
+    }
+
+    #[test]
+    fn migrate_upgrades_v0_1_state() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let invest = INVESTMENT.load(&deps.storage).unwrap();
+
+        // roll the storage back to the v0.1.0 layout
+        let old = InvestmentInfoV0_1 {
+            owner: invest.owner,
+            bond_denom: invest.bond_denom,
+            unbonding_period: invest.unbonding_period,
+            exit_tax: invest.exit_tax,
+            validator: invest.validator,
+            min_withdrawal: invest.min_withdrawal,
+        };
+        INVESTMENT_V0_1.save(&mut deps.storage, &old).unwrap();
+        crate::state::CREATOR.remove(&mut deps.storage);
+        VALIDATOR_APPROVERS.remove(&mut deps.storage);
+        KEEPER_FEE.remove(&mut deps.storage);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        assert!(get_investment(deps.as_ref()).is_err());
+        let old_claims = vec![
+            Claim::new(100, Expiration::AtHeight(20)),
+            Claim::new(50, Expiration::AtHeight(30)),
+        ];
+        let bob = Addr::unchecked("bob");
+        LEGACY_CLAIMS
+            .save(&mut deps.storage, &bob, &old_claims)
+            .unwrap();
+        // a holder from before there were balance snapshots
+        let early_fan = Addr::unchecked("early-fan");
+        cw20_base::state::BALANCES
+            .save(&mut deps.storage, &early_fan, &Uint128::new(1_000))
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.owner, CREATOR);
+        assert_eq!(invest.exit_tax_brackets, vec![]);
+        assert_eq!(query_creator(deps.as_ref()).unwrap().creator, CREATOR);
+        assert_eq!(get_claims(deps.as_ref(), "bob"), old_claims);
+        assert!(!LEGACY_CLAIMS.has(&deps.storage, &bob));
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // moving their tokens keeps the balance they voted with before
+        let mut env = mock_env();
+        env.block.height += 10;
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "bob".into(),
+            amount: Uint128::new(400),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("early-fan", &[]),
+            transfer,
+        )
+        .unwrap();
+        let before = query_balance_at(deps.as_ref(), "early-fan".into(), env.block.height - 5);
+        assert_eq!(before.unwrap().balance, Uint128::new(1_000));
+        let after = query_balance_at(deps.as_ref(), "early-fan".into(), env.block.height + 1);
+        assert_eq!(after.unwrap().balance, Uint128::new(600));
+
+        // migrating again is a no-op, but other contracts can't be migrated from
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.9.1").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrate {
+                contract: "crates.io:cw20-base".into(),
+                version: "0.9.1".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn token_info_is_plain_cw20() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        let bin = query(deps.as_ref(), mock_env(), QueryMsg::TokenInfo {}).unwrap();
+        let token: TokenInfoResponse = from_binary(&bin).unwrap();
+        assert_eq!(
+            token,
+            TokenInfoResponse {
+                name: msg.name,
+                symbol: msg.symbol,
+                decimals: 2,
+                total_supply: Uint128::zero(),
+            }
+        );
+
+        let bin = query(deps.as_ref(), mock_env(), QueryMsg::TokenInfoWithMeta {}).unwrap();
+        let meta: TokenInfoResponseWithMeta = from_binary(&bin).unwrap();
+        assert_eq!(meta.work, msg.work);
+        assert_eq!(meta.token_info_response, token);
+    }
+
+    #[test]
+    fn enumerable_queries_page_through_accounts() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        for buyer in &["carl", "alice", "bob"] {
+            let info = mock_info(buyer, &coins(500_000_000, DENOM));
+            let buy = ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            };
+            execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        }
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "dex".into(),
+            amount: Uint128::new(10),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), allow).unwrap();
+
+        let page = QueryMsg::AllAccounts {
+            start_after: None,
+            limit: Some(2),
+        };
+        let bin = query(deps.as_ref(), mock_env(), page).unwrap();
+        let accounts: AllAccountsResponse = from_binary(&bin).unwrap();
+        assert_eq!(accounts.accounts, vec!["alice", "bob"]);
+        let page = QueryMsg::AllAccounts {
+            start_after: Some("bob".into()),
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), mock_env(), page).unwrap();
+        let accounts: AllAccountsResponse = from_binary(&bin).unwrap();
+        assert_eq!(accounts.accounts, vec!["carl"]);
+
+        let allowances = QueryMsg::AllAllowances {
+            owner: "alice".into(),
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), mock_env(), allowances).unwrap();
+        let allowances: AllAllowancesResponse = from_binary(&bin).unwrap();
+        assert_eq!(allowances.allowances.len(), 1);
+        assert_eq!(allowances.allowances[0].spender, "dex");
+        assert_eq!(allowances.allowances[0].allowance, Uint128::new(10));
+    }
+
+    #[test]
+    fn marketing_belongs_to_the_creator() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.creator_address = Some("artist".into());
+        msg.marketing = Some(InstantiateMarketingInfo {
+            project: Some("https://squarepusher.net".into()),
+            description: None,
+            marketing: None,
+            logo: Some(Logo::Url("https://squarepusher.net/logo.png".into())),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let marketing = query_marketing_info(deps.as_ref()).unwrap();
+        assert_eq!(marketing.marketing, Some(Addr::unchecked("artist")));
+        assert_eq!(
+            marketing.logo,
+            Some(LogoInfo::Url("https://squarepusher.net/logo.png".into()))
+        );
+
+        let update = ExecuteMsg::UpdateMarketing {
+            project: None,
+            description: Some("Remastered".into()),
+            marketing: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Base(cw20_base::ContractError::Unauthorized {})
+        );
+        execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), update).unwrap();
+        let marketing = query_marketing_info(deps.as_ref()).unwrap();
+        assert_eq!(marketing.description, Some("Remastered".into()));
+
+        // handing over the creator role hands over marketing too
+        let transfer = ExecuteMsg::TransferCreator {
+            new_creator: "label".into(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("artist", &[]),
+            transfer,
+        )
+        .unwrap();
+        let info = mock_info("label", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptCreator {},
+        )
+        .unwrap();
+        let marketing = query_marketing_info(deps.as_ref()).unwrap();
+        assert_eq!(marketing.marketing, Some(Addr::unchecked("label")));
+    }
+
+    #[test]
+    fn stats_split_volume_by_direction() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(200_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 200_000_000, DENOM);
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        for payment in [500_000_000, 100_000_000] {
+            let info = mock_info(INVESTOR, &coins(payment, DENOM));
+            execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        }
+
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(100),
+            min_reserve_out: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), burn).unwrap();
+        // 2 of the 100 go to the owner as exit tax
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond).unwrap();
+
+        let stats = query_stats(deps.as_ref()).unwrap();
+        assert_eq!(
+            stats,
+            StatsResponse {
+                bought_reserve: Uint128::new(600_000_000),
+                sold_reserve: Uint128::new(100_000_000),
+                bonded: Uint128::new(200_000_000),
+                unbonded: Uint128::new(98_000_000),
+                buyers: 2,
+                buys: 2,
+                sells: 1,
+                bonds: 1,
+                unbonds: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn patron_tiers_follow_lifetime_support() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let tier = |name: &str, threshold: u128| PatronTier {
+            name: name.into(),
+            threshold: Uint128::new(threshold),
+        };
+        let set = ExecuteMsg::SetPatronTiers {
+            tiers: vec![tier("gold", 500_000_000), tier("silver", 100_000_000)],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), set).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPatronTiers {});
+        let set = ExecuteMsg::SetPatronTiers {
+            tiers: vec![tier("silver", 100_000_000), tier("gold", 500_000_000)],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            set.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), set).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(300_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        let patron = query_patron_tier(deps.as_ref(), INVESTOR.into()).unwrap();
+        assert_eq!(patron.contributed, Uint128::new(300_000_000));
+        assert_eq!(patron.tier, Some(tier("silver", 100_000_000)));
+        assert_eq!(patron.next_tier, Some(tier("gold", 500_000_000)));
+
+        // selling doesn't take support back
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(300),
+            min_reserve_out: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INVESTOR, &[]), burn).unwrap();
+        let info = mock_info(INVESTOR, &coins(200_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        let patron = query_patron_tier(deps.as_ref(), INVESTOR.into()).unwrap();
+        assert_eq!(patron.contributed, Uint128::new(500_000_000));
+        assert_eq!(patron.tier, Some(tier("gold", 500_000_000)));
+        assert_eq!(patron.next_tier, None);
+
+        let patron = query_patron_tier(deps.as_ref(), "bob".into()).unwrap();
+        assert_eq!(patron.tier, None);
+        assert_eq!(patron.next_tier, Some(tier("silver", 100_000_000)));
+    }
+
+    #[test]
+    fn large_buys_get_an_nft_receipt() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.nft_receipts = Some(NftReceipts {
+            collection: "receipts".into(),
+            min_reserve: Uint128::new(400_000_000),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(300_000_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+        assert!(res.messages.is_empty());
+
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        let meta = TOKEN_INFO_WITH_META.load(&deps.storage).unwrap();
+        let mint = Cw721ExecuteMsg::Mint(ReceiptMintMsg {
+            token_id: "1".into(),
+            owner: INVESTOR.into(),
+            token_uri: None,
+            extension: ReceiptMetadata {
+                creator: meta.creator,
+                work: meta.work,
+                description: meta.description,
+                external_permalink_uri: meta.external_permalink_uri,
+                reserve: Uint128::new(500_000_000),
+                reserve_denom: DENOM.into(),
+                tokens: Uint128::new(500),
+            },
+        });
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: "receipts".into(),
+                    msg: to_binary(&mint).unwrap(),
+                    funds: vec![],
+                },
+                RECEIPT_REPLY_ID
+            )]
+        );
+
+        // a collection that won't mint leaves the buy standing
+        let reply_msg = Reply {
+            id: RECEIPT_REPLY_ID,
+            result: ContractResult::Err("unauthorized".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(800));
+
+        let set = ExecuteMsg::SetNftReceipts { receipts: None };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), set).unwrap();
+        let receipts = query_nft_receipts(deps.as_ref()).unwrap();
+        assert_eq!(receipts.receipts, None);
+        assert_eq!(receipts.minted, 1);
+    }
+
+    #[test]
+    fn spot_price_at_height_uses_last_checkpoint() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut env = mock_env();
+        let opened_at = env.block.height;
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // nothing before the contract existed
+        let err = query_spot_price_at_height(deps.as_ref(), opened_at - 1).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+
+        env.block.height += 10;
+        let info = mock_info(INVESTOR, &coins(500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+
+        env.block.height += 10;
+        let info = mock_info(INVESTOR, &coins(1_500_000_000, DENOM));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
+
+        // opening price holds until the first buy
+        let price = query_spot_price_at_height(deps.as_ref(), opened_at + 5).unwrap();
+        assert_eq!(price.height, opened_at);
+        assert_eq!(price.spot_price, Decimal::zero());
+
+        let price = query_spot_price_at_height(deps.as_ref(), opened_at + 15).unwrap();
+        assert_eq!(price.height, opened_at + 10);
+        assert_eq!(price.spot_price, Decimal::percent(100));
+
+        let price = query_spot_price_at_height(deps.as_ref(), opened_at + 100).unwrap();
+        assert_eq!(price.height, opened_at + 20);
+        assert_eq!(price.spot_price, Decimal::percent(200));
+    }
+
+    //
+    //  ---- staking starts here ----
+    //
+
+    fn sample_validator(addr: &str) -> Validator {
+        Validator {
+            address: addr.into(),
+            commission: Decimal::percent(3),
+            max_commission: Decimal::percent(10),
+            max_change_rate: Decimal::percent(1),
+        }
+    }
+
+    fn set_validator(querier: &mut MockQuerier) {
+        querier.update_staking("ustake", &[sample_validator(DEFAULT_VALIDATOR)], &[]);
+    }
+
+    fn sample_delegation(addr: &str, amount: Coin) -> FullDelegation {
+        let can_redelegate = amount.clone();
+        let accumulated_rewards = coins(0, &amount.denom);
+        FullDelegation {
+            validator: addr.into(),
+            delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            amount,
+            can_redelegate,
+            accumulated_rewards,
+        }
+    }
+
+    fn set_delegation(querier: &mut MockQuerier, amount: u128, denom: &str) {
+        querier.update_staking(
+            "ustake",
+            &[sample_validator(DEFAULT_VALIDATOR)],
+            &[sample_delegation(DEFAULT_VALIDATOR, coin(amount, denom))],
+        );
+    }
+
+    // just a test helper, forgive the panic
+    fn later(env: &Env, delta: Duration) -> Env {
+        let time_delta = match delta {
+            Duration::Time(t) => t,
+            _ => panic!("Must provide duration in time"),
+        };
+        let mut res = env.clone();
+        res.block.time = res.block.time.plus_seconds(time_delta);
+        res
+    }
+
+    fn get_claims(deps: Deps, addr: &str) -> Vec<Claim> {
+        CLAIMS
+            .prefix(&Addr::unchecked(addr))
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.unwrap().1)
+            .collect()
+    }
+
+    #[test]
+    fn staking_tests_proper_staking_instantiation() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator("john"),
+                sample_validator("mary"),
+                sample_validator("my-validator-addr"),
+            ],
+            &[],
+        );
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        let creator = String::from("creator");
+        let msg = InstantiateMsg {
+            external_permalink_uri:
+                "https://squarepusher.bandcamp.com/album/feed-me-weird-things-remastered"
+                    .to_string(),
+            creator: "Squarepusher".to_string(),
+            work: "Feed Me Weird Things (Remaster)".to_string(),
+            description: "Feed Me Weird Things (Remaster) - Bandcamp".to_string(),
+            name: "Windscale2Coin".to_string(),
+            symbol: "WIND".to_string(),
+            decimals: 2,
+            reserve_denom: None,
+            reserve_decimals: 8,
+            asset_uri: None,
+            curve_type: curve_type.clone(),
+            swap_router: None,
+            registry: None,
+            marketing: None,
+            creator_address: None,
+            keeper_fee: None,
+            funding_goal: None,
+            max_supply: None,
+            sale_start: None,
+            sale_end: None,
+            reserve_token: None,
+            launch: None,
+            nft_receipts: None,
+            spread: None,
+            trade_cooldown: None,
+            unbond_limits: None,
+            purchase_only: None,
+            fee_config: None,
+            creator_escrow: None,
+            creator_allocation: None,
+            royalty_bps: None,
+            staking_params: StakingParams {
+                validator: String::from("my-validator-addr"),
+                unbonding_period: DAY * 3,
+                exit_tax: Decimal::percent(2),
+                min_withdrawal: Uint128::new(50),
+                allowed_validators: None,
+                target_weights: None,
+                reward_skim: None,
+                validator_approvers: None,
+                validator_approval_threshold: None,
+                exit_tax_brackets: None,
+                bond_tolerance: None,
+                max_exit_tax: None,
+                instant_unbond_penalty: None,
+                liquid_ratio: None,
+                claim_incentive: None,
+                fallback_validators: None,
+                unbond_epoch: None,
+                exit_tax_payout: None,
+                reward_strategy: None,
+                claim_expiry: None,
+                claim_recovery_address: None,
+            },
+        };
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // token info is proper
+        let token = query_token_info_with_meta(deps.as_ref()).unwrap();
+        assert_eq!(&token.token_info_response.name, &msg.name);
+        assert_eq!(&token.token_info_response.symbol, &msg.symbol);
+        assert_eq!(token.token_info_response.decimals, msg.decimals);
+        assert_eq!(token.token_info_response.total_supply, Uint128::zero());
+
+        // no balance
+        assert_eq!(get_balance(deps.as_ref(), &creator), Uint128::zero());
+        // no claims
+        assert_eq!(get_claims(deps.as_ref(), &creator), vec![]);
+
+        // investment info correct
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(&invest.owner, &creator);
+        assert_eq!(&invest.validator, &msg.staking_params.validator);
+        assert_eq!(invest.exit_tax, msg.staking_params.exit_tax);
+        assert_eq!(invest.min_withdrawal, msg.staking_params.min_withdrawal);
+
+        assert_eq!(invest.token_supply, Uint128::zero());
+        assert_eq!(invest.staked_tokens, coin(0, "ustake"));
+        // think this should be zero as nothing is staked yet
+        assert_eq!(invest.nominal_value, Decimal::zero());
+        // assert_eq!(invest.nominal_value, Decimal::one());
+    }
+
+    #[test]
+    fn staking_tests_instantiation_with_missing_validator() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier
+            .update_staking("ustake", &[sample_validator("john")], &[]);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        let creator = String::from("creator");
+        let msg = InstantiateMsg {
+            external_permalink_uri:
+                "https://squarepusher.bandcamp.com/album/feed-me-weird-things-remastered"
+                    .to_string(),
+            creator: "Squarepusher".to_string(),
+            work: "Feed Me Weird Things (Remaster)".to_string(),
+            description: "Feed Me Weird Things (Remaster) - Bandcamp".to_string(),
+            name: "Windscale2Coin".to_string(),
+            symbol: "WIND".to_string(),
+            decimals: 2,
+            reserve_denom: None,
+            reserve_decimals: 8,
+            asset_uri: None,
+            curve_type: curve_type.clone(),
+            swap_router: None,
+            registry: None,
+            marketing: None,
+            creator_address: None,
+            keeper_fee: None,
+            funding_goal: None,
+            max_supply: None,
+            sale_start: None,
+            sale_end: None,
+            reserve_token: None,
+            launch: None,
+            nft_receipts: None,
+            spread: None,
+            trade_cooldown: None,
+            unbond_limits: None,
+            purchase_only: None,
+            fee_config: None,
+            creator_escrow: None,
+            creator_allocation: None,
+            royalty_bps: None,
+            staking_params: StakingParams {
+                validator: String::from("my-validator-addr"),
+                unbonding_period: DAY * 3,
+                exit_tax: Decimal::percent(2),
+                min_withdrawal: Uint128::new(50),
+                allowed_validators: None,
+                target_weights: None,
+                reward_skim: None,
+                validator_approvers: None,
+                validator_approval_threshold: None,
+                exit_tax_brackets: None,
+                bond_tolerance: None,
+                max_exit_tax: None,
+                instant_unbond_penalty: None,
+                liquid_ratio: None,
+                claim_incentive: None,
+                fallback_validators: None,
+                unbond_epoch: None,
+                exit_tax_payout: None,
+                reward_strategy: None,
+                claim_expiry: None,
+                claim_recovery_address: None,
+            },
+        };
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotInValidatorSet {
+                validator: "my-validator-addr".into()
+            }
+        );
+    }
+
+    #[test]
+    fn staking_tests_bonding_issues_tokens() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        // deps.querier
+        //     .update_staking("ustake", &[sample_validator(DEFAULT_VALIDATOR)], &[]);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        //let curve_fn = curve_type.to_curve_fn(deps.querier);
+
+        let creator = String::from("creator");
+        let instantiate_msg = default_instantiate(
+            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
+            2,
+            8,
+            curve_type.clone(),
+            2,
+            50,
+        );
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // sense check
+        let loaded_curve_type = CURVE_TYPE.load(&deps.storage).unwrap();
+        //let curve_fn = loaded_curve_type.to_curve_fn();
+        assert_eq!(curve_type, loaded_curve_type);
+
+        // succeeds with proper token (5 BTC = 5*10^8 satoshi)
+        let bob = String::from("bob");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(
+            &bob,
+            &[
+                coin(1000, "this-one-is-ignored"),
+                coin(500_000_000, "ustake"),
+            ],
+        );
+
+        // try to bond and make sure we trigger delegation
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        let delegate = &res.messages[0];
+        match &delegate.msg {
+            CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
+                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
+                assert_eq!(amount, &coin(500_000_000, "ustake"));
+            }
+            _ => panic!("Unexpected message: {:?}", delegate),
+        }
+
+        // bob got 1000 derived tokens
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
+
+        // update delegations total
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // time for alice
+        // needs more to make it work
+        let alice = String::from("alice");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(
+            &alice,
+            &[
+                coin(2000, "this-one-is-ignored"),
+                coin(1_500_000_000, "ustake"),
+            ],
+        );
+
+        // try to bond and make sure we trigger delegation
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        let delegate = &res.messages[0];
+        match &delegate.msg {
+            CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
+                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
+                assert_eq!(amount, &coin(1_500_000_000, "ustake"));
+            }
+            _ => panic!("Unexpected message: {:?}", delegate),
+        }
+
+        // alice got 1000 derived tokens for a larger initial
+        assert_eq!(get_balance(deps.as_ref(), &alice), Uint128::new(1000));
+
+        // investment info correct (updated supply)
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.token_supply, Uint128::new(2000));
+        assert_eq!(invest.staked_tokens, coin(2_000_000_000, "ustake"));
+        assert_eq!(invest.nominal_value, Decimal::permille(2_000));
+
+        // token info also properly updated
+        let token = query_token_info_with_meta(deps.as_ref()).unwrap();
+        assert_eq!(token.token_info_response.total_supply, Uint128::new(2000));
+    }
+
+    #[test]
+    fn staking_tests_bond_for_recipient() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // a custodian bonds for bob
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: Some("bob".to_string()),
+        };
+        let info = mock_info("custodian", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "custodian"), Uint128::zero());
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(1000));
+
+        // if the delegation fails, bob's tokens are burned and the custodian refunded
+        let reply_msg = Reply {
+            id: BOND_REPLY_ID,
+            result: ContractResult::Err("validator jailed".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "custodian".into(),
+                amount: coins(500_000_000, "ustake"),
+            })]
+        );
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::zero());
+    }
+
+    #[test]
+    fn staking_tests_rebonding_changes_pricing() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        let creator = String::from("creator");
+        let instantiate_msg = default_instantiate(
+            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
+            2,
+            8,
+            curve_type.clone(),
+            2,
+            50,
+        );
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // let's bond some tokens now
+        let bob = String::from("bob");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(&bob, &[coin(10, "random"), coin(5_000_000, "ustake")]);
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // check bob got 100 derived tokens
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(100));
+
+        // update the querier with new bond
+        set_delegation(&mut deps.querier, 5_000_000, "ustake");
+
+        // fake a reinvestment (this must be sent by the contract itself)
+        let rebond_msg = ExecuteMsg::_BondAllTokens {};
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(500_000, "ustake"));
+        let _ = execute(deps.as_mut(), mock_env(), info, rebond_msg).unwrap();
+
+        // update the querier with new bond
+        set_delegation(&mut deps.querier, 5_500_000, "ustake");
+
+        // the rewards are bonded, but nothing is minted for them. Still 100 issued,
+        // each now redeeming for 10% more (a price of 0.11)
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.token_supply, Uint128::new(100));
+        assert_eq!(invest.staked_tokens, coin(5_500_000, "ustake"));
+        let spot_price = Decimal::from_str("0.11").unwrap();
+        assert_eq!(invest.nominal_value, spot_price);
+
+        // we bond some other tokens and get a different issuance price
+        let alice = String::from("alice");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(&alice, &[coin(1_500_000_000, "ustake")]);
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // update the querier with new bond
+        // i.e. alice + bob
+        set_delegation(&mut deps.querier, 1_505_500_000, "ustake");
+
+        // alice should have gotten over 1k but less than 2k
+        assert_eq!(get_balance(deps.as_ref(), &alice), Uint128::new(1554));
+
+        // 1554 + bob's 100, at the raised backing
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.token_supply, Uint128::new(1654));
+        assert_eq!(invest.staked_tokens, coin(1_505_500_000, "ustake"));
+        assert_eq!(invest.nominal_value, Decimal::from_str("1.8194").unwrap());
+    }
+
+    #[test]
+    fn staking_tests_bonding_fails_with_wrong_denom() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        let creator = String::from("creator");
+        let instantiate_msg = default_instantiate(
+            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
+            2,
+            8,
+            curve_type.clone(),
+            2,
+            50,
+        );
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // let's bond some tokens now
+        let bob = String::from("bob");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(&bob, &[coin(500, "photon")]);
+
+        // try to bond and make sure we trigger delegation
+        let err = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::EmptyBalance {
+                denom: "ustake".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn staking_tests_unbonding_changes_price_on_curve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        let creator = String::from("creator");
+        let instantiate_msg = default_instantiate(
+            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
+            2,
+            8,
+            curve_type.clone(),
+            10,
+            50,
+        );
+        let info = mock_info(&creator, &[]);
+
+        // make sure we can instantiate with this
+        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // let's bond some tokens now
+        let bob = String::from("bob");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(
+            &bob,
+            &[
+                coin(1000, "this-one-is-ignored"),
+                coin(500_000_000, "ustake"),
+            ],
+        );
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // update the querier with new bond
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // check bob's balance is 1k
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
+
+        // fake a reinvestment (this must be sent by the contract itself)
+        // after this we still have 1k issued, with 500_500_000 bonded
+        let rebond_msg = ExecuteMsg::_BondAllTokens {};
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(500_000, "ustake"));
+        let _ = execute(deps.as_mut(), mock_env(), info, rebond_msg).unwrap();
+
+        // update the querier with new bond, lower balance
+        set_delegation(&mut deps.querier, 500_500_000, "ustake");
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+
+        // creator now tries to unbond these tokens - this must fail
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(500_000),
+        };
+        let info = mock_info(&creator, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, unbond_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::overflow(OverflowError::new(
+                OverflowOperation::Sub,
+                0,
+                500_000
+            )))
+        );
+
+        // bob unbonds 100 tokens at 10% tax...
+        // 10 are taken and send to the owner
+        // 90 are unbonded in exchange for native tokens
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        let owner_cut = Uint128::new(10);
+        let bobs_claim = Uint128::new(86_035_950);
+        let bobs_balance = Uint128::new(900);
+        let env = mock_env();
+        let info = mock_info(&bob, &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, unbond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        let delegate = &res.messages[0];
+        match &delegate.msg {
+            CosmosMsg::Staking(StakingMsg::Undelegate { validator, amount }) => {
+                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
+                assert_eq!(amount, &coin(bobs_claim.u128(), "ustake"));
+            }
+            _ => panic!("Unexpected message: {:?}", delegate),
+        }
+
+        // update the querier with new bond, lower balance
+        // i.e 500_500_000 minus 86_035_950
+        set_delegation(&mut deps.querier, 414_464_050, "ustake");
+
+        // check balances
+        assert_eq!(get_balance(deps.as_ref(), &bob), bobs_balance);
+        assert_eq!(get_balance(deps.as_ref(), &creator), owner_cut);
+        // proper claims
+        let expected_claims = vec![Claim {
+            amount: bobs_claim,
+            release_at: (DAY * 3).after(&env.block),
+        }];
+        assert_eq!(expected_claims, get_claims(deps.as_ref(), &bob));
+
+        // spot price has changed, and still carries the rewards
+        let spot_price = Decimal::from_str("0.91091").unwrap();
+
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.token_supply, bobs_balance + owner_cut);
+        assert_eq!(invest.staked_tokens, coin(414_464_050, "ustake")); // 500_500_000 minus 86_035_950
+        assert_eq!(invest.nominal_value, spot_price);
+    }
+
+    #[test]
+    fn staking_tests_claims_paid_out_properly() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        // TODO test one with sqrt?
+        // let curve_type = CurveType::SquareRoot {
+        //     slope: Uint128::new(1),
+        //     scale: 2,
+        // };
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+
+        // create contract
+        let creator = String::from("creator");
+        let instantiate_msg = default_instantiate(
+            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
+            2,
+            8,
+            curve_type.clone(),
+            10,
+            50,
+        );
+        let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // bond some tokens
+        // let bob = String::from("bob");
+        // let info = mock_info(&bob, &coins(1000, "ustake"));
+        // execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond { validator: None, recipient: None }).unwrap();
+        // set_delegation(&mut deps.querier, 1000, "ustake");
+
+        // let's bond some tokens now
+        let bob = String::from("bob");
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let bond_info = mock_info(
+            &bob,
+            &[
+                coin(1000, "this-one-is-ignored"),
+                coin(500_000_000, "ustake"),
+            ],
+        );
+        let res = execute(deps.as_mut(), mock_env(), bond_info, bond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // update the querier with new bond
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // check bob's balance is 1k
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
+
+        // unbond part of them
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        let env = mock_env();
+        let info = mock_info(&bob, &[]);
+        execute(deps.as_mut(), env.clone(), info.clone(), unbond_msg).unwrap();
+        set_delegation(&mut deps.querier, 336_200_000, "ustake");
+
+        // bobs balance has gone down
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(800));
+
+        // overall staked tokens has only gone down by
+        // 500_000_000 - bob's claim
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.staked_tokens, coin(336_200_000, "ustake"));
+
+        // ensure claims are proper
+        let bobs_claim = Uint128::new(163_800_000);
+        let original_claims = vec![Claim {
+            amount: bobs_claim,
+            release_at: (DAY * 3).after(&env.block),
+        }];
+        assert_eq!(original_claims, get_claims(deps.as_ref(), &bob));
+
+        // bob cannot exercise claims without enough balance
+        let claim_ready = later(&env, (DAY * 3 + HOUR).unwrap());
+        let too_soon = later(&env, DAY);
+        let fail = execute(
+            deps.as_mut(),
+            claim_ready.clone(),
+            info.clone(),
+            ExecuteMsg::Claim {},
+        );
+        assert!(fail.is_err(), "{:?}", fail);
+
+        // provide the balance to cover bob's claim
+        // but claim is not yet mature - also prohibited
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(163_800_000, "ustake"));
+        let fail = execute(deps.as_mut(), too_soon, info.clone(), ExecuteMsg::Claim {});
+        assert!(fail.is_err(), "{:?}", fail);
+
+        // this should work with cash and claims ready
+        let res = execute(
+            deps.as_mut(),
+            claim_ready,
+            info.clone(),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        let payout = &res.messages[0];
+        match &payout.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                assert_eq!(amount, &coins(163_800_000, "ustake"));
+                assert_eq!(to_address, &bob);
+            }
+            _ => panic!("Unexpected message: {:?}", payout),
+        }
+
+        // claims have been removed
+        assert_eq!(get_claims(deps.as_ref(), &bob), vec![]);
+    }
+
+    #[test]
+    fn staking_tests_unbond_from_uses_allowance() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "custodian".into(),
+            amount: Uint128::new(200),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), allow).unwrap();
+
+        // can't go over the allowance
+        let unbond_from = ExecuteMsg::UnbondFrom {
+            owner: "bob".into(),
+            amount: Uint128::new(201),
+            claim_to_spender: None,
+        };
+        let info = mock_info("custodian", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), unbond_from).unwrap_err();
+        assert!(matches!(err, ContractError::Base(_)));
+
+        // the claim goes to the owner by default
+        let unbond_from = ExecuteMsg::UnbondFrom {
+            owner: "bob".into(),
+            amount: Uint128::new(200),
+            claim_to_spender: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, unbond_from).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(800));
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob")[0].amount,
+            Uint128::new(163_800_000)
+        );
+        assert_eq!(get_claims(deps.as_ref(), "custodian"), vec![]);
+    }
+
+    #[test]
+    fn staking_tests_slashing_is_written_down() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        let spot_price = get_investment(deps.as_ref()).unwrap().nominal_value;
+
+        // nothing to do while the books match
+        let info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Reconcile {},
+        )
+        .unwrap();
+        assert!(res.events.is_empty());
+
+        // the validator gets slashed by 20%
+        set_delegation(&mut deps.querier, 400_000_000, "ustake");
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Reconcile {}).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("slashing")
+                .add_attribute("lost", "100000000")
+                .add_attribute("backing", "0.8")]
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.staked_tokens, coin(400_000_000, "ustake"));
+        assert_eq!(
+            invest.nominal_value,
+            Decimal::from_ratio(Uint128::new(8) * spot_price, Uint128::new(10))
+        );
+
+        // unbonding still works, paying out 80% of what it would have
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond_msg).unwrap();
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob")[0].amount,
+            Uint128::new(131_040_000)
+        );
+    }
+
+    #[test]
+    fn staking_tests_reserve_donations_raise_backing() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // nobody holds anything to back yet
+        let info = mock_info("fan", &coins(100_000_000, "ustake"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::DonateToReserve {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToBack {});
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let bob = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), bob, bond_msg).unwrap();
+        let supply = get_investment(deps.as_ref()).unwrap().token_supply;
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::DonateToReserve {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.to_string(),
+                amount: coin(100_000_000, "ustake"),
+            })]
+        );
+        set_delegation(&mut deps.querier, 600_000_000, "ustake");
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.staked_tokens, coin(600_000_000, "ustake"));
+        assert_eq!(invest.token_supply, supply);
+
+        // the same unbond as when slashed to 80% pays out 120% of the undonated amount
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond_msg).unwrap();
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob")[0].amount,
+            Uint128::new(196_560_000)
+        );
+    }
+
+    #[test]
+    fn staking_tests_burning_without_refund_raises_backing() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(500));
+
+        let burn = |amount| ExecuteMsg::BurnWithoutRefund {
+            amount: Uint128::new(amount),
+        };
+        let info = mock_info("bob", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), burn(500)).unwrap_err();
+        assert_eq!(err, ContractError::BurnWouldOrphanReserve {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), burn(100)).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(400));
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.staked_tokens, coin(500_000_000, "ustake"));
+        assert_eq!(invest.token_supply, Uint128::new(400));
+
+        // the 400 left share all 500 of the reserve
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), info, unbond_msg).unwrap();
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob")[0].amount,
+            Uint128::new(225_000_000)
+        );
+    }
+
+    #[test]
+    fn staking_tests_health_flags_drift() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        let health = query_health(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(health.reserve, Uint128::new(500_000_000));
+        assert_eq!(health.staked, Uint128::new(500_000_000));
+        assert_eq!(health.supply, health.total_supply);
+        assert!(health.solvent);
+
+        // a slash shows up before anyone reconciles
+        set_delegation(&mut deps.querier, 400_000_000, "ustake");
+        let health = query_health(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(health.reserve, Uint128::new(500_000_000));
+        assert_eq!(health.staked, Uint128::new(400_000_000));
+        assert!(!health.solvent);
+
+        let info = mock_info("anyone", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Reconcile {}).unwrap();
+        let health = query_health(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(health.reserve, Uint128::new(400_000_000));
+        assert!(health.solvent);
+    }
+
+    #[test]
+    fn staking_tests_balance_changes_carry_cw20_attributes() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert!(res.attributes.ends_with(&[
+            attr("action", "mint"),
+            attr("to", "bob"),
+            attr("amount", "500"),
+        ]));
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // the burn and the exit tax minted to the owner both show up as cw20-base would
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond_msg).unwrap();
+        assert!(res.attributes.ends_with(&[
+            attr("action", "burn"),
+            attr("from", "bob"),
+            attr("amount", "200"),
+            attr("action", "mint"),
+            attr("to", CREATOR),
+            attr("amount", "20"),
+        ]));
+    }
+
+    #[test]
+    fn staking_tests_bond_tolerance_ignores_dust() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        msg.staking_params.bond_tolerance = Some(Uint128::new(10));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone()).unwrap();
+
+        // a few units either way are fine, and aren't treated as slashing
+        set_delegation(&mut deps.querier, 500_000_010, "ustake");
+        execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone()).unwrap();
+        set_delegation(&mut deps.querier, 999_999_990, "ustake");
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone()).unwrap();
+        assert!(res.events.is_empty());
+
+        // but not more than that
+        set_delegation(&mut deps.querier, 1_500_000_011, "ustake");
+        let err = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::BondedMismatch {
+                stored: Uint128::new(1_500_000_000),
+                queried: Uint128::new(1_500_000_011)
+            }
+        );
+    }
+
+    #[test]
+    fn staking_tests_pause_still_allows_claims() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone()).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        let bob = mock_info("bob", &[]);
+        execute(deps.as_mut(), mock_env(), bob.clone(), unbond_msg).unwrap();
+
+        // only the owner can pull the brake
+        let err =
+            execute(deps.as_mut(), mock_env(), bob.clone(), ExecuteMsg::Pause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let owner = mock_info(CREATOR, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        let paused: PausedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap()).unwrap();
+        assert!(paused.paused);
+
+        let err = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap_err();
+        assert_eq!(err, ContractError::ContractPaused {});
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "alice".into(),
+            amount: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), bob.clone(), transfer.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ContractPaused {});
+
+        // matured claims can still be collected
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(163_800_000, "ustake"));
+        let claim_ready = later(&mock_env(), (DAY * 3 + HOUR).unwrap());
+        execute(
+            deps.as_mut(),
+            claim_ready,
+            bob.clone(),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+
+        execute(deps.as_mut(), mock_env(), owner, ExecuteMsg::Unpause {}).unwrap();
+        execute(deps.as_mut(), mock_env(), bob, transfer).unwrap();
+    }
+
+    #[test]
+    fn halting_one_side_leaves_the_other_open() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap();
+
+        let halt = |buys, sells| ExecuteMsg::SetHalts { buys, sells };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            halt(Some(true), None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let owner = mock_info(CREATOR, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            halt(Some(true), None),
+        )
+        .unwrap();
+
+        // with buys halted holders can still sell
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::BuysHalted {});
+        let sell = ExecuteMsg::Burn {
+            amount: Uint128::new(10),
+            min_reserve_out: None,
+        };
+        let seller = mock_info(INVESTOR, &[]);
+        execute(deps.as_mut(), mock_env(), seller.clone(), sell.clone()).unwrap();
+
+        // and the other way round
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner,
+            halt(Some(false), Some(true)),
+        )
+        .unwrap();
+        let paused = query_paused(deps.as_ref()).unwrap();
+        assert_eq!(
+            paused,
+            PausedResponse {
+                paused: false,
+                buys_halted: false,
+                sells_halted: true,
+            }
+        );
+        let err = execute(deps.as_mut(), mock_env(), seller, sell).unwrap_err();
+        assert_eq!(err, ContractError::SellsHalted {});
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(190));
+    }
+
+    #[test]
+    fn buy_exact_refunds_the_change() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.spread = Some(Decimal::permille(3));
+        msg.fee_config = Some(FeeConfig {
+            collector: "launchpad".into(),
+            buy_fee_bps: 100,
+            sell_fee_bps: 100,
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(123_456_789, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+
+        let buy_exact = ExecuteMsg::BuyExact {
+            tokens: Uint128::new(150),
+        };
+        let info = mock_info(BUYER, &coins(1_000, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, buy_exact.clone()).unwrap_err();
+        let cost = match err {
+            ContractError::BuyExactUnderpaid { cost } => cost,
+            err => panic!("unexpected error: {}", err),
+        };
+        assert_eq!(cost, Uint128::new(86_242_886));
+
+        // paying over the odds buys the same 150, with the change sent back
+        let info = mock_info(BUYER, &coins(cost.u128() + 5_000_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, buy_exact).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(150));
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BUYER.into(),
+                amount: coins(5_000_000, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn sell_exact_reserve_burns_what_it_takes() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.spread = Some(Decimal::permille(3));
+        msg.fee_config = Some(FeeConfig {
+            collector: "launchpad".into(),
+            buy_fee_bps: 100,
+            sell_fee_bps: 100,
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(123_456_789, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        let bought = get_balance(deps.as_ref(), INVESTOR);
+
+        let sell = |max_supply_in| ExecuteMsg::SellExactReserve {
+            reserve_out: Uint128::new(40_000_000),
+            max_supply_in,
+        };
+        let seller = mock_info(INVESTOR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            seller.clone(),
+            sell(Uint128::new(1)),
+        )
+        .unwrap_err();
+        let amount = match err {
+            ContractError::SupplyInExceeded { amount, .. } => amount,
+            err => panic!("unexpected error: {}", err),
+        };
+        let res = execute(deps.as_mut(), mock_env(), seller.clone(), sell(amount)).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), bought - amount);
+        // tokens come in whole units, so the last one takes it a little over
+        assert_eq!(amount, Uint128::new(90));
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(40_244_546, DENOM),
+            })
+        );
+
+        let too_much = ExecuteMsg::SellExactReserve {
+            reserve_out: Uint128::new(1_000_000_000),
+            max_supply_in: bought,
+        };
+        let err = execute(deps.as_mut(), mock_env(), seller, too_much).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientReserve {
+                available: Uint128::new(81_571_166)
+            }
+        );
+    }
+
+    #[test]
+    fn purchase_bonds_a_staked_reserve_and_buys_otherwise() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        msg.purchase_only = Some(true);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        for msg in [buy, bond] {
+            let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert_eq!(err, ContractError::UsePurchase {});
+        }
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Purchase {}).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(100_000_000, DENOM),
+            })
+        );
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(100));
+
+        // a reserve that isn't staked is simply bought with
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.reserve_denom = Some("ujuno".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let info = mock_info(INVESTOR, &coins(100_000_000, "ujuno"));
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Purchase {}).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(100));
+    }
+    #[test]
+    fn sells_wait_out_the_trade_cooldown() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.trade_cooldown = Some(Duration::Height(5));
+        let mut env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info(CREATOR, &[]), msg).unwrap();
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, buy).unwrap();
+
+        let sell = ExecuteMsg::Burn {
+            amount: Uint128::new(10),
+            min_reserve_out: None,
+        };
+        let seller = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), env.clone(), seller.clone(), sell.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CooldownActive {
+                until: Expiration::AtHeight(env.block.height + 5)
+            }
+        );
+
+        env.block.height += 5;
+        execute(deps.as_mut(), env, seller, sell).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(90));
+    }
+    #[test]
+    fn staking_tests_ownership_transfer_needs_acceptance() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let transfer = ExecuteMsg::TransferOwnership {
+            new_owner: "dao".into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingOwner {});
+
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), transfer).unwrap();
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.owner, CREATOR);
+        assert_eq!(invest.pending_owner, Some("dao".to_string()));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.owner, "dao");
+        assert_eq!(invest.pending_owner, None);
+
+        // admin powers moved too
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn staking_tests_instant_unbond_pays_from_liquid_reserve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // the default 5% penalty is more than bob will stand
+        let bob = mock_info("bob", &[]);
+        let instant = ExecuteMsg::InstantUnbond {
+            amount: Uint128::new(200),
+            max_penalty: Some(Decimal::percent(1)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), bob.clone(), instant).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PenaltyExceeded {
+                penalty: Decimal::percent(5),
+                max: Decimal::percent(1),
+            }
+        );
+
+        // paid now out of what is liquid, while the stake is undelegated to refill it
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(300_000_000, "ustake"));
+        let instant = ExecuteMsg::InstantUnbond {
+            amount: Uint128::new(200),
+            max_penalty: Some(Decimal::percent(5)),
+        };
+        let res = execute(deps.as_mut(), mock_env(), bob.clone(), instant.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(StakingMsg::Undelegate {
+                    validator: DEFAULT_VALIDATOR.into(),
+                    amount: coin(200_000_000, "ustake"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "bob".into(),
+                    amount: coins(190_000_000, "ustake"),
+                }),
+            ]
+        );
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(300));
+        assert!(get_claims(deps.as_ref(), "bob").is_empty());
+
+        set_delegation(&mut deps.querier, 300_000_000, "ustake");
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(110_000_000, "ustake"));
+        let err = execute(deps.as_mut(), mock_env(), bob, instant).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientLiquidity {
+                available: Uint128::new(110_000_000)
+            }
+        );
+    }
+
+    #[test]
+    fn rewards_join_the_curve_reserve_without_minting() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        // a bond is delegated right away, a buy stays liquid until the next reinvest
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 100_000_000, DENOM);
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let info = mock_info(BUYER, &coins(50_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+        let curve_fn = curve_type.to_curve_fn(deps.as_ref().querier);
+        let state = query_curve_info(deps.as_ref(), curve_fn).unwrap();
+        assert_eq!(state.reserve, Uint128::new(150_000_000));
+        assert_eq!(state.staked_balance, Uint128::new(100_000_000));
+        assert_eq!(state.supply, Uint128::new(150));
+
+        // the buy and 10 in rewards are on hand. All of it is delegated,
+        // but only the rewards are new to the curve
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(60_000_000, DENOM));
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::_BondAllTokens {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(60_000_000, DENOM),
+            })]
+        );
+        assert!(res.attributes.contains(&attr("rewards", "10000000")));
+        let curve_fn = curve_type.to_curve_fn(deps.as_ref().querier);
+        let state = query_curve_info(deps.as_ref(), curve_fn).unwrap();
+        assert_eq!(state.reserve, Uint128::new(160_000_000));
+        assert_eq!(state.staked_balance, Uint128::new(160_000_000));
+        assert_eq!(state.supply, Uint128::new(150));
+    }
+
+    #[test]
+    fn reward_strategy_routes_rewards() {
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let setup = |strategy: RewardStrategy| {
+            let mut deps = mock_dependencies(&[]);
+            set_validator(&mut deps.querier);
+            let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+            msg.staking_params.reward_strategy = Some(strategy);
+            instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+            let bond = ExecuteMsg::Bond {
+                validator: None,
+                recipient: None,
+            };
+            let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+            execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+            set_delegation(&mut deps.querier, 100_000_000, DENOM);
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(10_000_000, DENOM));
+            deps
+        };
+        let reinvest = |deps: &mut OwnedDeps<_, _, _>| {
+            let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                ExecuteMsg::_BondAllTokens {},
+            )
+            .unwrap()
+        };
+
+        // the creator can't take more than all of it
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 2, 50);
+        msg.staking_params.reward_strategy = Some(RewardStrategy::SplitWithCreator { bps: 10_001 });
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRewardStrategy {});
+
+        // a quarter goes to the funding pool, the rest compounds
+        let mut deps = setup(RewardStrategy::SplitWithCreator { bps: 2_500 });
+        let res = reinvest(&mut deps);
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(7_500_000, DENOM),
+            })]
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.funding_pool, coin(2_500_000, DENOM));
+        assert_eq!(invest.token_supply, Uint128::new(100));
+        assert_eq!(
+            CURVE_STATE.load(&deps.storage).unwrap().curve_reserve,
+            Uint128::new(107_500_000)
+        );
+
+        // all of it is kept liquid for holders, the curve is untouched
+        let mut deps = setup(RewardStrategy::DistributeToHolders {});
+        let res = reinvest(&mut deps);
+        assert!(res.messages.is_empty());
+        assert!(res.attributes.contains(&attr("to_holders", "10000000")));
+        assert!(res
+            .attributes
+            .contains(&attr("rewards_per_token", "100000")));
+        assert_eq!(
+            CURVE_STATE.load(&deps.storage).unwrap().curve_reserve,
+            Uint128::new(100_000_000)
+        );
+        // and a second reinvest finds nothing new
+        let res = reinvest(&mut deps);
+        assert_eq!(res, Response::default());
+    }
+
+    #[test]
+    fn holders_claim_rewards_pro_rata() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        msg.staking_params.reward_strategy = Some(RewardStrategy::DistributeToHolders {});
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 100_000_000, DENOM);
+        let reinvest = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, balance: u128| {
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(balance, DENOM));
+            let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                ExecuteMsg::_BondAllTokens {},
+            )
+            .unwrap();
+        };
+
+        // the first rewards all go to the only holder, who then hands half their tokens on
+        reinvest(&mut deps, 10_000_000);
+        let transfer = ExecuteMsg::Transfer {
+            recipient: BUYER.to_string(),
+            amount: Uint128::new(50),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            transfer,
+        )
+        .unwrap();
+        let claimable = query_claimable_rewards(deps.as_ref(), mock_env(), BUYER.into()).unwrap();
+        assert_eq!(claimable.claimable, coin(0, DENOM));
+
+        // the next are shared equally
+        reinvest(&mut deps, 20_000_000);
+        let claimable = query_claimable_rewards(deps.as_ref(), mock_env(), BUYER.into()).unwrap();
+        assert_eq!(
+            claimable,
+            ClaimableRewardsResponse {
+                claimable: coin(5_000_000, DENOM),
+                rewards_per_token: Decimal::from_str("200000").unwrap(),
+            }
+        );
+        let info = mock_info(INVESTOR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::ClaimRewards {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(15_000_000, DENOM),
+            })]
+        );
+        let err =
+            execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ClaimRewards {}).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+        let holder_rewards = HOLDER_REWARDS.load(&deps.storage).unwrap();
+        assert_eq!(holder_rewards.unclaimed, Uint128::new(5_000_000));
+    }
+
+    #[test]
+    fn rewards_can_be_paid_to_a_withdraw_address() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 100_000_000, DENOM);
+
+        let set = |address: Option<&str>| ExecuteMsg::SetRewardWithdrawAddress {
+            address: address.map(Into::into),
+        };
+        let info = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, set(Some("splitter"))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(CREATOR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, set(Some("splitter"))).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(DistributionMsg::SetWithdrawAddress {
+                address: "splitter".into()
+            })]
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.reward_withdraw_address, Some("splitter".into()));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Reinvest {},
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("rewards_to", "splitter")));
+
+        // and back to the contract
+        let info = mock_info(CREATOR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, set(None)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(DistributionMsg::SetWithdrawAddress {
+                address: MOCK_CONTRACT_ADDR.into()
+            })]
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.reward_withdraw_address, None);
+    }
+
+    #[test]
+    fn claim_estimates_follow_the_chains_undelegations() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: StargateQuerier {
+                base,
+                unbonding_time: 3 * 24 * 60 * 60,
+                unbonding_entries: vec![],
+            },
+        };
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.unbond_epoch = Some(DAY);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier.base, 500_000_000, "ustake");
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond).unwrap();
+        let start = mock_env().block.time;
+        let released = Expiration::AtTime(start.plus_seconds(4 * 86_400));
+
+        // the epoch is over but nobody has processed it, so the chain's unbonding
+        // only starts now
+        let mut env = mock_env();
+        env.block.time = start.plus_seconds(2 * 86_400);
+        let estimates = query_claim_estimates(deps.as_ref(), env.clone(), "bob".into(), None, None)
+            .unwrap()
+            .claims;
+        let completes = start.plus_seconds(5 * 86_400);
+        assert_eq!(
+            estimates,
+            vec![ClaimEstimate {
+                id: 1,
+                amount: Uint128::new(100_000_000),
+                release_at: released,
+                undelegation_completes: Some(completes),
+                estimated_payout: Expiration::AtTime(completes),
+            }]
+        );
+
+        // once undelegated, the chain says when it comes back
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("keeper", &[]),
+            ExecuteMsg::ProcessEpoch {},
+        )
+        .unwrap();
+        let on_chain = start.seconds() + 5 * 86_400 + 1;
+        deps.querier.unbonding_entries = vec![(on_chain, 100_000_000)];
+        let estimates = query_claim_estimates(deps.as_ref(), mock_env(), "bob".into(), None, None)
+            .unwrap()
+            .claims;
+        let completes = Timestamp::from_seconds(on_chain);
+        assert_eq!(estimates[0].undelegation_completes, Some(completes));
+        assert_eq!(estimates[0].estimated_payout, Expiration::AtTime(completes));
+
+        // and once it is back, the claim pays out when it matures
+        deps.querier.unbonding_entries = vec![];
+        let estimates = query_claim_estimates(deps.as_ref(), mock_env(), "bob".into(), None, None)
+            .unwrap()
+            .claims;
+        assert_eq!(estimates[0].undelegation_completes, None);
+        assert_eq!(estimates[0].estimated_payout, released);
+    }
+
+    #[test]
+    fn expired_claims_are_recycled_into_the_reserve() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.claim_expiry = Some(Duration::Height(100));
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidClaimExpiry {});
+        msg.staking_params.claim_expiry = Some(DAY * 7);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond).unwrap();
+
+        // matured, but not yet unclaimed for long enough
+        let recycle = ExecuteMsg::RecycleExpiredClaims { limit: None };
+        let keeper = mock_info("keeper", &[]);
+        let env = later(&mock_env(), DAY * 9);
+        let err = execute(deps.as_mut(), env, keeper.clone(), recycle.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NoExpiredClaims {});
+
+        let env = later(&mock_env(), DAY * 10);
+        let res = execute(deps.as_mut(), env, keeper, recycle).unwrap();
+        assert_eq!(res.messages, vec![]);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "recycle_expired_claims"),
+                attr("recycled", "100000000"),
+                attr("backing", "1.25"),
+            ]
+        );
+        assert_eq!(get_claims(deps.as_ref(), "bob"), vec![]);
+        let curve_state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(curve_state.claims, Uint128::zero());
+        assert_eq!(curve_state.curve_reserve, Uint128::new(500_000_000));
+    }
+
+    #[test]
+    fn wind_down_settles_holders_pro_rata() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(400_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond.clone()).unwrap();
+        set_delegation(&mut deps.querier, 400_000_000, "ustake");
+        let info = mock_info(INVESTOR, &coins(100_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond.clone()).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        let wind_down = ExecuteMsg::InitiateWindDown {};
+        let info = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, wind_down.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(CREATOR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, wind_down).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Undelegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(500_000_000, "ustake"),
+            })]
+        );
+
+        // no more buying or selling, and nothing to settle until the stake is back
+        let info = mock_info("carl", &coins(100_000_000, "ustake"));
+        let err = execute(deps.as_mut(), mock_env(), info, bond).unwrap_err();
+        assert_eq!(err, ContractError::WindingDown {});
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond).unwrap_err();
+        assert_eq!(err, ContractError::WindingDown {});
+        let settles_at = (DAY * 3).after(&mock_env().block);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::Settle {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SettlementNotOpen { at: settles_at });
+
+        set_delegation(&mut deps.querier, 0, "ustake");
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(500_000_000, "ustake"));
+        let env = later(&mock_env(), DAY * 3);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            ExecuteMsg::Settle {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".into(),
+                amount: coins(400_000_000, "ustake"),
+            })]
+        );
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::zero());
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100_000_000, "ustake"));
+        let status = query_wind_down(deps.as_ref(), env.clone()).unwrap();
+        assert!(status.settlement_open);
+        assert_eq!(status.settleable, Uint128::new(100_000_000));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            ExecuteMsg::Settle {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: INVESTOR.into(),
+                amount: coins(100_000_000, "ustake"),
+            })]
+        );
+        let curve_state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(curve_state.supply, Uint128::zero());
+        assert_eq!(curve_state.curve_reserve, Uint128::zero());
+    }
+
+    #[test]
+    fn wind_down_settles_a_cw20_reserve() {
+        let mut base = MockQuerier::new(&[]);
+        set_validator(&mut base);
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Cw20BalanceQuerier {
+                base,
+                balance: Uint128::new(500_000_000),
+            },
+        };
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.reserve_token = Some("stablecoin".into());
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: INVESTOR.into(),
+            amount: Uint128::new(500_000_000),
+            msg: to_binary(&ReceiveMsg::Buy {
+                min_supply_out: None,
+                recipient: None,
+            })
+            .unwrap(),
+        });
+        let info = mock_info("stablecoin", &[]);
+        execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+        let info = mock_info(CREATOR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::InitiateWindDown {},
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+
+        // nothing to pay out, so nothing is burned
+        let env = later(&mock_env(), DAY * 3);
+        deps.querier.balance = Uint128::zero();
+        let info = mock_info(INVESTOR, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Settle {}).unwrap_err();
+        assert_eq!(err, ContractError::NothingToSettle {});
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(500));
+
+        // the payout is the cw20 balance, sent back through the token
+        deps.querier.balance = Uint128::new(500_000_000);
+        let status = query_wind_down(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(status.settleable, Uint128::new(500_000_000));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(INVESTOR, &[]),
+            ExecuteMsg::Settle {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "stablecoin".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: INVESTOR.into(),
+                    amount: Uint128::new(500_000_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::zero());
+    }
+
+    #[test]
+    fn unbonds_are_rate_limited() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.unbond_limits = Some(UnbondLimits {
+            per_block: Some(Uint128::new(300)),
+            per_day: Some(Uint128::zero()),
+        });
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidUnbondLimits {});
+        msg.unbond_limits = Some(UnbondLimits {
+            per_block: Some(Uint128::new(300)),
+            per_day: Some(Uint128::new(500)),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(1_000_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 1_000_000_000, "ustake");
+
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        let bob = mock_info("bob", &[]);
+        let mut env = mock_env();
+        execute(deps.as_mut(), env.clone(), bob.clone(), unbond.clone()).unwrap();
+        set_delegation(&mut deps.querier, 800_000_000, "ustake");
+        let err = execute(deps.as_mut(), env.clone(), bob.clone(), unbond.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RateLimited {
+                available: Uint128::new(100),
+                retry_at: Expiration::AtHeight(env.block.height + 1),
+            }
+        );
+
+        // the next block has room again, until the day's limit is reached
+        env.block.height += 1;
+        execute(deps.as_mut(), env.clone(), bob.clone(), unbond.clone()).unwrap();
+        set_delegation(&mut deps.querier, 600_000_000, "ustake");
+        env.block.height += 1;
+        let capacity = query_unbond_capacity(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(
+            capacity,
+            UnbondCapacityResponse {
+                remaining_in_block: Some(Uint128::new(300)),
+                remaining_in_day: Some(Uint128::new(100)),
+                remaining: Some(Uint128::new(100)),
+            }
+        );
+        let err = execute(deps.as_mut(), env.clone(), bob.clone(), unbond.clone()).unwrap_err();
+        let tomorrow = (env.block.time.seconds() / 86_400 + 1) * 86_400;
+        assert_eq!(
+            err,
+            ContractError::RateLimited {
+                available: Uint128::new(100),
+                retry_at: Expiration::AtTime(Timestamp::from_seconds(tomorrow)),
+            }
+        );
+        env.block.time = Timestamp::from_seconds(tomorrow);
+        execute(deps.as_mut(), env, bob, unbond).unwrap();
+    }
+
+    #[test]
+    fn royalty_info_follows_cw2981() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.royalty_bps = Some(10_001);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidRoyalty {});
+        msg.royalty_bps = Some(250);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let info: RoyaltyInfoResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RoyaltyInfo {
+                    sale_price: Uint128::new(1_000),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            RoyaltyInfoResponse {
+                address: CREATOR.into(),
+                royalty_amount: Uint128::new(25),
+                royalty_bps: 250,
+            }
+        );
+
+        let set = ExecuteMsg::SetRoyalty { bps: 0 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INVESTOR, &[]),
+            set.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), set).unwrap();
+        let check: CheckRoyaltiesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap())
+                .unwrap();
+        assert!(!check.royalty_payments);
+    }
+
+    #[test]
+    fn instantiate_registers_with_the_registry() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type.clone(), 0, 50);
+        msg.registry = Some("catalog".into());
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let registration = BondcampRegistration {
+            address: MOCK_CONTRACT_ADDR.into(),
+            creator: CREATOR.into(),
+            work: "Feed Me Weird Things (Remaster)".into(),
+            name: "Windscale2Coin".into(),
+            symbol: "WIND".into(),
+            decimals: 2,
+            curve_type,
+            reserve_denom: DENOM.into(),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "catalog".into(),
+                msg: to_binary(&RegistryExecuteMsg::Register(registration)).unwrap(),
+                funds: vec![],
+            })]
+        );
+    }
+
+    #[test]
+    fn do_instantiate_takes_a_custom_curve() {
+        // a curve of our own, whatever the message says
+        fn doubled<'a>(
+            _: &CurveType,
+            _: &dyn Api,
+            querier: QuerierWrapper<'a>,
+        ) -> Result<CurveFn<'a>, ContractError> {
+            let curve_type = CurveType::Constant {
+                value: Uint128::new(2),
+                scale: 0,
+            };
+            Ok(curve_type.to_curve_fn(querier))
+        }
+
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let unchecked = CurveType::Piecewise { segments: vec![] };
+        let msg = default_instantiate(None, 2, 8, unchecked, 0, 50);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPiecewiseCurve {});
+        do_instantiate::<Empty>(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+            doubled,
+        )
+        .unwrap();
+
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
+        let deps_mut = deps.as_mut();
+        let curve_fn = doubled(
+            &CurveType::Piecewise { segments: vec![] },
+            deps_mut.api,
+            deps_mut.querier,
+        )
+        .unwrap();
+        do_execute::<Empty>(deps_mut, mock_env(), info, buy, curve_fn).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), INVESTOR), Uint128::new(50));
+
+        // a failed delegation is unwound on the same curve
+        let info = mock_info(BUYER, &coins(100_000_000, DENOM));
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let deps_mut = deps.as_mut();
+        let curve_fn = doubled(&msg.curve_type, deps_mut.api, deps_mut.querier).unwrap();
+        do_execute::<Empty>(deps_mut, mock_env(), info, bond, curve_fn).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::new(50));
+        let reply_msg = Reply {
+            id: BOND_REPLY_ID,
+            result: ContractResult::Err("validator jailed".to_string()),
+        };
+        let deps_mut = deps.as_mut();
+        let curve_fn = doubled(&msg.curve_type, deps_mut.api, deps_mut.querier).unwrap();
+        do_reply::<Empty>(deps_mut, mock_env(), reply_msg, curve_fn).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), BUYER), Uint128::zero());
+
+        // and so is the investment query
+        let curve_fn = doubled(&msg.curve_type, &deps.api, deps.as_ref().querier).unwrap();
+        let invest: InvestmentResponse = from_binary(
+            &do_query(deps.as_ref(), mock_env(), QueryMsg::Investment {}, curve_fn).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(invest.nominal_value, Decimal::permille(2_000));
+    }
+
+    #[test]
+    fn responses_carry_a_chains_custom_messages() {
+        #[derive(Clone, Debug, PartialEq, schemars::JsonSchema)]
+        enum ChainMsg {
+            Ping {},
+        }
+
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        let res = do_instantiate::<ChainMsg>(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg,
+            check_curve_type,
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let deps_mut = deps.as_mut();
+        let curve_fn = CURVE_TYPE
+            .load(deps_mut.storage)
+            .unwrap()
+            .to_curve_fn(deps_mut.querier);
+        let info = mock_info(INVESTOR, &coins(100_000_000, DENOM));
+        let res = do_execute::<ChainMsg>(deps_mut, mock_env(), info, bond, curve_fn).unwrap();
+        assert!(matches!(
+            &res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Delegate { .. })
+        ));
+        // a derived contract adds its own alongside
+        let res = res.add_message(CosmosMsg::Custom(ChainMsg::Ping {}));
+        assert_eq!(res.messages.len(), 2);
+        set_delegation(&mut deps.querier, 100_000_000, DENOM);
+
+        // cw20-base's responses are lifted too
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "bob".to_string(),
+            amount: Uint128::new(10),
+        };
+        let deps_mut = deps.as_mut();
+        let curve_fn = CURVE_TYPE
+            .load(deps_mut.storage)
+            .unwrap()
+            .to_curve_fn(deps_mut.querier);
+        let info = mock_info(INVESTOR, &[]);
+        do_execute::<ChainMsg>(deps_mut, mock_env(), info, transfer, curve_fn).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(10));
+    }
+
+    #[test]
+    fn staking_tests_reinvest_keeps_a_liquid_buffer() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.liquid_ratio = Some(Decimal::percent(10));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(80_000_000, "ustake"));
+        let buffer = query_liquid_buffer(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(
+            buffer,
+            LiquidBufferResponse {
+                liquid_ratio: Decimal::percent(10),
+                target: Uint128::new(50_000_000),
+                liquid: Uint128::new(80_000_000),
+            }
+        );
+
+        // only what is above the buffer gets delegated
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            contract.clone(),
+            ExecuteMsg::_BondAllTokens {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(30_000_000, "ustake"),
+            })]
+        );
+
+        // the target grows with the reserve, so the rest stays put
+        set_delegation(&mut deps.querier, 530_000_000, "ustake");
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(52_000_000, "ustake"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            contract,
+            ExecuteMsg::_BondAllTokens {},
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn staking_tests_keeper_claims_for_owner() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.claim_incentive = Some(Decimal::percent(1));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), unbond).unwrap();
+
+        // once matured, anyone can push bob's claim out to him
+        let matured = later(&mock_env(), DAY * 3);
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(200_000_000, "ustake"));
+        let claim_for = ExecuteMsg::ClaimFor {
+            address: "bob".into(),
+        };
+        let res = execute(deps.as_mut(), matured, mock_info("keeper", &[]), claim_for).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "keeper".into(),
+                    amount: coins(2_000_000, "ustake"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "bob".into(),
+                    amount: coins(198_000_000, "ustake"),
+                }),
+            ]
+        );
+        assert!(get_claims(deps.as_ref(), "bob").is_empty());
+    }
+
+    #[test]
+    fn staking_tests_sweep_settles_all_matured_claims() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.claim_incentive = Some(Decimal::percent(1));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        let mut bonded = 0;
+        for staker in ["alice", "bob"] {
+            let info = mock_info(staker, &coins(500_000_000, "ustake"));
+            execute(deps.as_mut(), mock_env(), info, bond.clone()).unwrap();
+            bonded += 500_000_000;
+            set_delegation(&mut deps.querier, bonded, "ustake");
+        }
+        for staker in ["alice", "bob"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(staker, &[]),
+                unbond.clone(),
+            )
+            .unwrap();
+            bonded -= 100_000_000;
+            set_delegation(&mut deps.querier, bonded, "ustake");
+        }
+
+        // nothing has matured yet
+        let total = query_total_claims(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(total.claims, Uint128::new(200_000_000));
+        assert_eq!(total.matured, Uint128::zero());
+        assert_eq!(total.locked, Uint128::new(200_000_000));
+        assert!(total.complete);
+        let all = query_all_claims(deps.as_ref(), None, None).unwrap().claims;
+        let claimants: Vec<_> = all.iter().map(|c| c.address.as_str()).collect();
+        // keys lead with the address length, so shorter addresses come first
+        assert_eq!(claimants, vec!["bob", "alice"]);
+        assert_eq!(all[1].claims, get_claims(deps.as_ref(), "alice"));
+        let rest = query_all_claims(deps.as_ref(), Some("bob".into()), None).unwrap();
+        assert_eq!(rest.claims.len(), 1);
+        assert_eq!(rest.claims[0].address, "alice");
+        let sweep = ExecuteMsg::SweepClaims {
+            limit: None,
+            start_after: None,
+        };
+        let keeper = mock_info("keeper", &[]);
+        let err = execute(deps.as_mut(), mock_env(), keeper.clone(), sweep.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        let matured = later(&mock_env(), DAY * 3);
+        let total = query_total_claims(deps.as_ref(), matured.clone()).unwrap();
+        assert_eq!(total.matured, Uint128::new(200_000_000));
+        assert_eq!(total.locked, Uint128::zero());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(200_000_000, "ustake"));
+        let res = execute(deps.as_mut(), matured, keeper, sweep).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "bob".into(),
+                    amount: coins(99_000_000, "ustake"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "alice".into(),
+                    amount: coins(99_000_000, "ustake"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "keeper".into(),
+                    amount: coins(2_000_000, "ustake"),
+                }),
+            ]
         );
+        assert!(get_claims(deps.as_ref(), "alice").is_empty());
+        assert!(get_claims(deps.as_ref(), "bob").is_empty());
+        let curve = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(curve.claims, Uint128::zero());
     }
 
-    // just a test helper, forgive the panic
-    fn later(env: &Env, delta: Duration) -> Env {
-        let time_delta = match delta {
-            Duration::Time(t) => t,
-            _ => panic!("Must provide duration in time"),
-        };
-        let mut res = env.clone();
-        res.block.time = res.block.time.plus_seconds(time_delta);
-        res
+    #[test]
+    fn due_claimants_scan_a_bounded_page() {
+        use crate::claims::{create_claim, due_claimants};
+
+        let mut deps = mock_dependencies(&[]);
+        let block = mock_env().block;
+        let matured = Expiration::AtHeight(block.height);
+        let locked = Expiration::AtHeight(block.height + 100);
+        let (bob, carol, dave) = (
+            Addr::unchecked("bob"),
+            Addr::unchecked("cat"),
+            Addr::unchecked("dave"),
+        );
+        // more locked claims than one page looks at
+        for _ in 0..120 {
+            create_claim(&mut deps.storage, &bob, Uint128::new(1), locked).unwrap();
+        }
+        create_claim(&mut deps.storage, &carol, Uint128::new(1), matured).unwrap();
+        create_claim(&mut deps.storage, &dave, Uint128::new(1), locked).unwrap();
+        create_claim(&mut deps.storage, &dave, Uint128::new(1), matured).unwrap();
+
+        let (due, next) = due_claimants(&deps.storage, &block, None, 10).unwrap();
+        assert!(due.is_empty());
+        assert_eq!(next, Some(bob));
+        let (due, next) = due_claimants(&deps.storage, &block, next, 1).unwrap();
+        assert_eq!(due, vec![carol.clone()]);
+        assert_eq!(next, Some(carol));
+        let (due, next) = due_claimants(&deps.storage, &block, next, 1).unwrap();
+        assert_eq!(due, vec![dave]);
+        assert_eq!(next, None);
     }
 
-    fn get_claims(deps: Deps, addr: &str) -> Vec<Claim> {
-        CLAIMS
-            .query_claims(deps, &Addr::unchecked(addr))
+    #[test]
+    fn staking_tests_claims_are_paged_and_filtered() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        let mut bonded = 500_000_000;
+        set_delegation(&mut deps.querier, bonded, "ustake");
+
+        // one unbond a day, so each matures a day after the last
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        for day in 0..3 {
+            let env = later(&mock_env(), DAY * day);
+            execute(deps.as_mut(), env, mock_info("bob", &[]), unbond.clone()).unwrap();
+            bonded -= 100_000_000;
+            set_delegation(&mut deps.querier, bonded, "ustake");
+        }
+
+        let claims = |env, start_after, limit, only_mature| {
+            query_claims(
+                deps.as_ref(),
+                env,
+                "bob".into(),
+                start_after,
+                limit,
+                only_mature,
+            )
             .unwrap()
             .claims
+            .iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>()
+        };
+        let env = later(&mock_env(), DAY * 4);
+        assert_eq!(claims(env.clone(), None, None, None), vec![1, 2, 3]);
+        assert_eq!(claims(env.clone(), None, Some(2), None), vec![1, 2]);
+        assert_eq!(claims(env.clone(), Some(2), Some(2), None), vec![3]);
+        assert_eq!(claims(env.clone(), None, None, Some(true)), vec![1, 2]);
+        assert_eq!(claims(env, Some(1), None, Some(true)), vec![2]);
     }
 
     #[test]
-    fn staking_tests_proper_staking_instantiation() {
+    fn staking_tests_unbonds_are_batched_per_epoch() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        msg.staking_params.unbond_epoch = Some(Duration::Height(10));
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidUnbondEpoch {});
+        msg.staking_params.unbond_epoch = Some(DAY);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
+
+        // both unbonds wait for the epoch, their stake still delegated meanwhile
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        for _ in 0..2 {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("bob", &[]),
+                unbond.clone(),
+            )
+            .unwrap();
+            assert_eq!(res.messages, vec![]);
+        }
+        let epoch_end = DAY.after(&mock_env().block);
+        let epoch = query_unbond_epoch(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(
+            epoch,
+            UnbondEpochResponse {
+                epoch_length: Some(DAY),
+                id: 1,
+                pending: Uint128::new(200_000_000),
+                ends: Some(epoch_end),
+                ready: false,
+            }
+        );
+        // claims are dated from the end of the epoch
+        let released = (DAY * 4).after(&mock_env().block);
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob"),
+            vec![Claim::new(100_000_000, released); 2]
+        );
+
+        let process = ExecuteMsg::ProcessEpoch {};
+        let keeper = mock_info("keeper", &[]);
+        let err = execute(deps.as_mut(), mock_env(), keeper.clone(), process.clone()).unwrap_err();
+        assert_eq!(err, ContractError::EpochNotEnded {});
+        let res = execute(deps.as_mut(), later(&mock_env(), DAY), keeper, process).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Undelegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(200_000_000, "ustake"),
+            })]
+        );
+        let epoch = query_unbond_epoch(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(epoch.pending, Uint128::zero());
+        assert_eq!(epoch.ends, None);
+    }
+
+    #[test]
+    fn staking_tests_claims_can_be_sold_on() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+        let mut bonded = 500_000_000;
+        set_delegation(&mut deps.querier, bonded, "ustake");
+        let unbond = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        for day in 0..2 {
+            let env = later(&mock_env(), DAY * day);
+            execute(deps.as_mut(), env, mock_info("bob", &[]), unbond.clone()).unwrap();
+            bonded -= 100_000_000;
+            set_delegation(&mut deps.querier, bonded, "ustake");
+        }
+        let first = get_claims(deps.as_ref(), "bob")[0].release_at;
+        let second = get_claims(deps.as_ref(), "bob")[1].release_at;
+
+        let sell = |amount| ExecuteMsg::TransferClaim {
+            recipient: "alice".into(),
+            amount: Uint128::new(amount),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            sell(300_000_000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientClaims {
+                available: Uint128::new(200_000_000)
+            }
+        );
+
+        // the later claim goes whole, the earlier one is split
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            sell(150_000_000),
+        )
+        .unwrap();
+        assert_eq!(
+            get_claims(deps.as_ref(), "bob"),
+            vec![Claim::new(50_000_000, first)]
+        );
+        assert_eq!(
+            get_claims(deps.as_ref(), "alice"),
+            vec![
+                Claim::new(100_000_000, second),
+                Claim::new(50_000_000, first)
+            ]
+        );
+
+        // alice can claim hers once the earlier one matures
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(200_000_000, "ustake"));
+        let env = later(&mock_env(), DAY * 3);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("alice", &[]),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(50_000_000, "ustake"),
+            })]
+        );
+    }
+
+    #[test]
+    fn staking_tests_reinvest_preview_counts_pending_rewards() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond).unwrap();
+
+        let mut delegation = sample_delegation(DEFAULT_VALIDATOR, coin(500_000_000, "ustake"));
+        delegation.accumulated_rewards = coins(30, "ustake");
+        deps.querier.update_staking(
+            "ustake",
+            &[sample_validator(DEFAULT_VALIDATOR)],
+            &[delegation],
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(15, "ustake"));
+        let preview = query_reinvest_preview(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(
+            preview,
+            ReinvestPreviewResponse {
+                pending_rewards: Uint128::new(30),
+                liquid: Uint128::new(15),
+                to_bond: Uint128::new(45),
+                worthwhile: false,
+            }
+        );
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(20, "ustake"));
+        let preview = query_reinvest_preview(deps.as_ref(), mock_env()).unwrap();
+        assert!(preview.worthwhile);
+    }
+
+    #[test]
+    fn staking_tests_rate_history_keeps_the_latest_snapshots() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Constant {
+            value: Uint128::new(1),
+            scale: 0,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 0, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let bond = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let mut env = mock_env();
+        let start = env.block.height;
+        for i in 1..=105u128 {
+            env.block.height += 1;
+            let info = mock_info("bob", &coins(1_000_000, "ustake"));
+            execute(deps.as_mut(), env.clone(), info, bond.clone()).unwrap();
+            set_delegation(&mut deps.querier, i * 1_000_000, "ustake");
+        }
+
+        let history = query_rate_history(deps.as_ref(), Some(2)).unwrap();
+        assert_eq!(
+            history.snapshots,
+            vec![
+                RateSnapshot {
+                    height: start + 105,
+                    reserve: Uint128::new(105_000_000),
+                    supply: Uint128::new(105),
+                    nominal_value: Decimal::one(),
+                },
+                RateSnapshot {
+                    height: start + 104,
+                    reserve: Uint128::new(104_000_000),
+                    supply: Uint128::new(104),
+                    nominal_value: Decimal::one(),
+                },
+            ]
+        );
+
+        // only the last 100 are kept
+        let history = query_rate_history(deps.as_ref(), Some(500)).unwrap();
+        assert_eq!(history.snapshots.len(), 100);
+        assert_eq!(history.snapshots[99].height, start + 6);
+    }
+
+    #[test]
+    fn staking_tests_owner_updates_exit_tax_within_cap() {
+        let mut deps = mock_dependencies(&[]);
+        set_validator(&mut deps.querier);
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let mut msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        msg.staking_params.max_exit_tax = Some(Decimal::percent(15));
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let update = ExecuteMsg::UpdateStakingParams {
+            exit_tax: Some(Decimal::percent(16)),
+            min_withdrawal: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ExitTaxTooHigh {
+                max: Decimal::percent(15)
+            }
+        );
+
+        let update = ExecuteMsg::UpdateStakingParams {
+            exit_tax: Some(Decimal::percent(15)),
+            min_withdrawal: Some(Uint128::new(100)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap();
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.exit_tax, Decimal::percent(15));
+        assert_eq!(invest.min_withdrawal, Uint128::new(100));
+    }
+
+    #[test]
+    fn staking_tests_bond_to_allowed_validator() {
         let mut deps = mock_dependencies(&[]);
         deps.querier.update_staking(
             "ustake",
             &[
-                sample_validator("john"),
-                sample_validator("mary"),
-                sample_validator("my-validator-addr"),
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("fan-favourite"),
+                sample_validator("stranger"),
             ],
             &[],
         );
@@ -665,209 +8193,271 @@ mod tests {
             slope: Uint128::new(1),
             scale: 1,
         };
-
         let creator = String::from("creator");
-        let msg = InstantiateMsg {
-            external_permalink_uri:
-                "https://squarepusher.bandcamp.com/album/feed-me-weird-things-remastered"
-                    .to_string(),
-            creator: "Squarepusher".to_string(),
-            work: "Feed Me Weird Things (Remaster)".to_string(),
-            description: "Feed Me Weird Things (Remaster) - Bandcamp".to_string(),
-            name: "Windscale2Coin".to_string(),
-            symbol: "WIND".to_string(),
-            decimals: 2,
-            // reserve_denom: DENOM.to_string(),
-            reserve_decimals: 8,
-            asset_uri: None,
-            curve_type: curve_type.clone(),
-            staking_params: StakingParams {
-                validator: String::from("my-validator-addr"),
-                unbonding_period: DAY * 3,
-                exit_tax: Decimal::percent(2),
-                min_withdrawal: Uint128::new(50),
-            },
-        };
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.allowed_validators =
+            Some(vec![String::from("fan-favourite")]);
         let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        // make sure we can instantiate with this
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
-        assert_eq!(0, res.messages.len());
+        // validators outside the allowed set are rejected
+        let bob = String::from("bob");
+        let info = mock_info(&bob, &coins(500_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: Some(String::from("stranger")),
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), bond_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ValidatorNotAllowed {
+                validator: "stranger".into()
+            }
+        );
 
-        // token info is proper
-        let token = query_token_info_with_meta(deps.as_ref()).unwrap();
-        assert_eq!(&token.token_info_response.name, &msg.name);
-        assert_eq!(&token.token_info_response.symbol, &msg.symbol);
-        assert_eq!(token.token_info_response.decimals, msg.decimals);
-        assert_eq!(token.token_info_response.total_supply, Uint128::zero());
+        // bob picks the fan favourite
+        let bond_msg = ExecuteMsg::Bond {
+            validator: Some(String::from("fan-favourite")),
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: "fan-favourite".into(),
+                amount: coin(500_000_000, "ustake"),
+            })
+        );
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("fan-favourite"),
+            ],
+            &[sample_delegation(
+                "fan-favourite",
+                coin(500_000_000, "ustake"),
+            )],
+        );
 
-        // no balance
-        assert_eq!(get_balance(deps.as_ref(), &creator), Uint128::zero());
-        // no claims
-        assert_eq!(get_claims(deps.as_ref(), &creator), vec![]);
+        // accounting is tracked per validator
+        let delegations = query_delegations(deps.as_ref()).unwrap().delegations;
+        assert_eq!(delegations.len(), 1);
+        assert_eq!(delegations[0].validator, "fan-favourite");
+        assert_eq!(delegations[0].amount, Uint128::new(500_000_000));
 
-        // investment info correct
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(&invest.owner, &creator);
-        assert_eq!(&invest.validator, &msg.staking_params.validator);
-        assert_eq!(invest.exit_tax, msg.staking_params.exit_tax);
-        assert_eq!(invest.min_withdrawal, msg.staking_params.min_withdrawal);
+        // with nothing on the default validator, unbonding pulls from the fan favourite
+        let info = mock_info(&bob, &[]);
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(100),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, unbond_msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        match &res.messages[0].msg {
+            CosmosMsg::Staking(StakingMsg::Undelegate { validator, .. }) => {
+                assert_eq!(validator.as_str(), "fan-favourite");
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn staking_tests_rebalance_redelegates_to_target_weights() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("fan-favourite"),
+            ],
+            &[],
+        );
+
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let creator = String::from("creator");
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.allowed_validators =
+            Some(vec![String::from("fan-favourite")]);
+        instantiate_msg.staking_params.target_weights = Some(vec![
+            ValidatorWeight {
+                validator: String::from(DEFAULT_VALIDATOR),
+                weight: 3,
+            },
+            ValidatorWeight {
+                validator: String::from("fan-favourite"),
+                weight: 1,
+            },
+        ]);
+
+        // weights must be positive and name each validator once
+        let mut bad_msg = instantiate_msg.clone();
+        bad_msg.staking_params.target_weights = Some(vec![ValidatorWeight {
+            validator: String::from(DEFAULT_VALIDATOR),
+            weight: 0,
+        }]);
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info(&creator, &[]), bad_msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTargetWeights {});
+        let mut bad_msg = instantiate_msg.clone();
+        bad_msg.staking_params.target_weights = Some(vec![
+            ValidatorWeight {
+                validator: String::from("fan-favourite"),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: String::from("fan-favourite"),
+                weight: 1,
+            },
+        ]);
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info(&creator, &[]), bad_msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTargetWeights {});
+
+        let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // everything lands on the default validator
+        let info = mock_info("bob", &coins(400_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+
+        // a quarter of it moves over
+        let info = mock_info("keeper", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Rebalance {},
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: DEFAULT_VALIDATOR.into(),
+                dst_validator: "fan-favourite".into(),
+                amount: coin(100_000_000, "ustake"),
+            })
+        );
+        let delegations = query_delegations(deps.as_ref()).unwrap().delegations;
+        assert_eq!(
+            delegations,
+            vec![
+                ValidatorDelegation {
+                    validator: DEFAULT_VALIDATOR.into(),
+                    amount: Uint128::new(300_000_000),
+                },
+                ValidatorDelegation {
+                    validator: "fan-favourite".into(),
+                    amount: Uint128::new(100_000_000),
+                },
+            ]
+        );
 
-        assert_eq!(invest.token_supply, Uint128::zero());
-        assert_eq!(invest.staked_tokens, coin(0, "ustake"));
-        // think this should be zero as nothing is staked yet
-        assert_eq!(invest.nominal_value, Decimal::zero());
-        // assert_eq!(invest.nominal_value, Decimal::one());
+        // once balanced there is nothing to do
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Rebalance {}).unwrap();
+        assert_eq!(0, res.messages.len());
     }
 
     #[test]
-    fn staking_tests_instantiation_with_missing_validator() {
+    fn staking_tests_withdraw_rewards_only_does_not_rebond() {
         let mut deps = mock_dependencies(&[]);
-        deps.querier
-            .update_staking("ustake", &[sample_validator("john")], &[]);
+        set_validator(&mut deps.querier);
 
         let curve_type = CurveType::Linear {
             slope: Uint128::new(1),
             scale: 1,
         };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
 
-        let creator = String::from("creator");
-        let msg = InstantiateMsg {
-            external_permalink_uri:
-                "https://squarepusher.bandcamp.com/album/feed-me-weird-things-remastered"
-                    .to_string(),
-            creator: "Squarepusher".to_string(),
-            work: "Feed Me Weird Things (Remaster)".to_string(),
-            description: "Feed Me Weird Things (Remaster) - Bandcamp".to_string(),
-            name: "Windscale2Coin".to_string(),
-            symbol: "WIND".to_string(),
-            decimals: 2,
-            // reserve_denom: DENOM.to_string(),
-            reserve_decimals: 8,
-            asset_uri: None,
-            curve_type: curve_type.clone(),
-            staking_params: StakingParams {
-                validator: String::from("my-validator-addr"),
-                unbonding_period: DAY * 3,
-                exit_tax: Decimal::percent(2),
-                min_withdrawal: Uint128::new(50),
-            },
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
         };
-        let info = mock_info(&creator, &[]);
+        let info = mock_info("bob", &coins(1_000, "ustake"));
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
 
-        // make sure we can instantiate with this
-        let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        let info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::WithdrawRewardsOnly {},
+        )
+        .unwrap();
         assert_eq!(
-            err,
-            ContractError::NotInValidatorSet {
-                validator: "my-validator-addr".into()
-            }
+            res.messages,
+            vec![SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                validator: DEFAULT_VALIDATOR.into(),
+            })]
         );
     }
 
     #[test]
-    fn staking_tests_bonding_issues_tokens() {
+    fn staking_tests_failed_delegation_rolls_back_bond() {
         let mut deps = mock_dependencies(&[]);
         set_validator(&mut deps.querier);
-        // deps.querier
-        //     .update_staking("ustake", &[sample_validator(DEFAULT_VALIDATOR)], &[]);
 
         let curve_type = CurveType::Linear {
             slope: Uint128::new(1),
             scale: 1,
         };
+        let msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
 
-        //let curve_fn = curve_type.to_curve_fn();
-
-        let creator = String::from("creator");
-        let instantiate_msg = default_instantiate(
-            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
-            2,
-            8,
-            curve_type.clone(),
-            2,
-            50,
-        );
-        let info = mock_info(&creator, &[]);
-
-        // make sure we can instantiate with this
-        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // sense check
-        let loaded_curve_type = CURVE_TYPE.load(&deps.storage).unwrap();
-        //let curve_fn = loaded_curve_type.to_curve_fn();
-        assert_eq!(curve_type, loaded_curve_type);
-
-        // succeeds with proper token (5 BTC = 5*10^8 satoshi)
-        let bob = String::from("bob");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(
-            &bob,
-            &[
-                coin(1000, "this-one-is-ignored"),
-                coin(500_000_000, "ustake"),
-            ],
-        );
-
-        // try to bond and make sure we trigger delegation
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
         let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-        let delegate = &res.messages[0];
-        match &delegate.msg {
-            CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
-                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
-                assert_eq!(amount, &coin(500_000_000, "ustake"));
-            }
-            _ => panic!("Unexpected message: {:?}", delegate),
-        }
-
-        // bob got 1000 derived tokens
-        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
-
-        // update delegations total
-        set_delegation(&mut deps.querier, 500_000_000, "ustake");
-
-        // time for alice
-        // needs more to make it work
-        let alice = String::from("alice");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(
-            &alice,
-            &[
-                coin(2000, "this-one-is-ignored"),
-                coin(1_500_000_000, "ustake"),
-            ],
+        assert_eq!(res.messages[0].id, BOND_REPLY_ID);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::new(1000));
+
+        // the chain rejects the delegation
+        let reply_msg = Reply {
+            id: BOND_REPLY_ID,
+            result: ContractResult::Err("validator jailed".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".into(),
+                amount: coins(500_000_000, "ustake"),
+            })]
         );
 
-        // try to bond and make sure we trigger delegation
-        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-        let delegate = &res.messages[0];
-        match &delegate.msg {
-            CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
-                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
-                assert_eq!(amount, &coin(1_500_000_000, "ustake"));
-            }
-            _ => panic!("Unexpected message: {:?}", delegate),
-        }
-
-        // alice got 1000 derived tokens for a larger initial
-        assert_eq!(get_balance(deps.as_ref(), &alice), Uint128::new(1000));
-
-        // investment info correct (updated supply)
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(invest.token_supply, Uint128::new(2000));
-        assert_eq!(invest.staked_tokens, coin(2_000_000_000, "ustake"));
-        assert_eq!(invest.nominal_value, Decimal::permille(2_000));
-
-        // token info also properly updated
-        let token = query_token_info_with_meta(deps.as_ref()).unwrap();
-        assert_eq!(token.token_info_response.total_supply, Uint128::new(2000));
+        // the mint, the cost basis and the curve are put back
+        assert_eq!(get_balance(deps.as_ref(), "bob"), Uint128::zero());
+        let paid = crate::state::CONTRIBUTIONS
+            .load(&deps.storage, &Addr::unchecked("bob"))
+            .unwrap();
+        assert_eq!(paid, Uint128::zero());
+        let state = CURVE_STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.curve_reserve, Uint128::zero());
+        assert_eq!(state.supply, Uint128::zero());
+        let delegated = DELEGATIONS.load(&deps.storage, DEFAULT_VALIDATOR).unwrap();
+        assert_eq!(delegated, Uint128::zero());
+
+        // nothing is left to undo
+        let reply_msg = Reply {
+            id: BOND_REPLY_ID,
+            result: ContractResult::Err("again".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
     }
 
     #[test]
-    fn staking_tests_rebonding_changes_pricing() {
+    fn staking_tests_reinvest_skims_rewards_into_funding_pool() {
         let mut deps = mock_dependencies(&[]);
         set_validator(&mut deps.querier);
 
@@ -875,345 +8465,460 @@ mod tests {
             slope: Uint128::new(1),
             scale: 1,
         };
-
         let creator = String::from("creator");
-        let instantiate_msg = default_instantiate(
-            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
-            2,
-            8,
-            curve_type.clone(),
-            2,
-            50,
-        );
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 2, 50);
+        instantiate_msg.staking_params.reward_skim = Some(Decimal::percent(20));
         let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        // make sure we can instantiate with this
-        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // let's bond some tokens now
-        let bob = String::from("bob");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(&bob, &[coin(10, "random"), coin(5_000_000, "ustake")]);
-        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-
-        // check bob got 100 derived tokens
-        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(100));
-
-        // update the querier with new bond
+        let info = mock_info("bob", &coins(5_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
         set_delegation(&mut deps.querier, 5_000_000, "ustake");
 
-        // fake a reinvestment (this must be sent by the contract itself)
-        let rebond_msg = ExecuteMsg::_BondAllTokens {};
+        // a fifth of the rewards stay behind
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         deps.querier
             .update_balance(MOCK_CONTRACT_ADDR, coins(500_000, "ustake"));
-        let _ = execute(deps.as_mut(), mock_env(), info, rebond_msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::_BondAllTokens {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: DEFAULT_VALIDATOR.into(),
+                amount: coin(400_000, "ustake"),
+            })
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100_000, "ustake"));
+        set_delegation(&mut deps.querier, 5_400_000, "ustake");
+
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.staked_tokens, coin(5_400_000, "ustake"));
+        assert_eq!(invest.funding_pool, coin(100_000, "ustake"));
+
+        // only the creator can withdraw, and not more than is there
+        let withdraw = ExecuteMsg::WithdrawFundingPool { amount: None };
+        let info = mock_info("bob", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(&creator, &[]);
+        let too_much = ExecuteMsg::WithdrawFundingPool {
+            amount: Some(Uint128::new(100_001)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), too_much).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FundingPoolTooSmall {
+                available: Uint128::new(100_000)
+            }
+        );
 
-        // update the querier with new bond
-        set_delegation(&mut deps.querier, 5_500_000, "ustake");
+        let res = execute(deps.as_mut(), mock_env(), info, withdraw).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: creator,
+                amount: coins(100_000, "ustake"),
+            })
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.funding_pool, coin(0, "ustake"));
+    }
 
-        // we should now see 104 issued and 5_500_000 bonded (and a price of 0.104)
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(invest.token_supply, Uint128::new(104));
-        assert_eq!(invest.staked_tokens, coin(5_500_000, "ustake"));
-        let spot_price = Decimal::from_str("0.104").unwrap();
-        assert_eq!(invest.nominal_value, spot_price);
+    #[test]
+    fn staking_tests_validator_change_needs_all_approvers() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("new-validator"),
+            ],
+            &[],
+        );
 
-        // we bond some other tokens and get a different issuance price
-        let alice = String::from("alice");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(&alice, &[coin(1_500_000_000, "ustake")]);
-        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let creator = String::from("creator");
+        let label = String::from("label");
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.validator_approvers =
+            Some(vec![creator.clone(), label.clone()]);
+
+        // a repeated approver doesn't count towards the threshold
+        let mut repeated_msg = instantiate_msg.clone();
+        repeated_msg.staking_params.validator_approvers = Some(vec![label.clone(), label.clone()]);
+        repeated_msg.staking_params.validator_approval_threshold = Some(2);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&creator, &[]),
+            repeated_msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidApprovalThreshold {});
 
-        // update the querier with new bond
-        // i.e. alice + bob
-        set_delegation(&mut deps.querier, 1_505_500_000, "ustake");
+        let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        // alice should have gotten over 1k but less than 2k
-        assert_eq!(get_balance(deps.as_ref(), &alice), Uint128::new(1631));
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
 
-        // 1631 + bob's 104
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(invest.token_supply, Uint128::new(1735));
-        assert_eq!(invest.staked_tokens, coin(1_505_500_000, "ustake"));
-        assert_eq!(invest.nominal_value, Decimal::from_str("1.735").unwrap());
+        // outsiders can't propose
+        let propose = ExecuteMsg::ProposeValidatorChange {
+            validator: "new-validator".into(),
+        };
+        let info = mock_info("bob", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, propose.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // one approval isn't enough
+        let info = mock_info(&creator, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), propose).unwrap();
+        assert_eq!(0, res.messages.len());
+        let approve = ExecuteMsg::ApproveValidatorChange {
+            validator: "new-validator".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, approve.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyApproved {});
+        let change = query_validator_change(deps.as_ref()).unwrap();
+        assert_eq!(change.proposed_validator, Some("new-validator".into()));
+        assert_eq!(change.approvals, vec![creator.clone()]);
+
+        // the label has to approve the same validator
+        let info = mock_info(&label, &[]);
+        let wrong = ExecuteMsg::ApproveValidatorChange {
+            validator: DEFAULT_VALIDATOR.into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), wrong).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NoValidatorChangeProposed {
+                validator: DEFAULT_VALIDATOR.into()
+            }
+        );
+        let res = execute(deps.as_mut(), mock_env(), info, approve).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: DEFAULT_VALIDATOR.into(),
+                dst_validator: "new-validator".into(),
+                amount: coin(500_000_000, "ustake"),
+            })
+        );
+
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.validator, "new-validator");
+        let change = query_validator_change(deps.as_ref()).unwrap();
+        assert_eq!(change.proposed_validator, None);
     }
 
     #[test]
-    fn staking_tests_bonding_fails_with_wrong_denom() {
+    fn staking_tests_rebond_all_tokens_when_validator_drops_out() {
         let mut deps = mock_dependencies(&[]);
-        set_validator(&mut deps.querier);
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("new-validator"),
+            ],
+            &[],
+        );
 
         let curve_type = CurveType::Linear {
             slope: Uint128::new(1),
             scale: 1,
         };
-
         let creator = String::from("creator");
-        let instantiate_msg = default_instantiate(
-            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
-            2,
-            8,
-            curve_type.clone(),
-            2,
-            50,
-        );
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.validator_approvers =
+            Some(vec![creator.clone(), "label".into()]);
         let info = mock_info(&creator, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        // make sure we can instantiate with this
-        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // let's bond some tokens now
-        let bob = String::from("bob");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(&bob, &[coin(500, "photon")]);
-
-        // try to bond and make sure we trigger delegation
-        let err = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap_err();
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+
+        // while the validator is active, the owner can't go around the approvers
+        let rebond = ExecuteMsg::RebondAllTokens {
+            validator_address: "new-validator".into(),
+        };
+        let info = mock_info(&creator, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), rebond.clone()).unwrap_err();
         assert_eq!(
             err,
-            ContractError::EmptyBalance {
-                denom: "ustake".to_string()
+            ContractError::ValidatorStillActive {
+                validator: DEFAULT_VALIDATOR.into()
             }
         );
+
+        deps.querier
+            .update_staking("ustake", &[sample_validator("new-validator")], &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            rebond.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res = execute(deps.as_mut(), mock_env(), info, rebond).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: DEFAULT_VALIDATOR.into(),
+                dst_validator: "new-validator".into(),
+                amount: coin(500_000_000, "ustake"),
+            })
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.validator, "new-validator");
     }
 
     #[test]
-    fn staking_tests_unbonding_changes_price_on_curve() {
+    fn staking_tests_failover_to_first_active_fallback() {
         let mut deps = mock_dependencies(&[]);
-        set_validator(&mut deps.querier);
+        deps.querier.update_staking(
+            "ustake",
+            &[
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("first-fallback"),
+                sample_validator("second-fallback"),
+            ],
+            &[],
+        );
 
         let curve_type = CurveType::Linear {
             slope: Uint128::new(1),
             scale: 1,
         };
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.fallback_validators =
+            Some(vec!["first-fallback".into(), "second-fallback".into()]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
 
-        let creator = String::from("creator");
-        let instantiate_msg = default_instantiate(
-            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
-            2,
-            8,
-            curve_type.clone(),
-            10,
-            50,
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+
+        let info = mock_info("bob", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Failover {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ValidatorStillActive {
+                validator: DEFAULT_VALIDATOR.into()
+            }
         );
-        let info = mock_info(&creator, &[]);
 
-        // make sure we can instantiate with this
-        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        // the validator is jailed, and so is the first fallback. Reinvest notices
+        deps.querier
+            .update_staking("ustake", &[sample_validator("second-fallback")], &[]);
+        let env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Reinvest {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("validator_healthy", "false"),
+                attr("rewards_to", MOCK_CONTRACT_ADDR)
+            ]
+        );
+        let health = query_validator_health(deps.as_ref()).unwrap();
+        assert_eq!(
+            health,
+            ValidatorHealthResponse {
+                validator: DEFAULT_VALIDATOR.into(),
+                healthy: false,
+                unhealthy_since: Some(env.block.height),
+                fallback_validators: vec!["first-fallback".into(), "second-fallback".into()],
+            }
+        );
 
-        // let's bond some tokens now
-        let bob = String::from("bob");
-        let bond_msg = ExecuteMsg::Bond {};
-        let info = mock_info(
-            &bob,
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Failover {}).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: DEFAULT_VALIDATOR.into(),
+                dst_validator: "second-fallback".into(),
+                amount: coin(500_000_000, "ustake"),
+            })
+        );
+        let health = query_validator_health(deps.as_ref()).unwrap();
+        assert_eq!(health.validator, "second-fallback");
+        assert!(health.healthy);
+        assert_eq!(health.unhealthy_since, None);
+    }
+
+    #[test]
+    fn staking_tests_sudo_switches_validator() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier.update_staking(
+            "ustake",
             &[
-                coin(1000, "this-one-is-ignored"),
-                coin(500_000_000, "ustake"),
+                sample_validator(DEFAULT_VALIDATOR),
+                sample_validator("new-validator"),
             ],
+            &[],
         );
-        let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-
-        // update the querier with new bond
-        set_delegation(&mut deps.querier, 500_000_000, "ustake");
-
-        // check bob's balance is 1k
-        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
 
-        // fake a reinvestment (this must be sent by the contract itself)
-        // after this we have 5_000_000 issued and 5_500_000 bonded
-        let rebond_msg = ExecuteMsg::_BondAllTokens {};
-        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
-        deps.querier
-            .update_balance(MOCK_CONTRACT_ADDR, coins(500_000, "ustake"));
-        let _ = execute(deps.as_mut(), mock_env(), info, rebond_msg).unwrap();
+        let curve_type = CurveType::Linear {
+            slope: Uint128::new(1),
+            scale: 1,
+        };
+        let msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
 
-        // update the querier with new bond, lower balance
-        set_delegation(&mut deps.querier, 500_500_000, "ustake");
-        deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+        let info = mock_info("bob", &coins(500_000_000, "ustake"));
+        let bond_msg = ExecuteMsg::Bond {
+            validator: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
 
-        // creator now tries to unbond these tokens - this must fail
-        let unbond_msg = ExecuteMsg::Unbond {
-            amount: Uint128::new(500_000),
+        let switch = SudoMsg::SwitchValidator {
+            validator: "unknown".into(),
         };
-        let info = mock_info(&creator, &[]);
-        let err = execute(deps.as_mut(), mock_env(), info, unbond_msg).unwrap_err();
+        let err = sudo(deps.as_mut(), mock_env(), switch).unwrap_err();
         assert_eq!(
             err,
-            ContractError::Std(StdError::overflow(OverflowError::new(
-                OverflowOperation::Sub,
-                0,
-                500_000
-            )))
+            ContractError::NotInValidatorSet {
+                validator: "unknown".into()
+            }
         );
 
-        // bob unbonds 100 tokens at 10% tax...
-        // 10 are taken and send to the owner
-        // 90 are unbonded in exchange for native tokens
-        let unbond_msg = ExecuteMsg::Unbond {
-            amount: Uint128::new(100),
+        // no need for the validator to be gone, or for any approvals
+        let switch = SudoMsg::SwitchValidator {
+            validator: "new-validator".into(),
         };
-        let owner_cut = Uint128::new(10);
-        let bobs_claim = Uint128::new(86_450_000);
-        let bobs_balance = Uint128::new(900);
-        let env = mock_env();
-        let info = mock_info(&bob, &[]);
-        let res = execute(deps.as_mut(), env.clone(), info, unbond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-        let delegate = &res.messages[0];
-        match &delegate.msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate { validator, amount }) => {
-                assert_eq!(validator.as_str(), DEFAULT_VALIDATOR);
-                assert_eq!(amount, &coin(bobs_claim.u128(), "ustake"));
-            }
-            _ => panic!("Unexpected message: {:?}", delegate),
-        }
-
-        // update the querier with new bond, lower balance
-        // i.e 500_500_000 minus 86_450_000
-        set_delegation(&mut deps.querier, 414_050_000, "ustake");
-
-        // check balances
-        assert_eq!(get_balance(deps.as_ref(), &bob), bobs_balance);
-        assert_eq!(get_balance(deps.as_ref(), &creator), owner_cut);
-        // proper claims
-        let expected_claims = vec![Claim {
-            amount: bobs_claim,
-            release_at: (DAY * 3).after(&env.block),
-        }];
-        assert_eq!(expected_claims, get_claims(deps.as_ref(), &bob));
-
-        // spot price has changed
-        let spot_price = Decimal::from_str("0.91").unwrap();
+        let res = sudo(deps.as_mut(), mock_env(), switch).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: DEFAULT_VALIDATOR.into(),
+                dst_validator: "new-validator".into(),
+                amount: coin(500_000_000, "ustake"),
+            })
+        );
+        let invest = get_investment(deps.as_ref()).unwrap();
+        assert_eq!(invest.validator, "new-validator");
 
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(invest.token_supply, bobs_balance + owner_cut);
-        assert_eq!(invest.staked_tokens, coin(414_050_000, "ustake")); // 500_500_000 minus 86_450_000
-        assert_eq!(invest.nominal_value, spot_price);
+        // reinvest goes through the same path as the execute message
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::Reinvest {}).unwrap();
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.into(),
+                msg: to_binary(&ExecuteMsg::_BondAllTokens {}).unwrap(),
+                funds: vec![],
+            })
+        );
     }
 
     #[test]
-    fn staking_tests_claims_paid_out_properly() {
+    fn staking_tests_exit_tax_brackets_charge_large_unbonds_more() {
         let mut deps = mock_dependencies(&[]);
         set_validator(&mut deps.querier);
 
-        // TODO test one with sqrt?
-        // let curve_type = CurveType::SquareRoot {
-        //     slope: Uint128::new(1),
-        //     scale: 2,
-        // };
         let curve_type = CurveType::Linear {
             slope: Uint128::new(1),
             scale: 1,
         };
 
-        // create contract
+        // brackets must be in increasing order
         let creator = String::from("creator");
-        let instantiate_msg = default_instantiate(
-            Some("https://f4.bcbits.com/img/a0113459728_10.jpg".to_string()),
-            2,
-            8,
-            curve_type.clone(),
-            10,
-            50,
-        );
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type.clone(), 10, 50);
+        instantiate_msg.staking_params.exit_tax_brackets = Some(vec![
+            TaxBracket {
+                threshold: Decimal::percent(50),
+                rate: Decimal::percent(50),
+            },
+            TaxBracket {
+                threshold: Decimal::percent(10),
+                rate: Decimal::percent(20),
+            },
+        ]);
+        let info = mock_info(&creator, &[]);
+        let err = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTaxBracket {});
+
+        // 10% up to a tenth of supply, 20% up to half, 50% above that
+        let mut instantiate_msg = default_instantiate(None, 2, 8, curve_type, 10, 50);
+        instantiate_msg.staking_params.exit_tax_brackets = Some(vec![
+            TaxBracket {
+                threshold: Decimal::percent(10),
+                rate: Decimal::percent(20),
+            },
+            TaxBracket {
+                threshold: Decimal::percent(50),
+                rate: Decimal::percent(50),
+            },
+        ]);
         let info = mock_info(&creator, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        // bond some tokens
-        // let bob = String::from("bob");
-        // let info = mock_info(&bob, &coins(1000, "ustake"));
-        // execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap();
-        // set_delegation(&mut deps.querier, 1000, "ustake");
-
-        // let's bond some tokens now
+        // bob gets 1000 tokens, which is the whole supply
         let bob = String::from("bob");
-        let bond_msg = ExecuteMsg::Bond {};
-        let bond_info = mock_info(
-            &bob,
-            &[
-                coin(1000, "this-one-is-ignored"),
-                coin(500_000_000, "ustake"),
-            ],
-        );
-        let res = execute(deps.as_mut(), mock_env(), bond_info, bond_msg).unwrap();
-        assert_eq!(1, res.messages.len());
-
-        // update the querier with new bond
-        set_delegation(&mut deps.querier, 500_000_000, "ustake");
-
-        // check bob's balance is 1k
-        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
-
-        // unbond part of them
-        let unbond_msg = ExecuteMsg::Unbond {
-            amount: Uint128::new(200),
-        };
-        let env = mock_env();
-        let info = mock_info(&bob, &[]);
-        execute(deps.as_mut(), env.clone(), info.clone(), unbond_msg).unwrap();
-        set_delegation(&mut deps.querier, 336_200_000, "ustake");
-
-        // bobs balance has gone down
-        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(800));
-
-        // overall staked tokens has only gone down by
-        // 500_000_000 - bob's claim
-        let invest = query_investment(deps.as_ref()).unwrap();
-        assert_eq!(invest.staked_tokens, coin(336_200_000, "ustake"));
-
-        // ensure claims are proper
-        let bobs_claim = Uint128::new(163_800_000);
-        let original_claims = vec![Claim {
-            amount: bobs_claim,
-            release_at: (DAY * 3).after(&env.block),
-        }];
-        assert_eq!(original_claims, get_claims(deps.as_ref(), &bob));
-
-        // bob cannot exercise claims without enough balance
-        let claim_ready = later(&env, (DAY * 3 + HOUR).unwrap());
-        let too_soon = later(&env, DAY);
-        let fail = execute(
-            deps.as_mut(),
-            claim_ready.clone(),
-            info.clone(),
-            ExecuteMsg::Claim {},
-        );
-        assert!(fail.is_err(), "{:?}", fail);
-
-        // provide the balance to cover bob's claim
-        // but claim is not yet mature - also prohibited
-        deps.querier
-            .update_balance(MOCK_CONTRACT_ADDR, coins(163_800_000, "ustake"));
-        let fail = execute(deps.as_mut(), too_soon, info.clone(), ExecuteMsg::Claim {});
-        assert!(fail.is_err(), "{:?}", fail);
-
-        // this should work with cash and claims ready
-        let res = execute(
+        let info = mock_info(&bob, &coins(500_000_000, "ustake"));
+        execute(
             deps.as_mut(),
-            claim_ready,
-            info.clone(),
-            ExecuteMsg::Claim {},
+            mock_env(),
+            info,
+            ExecuteMsg::Bond {
+                validator: None,
+                recipient: None,
+            },
         )
         .unwrap();
-        assert_eq!(1, res.messages.len());
-        let payout = &res.messages[0];
-        match &payout.msg {
-            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
-                assert_eq!(amount, &coins(163_800_000, "ustake"));
-                assert_eq!(to_address, &bob);
-            }
-            _ => panic!("Unexpected message: {:?}", payout),
-        }
+        set_delegation(&mut deps.querier, 500_000_000, "ustake");
 
-        // claims have been removed
-        assert_eq!(get_claims(deps.as_ref(), &bob), vec![]);
+        // unbonding 600 pays 10 + 80 + 50
+        let info = mock_info(&bob, &[]);
+        let unbond_msg = ExecuteMsg::Unbond {
+            amount: Uint128::new(600),
+        };
+        execute(deps.as_mut(), mock_env(), info, unbond_msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(400));
+        assert_eq!(get_balance(deps.as_ref(), &creator), Uint128::new(140));
     }
 
     //
@@ -1243,7 +8948,10 @@ mod tests {
 
         // spend 45_000 uatom for 30_000_000 EPOXY
         let info = mock_info(bob, &coins(45_000, DENOM));
-        let buy = ExecuteMsg::Buy {};
+        let buy = ExecuteMsg::Buy {
+            min_supply_out: None,
+            recipient: None,
+        };
         execute(deps.as_mut(), mock_env(), info, buy).unwrap();
 
         // check balances
@@ -1301,6 +9009,7 @@ mod tests {
         let burn_from = ExecuteMsg::BurnFrom {
             owner: bob.into(),
             amount: Uint128::new(3_300_000),
+            min_reserve_out: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, burn_from).unwrap_err();
         assert_eq!(
@@ -1317,6 +9026,7 @@ mod tests {
         let burn_from = ExecuteMsg::BurnFrom {
             owner: bob.into(),
             amount: Uint128::new(1_000_000),
+            min_reserve_out: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, burn_from).unwrap();
 
@@ -1365,7 +9075,16 @@ mod tests {
 
         // bond some tokens to create a balance
         let info = mock_info(&bob, &[coin(10, "random"), coin(500_000_000, "ustake")]);
-        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Bond {
+                validator: None,
+                recipient: None,
+            },
+        )
+        .unwrap();
 
         // bob got 1000 DRV for 1000 stake
         assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(1000));
@@ -1416,12 +9135,14 @@ mod tests {
         // burn some, but not too much
         let burn_too_much = ExecuteMsg::Burn {
             amount: Uint128::new(1000),
+            min_reserve_out: None,
         };
         let failed = execute(deps.as_mut(), mock_env(), bob_info.clone(), burn_too_much);
         assert!(failed.is_err());
         assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(550));
         let burn = ExecuteMsg::Burn {
             amount: Uint128::new(130),
+            min_reserve_out: None,
         };
         execute(deps.as_mut(), mock_env(), bob_info, burn).unwrap();
         assert_eq!(get_balance(deps.as_ref(), &bob), Uint128::new(420));
@@ -0,0 +1,32 @@
+use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::state::{COOLDOWN_ENDS, TRADE_COOLDOWN};
+
+/// after buying or bonding, address can't sell or unbond until the cooldown is over
+pub fn start_cooldown(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    address: &Addr,
+) -> StdResult<()> {
+    if let Some(cooldown) = TRADE_COOLDOWN.may_load(storage)? {
+        COOLDOWN_ENDS.save(storage, address, &cooldown.after(block))?;
+    }
+    Ok(())
+}
+
+pub fn assert_cooled_down(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    match COOLDOWN_ENDS.may_load(storage, address)? {
+        Some(until) if !until.is_expired(block) => Err(ContractError::CooldownActive { until }),
+        // no need to keep it once it has passed
+        Some(_) => {
+            COOLDOWN_ENDS.remove(storage, address);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
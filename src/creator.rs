@@ -0,0 +1,118 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult};
+use cw0::nonpayable;
+use cw20_base::state::MARKETING_INFO;
+
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::CreatorResponse;
+use crate::state::{CREATOR, METADATA_LOCKED, PENDING_CREATOR, TOKEN_INFO_WITH_META};
+
+pub fn assert_creator(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    if info.sender != CREATOR.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// only the creator can update the metadata of the work. None leaves a field as it is
+pub fn update_metadata<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    external_permalink_uri: Option<String>,
+    creator: Option<String>,
+    description: Option<String>,
+    asset_uri: Option<String>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    if METADATA_LOCKED.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::MetadataLocked {});
+    }
+
+    let mut meta = TOKEN_INFO_WITH_META.load(deps.storage)?;
+    if let Some(uri) = external_permalink_uri {
+        meta.external_permalink_uri = uri;
+    }
+    if let Some(creator) = creator {
+        meta.creator = creator;
+    }
+    if let Some(description) = description {
+        meta.description = description;
+    }
+    if asset_uri.is_some() {
+        meta.asset_uri = asset_uri;
+    }
+    TOKEN_INFO_WITH_META.save(deps.storage, &meta)?;
+
+    Ok(Response::new().add_attribute("action", "update_metadata"))
+}
+
+/// makes the metadata immutable. there is no way to unlock it again
+pub fn lock_metadata<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    METADATA_LOCKED.save(deps.storage, &true)?;
+
+    Ok(Response::new().add_attribute("action", "lock_metadata"))
+}
+
+/// the first step of handing over the creator role, which the new creator must accept.
+/// proposing again replaces any pending transfer
+pub fn transfer_creator<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_creator: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+
+    let new_creator = deps.api.addr_validate(&new_creator)?;
+    PENDING_CREATOR.save(deps.storage, &new_creator)?;
+
+    let res = Response::new()
+        .add_attribute("action", "transfer_creator")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", new_creator);
+    Ok(res)
+}
+
+pub fn accept_creator<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let pending = PENDING_CREATOR
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingCreator {})?;
+    if info.sender != pending {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let old = CREATOR.load(deps.storage)?;
+    CREATOR.save(deps.storage, &pending)?;
+    PENDING_CREATOR.remove(deps.storage);
+
+    // marketing moves with the role, unless it was handed to someone else
+    let mut marketing = MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default();
+    if marketing.marketing.as_ref() == Some(&old) {
+        marketing.marketing = Some(pending.clone());
+        MARKETING_INFO.save(deps.storage, &marketing)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "accept_creator")
+        .add_attribute("creator", pending);
+    Ok(res)
+}
+
+pub fn query_creator(deps: Deps) -> StdResult<CreatorResponse> {
+    let creator = CREATOR.load(deps.storage)?;
+    let pending_creator = PENDING_CREATOR.may_load(deps.storage)?;
+    Ok(CreatorResponse {
+        creator: creator.into(),
+        pending_creator: pending_creator.map(Into::into),
+    })
+}
@@ -0,0 +1,356 @@
+use cosmwasm_std::{Decimal as StdDecimal, QuerierWrapper, Uint128};
+use cw20_bonding::curves::{decimal, Curve, DecimalPlaces};
+use integer_sqrt::IntegerSquareRoot;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal::MathematicalOps;
+
+use crate::error::ContractError;
+use crate::msg::{
+    BaseCurveType, CurveQueryMsg, CurveReserveResponse, CurveSegment, CurveSpotPriceResponse,
+    CurveSupplyResponse, CurveType,
+};
+
+/// Like cw20-bonding's CurveFn, but the curves may borrow a querier (see ExternalCurve)
+pub type CurveFn<'a> = Box<dyn Fn(DecimalPlaces) -> Box<dyn Curve + 'a> + 'a>;
+
+// wraps one of the cw20-bonding curves
+fn base_curve_fn<'a>(curve_type: BaseCurveType) -> CurveFn<'a> {
+    let base = curve_type.to_curve_fn();
+    Box::new(move |places| -> Box<dyn Curve + 'a> { base(places) })
+}
+
+impl CurveType {
+    /// the querier is only used by External curves
+    pub fn to_curve_fn<'a>(&self, querier: QuerierWrapper<'a>) -> CurveFn<'a> {
+        match self.clone() {
+            CurveType::Constant { value, scale } => {
+                base_curve_fn(BaseCurveType::Constant { value, scale })
+            }
+            CurveType::Linear { slope, scale } => {
+                base_curve_fn(BaseCurveType::Linear { slope, scale })
+            }
+            CurveType::SquareRoot { slope, scale } => {
+                base_curve_fn(BaseCurveType::SquareRoot { slope, scale })
+            }
+            CurveType::Piecewise { segments } => {
+                let calc =
+                    move |places| -> Box<dyn Curve> { Box::new(Piecewise::new(&segments, places)) };
+                Box::new(calc)
+            }
+            CurveType::Sigmoid {
+                max_price,
+                midpoint,
+                steepness,
+                scale,
+            } => {
+                let calc = move |places| -> Box<dyn Curve> {
+                    Box::new(Sigmoid::new(
+                        decimal(max_price, scale),
+                        decimal(midpoint, scale),
+                        decimal(steepness, scale),
+                        places,
+                    ))
+                };
+                Box::new(calc)
+            }
+            CurveType::ReserveRatio {
+                ratio,
+                price,
+                scale,
+            } => {
+                let calc = move |places| -> Box<dyn Curve> {
+                    Box::new(ReserveRatio::new(
+                        decimal(ratio, scale),
+                        decimal(price, scale),
+                        places,
+                    ))
+                };
+                Box::new(calc)
+            }
+            CurveType::External { contract } => {
+                let calc = move |places| -> Box<dyn Curve + 'a> {
+                    Box::new(ExternalCurve {
+                        querier,
+                        contract: contract.clone(),
+                        normalize: places,
+                    })
+                };
+                Box::new(calc)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if let CurveType::Piecewise { segments } = self {
+            let starts_at_zero = matches!(segments.first(), Some(s) if s.start_supply.is_zero());
+            let sorted = segments
+                .windows(2)
+                .all(|pair| pair[0].start_supply < pair[1].start_supply);
+            if !starts_at_zero || !sorted {
+                return Err(ContractError::InvalidPiecewiseCurve {});
+            }
+        }
+        if let CurveType::Sigmoid {
+            max_price,
+            steepness,
+            ..
+        } = self
+        {
+            if max_price.is_zero() || steepness.is_zero() {
+                return Err(ContractError::InvalidSigmoidCurve {});
+            }
+        }
+        if let CurveType::ReserveRatio {
+            ratio,
+            price,
+            scale,
+        } = self
+        {
+            let ratio = decimal(*ratio, *scale);
+            if ratio <= Decimal::ZERO || ratio > Decimal::ONE || price.is_zero() {
+                return Err(ContractError::InvalidReserveRatio {});
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Piecewise strings together cw20-bonding curves over ranges of the supply.
+/// The reserve is the sum of what each segment holds up to the current supply
+pub struct Piecewise {
+    segments: Vec<(Uint128, Box<dyn Curve>)>,
+}
+
+impl Piecewise {
+    pub fn new(segments: &[CurveSegment], places: DecimalPlaces) -> Self {
+        let segments = segments
+            .iter()
+            .map(|s| (s.start_supply, s.curve.to_curve_fn()(places)))
+            .collect();
+        Piecewise { segments }
+    }
+
+    // the supply range of segment i, the last one being open-ended
+    fn end_of(&self, i: usize) -> Option<Uint128> {
+        self.segments.get(i + 1).map(|(start, _)| *start)
+    }
+
+    // the reserve held by segment i between its start and supply
+    fn reserve_in(&self, i: usize, supply: Uint128) -> Uint128 {
+        let (start, curve) = &self.segments[i];
+        curve.reserve(supply).saturating_sub(curve.reserve(*start))
+    }
+}
+
+impl Curve for Piecewise {
+    fn spot_price(&self, supply: Uint128) -> StdDecimal {
+        let (_, curve) = self
+            .segments
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= supply)
+            .unwrap_or(&self.segments[0]);
+        curve.spot_price(supply)
+    }
+
+    fn reserve(&self, supply: Uint128) -> Uint128 {
+        let mut reserve = Uint128::zero();
+        for i in 0..self.segments.len() {
+            if self.segments[i].0 >= supply {
+                break;
+            }
+            let end = match self.end_of(i) {
+                Some(end) => std::cmp::min(end, supply),
+                None => supply,
+            };
+            reserve += self.reserve_in(i, end);
+        }
+        reserve
+    }
+
+    fn supply(&self, reserve: Uint128) -> Uint128 {
+        let mut remaining = reserve;
+        for i in 0..self.segments.len() {
+            let (start, curve) = &self.segments[i];
+            if let Some(end) = self.end_of(i) {
+                let held = self.reserve_in(i, end);
+                if remaining >= held {
+                    remaining -= held;
+                    continue;
+                }
+            }
+            // the rest of the reserve lands in this segment
+            return curve.supply(curve.reserve(*start) + remaining);
+        }
+        Uint128::zero()
+    }
+}
+
+/// Sigmoid is an algebraic S-curve, so it only needs square roots:
+///   price(s) = max/2 * (1 + (s-m) / sqrt(w^2 + (s-m)^2))
+/// where m is the midpoint and w = 1/steepness. Its integral (the reserve) is
+///   max/2 * (s + sqrt(w^2 + (s-m)^2) - sqrt(w^2 + m^2))
+/// which can be solved for s
+pub struct Sigmoid {
+    pub max_price: Decimal,
+    pub midpoint: Decimal,
+    pub width: Decimal,
+    pub normalize: DecimalPlaces,
+}
+
+impl Sigmoid {
+    pub fn new(
+        max_price: Decimal,
+        midpoint: Decimal,
+        steepness: Decimal,
+        normalize: DecimalPlaces,
+    ) -> Self {
+        Sigmoid {
+            max_price,
+            midpoint,
+            width: Decimal::ONE / steepness,
+            normalize,
+        }
+    }
+
+    // sqrt(w^2 + x^2), the distance that shapes the curve
+    fn hypot(&self, x: Decimal) -> Decimal {
+        square_root(self.width * self.width + x * x)
+    }
+}
+
+impl Curve for Sigmoid {
+    fn spot_price(&self, supply: Uint128) -> StdDecimal {
+        let x = self.normalize.from_supply(supply) - self.midpoint;
+        let half = self.max_price / Decimal::TWO;
+        decimal_to_std(half + half * x / self.hypot(x))
+    }
+
+    fn reserve(&self, supply: Uint128) -> Uint128 {
+        let s = self.normalize.from_supply(supply);
+        let half = self.max_price / Decimal::TWO;
+        let reserve = half * (s + self.hypot(s - self.midpoint) - self.hypot(self.midpoint));
+        self.normalize.to_reserve(reserve.max(Decimal::ZERO))
+    }
+
+    fn supply(&self, reserve: Uint128) -> Uint128 {
+        let r = self.normalize.from_reserve(reserve);
+        // y = x + sqrt(w^2 + x^2) with x = s - m, so x = (y^2 - w^2) / 2y
+        let y = r * Decimal::TWO / self.max_price + self.hypot(self.midpoint) - self.midpoint;
+        if y <= Decimal::ZERO {
+            return Uint128::zero();
+        }
+        let x = (y * y - self.width * self.width) / (y + y);
+        self.normalize
+            .to_supply((self.midpoint + x).max(Decimal::ZERO))
+    }
+}
+
+/// ReserveRatio keeps the reserve a fixed fraction of the market cap, which makes
+///   reserve(s) = price * ratio * s^(1/ratio)
+/// and spot_price(s) = reserve(s) / (s * ratio) = price * s^(1/ratio - 1)
+pub struct ReserveRatio {
+    pub ratio: Decimal,
+    pub price: Decimal,
+    pub normalize: DecimalPlaces,
+}
+
+impl ReserveRatio {
+    pub fn new(ratio: Decimal, price: Decimal, normalize: DecimalPlaces) -> Self {
+        ReserveRatio {
+            ratio,
+            price,
+            normalize,
+        }
+    }
+
+    // powd doesn't handle a zero base
+    fn pow(base: Decimal, exp: Decimal) -> Decimal {
+        if base.is_zero() {
+            Decimal::ZERO
+        } else {
+            base.powd(exp)
+        }
+    }
+}
+
+impl Curve for ReserveRatio {
+    fn spot_price(&self, supply: Uint128) -> StdDecimal {
+        let s = self.normalize.from_supply(supply);
+        let exp = Decimal::ONE / self.ratio - Decimal::ONE;
+        if exp.is_zero() {
+            return decimal_to_std(self.price);
+        }
+        decimal_to_std(self.price * Self::pow(s, exp))
+    }
+
+    fn reserve(&self, supply: Uint128) -> Uint128 {
+        let s = self.normalize.from_supply(supply);
+        let reserve = self.price * self.ratio * Self::pow(s, Decimal::ONE / self.ratio);
+        self.normalize.to_reserve(reserve)
+    }
+
+    fn supply(&self, reserve: Uint128) -> Uint128 {
+        let r = self.normalize.from_reserve(reserve);
+        let supply = Self::pow(r / (self.price * self.ratio), self.ratio);
+        self.normalize.to_supply(supply)
+    }
+}
+
+/// ExternalCurve asks another contract for its prices, via CurveQueryMsg.
+/// The Curve trait can't return errors, so a failed query aborts the transaction
+pub struct ExternalCurve<'a> {
+    pub querier: QuerierWrapper<'a>,
+    pub contract: String,
+    pub normalize: DecimalPlaces,
+}
+
+impl<'a> ExternalCurve<'a> {
+    fn query<T: serde::de::DeserializeOwned>(&self, msg: &CurveQueryMsg) -> T {
+        self.querier
+            .query_wasm_smart(&self.contract, msg)
+            .unwrap_or_else(|e| panic!("external curve {} failed: {}", self.contract, e))
+    }
+}
+
+impl<'a> Curve for ExternalCurve<'a> {
+    fn spot_price(&self, supply: Uint128) -> StdDecimal {
+        let res: CurveSpotPriceResponse = self.query(&CurveQueryMsg::SpotPrice {
+            supply,
+            decimals: self.normalize,
+        });
+        res.spot_price
+    }
+
+    fn reserve(&self, supply: Uint128) -> Uint128 {
+        let res: CurveReserveResponse = self.query(&CurveQueryMsg::Reserve {
+            supply,
+            decimals: self.normalize,
+        });
+        res.reserve
+    }
+
+    fn supply(&self, reserve: Uint128) -> Uint128 {
+        let res: CurveSupplyResponse = self.query(&CurveQueryMsg::Supply {
+            reserve,
+            decimals: self.normalize,
+        });
+        res.supply
+    }
+}
+
+fn decimal_to_std(x: Decimal) -> StdDecimal {
+    let str_val = x.round_dp(18).to_string();
+    str_val.parse().unwrap()
+}
+
+// square root to 6 decimal places
+fn square_root(square: Decimal) -> Decimal {
+    const EXTRA_DIGITS: u32 = 12;
+    let multiplier = 10u128.saturating_pow(EXTRA_DIGITS);
+    let extended = square * decimal(multiplier, 0);
+    let extended = extended.floor().to_u128().unwrap();
+    let root = extended.integer_sqrt();
+    decimal(root, EXTRA_DIGITS / 2)
+}
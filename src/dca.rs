@@ -0,0 +1,192 @@
+use crate::curves::CurveFn;
+use cosmwasm_std::{
+    coins, BankMsg, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Uint128,
+};
+use cw0::{must_pay, nonpayable, Duration, Expiration};
+use cw_storage_plus::Bound;
+
+use crate::bonding::{assert_native_reserve, do_buy};
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::{DcaPlanResponse, DcaPlansResponse};
+use crate::state::{DcaPlan, CURVE_STATE, DCA_COUNT, DCA_PLANS, ESCROWED_RESERVE, KEEPER_FEE};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+// due straight away, counted the same way as the period
+fn now(period: &Duration, block: &BlockInfo) -> Expiration {
+    match period {
+        Duration::Height(_) => Expiration::AtHeight(block.height),
+        Duration::Time(_) => Expiration::AtTime(block.time),
+    }
+}
+
+/// escrows the reserve sent for deposits buys of amount_per_period, the first of which
+/// can be made straight away
+pub fn create_dca<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount_per_period: Uint128,
+    period: Duration,
+    deposits: u64,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let no_time = matches!(period, Duration::Height(0) | Duration::Time(0));
+    if amount_per_period.is_zero() || deposits == 0 || no_time {
+        return Err(ContractError::InvalidDca {});
+    }
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let reserve = must_pay(&info, &reserve_denom)?;
+    let expected = amount_per_period
+        .checked_mul(deposits.into())
+        .map_err(StdError::overflow)?;
+    if reserve != expected {
+        return Err(ContractError::DcaFundingMismatch { expected });
+    }
+
+    let id = DCA_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    DCA_COUNT.save(deps.storage, &id)?;
+    DCA_PLANS.save(
+        deps.storage,
+        id.into(),
+        &DcaPlan {
+            owner: info.sender.clone(),
+            amount_per_period,
+            period,
+            deposits_left: deposits,
+            next: now(&period, &env.block),
+        },
+    )?;
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed + reserve)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "create_dca")
+        .add_attribute("plan", id.to_string())
+        .add_attribute("from", info.sender)
+        .add_attribute("amount_per_period", amount_per_period)
+        .add_attribute("deposits", deposits.to_string());
+    Ok(res)
+}
+
+/// anyone can make a plan's buy once it is due, taking the keeper fee out of it.
+/// The next one is due a period from now, and the plan ends with its last deposit
+pub fn execute_dca<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut plan = DCA_PLANS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::DcaNotFound { id })?;
+    if !plan.next.is_expired(&env.block) {
+        return Err(ContractError::DcaNotDue {
+            id,
+            next: plan.next,
+        });
+    }
+
+    plan.deposits_left -= 1;
+    if plan.deposits_left == 0 {
+        DCA_PLANS.remove(deps.storage, id.into());
+    } else {
+        plan.next = plan.period.after(&env.block);
+        DCA_PLANS.save(deps.storage, id.into(), &plan)?;
+    }
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(plan.amount_per_period)?)
+    })?;
+
+    let fee = plan.amount_per_period * KEEPER_FEE.load(deps.storage)?;
+    let payment = plan
+        .amount_per_period
+        .checked_sub(fee)
+        .map_err(StdError::overflow)?;
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+
+    let mut res = do_buy(deps, env, plan.owner, payment, curve_fn, None)?
+        .add_attribute("plan", id.to_string())
+        .add_attribute("deposits_left", plan.deposits_left.to_string())
+        .add_attribute("keeper", info.sender.as_str())
+        .add_attribute("keeper_fee", fee);
+    if !fee.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(fee.u128(), reserve_denom),
+        });
+    }
+    Ok(res)
+}
+
+/// refunds the deposits a plan has left to its owner
+pub fn cancel_dca<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let plan = DCA_PLANS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::DcaNotFound { id })?;
+    if info.sender != plan.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    DCA_PLANS.remove(deps.storage, id.into());
+    let refund = plan.amount_per_period * Uint128::from(plan.deposits_left);
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(refund)?)
+    })?;
+
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: plan.owner.to_string(),
+            amount: coins(refund.u128(), reserve_denom),
+        })
+        .add_attribute("action", "cancel_dca")
+        .add_attribute("plan", id.to_string())
+        .add_attribute("refund", refund);
+    Ok(res)
+}
+
+pub fn query_dca_plans(
+    deps: Deps,
+    owner: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DcaPlansResponse> {
+    let owner = owner.map(|o| deps.api.addr_validate(&o)).transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let plans = DCA_PLANS
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| match (item, &owner) {
+            (Ok((_, plan)), Some(owner)) => &plan.owner == owner,
+            _ => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (key, plan) = item?;
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&key);
+            Ok(DcaPlanResponse {
+                id: u64::from_be_bytes(id_bytes),
+                owner: plan.owner.into(),
+                amount_per_period: plan.amount_per_period,
+                period: plan.period,
+                deposits_left: plan.deposits_left,
+                next: plan.next,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(DcaPlansResponse { plans })
+}
@@ -0,0 +1,124 @@
+use cosmwasm_std::{
+    BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StakingMsg, StdResult, Storage, Uint128,
+};
+use cw0::{nonpayable, Duration, Expiration};
+
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::UnbondEpochResponse;
+use crate::staking::undelegate;
+use crate::state::{InvestmentInfo, INVESTMENT, UNBOND_EPOCH};
+
+/// the epoch has to be counted the same way as the unbonding period,
+/// so claims can be dated from its end
+pub fn validate_unbond_epoch(
+    epoch: &Duration,
+    unbonding_period: &Duration,
+) -> Result<(), ContractError> {
+    match (epoch, unbonding_period) {
+        (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_)) => {
+            Ok(())
+        }
+        _ => Err(ContractError::InvalidUnbondEpoch {}),
+    }
+}
+
+/// undelegates amount, or with an unbond epoch set, adds it to the epoch's batch so each
+/// validator sees one undelegation per epoch rather than hitting the staking module's
+/// limit of 7 in flight. Returns when a claim on amount can be released, along with any
+/// undelegations due now, which includes the last epoch's batch if it has ended
+pub fn queue_undelegation(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    invest: &InvestmentInfo,
+    amount: Uint128,
+) -> StdResult<(Expiration, Vec<StakingMsg>)> {
+    let length = match invest.unbond_epoch {
+        Some(length) => length,
+        None => {
+            let msgs = undelegate(storage, invest, amount)?;
+            return Ok((invest.unbonding_period.after(block), msgs));
+        }
+    };
+
+    let mut epoch = UNBOND_EPOCH.may_load(storage)?.unwrap_or_default();
+    let mut msgs = vec![];
+    if !epoch.pending.is_zero() && epoch.ends.is_expired(block) {
+        msgs = undelegate(storage, invest, epoch.pending)?;
+        epoch.pending = Uint128::zero();
+    }
+    if epoch.pending.is_zero() {
+        epoch.id += 1;
+        epoch.ends = length.after(block);
+    }
+    epoch.pending += amount;
+    UNBOND_EPOCH.save(storage, &epoch)?;
+
+    Ok((
+        release_after(epoch.ends, invest.unbonding_period, block),
+        msgs,
+    ))
+}
+
+// the unbonding period, counted from the end of the epoch
+fn release_after(ends: Expiration, unbonding_period: Duration, block: &BlockInfo) -> Expiration {
+    let mut at_end = block.clone();
+    match ends {
+        Expiration::AtHeight(height) => at_end.height = height,
+        Expiration::AtTime(time) => at_end.time = time,
+        Expiration::Never {} => {}
+    }
+    unbonding_period.after(&at_end)
+}
+
+/// what is waiting for the epoch to end, still delegated but no longer in the reserve
+pub fn pending_undelegation(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(UNBOND_EPOCH
+        .may_load(storage)?
+        .map(|epoch| epoch.pending)
+        .unwrap_or_default())
+}
+
+/// undelegates the batch of an epoch that has ended. Anyone can call this; the next
+/// unbond would do it otherwise, but claims are dated from the epoch end either way
+pub fn process_epoch<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let mut epoch = UNBOND_EPOCH.may_load(deps.storage)?.unwrap_or_default();
+    if epoch.pending.is_zero() || !epoch.ends.is_expired(&env.block) {
+        return Err(ContractError::EpochNotEnded {});
+    }
+
+    let msgs = undelegate(deps.storage, &invest, epoch.pending)?;
+    let undelegated = epoch.pending;
+    epoch.pending = Uint128::zero();
+    UNBOND_EPOCH.save(deps.storage, &epoch)?;
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "process_epoch")
+        .add_attribute("epoch", epoch.id.to_string())
+        .add_attribute("undelegated", undelegated);
+    Ok(res)
+}
+
+pub fn query_unbond_epoch(deps: Deps, env: Env) -> StdResult<UnbondEpochResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let epoch = UNBOND_EPOCH.may_load(deps.storage)?.unwrap_or_default();
+    let ends = if epoch.pending.is_zero() {
+        None
+    } else {
+        Some(epoch.ends)
+    };
+    Ok(UnbondEpochResponse {
+        epoch_length: invest.unbond_epoch,
+        id: epoch.id,
+        pending: epoch.pending,
+        ends,
+        ready: ends.map(|e| e.is_expired(&env.block)).unwrap_or(false),
+    })
+}
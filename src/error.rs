@@ -1,5 +1,6 @@
-use cosmwasm_std::{StdError, Uint128};
-use cw0::PaymentError;
+use cosmwasm_std::{Decimal, StdError, Uint128};
+use cw0::{Expiration, PaymentError};
+use cw_controllers::HookError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -13,12 +14,45 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Validator '{validator}' not in current validator set")]
     NotInValidatorSet { validator: String },
 
+    #[error("Validator '{validator}' is not one of the allowed validators")]
+    ValidatorNotAllowed { validator: String },
+
+    #[error("Reward skim cannot be more than 100%")]
+    InvalidRewardSkim {},
+
+    #[error("Funding pool only holds {available}")]
+    FundingPoolTooSmall { available: Uint128 },
+
+    #[error("Approval threshold must be between 1 and the number of approvers")]
+    InvalidApprovalThreshold {},
+
+    #[error("No validator change has been proposed for '{validator}'")]
+    NoValidatorChangeProposed { validator: String },
+
+    #[error("Already approved")]
+    AlreadyApproved {},
+
+    #[error("Got {amount}, less than the minimum of {min}")]
+    SlippageExceeded { amount: Uint128, min: Uint128 },
+
+    #[error("No swap router is configured")]
+    NoSwapRouter {},
+
+    #[error("{denom} is already the reserve denom, no swap needed")]
+    SwapNotNeeded { denom: String },
+
+    #[error("No target weights are configured for rebalancing")]
+    NoTargetWeights {},
+
     #[error("Different denominations in bonds: '{denom1}' vs. '{denom2}'")]
     DifferentBondDenom { denom1: String, denom2: String },
 
@@ -51,4 +85,309 @@ pub enum ContractError {
 
     #[error("Minting cannot exceed the cap")]
     CannotExceedCap {},
+
+    #[error("Exit tax brackets must have increasing thresholds and rates of at most 100%")]
+    InvalidTaxBracket {},
+
+    #[error("No creator transfer is pending")]
+    NoPendingCreator {},
+
+    #[error("Keeper fee must be at most 100%")]
+    InvalidKeeperFee {},
+
+    #[error("Order {id} not found")]
+    OrderNotFound { id: u64 },
+
+    #[error("Spot price {spot_price} has not reached the order limit {limit}")]
+    LimitNotReached { spot_price: Decimal, limit: Decimal },
+
+    #[error("Order is expired")]
+    OrderExpired {},
+
+    #[error("Not enough donated to withdraw, {available} available")]
+    DonationTooSmall { available: Uint128 },
+
+    #[error("Cannot migrate from {contract} {version}")]
+    CannotMigrate { contract: String, version: String },
+
+    #[error("{validator} is still in the validator set, propose a change instead")]
+    ValidatorStillActive { validator: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Contract is paused")]
+    ContractPaused {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Exit tax can be at most {max}")]
+    ExitTaxTooHigh { max: Decimal },
+
+    #[error("Metadata is locked")]
+    MetadataLocked {},
+
+    #[error("Piecewise curve segments must start at 0 and be sorted by start_supply")]
+    InvalidPiecewiseCurve {},
+
+    #[error("Sigmoid curve needs a non-zero max_price and steepness")]
+    InvalidSigmoidCurve {},
+
+    #[error("Reserve ratio must be above 0 and at most 1, with a non-zero price")]
+    InvalidReserveRatio {},
+
+    #[error("The funding goal has been reached, the curve is closed")]
+    FundingGoalReached {},
+
+    #[error("The funding deadline passed without reaching the goal, only refunds are possible")]
+    FundingRefund {},
+
+    #[error("The sale is not active")]
+    SaleNotActive {},
+
+    #[error("Only whitelisted addresses can buy during the presale")]
+    NotWhitelisted {},
+
+    #[error("Presale allowance exceeded, {remaining} left")]
+    PresaleCapExceeded { remaining: Uint128 },
+
+    #[error("Instant unbond penalty cannot be more than 100%")]
+    InvalidInstantUnbondPenalty {},
+
+    #[error("Instant unbond penalty {penalty} is above the {max} allowed")]
+    PenaltyExceeded { penalty: Decimal, max: Decimal },
+
+    #[error("Not enough liquid reserve to pay out instantly, {available} available")]
+    InsufficientLiquidity { available: Uint128 },
+
+    #[error("Liquid ratio cannot be more than 100%")]
+    InvalidLiquidRatio {},
+
+    #[error("Claim incentive cannot be more than 100%")]
+    InvalidClaimIncentive {},
+
+    #[error("The reserve is a cw20 token, buy by sending it with a Buy message")]
+    Cw20Reserve {},
+
+    #[error("Reserve denom '{denom}' is not a known ibc denom")]
+    InvalidIbcDenom { denom: String },
+
+    #[error("The reserve is not the staking denom, so it cannot be bonded")]
+    ReserveNotStaked {},
+
+    #[error("Unbonding period must be at least the chain's {min} seconds")]
+    UnbondingPeriodTooShort { min: u64 },
+
+    #[error("None of the fallback validators are in the active set")]
+    NoHealthyFallback {},
+
+    #[error("A poll needs at least two options")]
+    PollNeedsOptions {},
+
+    #[error("Poll {id} not found")]
+    PollNotFound { id: u64 },
+
+    #[error("Poll {id} is closed")]
+    PollClosed { id: u64 },
+
+    #[error("Poll has no option {option}")]
+    InvalidPollOption { option: u32 },
+
+    #[error("Already voted in this poll")]
+    AlreadyVoted {},
+
+    #[error("No tokens held when the poll opened")]
+    NoVotingPower {},
+
+    #[error("Only {available} in claims to transfer")]
+    InsufficientClaims { available: Uint128 },
+
+    #[error("Unbond epoch must be counted in the same units as the unbonding period")]
+    InvalidUnbondEpoch {},
+
+    #[error("No unbonding epoch has ended with anything to undelegate")]
+    EpochNotEnded {},
+
+    #[error("Commitment must be a 32 byte sha256 hash")]
+    InvalidCommitment {},
+
+    #[error("Reveal or cancel the open commitment first")]
+    CommitmentExists {},
+
+    #[error("No buy commitment to reveal")]
+    NoCommitment {},
+
+    #[error("Reveal has to come in a later block than the commitment")]
+    RevealTooSoon {},
+
+    #[error("Salt doesn't match the commitment")]
+    CommitmentMismatch {},
+
+    #[error(
+        "Launch needs a tranche, a nonzero floor no higher than the start price, and a duration"
+    )]
+    InvalidLaunch {},
+
+    #[error("Launch floor must raise at least {floor} for the tranche, what the curve charges")]
+    LaunchBelowCurve { floor: Uint128 },
+
+    #[error("There is no launch auction")]
+    NoLaunch {},
+
+    #[error("The launch auction is closed")]
+    LaunchClosed {},
+
+    #[error("The launch auction is still taking bids")]
+    LaunchNotOver {},
+
+    #[error("The launch auction hasn't been settled")]
+    LaunchNotSettled {},
+
+    #[error("No launch bid to claim")]
+    NoLaunchBid {},
+
+    #[error("Nothing has been issued for a reserve donation to back")]
+    NothingToBack {},
+
+    #[error("Burning every token would leave the reserve backing nothing")]
+    BurnWouldOrphanReserve {},
+
+    #[error("{denom} backs the curve and can't be swept")]
+    CannotSweep { denom: String },
+
+    #[error("No {denom} to sweep")]
+    NothingToSweep { denom: String },
+
+    #[error("{token} is held for holders and can't be recovered")]
+    CannotRecover { token: String },
+
+    #[error("Only {available} of that token can be recovered")]
+    RecoverTooMuch { available: Uint128 },
+
+    #[error("Patron tiers need names and thresholds that rise from above zero")]
+    InvalidPatronTiers {},
+
+    #[error("A DCA plan needs an amount, a period and at least one deposit")]
+    InvalidDca {},
+
+    #[error("A DCA plan of these deposits must be funded with exactly {expected}")]
+    DcaFundingMismatch { expected: Uint128 },
+
+    #[error("DCA plan {id} not found")]
+    DcaNotFound { id: u64 },
+
+    #[error("DCA plan {id} isn't due until {next}")]
+    DcaNotDue { id: u64, next: Expiration },
+
+    #[error("The spread must be below 100%")]
+    InvalidSpread {},
+
+    #[error("Protocol fees must be below 10000 bps")]
+    InvalidFeeConfig {},
+
+    #[error("No protocol fee is configured")]
+    NoFeeConfig {},
+
+    #[error("No protocol fees to collect")]
+    NoFeesAccrued {},
+
+    #[error("The creator escrow needs a share below 100% and milestones releasing all of it")]
+    InvalidCreatorEscrow {},
+
+    #[error("No creator escrow is configured")]
+    NoCreatorEscrow {},
+
+    #[error("No milestone {milestone}")]
+    MilestoneNotFound { milestone: u32 },
+
+    #[error("Milestone {milestone} hasn't been reached")]
+    MilestoneLocked { milestone: u32 },
+
+    #[error("Poll {poll_id} doesn't approve the milestone")]
+    MilestoneNotApproved { poll_id: u64 },
+
+    #[error("Nothing in the creator escrow to release")]
+    NothingToRelease {},
+
+    #[error("The creator allocation needs an amount and a vesting duration")]
+    InvalidVesting {},
+
+    #[error("No creator allocation is vesting")]
+    NoVesting {},
+
+    #[error("Nothing has vested to claim")]
+    NothingVested {},
+
+    #[error("Payees need distinct addresses and weights above zero")]
+    InvalidPayees {},
+
+    #[error("{address} is frozen")]
+    AddressFrozen { address: String },
+
+    #[error("Buying is halted")]
+    BuysHalted {},
+
+    #[error("Selling is halted")]
+    SellsHalted {},
+
+    #[error("Can't sell or unbond so soon after buying, wait until {until}")]
+    CooldownActive { until: Expiration },
+
+    #[error("Those tokens cost {cost}, more than was sent")]
+    BuyExactUnderpaid { cost: Uint128 },
+
+    #[error("That takes selling {amount} tokens, more than the maximum of {max}")]
+    SupplyInExceeded { amount: Uint128, max: Uint128 },
+
+    #[error("The curve only holds {available}")]
+    InsufficientReserve { available: Uint128 },
+
+    #[error("Buy and Bond are turned off, use Purchase")]
+    UsePurchase {},
+
+    #[error("The creator's split of rewards cannot be more than 10000 bps")]
+    InvalidRewardStrategy {},
+
+    #[error("Claim expiry must be counted the same way as the unbonding period")]
+    InvalidClaimExpiry {},
+
+    #[error("Claims do not expire")]
+    ClaimsDoNotExpire {},
+
+    #[error("No claims have expired")]
+    NoExpiredClaims {},
+
+    #[error("The project is winding down")]
+    WindingDown {},
+
+    #[error("The project is not winding down")]
+    NotWindingDown {},
+
+    #[error("Settlement opens at {at}")]
+    SettlementNotOpen { at: Expiration },
+
+    #[error("No tokens to settle")]
+    NothingToSettle {},
+
+    #[error("Royalty cannot be more than 10000 bps")]
+    InvalidRoyalty {},
+
+    #[error("Unbond limits must be above zero")]
+    InvalidUnbondLimits {},
+
+    #[error("Unbonding is rate limited, only {available} can be unbonded until {retry_at}")]
+    RateLimited {
+        available: Uint128,
+        retry_at: Expiration,
+    },
+
+    #[error("Target weights must be above zero, with one per validator")]
+    InvalidTargetWeights {},
+
+    #[error("Unbonding period must be counted in time, as the chain's is")]
+    UnbondingPeriodNotTime {},
+
+    #[error("Milestone {milestone} isn't awaiting a vote, or the poll would be too short")]
+    InvalidMilestonePoll { milestone: u32 },
 }
@@ -0,0 +1,157 @@
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Empty, MessageInfo, Order, Response, StdResult, Storage, Uint128,
+};
+use cw0::nonpayable;
+use cw_storage_plus::Bound;
+
+use crate::bonding::send_reserve;
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, FeeConfig, TradeDirection};
+use crate::query::{FeeExemptionsResponse, ProtocolFeesResponse};
+use crate::state::{CURVE_STATE, FEES_ACCRUED, FEES_COLLECTED, FEE_CONFIG, FEE_EXEMPT, INVESTMENT};
+
+const BPS: u16 = 10_000;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// checks the collector address and that neither fee takes the whole trade
+pub fn validate_fee_config(deps: Deps, fee_config: FeeConfig) -> Result<FeeConfig, ContractError> {
+    if fee_config.buy_fee_bps >= BPS || fee_config.sell_fee_bps >= BPS {
+        return Err(ContractError::InvalidFeeConfig {});
+    }
+    Ok(FeeConfig {
+        collector: deps.api.addr_validate(&fee_config.collector)?.into_string(),
+        ..fee_config
+    })
+}
+
+/// whether trader pays no exit tax or protocol fees
+pub fn is_fee_exempt(storage: &dyn Storage, trader: &Addr) -> bool {
+    FEE_EXEMPT.has(storage, trader)
+}
+
+/// sets aside the collector's fee on trader's buy of, or sell releasing, amount of
+/// reserve, returning it
+pub fn take_protocol_fee(
+    storage: &mut dyn Storage,
+    trader: &Addr,
+    amount: Uint128,
+    direction: TradeDirection,
+) -> StdResult<Uint128> {
+    let fee = amount * fee_rate(storage, trader, direction)?;
+    if !fee.is_zero() {
+        let accrued = fees_accrued(storage)? + fee;
+        FEES_ACCRUED.save(storage, &accrued)?;
+    }
+    Ok(fee)
+}
+
+/// the share of a trade the protocol fee takes from trader
+pub fn fee_rate(
+    storage: &dyn Storage,
+    trader: &Addr,
+    direction: TradeDirection,
+) -> StdResult<Decimal> {
+    let fee_config = match FEE_CONFIG.may_load(storage)? {
+        Some(fee_config) if !is_fee_exempt(storage, trader) => fee_config,
+        _ => return Ok(Decimal::zero()),
+    };
+    let bps = match direction {
+        TradeDirection::Buy => fee_config.buy_fee_bps,
+        TradeDirection::Sell => fee_config.sell_fee_bps,
+        _ => 0,
+    };
+    Ok(Decimal::from_ratio(bps, BPS))
+}
+
+/// fees set aside so far, not yet sent to the collector
+pub fn fees_accrued(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(FEES_ACCRUED.may_load(storage)?.unwrap_or_default())
+}
+
+/// sends the collector the fees accrued. Anyone can call this
+pub fn collect_fees<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let fee_config = FEE_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoFeeConfig {})?;
+    let accrued = fees_accrued(deps.storage)?;
+    if accrued.is_zero() {
+        return Err(ContractError::NoFeesAccrued {});
+    }
+    FEES_ACCRUED.save(deps.storage, &Uint128::zero())?;
+    let collected = FEES_COLLECTED.may_load(deps.storage)?.unwrap_or_default();
+    FEES_COLLECTED.save(deps.storage, &(collected + accrued))?;
+
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let msg = send_reserve(
+        deps.storage,
+        fee_config.collector.clone(),
+        accrued,
+        reserve_denom,
+    )?;
+    let res = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "collect_fees")
+        .add_attribute("collector", fee_config.collector)
+        .add_attribute("amount", accrued);
+    Ok(res)
+}
+
+/// adds and removes fee exemptions, removals last. Owner only
+pub fn update_fee_exemptions<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for addr in add.iter() {
+        let addr = deps.api.addr_validate(addr)?;
+        FEE_EXEMPT.save(deps.storage, &addr, &Empty {})?;
+    }
+    for addr in remove.iter() {
+        let addr = deps.api.addr_validate(addr)?;
+        FEE_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_fee_exemptions")
+        .add_attribute("added", add.join(","))
+        .add_attribute("removed", remove.join(","));
+    Ok(res)
+}
+
+pub fn query_fee_exemptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeExemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::exclusive(s.as_bytes()));
+
+    let addresses = FEE_EXEMPT
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(String::from_utf8)
+        .collect::<Result<_, _>>()?;
+    Ok(FeeExemptionsResponse { addresses })
+}
+
+pub fn query_protocol_fees(deps: Deps) -> StdResult<ProtocolFeesResponse> {
+    Ok(ProtocolFeesResponse {
+        fee_config: FEE_CONFIG.may_load(deps.storage)?,
+        accrued: fees_accrued(deps.storage)?,
+        collected: FEES_COLLECTED.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
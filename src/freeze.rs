@@ -0,0 +1,89 @@
+use cosmwasm_std::{
+    Api, Deps, DepsMut, Empty, Event, MessageInfo, Order, Response, StdResult, Storage,
+};
+use cw0::nonpayable;
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::FrozenResponse;
+use crate::state::{FROZEN, INVESTMENT};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// fails if any of the accounts is frozen
+pub fn assert_not_frozen(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    accounts: &[String],
+) -> Result<(), ContractError> {
+    for account in accounts {
+        let addr = api.addr_validate(account)?;
+        if FROZEN.may_load(storage, &addr)?.is_some() {
+            return Err(ContractError::AddressFrozen {
+                address: account.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn freeze<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response<C>, ContractError> {
+    set_frozen(deps, info, address, true)
+}
+
+pub fn unfreeze<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response<C>, ContractError> {
+    set_frozen(deps, info, address, false)
+}
+
+// only the owner keeps the blocklist
+fn set_frozen<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    frozen: bool,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let addr = deps.api.addr_validate(&address)?;
+    if frozen {
+        FROZEN.save(deps.storage, &addr, &Empty {})?;
+    } else {
+        FROZEN.remove(deps.storage, &addr);
+    }
+
+    let action = if frozen { "freeze" } else { "unfreeze" };
+    let res = Response::new()
+        .add_event(Event::new(action).add_attribute("address", addr))
+        .add_attribute("action", action);
+    Ok(res)
+}
+
+pub fn query_frozen(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FrozenResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::exclusive(s.as_bytes()));
+
+    let addresses = FROZEN
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(String::from_utf8)
+        .collect::<Result<_, _>>()?;
+    Ok(FrozenResponse { addresses })
+}
@@ -0,0 +1,78 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, MessageInfo, Response, StdResult, Storage, SubMsg, Uint128,
+};
+use cw0::nonpayable;
+
+use crate::error::ContractError;
+use crate::msg::{lift_submsg, CustomMsg, TradeDirection, TradeHookMsg};
+use crate::query::HooksResponse;
+use crate::state::{HOOKS, INVESTMENT};
+
+/// the messages notifying every registered hook of a trade. A failing hook fails the trade
+pub fn trade_hooks<C: CustomMsg>(
+    storage: &dyn Storage,
+    token: &Addr,
+    account: &Addr,
+    amount: Uint128,
+    direction: TradeDirection,
+) -> StdResult<Vec<SubMsg<C>>> {
+    let msg = TradeHookMsg {
+        token: token.to_string(),
+        account: account.to_string(),
+        amount,
+        direction,
+    };
+    // cw-controllers only builds plain submessages
+    HOOKS
+        .prepare_hooks(storage, |h| {
+            msg.clone().into_cosmos_msg::<_, Empty>(h).map(SubMsg::new)
+        })?
+        .into_iter()
+        .map(lift_submsg)
+        .collect()
+}
+
+pub fn add_hook<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook)?;
+
+    let res = Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", addr);
+    Ok(res)
+}
+
+pub fn remove_hook<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, hook)?;
+
+    let res = Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("hook", addr);
+    Ok(res)
+}
+
+pub fn query_hooks(deps: Deps) -> StdResult<HooksResponse> {
+    let hooks = HOOKS.query_hooks(deps)?.hooks;
+    Ok(HooksResponse { hooks })
+}
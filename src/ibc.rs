@@ -0,0 +1,40 @@
+use cosmwasm_std::{QuerierWrapper, StdError};
+
+use crate::error::ContractError;
+use crate::proto::{bytes_field, encode_bytes, query_stargate};
+use crate::query::DenomTrace;
+
+/// the ibc-transfer module's gRPC query for where an ibc/... voucher came from
+pub const DENOM_TRACE_PATH: &str = "/ibc.applications.transfer.v1.Query/DenomTrace";
+
+/// looks up the trace of an ibc/... denom, erroring if the chain does not know it.
+/// Any other denom is taken as native to this chain and has no trace
+pub fn query_denom_trace(
+    querier: &QuerierWrapper,
+    denom: &str,
+) -> Result<Option<DenomTrace>, ContractError> {
+    let invalid = || ContractError::InvalidIbcDenom {
+        denom: denom.to_string(),
+    };
+    let hash = match denom.strip_prefix("ibc/") {
+        Some(hash) => hash,
+        None => return Ok(None),
+    };
+    // the hash is a hex encoded sha256 of the trace
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    // QueryDenomTraceRequest { hash = 1 }
+    let response = query_stargate(querier, DENOM_TRACE_PATH, encode_bytes(1, hash.as_bytes()))?
+        .ok_or_else(invalid)?;
+
+    // QueryDenomTraceResponse { denom_trace = 1 }, DenomTrace { path = 1, base_denom = 2 }
+    let trace = bytes_field(&response, 1)?;
+    let path = String::from_utf8(bytes_field(&trace, 1)?).map_err(StdError::from)?;
+    let base_denom = String::from_utf8(bytes_field(&trace, 2)?).map_err(StdError::from)?;
+    if base_denom.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Some(DenomTrace { path, base_denom }))
+}
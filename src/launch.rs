@@ -0,0 +1,273 @@
+use cosmwasm_std::{
+    coins, BankMsg, BlockInfo, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo, Response,
+    StdResult, Storage, Uint128,
+};
+use cw0::{must_pay, nonpayable, Duration};
+use cw20_bonding::curves::Curve;
+
+use crate::bonding::{
+    assert_native_reserve, checkpoint_spot_price, execute_mint, record_contribution,
+};
+use crate::curves::CurveFn;
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, LaunchAuction, TradeDirection};
+use crate::patrons::record_support;
+use crate::query::LaunchResponse;
+use crate::state::{
+    Launch, LaunchPhase, CREATOR, CURVE_STATE, ESCROWED_RESERVE, LAUNCH, LAUNCH_BIDS,
+};
+use crate::stats::record_trade;
+
+/// sets up the auction to run from this block. Its floor must not undercut the curve,
+/// or the curve couldn't issue the tranche for what the auction raises
+pub fn start_launch(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    auction: LaunchAuction,
+    curve: &dyn Curve,
+) -> Result<(), ContractError> {
+    assert_native_reserve(storage)?;
+    let no_time = matches!(auction.duration, Duration::Height(0) | Duration::Time(0));
+    if auction.tranche.is_zero()
+        || auction.end_price.is_zero()
+        || auction.start_price < auction.end_price
+        || no_time
+    {
+        return Err(ContractError::InvalidLaunch {});
+    }
+    let floor = curve.reserve(auction.tranche);
+    if auction.tranche * auction.end_price < floor {
+        return Err(ContractError::LaunchBelowCurve { floor });
+    }
+
+    let launch = Launch {
+        tranche: auction.tranche,
+        start_price: auction.start_price,
+        end_price: auction.end_price,
+        duration: auction.duration,
+        start_height: block.height,
+        start_time: block.time,
+        committed: Uint128::zero(),
+        phase: LaunchPhase::Bidding {},
+    };
+    LAUNCH.save(storage, &launch)?;
+    Ok(())
+}
+
+/// the curve stays shut until the launch auction is settled
+pub fn assert_launched(storage: &dyn Storage) -> Result<(), ContractError> {
+    match LAUNCH.may_load(storage)? {
+        Some(Launch {
+            phase: LaunchPhase::Bidding {},
+            ..
+        }) => Err(ContractError::LaunchNotSettled {}),
+        _ => Ok(()),
+    }
+}
+
+// the price falls in a straight line from start_price to end_price over the duration
+fn current_price(launch: &Launch, block: &BlockInfo) -> Decimal {
+    let (elapsed, total) = match launch.duration {
+        Duration::Height(blocks) => (block.height.saturating_sub(launch.start_height), blocks),
+        Duration::Time(secs) => (
+            block
+                .time
+                .seconds()
+                .saturating_sub(launch.start_time.seconds()),
+            secs,
+        ),
+    };
+    if elapsed >= total {
+        return launch.end_price;
+    }
+    let drop = launch.start_price - launch.end_price;
+    launch.start_price
+        - Decimal::from_ratio(
+            Uint128::new(drop.numerator()).multiply_ratio(elapsed, total),
+            drop.denominator(),
+        )
+}
+
+// bids close once they would buy the whole tranche at the current price, or time runs out
+fn bidding_over(launch: &Launch, block: &BlockInfo) -> bool {
+    let price = current_price(launch, block);
+    price == launch.end_price || launch.committed >= launch.tranche * price
+}
+
+// everyone pays the same: what the bids came to per token if they sold the tranche out,
+// otherwise the floor
+fn clearing_price(launch: &Launch) -> Decimal {
+    let sold_out_at = Decimal::from_ratio(launch.committed, launch.tranche);
+    if sold_out_at > launch.end_price {
+        sold_out_at
+    } else {
+        launch.end_price
+    }
+}
+
+fn tokens_for(reserve: Uint128, price: Decimal) -> Uint128 {
+    reserve.multiply_ratio(price.denominator(), price.numerator())
+}
+
+/// escrows the reserve sent as a bid at the going price. Anything past what would sell
+/// out the tranche at that price is refunded
+pub fn launch_bid<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let payment = must_pay(&info, &reserve_denom)?;
+    let mut launch = LAUNCH
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLaunch {})?;
+    if launch.phase != (LaunchPhase::Bidding {}) || bidding_over(&launch, &env.block) {
+        return Err(ContractError::LaunchClosed {});
+    }
+
+    let price = current_price(&launch, &env.block);
+    let room = (launch.tranche * price).saturating_sub(launch.committed);
+    let bid = payment.min(room);
+    launch.committed += bid;
+    LAUNCH.save(deps.storage, &launch)?;
+    LAUNCH_BIDS.update(deps.storage, &info.sender, |b| -> StdResult<_> {
+        Ok(b.unwrap_or_default() + bid)
+    })?;
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed + bid)
+    })?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "launch_bid")
+        .add_attribute("from", info.sender.as_str())
+        .add_attribute("bid", bid)
+        .add_attribute("price", price.to_string());
+    let refund = payment - bid;
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into_string(),
+            amount: coins(refund.u128(), reserve_denom),
+        });
+    }
+    Ok(res)
+}
+
+/// closes the auction once bidding is over and opens the curve. The bids seed the reserve,
+/// and supply moves to where the curve has it for that reserve. What the curve issues
+/// beyond the tokens sold, the premium bidders paid over it, goes to the creator.
+/// Anyone can call this
+pub fn settle_launch<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut launch = LAUNCH
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLaunch {})?;
+    if launch.phase != (LaunchPhase::Bidding {}) {
+        return Err(ContractError::LaunchClosed {});
+    }
+    if !bidding_over(&launch, &env.block) {
+        return Err(ContractError::LaunchNotOver {});
+    }
+
+    let clearing_price = clearing_price(&launch);
+    let sold = tokens_for(launch.committed, clearing_price);
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(launch.committed)?)
+    })?;
+    let mut state = CURVE_STATE.load(deps.storage)?;
+    let curve = curve_fn(state.decimals);
+    state.curve_reserve += launch.committed;
+    let new_supply = curve.supply(state.curve_reserve);
+    // the floor keeps the tranche above the curve, short of rounding
+    let premium = new_supply.saturating_sub(state.supply + sold);
+    state.supply = new_supply;
+    CURVE_STATE.save(deps.storage, &state)?;
+    checkpoint_spot_price(deps.storage, &env.block, curve.spot_price(state.supply))?;
+
+    launch.phase = LaunchPhase::Settled { clearing_price };
+    LAUNCH.save(deps.storage, &launch)?;
+
+    let res = Response::new()
+        .add_attribute("action", "settle_launch")
+        .add_attribute("clearing_price", clearing_price.to_string())
+        .add_attribute("reserve", launch.committed)
+        .add_attribute("sold", sold)
+        .add_attribute("premium", premium);
+    if premium.is_zero() {
+        return Ok(res);
+    }
+    let creator = CREATOR.load(deps.storage)?;
+    let sub_info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let mint = execute_mint::<C>(deps, env, sub_info, creator.into_string(), premium)?;
+    Ok(res.add_attributes(mint.attributes))
+}
+
+/// mints the sender's share of the tranche, their bid at the clearing price
+pub fn claim_launch_tokens<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let clearing_price = match LAUNCH.may_load(deps.storage)? {
+        Some(Launch {
+            phase: LaunchPhase::Settled { clearing_price },
+            ..
+        }) => clearing_price,
+        Some(_) => return Err(ContractError::LaunchNotSettled {}),
+        None => return Err(ContractError::NoLaunch {}),
+    };
+    let bid = LAUNCH_BIDS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoLaunchBid {})?;
+    LAUNCH_BIDS.remove(deps.storage, &info.sender);
+    record_contribution(deps.storage, &info.sender, bid)?;
+    record_trade(deps.storage, &info.sender, TradeDirection::Buy, bid)?;
+    record_support(deps.storage, &info.sender, bid)?;
+
+    let tokens = tokens_for(bid, clearing_price);
+    let sub_info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let mint = execute_mint::<C>(
+        deps.branch(),
+        env,
+        sub_info,
+        info.sender.to_string(),
+        tokens,
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "claim_launch_tokens")
+        .add_attribute("to", info.sender)
+        .add_attribute("bid", bid)
+        .add_attribute("tokens", tokens)
+        .add_attributes(mint.attributes);
+    Ok(res)
+}
+
+pub fn query_launch(deps: Deps, env: Env) -> StdResult<LaunchResponse> {
+    let launch = LAUNCH.load(deps.storage)?;
+    let clearing_price = match launch.phase {
+        LaunchPhase::Settled { clearing_price } => Some(clearing_price),
+        LaunchPhase::Bidding {} => None,
+    };
+    Ok(LaunchResponse {
+        tranche: launch.tranche,
+        start_price: launch.start_price,
+        end_price: launch.end_price,
+        current_price: current_price(&launch, &env.block),
+        committed: launch.committed,
+        bidding_open: clearing_price.is_none() && !bidding_over(&launch, &env.block),
+        clearing_price,
+    })
+}
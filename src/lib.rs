@@ -1,9 +1,39 @@
 pub mod bonding;
+pub mod claims;
+pub mod commit;
 pub mod contract;
+pub mod cooldown;
+pub mod creator;
+pub mod curves;
+pub mod dca;
+pub mod epoch;
 mod error;
+pub mod fees;
+pub mod freeze;
+pub mod hooks;
+pub mod ibc;
+pub mod launch;
+pub mod milestones;
 pub mod msg;
+pub mod orders;
+pub mod patrons;
+pub mod pause;
+pub mod polls;
+pub mod presale;
+mod proto;
 pub mod query;
+pub mod receipts;
+pub mod registry;
+pub mod rewards;
+pub mod royalty;
+pub mod snapshot;
 pub mod staking;
 pub mod state;
+pub mod stats;
+pub mod tax;
+pub mod treasury;
+pub mod unbond_limits;
+pub mod vesting;
+pub mod wind_down;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,242 @@
+use cosmwasm_std::{
+    BlockInfo, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+use cw0::{nonpayable, Duration};
+
+use crate::bonding::send_reserve;
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::{CreatorEscrowConfig, CustomMsg, MilestoneUnlock};
+use crate::query::{CreatorEscrowResponse, MilestoneStatus};
+use crate::snapshot::query_total_supply_at;
+use crate::state::{CreatorEscrow, CREATOR, CREATOR_ESCROW, CURVE_STATE, POLLS};
+
+/// checks the share taken is a fraction, and that the milestones release all of it
+pub fn validate_creator_escrow(config: &CreatorEscrowConfig) -> Result<(), ContractError> {
+    let total = config
+        .milestones
+        .iter()
+        .fold(Decimal::zero(), |total, m| total + m.share);
+    let empty_milestone = config.milestones.iter().any(|m| {
+        m.share.is_zero()
+            || matches!(&m.unlock, MilestoneUnlock::Vote { quorum, .. }
+                if quorum.is_zero() || *quorum > Decimal::one())
+    });
+    if config.share.is_zero() || config.share >= Decimal::one() {
+        return Err(ContractError::InvalidCreatorEscrow {});
+    }
+    if empty_milestone || total != Decimal::one() {
+        return Err(ContractError::InvalidCreatorEscrow {});
+    }
+    Ok(())
+}
+
+pub fn start_creator_escrow(
+    storage: &mut dyn Storage,
+    config: CreatorEscrowConfig,
+) -> Result<(), ContractError> {
+    validate_creator_escrow(&config)?;
+    let escrow = CreatorEscrow {
+        share: config.share,
+        released: vec![false; config.milestones.len()],
+        milestones: config.milestones,
+        accrued: Uint128::zero(),
+        paid: Uint128::zero(),
+        polls_used: vec![],
+    };
+    CREATOR_ESCROW.save(storage, &escrow)?;
+    Ok(())
+}
+
+/// the share of each buy escrowed for the creator, if any
+pub fn creator_share(storage: &dyn Storage) -> StdResult<Decimal> {
+    Ok(CREATOR_ESCROW
+        .may_load(storage)?
+        .map(|escrow| escrow.share)
+        .unwrap_or_else(Decimal::zero))
+}
+
+/// sets the creator's share of a buy's payment aside in escrow, returning it
+pub fn take_creator_share(storage: &mut dyn Storage, payment: Uint128) -> StdResult<Uint128> {
+    let mut escrow = match CREATOR_ESCROW.may_load(storage)? {
+        Some(escrow) => escrow,
+        None => return Ok(Uint128::zero()),
+    };
+    let share = payment * escrow.share;
+    if !share.is_zero() {
+        escrow.accrued += share;
+        CREATOR_ESCROW.save(storage, &escrow)?;
+    }
+    Ok(share)
+}
+
+/// what the escrow holds that hasn't been paid to the creator
+pub fn creator_escrow_held(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(CREATOR_ESCROW
+        .may_load(storage)?
+        .map(|escrow| escrow.accrued - escrow.paid)
+        .unwrap_or_default())
+}
+
+// the escrow the released milestones entitle the creator to and they haven't had yet.
+// Buys after a release add to what that milestone has freed
+fn releasable(escrow: &CreatorEscrow) -> Uint128 {
+    let share = escrow
+        .milestones
+        .iter()
+        .zip(escrow.released.iter())
+        .filter(|(_, released)| **released)
+        .fold(Decimal::zero(), |total, (m, _)| total + m.share);
+    (escrow.accrued * share).saturating_sub(escrow.paid)
+}
+
+/// checks a poll can be opened for milestone: one that is still to be reached by vote,
+/// for at least as long as it asks
+pub fn assert_milestone_poll(
+    storage: &dyn Storage,
+    milestone: u32,
+    voting_period: &Duration,
+) -> Result<(), ContractError> {
+    let escrow = CREATOR_ESCROW
+        .may_load(storage)?
+        .ok_or(ContractError::NoCreatorEscrow {})?;
+    let index = milestone as usize;
+    let unlock = escrow
+        .milestones
+        .get(index)
+        .map(|m| &m.unlock)
+        .ok_or(ContractError::MilestoneNotFound { milestone })?;
+    let long_enough = match (unlock, voting_period) {
+        (
+            MilestoneUnlock::Vote {
+                min_voting_period: Duration::Height(min),
+                ..
+            },
+            Duration::Height(period),
+        )
+        | (
+            MilestoneUnlock::Vote {
+                min_voting_period: Duration::Time(min),
+                ..
+            },
+            Duration::Time(period),
+        ) => period >= min,
+        _ => false,
+    };
+    if escrow.released[index] || !long_enough {
+        return Err(ContractError::InvalidMilestonePoll { milestone });
+    }
+    Ok(())
+}
+
+// a poll approves a milestone once it was opened for it and has closed with quorum of
+// the supply voting, and its first option ahead of all the others together
+fn poll_approves(
+    deps: Deps,
+    block: &BlockInfo,
+    poll_id: u64,
+    milestone: u32,
+    quorum: Decimal,
+) -> Result<bool, ContractError> {
+    let poll = POLLS
+        .may_load(deps.storage, poll_id.into())?
+        .ok_or(ContractError::PollNotFound { id: poll_id })?;
+    if poll.milestone != Some(milestone) || !poll.end.is_expired(block) {
+        return Ok(false);
+    }
+    let supply = query_total_supply_at(deps, poll.start_height)?.total_supply;
+    let turnout: Uint128 = poll.tallies.iter().sum();
+    if turnout.is_zero() || turnout < supply * quorum {
+        return Ok(false);
+    }
+    let against: Uint128 = poll.tallies.iter().skip(1).sum();
+    Ok(poll.tallies[0] > against)
+}
+
+/// releases a milestone once it has been reached, paying the creator its tranche and
+/// whatever the milestones already released have freed since. A milestone that unlocks
+/// by vote needs a closed poll opened for it approving it, which can't be used again.
+/// Creator only
+pub fn release_milestone<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    milestone: u32,
+    poll_id: Option<u64>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    let mut escrow = CREATOR_ESCROW
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoCreatorEscrow {})?;
+    let index = milestone as usize;
+    let unlock = escrow
+        .milestones
+        .get(index)
+        .map(|m| m.unlock.clone())
+        .ok_or(ContractError::MilestoneNotFound { milestone })?;
+
+    if !escrow.released[index] {
+        match unlock {
+            MilestoneUnlock::After { expiration } => {
+                if !expiration.is_expired(&env.block) {
+                    return Err(ContractError::MilestoneLocked { milestone });
+                }
+            }
+            MilestoneUnlock::Vote { quorum, .. } => {
+                let poll_id = poll_id.ok_or(ContractError::MilestoneLocked { milestone })?;
+                if escrow.polls_used.contains(&poll_id)
+                    || !poll_approves(deps.as_ref(), &env.block, poll_id, milestone, quorum)?
+                {
+                    return Err(ContractError::MilestoneNotApproved { poll_id });
+                }
+                escrow.polls_used.push(poll_id);
+            }
+        }
+        escrow.released[index] = true;
+    }
+
+    let amount = releasable(&escrow);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToRelease {});
+    }
+    escrow.paid += amount;
+    CREATOR_ESCROW.save(deps.storage, &escrow)?;
+
+    let creator = CREATOR.load(deps.storage)?;
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let res = Response::new()
+        .add_message(send_reserve(
+            deps.storage,
+            creator.to_string(),
+            amount,
+            reserve_denom,
+        )?)
+        .add_attribute("action", "release_milestone")
+        .add_attribute("milestone", milestone.to_string())
+        .add_attribute("to", creator)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn query_creator_escrow(deps: Deps) -> StdResult<CreatorEscrowResponse> {
+    let escrow = CREATOR_ESCROW.load(deps.storage)?;
+    let releasable = releasable(&escrow);
+    let milestones = escrow
+        .milestones
+        .into_iter()
+        .zip(escrow.released)
+        .map(|(milestone, released)| MilestoneStatus {
+            share: milestone.share,
+            unlock: milestone.unlock,
+            released,
+        })
+        .collect();
+    Ok(CreatorEscrowResponse {
+        share: escrow.share,
+        accrued: escrow.accrued,
+        paid: escrow.paid,
+        releasable,
+        milestones,
+    })
+}
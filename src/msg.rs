@@ -1,11 +1,111 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use cosmwasm_std::{Binary, Decimal, Uint128};
+pub use crate::query::ClaimsResponse;
+use cosmwasm_std::{
+    to_binary, Binary, CosmosMsg, Decimal, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
 use cw0::Duration;
-use cw20::Expiration;
-pub use cw20_bonding::msg::CurveType;
-pub use cw_controllers::ClaimsResponse;
+use cw20::{Cw20ReceiveMsg, Expiration, Logo};
+use cw20_base::msg::InstantiateMarketingInfo;
+use cw20_bonding::curves::DecimalPlaces;
+pub use cw20_bonding::msg::CurveType as BaseCurveType;
+
+/// The messages a chain with custom bindings (eg. Osmosis, Injective) adds to CosmosMsg.
+/// Responses are generic over it, so a contract embedding this one can add its own
+/// alongside ours. Anything CosmosMsg::Custom accepts will do, Empty being the default.
+/// Custom queries need no such parameter, Deps and QuerierWrapper being untyped in
+/// cosmwasm 0.16, so a derived contract queries its chain with QuerierWrapper::custom_query
+pub trait CustomMsg: Clone + fmt::Debug + PartialEq + JsonSchema {}
+
+impl<T> CustomMsg for T where T: Clone + fmt::Debug + PartialEq + JsonSchema {}
+
+/// lifts a response built without custom messages, as cw20-base and cw-controllers
+/// build them, into one that can carry them
+pub fn lift_response<C: CustomMsg>(res: Response) -> StdResult<Response<C>> {
+    let messages = res
+        .messages
+        .into_iter()
+        .map(lift_submsg)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut lifted = Response::new()
+        .add_submessages(messages)
+        .add_attributes(res.attributes)
+        .add_events(res.events);
+    if let Some(data) = res.data {
+        lifted = lifted.set_data(data);
+    }
+    Ok(lifted)
+}
+
+pub fn lift_submsg<C: CustomMsg>(sub: SubMsg) -> StdResult<SubMsg<C>> {
+    let msg = match sub.msg {
+        CosmosMsg::Bank(msg) => msg.into(),
+        CosmosMsg::Staking(msg) => msg.into(),
+        CosmosMsg::Distribution(msg) => msg.into(),
+        CosmosMsg::Wasm(msg) => msg.into(),
+        _ => return Err(StdError::generic_err("cannot lift message")),
+    };
+    Ok(SubMsg {
+        id: sub.id,
+        msg,
+        gas_limit: sub.gas_limit,
+        reply_on: sub.reply_on,
+    })
+}
+
+/// The curves from cw20-bonding, plus the ones implemented in this crate (see curves.rs)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveType {
+    Constant {
+        value: Uint128,
+        scale: u32,
+    },
+    Linear {
+        slope: Uint128,
+        scale: u32,
+    },
+    SquareRoot {
+        slope: Uint128,
+        scale: u32,
+    },
+    /// Piecewise prices each range of the supply with its own curve, eg. a steep one early
+    /// on and a flatter one later. Segments are sorted by start_supply, the first starting at 0
+    Piecewise {
+        segments: Vec<CurveSegment>,
+    },
+    /// Sigmoid starts slow, rises fastest around midpoint (in whole tokens) and levels off
+    /// towards max_price. Higher steepness makes the middle sharper.
+    /// max_price, midpoint and steepness are all divided by 10^scale
+    Sigmoid {
+        max_price: Uint128,
+        midpoint: Uint128,
+        steepness: Uint128,
+        scale: u32,
+    },
+    /// ReserveRatio is the Bancor continuous token: price = reserve / (supply * ratio),
+    /// with ratio in (0, 1]. price is the spot price at one whole token.
+    /// ratio and price are divided by 10^scale
+    ReserveRatio {
+        ratio: Uint128,
+        price: Uint128,
+        scale: u32,
+    },
+    /// External asks another contract for prices, which must answer `CurveQueryMsg`
+    External {
+        contract: String,
+    },
+}
+
+/// One piece of a piecewise curve. It applies from start_supply up to the next segment,
+/// and is evaluated at the total supply (not relative to start_supply)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurveSegment {
+    pub start_supply: Uint128,
+    pub curve: BaseCurveType,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StakingParams {
@@ -20,6 +120,205 @@ pub struct StakingParams {
     /// This is the minimum amount we will pull out to reinvest, as well as a minimum
     /// that can be unbonded (to avoid needless staking tx)
     pub min_withdrawal: Uint128,
+    /// (optional) other validators that bonders may choose to delegate to instead
+    pub allowed_validators: Option<Vec<String>>,
+    /// (optional) how stake should be spread across the default and allowed validators.
+    /// Rebalance redelegates towards these weights
+    pub target_weights: Option<Vec<ValidatorWeight>>,
+    /// (optional) share of reinvested staking rewards kept liquid in the creator's funding
+    /// pool instead of being re-delegated
+    pub reward_skim: Option<Decimal>,
+    /// (optional) addresses that must approve switching the validator. Defaults to the owner
+    pub validator_approvers: Option<Vec<String>>,
+    /// (optional) how many of the approvers are needed. Defaults to all of them
+    pub validator_approval_threshold: Option<u64>,
+    /// (optional) progressive tax brackets for large exits, sorted by threshold.
+    /// exit_tax is charged on the part of an unbond below the first threshold
+    pub exit_tax_brackets: Option<Vec<TaxBracket>>,
+    /// (optional) how far stored and queried delegations may differ before bonding and
+    /// unbonding fail, to allow for rounding dust. Defaults to zero
+    pub bond_tolerance: Option<Uint128>,
+    /// (optional) the highest exit_tax the owner can later set. Defaults to exit_tax,
+    /// so it can only be lowered
+    pub max_exit_tax: Option<Decimal>,
+    /// (optional) share of an InstantUnbond kept back for the remaining holders. Defaults to 5%
+    pub instant_unbond_penalty: Option<Decimal>,
+    /// (optional) share of the reserve reinvest keeps liquid as a buffer for claims and
+    /// instant unbonds, rather than delegating it. Defaults to zero
+    pub liquid_ratio: Option<Decimal>,
+    /// (optional) share of a claim paid to a keeper who pushes it out with ClaimFor.
+    /// Defaults to zero
+    pub claim_incentive: Option<Decimal>,
+    /// (optional) validators to fail over to, in order of preference, should the default
+    /// validator be jailed or otherwise leave the active set
+    pub fallback_validators: Option<Vec<String>>,
+    /// (optional) batches unbonds into one undelegation per validator at the end of each
+    /// epoch of this length, counted like unbonding_period. Without it, each unbond
+    /// undelegates straight away
+    pub unbond_epoch: Option<Duration>,
+    /// (optional) how the owner is paid the exit tax. Defaults to minting it as tokens
+    pub exit_tax_payout: Option<ExitTaxPayout>,
+    /// (optional) where reinvested staking rewards go. Defaults to compounding them
+    pub reward_strategy: Option<RewardStrategy>,
+    /// (optional) how long a matured claim may go unclaimed, counted like
+    /// unbonding_period, before RecycleExpiredClaims takes it back. Claims never expire
+    /// without it
+    pub claim_expiry: Option<Duration>,
+    /// (optional) where expired claims are sent. Without it they go back into the reserve,
+    /// to the holders
+    pub claim_recovery_address: Option<String>,
+}
+
+/// Caps on unbonding throughput, in tokens burnt. Days are counted from midnight UTC
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondLimits {
+    pub per_block: Option<Uint128>,
+    pub per_day: Option<Uint128>,
+}
+
+/// Someone taking a part of the exit tax, relative to the sum of all weights
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payee {
+    pub address: String,
+    pub weight: u64,
+}
+
+/// How the owner is paid the exit tax
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitTaxPayout {
+    /// minted to the owner as more tokens, diluting the supply
+    Mint {},
+    /// carved out of the unbonded reserve, for the owner to withdraw with
+    /// WithdrawOwnerFees once the undelegation matures
+    Native {},
+}
+
+/// Where staking rewards go once the reward skim is taken
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardStrategy {
+    /// into the curve reserve, so every token redeems for more
+    Compound {},
+    /// set aside for holders, pro-rata to their balance
+    DistributeToHolders {},
+    /// bps of them to the creator's funding pool, the rest compounds
+    SplitWithCreator { bps: u16 },
+}
+
+/// The share of stake a validator should hold, relative to the sum of all weights
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorWeight {
+    pub validator: String,
+    pub weight: u64,
+}
+
+/// A whitelisted presale buyer, and how much reserve they may spend before the presale ends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistEntry {
+    pub address: String,
+    pub cap: Uint128,
+}
+
+/// A level of support the creator can reward. Reaching threshold of reserve spent
+/// over all time makes an address a patron of this tier
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PatronTier {
+    pub name: String,
+    pub threshold: Uint128,
+}
+
+/// A marginal exit tax bracket. The part of an unbond above `threshold`
+/// (a fraction of the total supply) is taxed at `rate`, up to the next bracket
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxBracket {
+    pub threshold: Decimal,
+    pub rate: Decimal,
+}
+
+/// How much reserve the campaign aims to raise, and optionally by when
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FundingGoal {
+    pub amount: Uint128,
+    pub deadline: Option<Expiration>,
+    /// (optional) crowdfund mode: stop selling on the curve once the goal is raised
+    pub closes_curve: Option<bool>,
+}
+
+/// A Dutch auction of tranche tokens, its price falling from start_price to end_price over
+/// duration. Prices are reserve per supply token, in base units. The proceeds seed the
+/// reserve, and the curve opens where they put it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LaunchAuction {
+    pub tranche: Uint128,
+    pub start_price: Decimal,
+    /// the floor, which can't be below what the curve would charge for the tranche
+    pub end_price: Decimal,
+    pub duration: Duration,
+}
+
+/// A companion cw721 collection, which must let this contract mint. Every single buy
+/// of at least min_reserve gets a receipt minted to the buyer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftReceipts {
+    pub collection: String,
+    pub min_reserve: Uint128,
+}
+
+/// A protocol fee on every buy and sell, eg. for the launchpad that deployed the contract.
+/// The fees are in basis points of the reserve paid in or released, and accrue to collector
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeConfig {
+    pub collector: String,
+    pub buy_fee_bps: u16,
+    pub sell_fee_bps: u16,
+}
+
+/// What releases a milestone's tranche of the creator escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneUnlock {
+    /// reached once expiration has passed
+    After { expiration: Expiration },
+    /// reached when holders approve it in a poll opened for it, running at least
+    /// min_voting_period, that at least quorum of the supply voted in
+    Vote {
+        quorum: Decimal,
+        min_voting_period: Duration,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Milestone {
+    /// the part of the escrow this milestone releases
+    pub share: Decimal,
+    pub unlock: MilestoneUnlock,
+}
+
+/// Crowdfunding with accountability: share of every buy goes to an escrow for the
+/// creator, released in tranches as milestones are reached. The milestones' shares
+/// must add up to the whole escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorEscrowConfig {
+    pub share: Decimal,
+    pub milestones: Vec<Milestone>,
+}
+
+/// How the creator's allocation unlocks, counted from instantiation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingSchedule {
+    /// unlocks in a straight line over the duration
+    Linear { duration: Duration },
+    /// unlocks all at once when the duration is up
+    Cliff { duration: Duration },
+}
+
+/// Tokens minted to the creator up front, held by the contract until they vest
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorAllocation {
+    pub amount: Uint128,
+    pub vesting_schedule: VestingSchedule,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -53,10 +352,10 @@ pub struct InstantiateMsg {
     /// If it is eg. BTC, where a balance of 10^8 means 1 BTC, then use 8 here.
     pub decimals: u8,
 
-    /// this is the reserve token denom (only support native for now)
-    // think this probably shouldn't even be settable thanks to staking constraints
-    // which mean staking is native
-    // pub reserve_denom: String,
+    /// (optional) the reserve token denom, eg. an ibc/... voucher, which is checked against the
+    /// chain's ibc-transfer module. Defaults to the staking denom, and only that can be bonded.
+    /// See reserve_token for a cw20
+    pub reserve_denom: Option<String>,
     /// number of decimal places for the reserve token, needed for proper curve math.
     /// Same format as decimals above, eg. if it is uatom, where 1 unit is 10^-6 ATOM, use 6 here
     pub reserve_decimals: u8,
@@ -69,19 +368,120 @@ pub struct InstantiateMsg {
 
     /// put all the staking params into a basket
     pub staking_params: StakingParams,
+
+    /// (optional) a DEX router implementing `RouterExecuteMsg`, so buyers can pay in any denom
+    pub swap_router: Option<String>,
+
+    /// (optional) a catalog contract implementing `RegistryExecuteMsg`, told about this
+    /// token once it is instantiated
+    pub registry: Option<String>,
+
+    /// (optional) cw20 marketing info. The marketing address defaults to the creator
+    pub marketing: Option<InstantiateMarketingInfo>,
+
+    /// (optional) the address that administers metadata and the funding pool.
+    /// Defaults to the instantiator, who is also the owner
+    pub creator_address: Option<String>,
+
+    /// (optional) the share of a limit order paid to the keeper who fills it. Defaults to 0.5%
+    pub keeper_fee: Option<Decimal>,
+
+    /// (optional) a funding goal for the campaign, reported by the FundingProgress query
+    pub funding_goal: Option<FundingGoal>,
+
+    /// (optional) the most supply tokens that can ever be minted, for a fixed edition size
+    pub max_supply: Option<Uint128>,
+
+    /// (optional) when Buy and Bond open, for a timed drop
+    pub sale_start: Option<Expiration>,
+    /// (optional) when Buy and Bond close again
+    pub sale_end: Option<Expiration>,
+
+    /// (optional) a cw20 (eg. a stablecoin) to use as the reserve instead of the native
+    /// staking denom. Buying is then done by sending it with a ReceiveMsg, and
+    /// bonding is unavailable as there is nothing to stake
+    pub reserve_token: Option<String>,
+
+    /// (optional) sell a first tranche by descending-price auction before the curve opens
+    pub launch: Option<LaunchAuction>,
+
+    /// (optional) mint an NFT receipt from a cw721 collection for large buys
+    pub nft_receipts: Option<NftReceipts>,
+
+    /// (optional) the share of every buy and sell kept in the reserve for the holders,
+    /// eg. 0.003 for 30 bps, so a round trip can't be arbitraged. Defaults to none
+    pub spread: Option<Decimal>,
+
+    /// (optional) how long after buying or bonding an address must wait before it can
+    /// sell or unbond, to deter wash trading against the curve
+    pub trade_cooldown: Option<Duration>,
+
+    /// (optional) caps on how many tokens can be unbonded per block and per day,
+    /// to slow a run on the curve
+    pub unbond_limits: Option<UnbondLimits>,
+
+    /// (optional) turn off Buy and Bond, leaving Purchase as the one way to buy.
+    /// Defaults to false
+    pub purchase_only: Option<bool>,
+
+    /// (optional) a protocol fee on buys and sells, which can't be changed later
+    pub fee_config: Option<FeeConfig>,
+
+    /// (optional) escrow a share of every buy for the creator, released by milestone
+    pub creator_escrow: Option<CreatorEscrowConfig>,
+
+    /// (optional) pre-mint tokens for the creator, vesting on a schedule. They aren't
+    /// bought on the curve, so they dilute holders once sold
+    pub creator_allocation: Option<CreatorAllocation>,
+
+    /// (optional) royalty in bps marketplaces are asked to pay the creator on secondary
+    /// sales, as cw2981 has it. Defaults to none
+    pub royalty_bps: Option<u16>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     /// Buy will attempt to purchase as many supply tokens as possible.
-    /// You must send only reserve tokens in that message
-    Buy {},
+    /// You must send only reserve tokens in that message.
+    /// If fewer than min_supply_out tokens would be minted, the buy fails.
+    /// The tokens go to recipient if set, otherwise to the sender
+    Buy {
+        min_supply_out: Option<Uint128>,
+        recipient: Option<String>,
+    },
+
+    /// BuyExact buys exactly tokens, working out what they cost from the curve and
+    /// refunding the rest of what was sent
+    BuyExact { tokens: Uint128 },
+
+    /// BuyWithSwap swaps the funds sent into the reserve denom via the configured router,
+    /// then buys with the proceeds. min_reserve_out bounds the swap, min_tokens_out the buy
+    BuyWithSwap {
+        min_reserve_out: Option<Uint128>,
+        min_tokens_out: Uint128,
+    },
+    /// _BuySwapped can only be called by the contract itself, after the swap from BuyWithSwap
+    _BuySwapped {
+        buyer: String,
+        balance_before: Uint128,
+        min_tokens_out: Uint128,
+    },
 
     /// Implements CW20. Transfer is a base message to move tokens to another account without triggering actions
     Transfer { recipient: String, amount: Uint128 },
-    /// Implements CW20. Burn is a base message to destroy tokens forever
-    Burn { amount: Uint128 },
+    /// Implements CW20. Burn is a base message to destroy tokens forever.
+    /// If less than min_reserve_out would be released, the burn fails
+    Burn {
+        amount: Uint128,
+        min_reserve_out: Option<Uint128>,
+    },
+    /// SellExactReserve burns just enough tokens to be paid reserve_out, failing if that
+    /// would take more than max_supply_in
+    SellExactReserve {
+        reserve_out: Uint128,
+        max_supply_in: Uint128,
+    },
     /// Implements CW20.  Send is a base message to transfer tokens to a contract and trigger an action
     /// on the receiving contract.
     Send {
@@ -120,47 +520,647 @@ pub enum ExecuteMsg {
         amount: Uint128,
         msg: Binary,
     },
-    /// Implements CW20 "approval" extension. Destroys tokens forever
-    BurnFrom { owner: String, amount: Uint128 },
+    /// Implements CW20 "approval" extension. Destroys tokens forever.
+    /// If less than min_reserve_out would be released, the burn fails
+    BurnFrom {
+        owner: String,
+        amount: Uint128,
+        min_reserve_out: Option<Uint128>,
+    },
+    /// BurnToDenom sells on the curve like Burn, then swaps the released reserve into
+    /// target_denom via the configured router. min_out bounds what the seller receives
+    BurnToDenom {
+        amount: Uint128,
+        target_denom: String,
+        min_out: Uint128,
+    },
+    /// Purchase takes the reserve sent and mints tokens for it, bonding it if the reserve
+    /// is the staking denom and buying on the curve otherwise
+    Purchase {},
+
     /// Here be staking dragons
-    /// Bond will bond all staking tokens sent with the message and release derivative tokens
-    Bond {},
+    /// Bond will bond all staking tokens sent with the message and release derivative tokens.
+    /// validator can be any of the allowed validators, and defaults to the main one.
+    /// The derivative tokens go to recipient if set, otherwise to the sender
+    Bond {
+        validator: Option<String>,
+        recipient: Option<String>,
+    },
     /// Unbond will "burn" the given amount of derivative tokens and send the unbonded
     /// staking tokens to the message sender (after exit tax is deducted)
     Unbond { amount: Uint128 },
+    /// Like Unbond, but burns from owner using the sender's allowance.
+    /// The claim goes to the owner, or to the sender if claim_to_spender is set
+    UnbondFrom {
+        owner: String,
+        amount: Uint128,
+        claim_to_spender: Option<bool>,
+    },
+    /// Reconcile writes down the reserve if our delegations were slashed.
+    /// Bond and Unbond also do this, so it's only needed to update queries
+    Reconcile {},
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the chain-defined waiting period (eg. 3 weeks)
     Claim {},
+    /// ClaimFor pays out address's matured claims to address. Anyone can call this,
+    /// and is paid the claim incentive for doing so
+    ClaimFor { address: String },
+    /// SweepClaims pays out the matured claims of up to limit addresses after start_after
+    /// in one go, as far as the liquid balance goes. The caller gets the claim incentive on
+    /// each. Only so many claims are looked at per call, the next_start_after attribute
+    /// says where to carry on from
+    SweepClaims {
+        limit: Option<u32>,
+        start_after: Option<String>,
+    },
+    /// ProcessEpoch undelegates the unbonds batched in an epoch that has ended.
+    /// Anyone can call this
+    ProcessEpoch {},
+    /// TransferClaim hands amount of the sender's claims to recipient, keeping their
+    /// release times. The claims released last are handed over first
+    TransferClaim { recipient: String, amount: Uint128 },
+    /// ClaimRewards pays out the sender's share of the staking rewards distributed
+    /// to holders, pro-rata to their balance while the rewards came in
+    ClaimRewards {},
+    /// Rebalance redelegates stake between validators to converge on the target weights.
+    /// Anyone can call this
+    Rebalance {},
     /// Reinvest will check for all accumulated rewards, withdraw them, and
     /// re-bond them to the same validator. Anyone can call this, which updates
     /// the value of the token (how much under custody).
     Reinvest {},
+    /// WithdrawRewardsOnly claims the staking rewards from every validator without re-bonding
+    /// them, leaving them liquid until the next Reinvest. Anyone can call this
+    WithdrawRewardsOnly {},
+    /// SetRewardWithdrawAddress has the staking module pay our rewards to address (eg. a
+    /// splitter contract) instead of the contract, so Reinvest no longer bonds them.
+    /// None sends them back to the contract. Owner only
+    SetRewardWithdrawAddress { address: Option<String> },
+    /// RecycleExpiredClaims takes back up to limit claims left unclaimed for claim_expiry
+    /// after maturing, into the reserve or to the recovery address. Anyone can call this
+    RecycleExpiredClaims { limit: Option<u32> },
+    /// WithdrawFundingPool sends the rewards skimmed into the funding pool to the creator.
+    /// If amount is None, the whole pool is withdrawn
+    WithdrawFundingPool { amount: Option<Uint128> },
+    /// ProposeValidatorChange starts a switch of all stake to a new validator, counting as
+    /// the first approval. Only approvers can call this, and it replaces any open proposal
+    ProposeValidatorChange { validator: String },
+    /// ApproveValidatorChange adds an approver's vote to the open proposal. Once enough
+    /// approvers agree, all stake is redelegated and the new validator becomes the default
+    ApproveValidatorChange { validator: String },
+
+    /// Implements CW20 "marketing" extension. If authorized, updates marketing metadata.
+    /// Setting None/null for any of these will leave it unchanged.
+    /// Setting Some("") will clear this field on the contract storage
+    UpdateMarketing {
+        /// A URL pointing to the project behind this token.
+        project: Option<String>,
+        /// A longer description of the token and it's utility. Designed for tooltips or such
+        description: Option<String>,
+        /// The address (if any) who can update this data structure
+        marketing: Option<String>,
+    },
+    /// Implements CW20 "marketing" extension. If set as the "marketing" role on the contract,
+    /// upload a new URL, SVG, or PNG for the token
+    UploadLogo(Logo),
+
+    /// UpdateMetadata lets the creator change the metadata of the work.
+    /// Fields left as None are unchanged
+    UpdateMetadata {
+        external_permalink_uri: Option<String>,
+        creator: Option<String>,
+        description: Option<String>,
+        asset_uri: Option<String>,
+    },
+    /// LockMetadata permanently stops the metadata from being updated. Creator only
+    LockMetadata {},
+    /// SetRoyalty changes the royalty on secondary sales, in bps. Creator only
+    SetRoyalty { bps: u16 },
+    /// PlaceBuyOrder escrows the reserve tokens sent, to buy with once the spot price
+    /// is at or below max_spot_price
+    PlaceBuyOrder { max_spot_price: Decimal },
+    /// CancelBuyOrder refunds an open buy order, called by the one who placed it
+    CancelBuyOrder { id: u64 },
+    /// ExecuteBuyOrder fills a buy order whose limit has been reached. Anyone can call this,
+    /// and is paid the keeper fee out of the order
+    ExecuteBuyOrder { id: u64 },
+    /// CommitBuy escrows the reserve sent, to buy with once the sender reveals what is
+    /// behind hash: the sha256 of their address, min_supply_out as 16 big-endian bytes
+    /// and a secret salt
+    CommitBuy { hash: Binary },
+    /// RevealBuy buys with the sender's commitment at the curve as it is in the reveal block,
+    /// which has to be later than the commit, failing if that mints less than min_supply_out
+    RevealBuy {
+        salt: Binary,
+        min_supply_out: Uint128,
+    },
+    /// CancelBuyCommitment refunds the sender's unrevealed commitment
+    CancelBuyCommitment {},
+    /// LaunchBid bids the reserve sent in the launch auction at its current price.
+    /// Whatever would take it past the tranche is refunded
+    LaunchBid {},
+    /// SettleLaunch ends the launch auction once it has sold out or run its course,
+    /// and opens the curve. Anyone can call this
+    SettleLaunch {},
+    /// ClaimLaunchTokens mints the sender's launch tokens once the auction is settled
+    ClaimLaunchTokens {},
+    /// PlaceSellOrder escrows amount of the sender's tokens, to sell once the spot price is
+    /// at or above min_spot_price. Orders never expire unless expires is set
+    PlaceSellOrder {
+        amount: Uint128,
+        min_spot_price: Decimal,
+        expires: Option<Expiration>,
+    },
+    /// CancelSellOrder returns the escrowed tokens. Only the one who placed the order can
+    /// cancel it, unless it has expired
+    CancelSellOrder { id: u64 },
+    /// ExecuteSellOrder fills an unexpired sell order whose limit has been reached. Anyone can
+    /// call this, and is paid the keeper fee out of the released reserve
+    ExecuteSellOrder { id: u64 },
+    /// CreateDca escrows the reserve sent to buy amount_per_period every period, for as
+    /// many periods as deposits. Exactly amount_per_period * deposits must be sent
+    CreateDca {
+        amount_per_period: Uint128,
+        period: Duration,
+        deposits: u64,
+    },
+    /// ExecuteDca makes a plan's buy for the period once it is due. Anyone can call this,
+    /// and is paid the keeper fee out of the buy
+    ExecuteDca { id: u64 },
+    /// CancelDca refunds what is left of a plan, called by the one who created it
+    CancelDca { id: u64 },
+    /// CollectFees sends the protocol fees accrued to the fee collector. Anyone can call this
+    CollectFees {},
+    /// UpdateFeeExemptions adds and removes addresses, eg. the artist or a market maker,
+    /// that pay no exit tax or protocol fees. Owner only
+    UpdateFeeExemptions {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Freeze stops address from transferring, buying, selling or unbonding. Owner only
+    Freeze { address: String },
+    /// Unfreeze lifts a freeze. Owner only
+    Unfreeze { address: String },
+    /// ReleaseMilestone pays the creator the escrow a reached milestone frees, by index.
+    /// A milestone unlocked by vote needs the id of a closed poll whose first option won
+    /// a majority. Creator only
+    ReleaseMilestone {
+        milestone: u32,
+        poll_id: Option<u64>,
+    },
+    /// WithdrawOwnerFees pays the payees the native exit tax that has finished unbonding.
+    /// Owner only
+    WithdrawOwnerFees {},
+    /// UpdatePayees splits the exit tax between payees by weight, in place of the owner
+    /// taking it all. Owner only
+    UpdatePayees { payees: Vec<Payee> },
+    /// ClaimVested sends the creator the part of their allocation that has vested. Creator only
+    ClaimVested {},
+
+    /// Donate accepts any native coins as a donation to the treasury, outside the curve
+    Donate {},
+    /// BurnWithoutRefund destroys amount of the sender's tokens without paying out any
+    /// reserve, raising what every remaining token redeems for
+    BurnWithoutRefund { amount: Uint128 },
+    /// DonateToReserve adds the reserve sent to the curve's backing without minting,
+    /// raising what every token redeems for
+    DonateToReserve {},
+    /// Receive buys with the reserve token if it is a cw20, which must come with a ReceiveMsg.
+    /// Any other cw20 sent to this contract is accepted as a donation to the treasury
+    Receive(Cw20ReceiveMsg),
+    /// WithdrawNativeDonation sends native coins from the treasury to the creator
+    WithdrawNativeDonation { denom: String, amount: Uint128 },
+    /// WithdrawCw20Donation sends donated cw20 tokens from the treasury to the creator
+    WithdrawCw20Donation { token: String, amount: Uint128 },
+    /// Sweep sends the owner everything held of a native denom other than the reserve
+    /// and bond denoms, eg. tokens sent here by mistake
+    Sweep { denom: String },
+    /// RecoverCw20 sends the owner's choice of recipient a cw20 that was transferred here
+    /// directly. Neither this token, a cw20 reserve nor donations can be recovered
+    RecoverCw20 {
+        token: String,
+        amount: Uint128,
+        recipient: String,
+    },
+
+    /// TransferCreator proposes a new creator, who must accept before taking over the role
+    TransferCreator { new_creator: String },
+    /// AcceptCreator completes a transfer, called by the proposed creator
+    AcceptCreator {},
+    /// UpdateStakingParams changes the exit tax (up to the max set at instantiate)
+    /// and the minimum withdrawal. None leaves a value as it is. Owner only
+    UpdateStakingParams {
+        exit_tax: Option<Decimal>,
+        min_withdrawal: Option<Uint128>,
+    },
+    /// TransferOwnership proposes a new owner, who must accept before taking over.
+    /// Owner only
+    TransferOwnership { new_owner: String },
+    /// AcceptOwnership completes a transfer, called by the proposed owner
+    AcceptOwnership {},
+    /// InstantUnbond is Unbond paid out straight away from the contract's liquid reserve,
+    /// less a penalty. Fails if the penalty is above max_penalty, or there isn't enough liquidity
+    InstantUnbond {
+        amount: Uint128,
+        max_penalty: Option<Decimal>,
+    },
+    /// Pause stops buying, bonding, unbonding and transfers. Claims still work. Owner only
+    Pause {},
+    /// Unpause lifts a pause. Owner only
+    Unpause {},
+    /// InitiateWindDown closes the project: buying and selling stop for good and all stake
+    /// is undelegated, for holders to Settle once it is back. Owner only
+    InitiateWindDown {},
+    /// Settle burns all the sender's tokens for their pro-rata share of what the contract
+    /// holds, once a wind down has got the stake back
+    Settle {},
+    /// SetHalts stops just the buy side (buying and bonding) or the sell side (selling
+    /// and unbonding). None leaves a side as it is. Owner only
+    SetHalts {
+        buys: Option<bool>,
+        sells: Option<bool>,
+    },
+    /// SetPresale runs a presale until end, during which only the whitelist can Buy or Bond,
+    /// each up to their cap of reserve. Owner only
+    SetPresale {
+        end: Expiration,
+        whitelist: Vec<WhitelistEntry>,
+    },
+    /// CreatePoll asks holders to choose between options until voting_period has passed.
+    /// A poll for a milestone that unlocks by vote approves it with its first option.
+    /// Creator only
+    CreatePoll {
+        description: String,
+        options: Vec<String>,
+        voting_period: Duration,
+        milestone: Option<u32>,
+    },
+    /// CastVote votes the sender's balance as of the poll opening for one of its options,
+    /// by index
+    CastVote { poll_id: u64, option: u32 },
+    /// SetPatronTiers replaces the patron tiers, which must have names and rising
+    /// thresholds. An empty list removes them. Creator only
+    SetPatronTiers { tiers: Vec<PatronTier> },
+    /// SetNftReceipts changes the collection and threshold for NFT receipts,
+    /// or with None stops minting them. Creator only
+    SetNftReceipts { receipts: Option<NftReceipts> },
+    /// AddHook registers a contract to be sent a TradeHookMsg after every buy, sell,
+    /// bond and unbond. Owner only
+    AddHook { addr: String },
+    /// RemoveHook stops notifying a registered hook. Owner only
+    RemoveHook { addr: String },
     /// _BondAllTokens can only be called by the contract itself, after all rewards have been
     /// withdrawn. This is an example of using "callbacks" in message flows.
     /// This can only be invoked by the contract itself as a return from Reinvest
     _BondAllTokens {},
-    // /// TODO
-    // /// essentially a DR feature.
-    // /// imagine a validator you've configured falls out of the validator set
-    // /// this will update the configured validator and rebond all the tokens
-    // RebondAllTokens { validator_address: String },
+    /// essentially a DR feature.
+    /// imagine a validator you've configured falls out of the validator set
+    /// this will update the configured validator and rebond all the tokens.
+    /// Only the owner can call this, and only once the configured validator is gone,
+    /// otherwise changes go through ProposeValidatorChange
+    RebondAllTokens { validator_address: String },
+    /// Failover moves all stake to the first healthy fallback validator, once the configured
+    /// validator has left the active set (eg. jailed or tombstoned). Anyone can call this
+    Failover {},
+}
+
+/// The swap interface we expect a configured router to implement. It should swap the funds
+/// sent into ask_denom and send them to `to` (or back to the caller), failing below min_return
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouterExecuteMsg {
+    Swap {
+        ask_denom: String,
+        min_return: Option<Uint128>,
+        to: Option<String>,
+    },
+}
+
+/// The interface we expect a registry to implement, to catalog bondcamp tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryExecuteMsg {
+    Register(BondcampRegistration),
+}
+
+/// What a registry is told about a new token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondcampRegistration {
+    pub address: String,
+    pub creator: String,
+    pub work: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub curve_type: CurveType,
+    pub reserve_denom: String,
+}
+
+/// The part of the cw721-base interface we use to mint receipts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint(ReceiptMintMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptMintMsg {
+    pub token_id: String,
+    pub owner: String,
+    /// the work's asset URI, if it has one
+    pub token_uri: Option<String>,
+    pub extension: ReceiptMetadata,
+}
+
+/// What a receipt records: the work, and what the buy paid and minted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptMetadata {
+    pub creator: String,
+    pub work: String,
+    pub description: String,
+    pub external_permalink_uri: String,
+    pub reserve: Uint128,
+    pub reserve_denom: String,
+    pub tokens: Uint128,
+}
+
+/// The message to embed when sending a cw20 reserve token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Buy with the tokens sent, as ExecuteMsg::Buy does with native funds
+    Buy {
+        min_supply_out: Option<Uint128>,
+        recipient: Option<String>,
+    },
+}
+
+/// Which way a trade moved tokens, as reported to hooks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeDirection {
+    Buy,
+    Sell,
+    Bond,
+    Unbond,
+}
+
+/// Sent to every registered hook after a trade. amount is the supply minted or burned
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradeHookMsg {
+    pub token: String,
+    pub account: String,
+    pub amount: Uint128,
+    pub direction: TradeDirection,
+}
+
+impl TradeHookMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = TradeHookExecuteMsg::TradeHook(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>, C: CustomMsg>(
+        self,
+        contract_addr: T,
+    ) -> StdResult<CosmosMsg<C>> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// The hook contract's ExecuteMsg must have this variant
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum TradeHookExecuteMsg {
+    TradeHook(TradeHookMsg),
+}
+
+/// The queries we expect a `CurveType::External` contract to answer. decimals says how
+/// supply and reserve amounts are normalized, as with the built-in curves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveQueryMsg {
+    /// Returns CurveSpotPriceResponse
+    SpotPrice {
+        supply: Uint128,
+        decimals: DecimalPlaces,
+    },
+    /// Returns CurveReserveResponse
+    Reserve {
+        supply: Uint128,
+        decimals: DecimalPlaces,
+    },
+    /// Returns CurveSupplyResponse
+    Supply {
+        reserve: Uint128,
+        decimals: DecimalPlaces,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurveSpotPriceResponse {
+    pub spot_price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurveReserveResponse {
+    pub reserve: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurveSupplyResponse {
+    pub supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+/// Operations the chain itself can trigger, e.g. from an end-blocker or a gov proposal
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Same as ExecuteMsg::Reinvest
+    Reinvest {},
+    /// Moves all bonded tokens to this validator and makes it the default
+    SwitchValidator { validator: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Claims shows the number of tokens this address can access when they are done unbonding
-    Claims { address: String },
+    /// Claims shows the number of tokens this address can access when they are done unbonding,
+    /// oldest first. only_mature leaves out those still unbonding
+    Claims {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        only_mature: Option<bool>,
+    },
+    /// ClaimEstimates pages through address's claims like Claims, adding when the
+    /// undelegation behind each is expected back from the chain and so when it should
+    /// really pay out
+    ClaimEstimates {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     /// Investment shows metadata on the staking info of the contract
     Investment {},
+    /// Delegations shows how much the contract has delegated to each validator
+    Delegations {},
+    /// LiquidBuffer shows how much reserve is held undelegated against its target
+    LiquidBuffer {},
+    /// ClaimableRewards shows the staking rewards address can take with ClaimRewards
+    ClaimableRewards { address: String },
+    /// TotalClaims shows everything owed to unbonders, and how much of it has matured
+    TotalClaims {},
+    /// Shows the unbonds batched in the current epoch, and when they will be undelegated
+    UnbondEpoch {},
+    /// Shows how much more can be unbonded before the unbond limits are hit
+    UnbondCapacity {},
+    /// Lists every address with claims outstanding, shorter addresses first
+    AllClaims {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// ReinvestPreview shows what a Reinvest would bond now, for keepers deciding
+    /// whether it is worth the gas
+    ReinvestPreview {},
+    /// Shows the approvers for validator changes and any proposal awaiting approval
+    ValidatorChange {},
+    /// Shows whether the configured validator is still active, and the fallbacks
+    ValidatorHealth {},
+    /// Compares the books to what is actually delegated and held, for monitoring
+    Health {},
+    /// Shows the reserve address has committed to a buy, if any
+    BuyCommitment { address: String },
+    /// Shows the launch auction, if there is one
+    Launch {},
+    /// Lists the open buy orders, oldest first
+    BuyOrders {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the open sell orders, oldest first
+    SellOrders {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the active DCA plans, oldest first, only those of owner if set
+    DcaPlans {
+        owner: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Shows the creator escrow: what has accrued, been paid, and each milestone
+    CreatorEscrow {},
+    /// Shows the exit tax the owner has earned over all time, in tokens and natively,
+    /// and what is still to be withdrawn
+    OwnerFees {},
+    /// Lists who the exit tax is split between, which is the owner alone unless set
+    Payees {},
+    /// Shows the creator allocation: how much has vested, been claimed, and is still locked
+    CreatorVesting {},
+    /// Shows the protocol fee config, if any, and the fees accrued and collected
+    ProtocolFees {},
+    /// Lists the addresses exempt from exit tax and protocol fees, in storage order
+    FeeExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the frozen addresses, in storage order
+    Frozen {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Shows the native and cw20 donations held in the treasury
+    Treasury {},
+    /// Shows who holds the creator role, and any transfer waiting to be accepted
+    Creator {},
+    /// RoyaltyInfo shows what a secondary sale for sale_price owes the creator, and
+    /// who to pay, like cw2981
+    RoyaltyInfo { sale_price: Uint128 },
+    /// CheckRoyalties shows whether the creator asks for royalties, like cw2981
+    CheckRoyalties {},
+    /// Shows whether the owner has paused the contract, or halted either side
+    Paused {},
+    /// Shows whether the project is winding down, and when and for how much holders
+    /// can Settle
+    WindDown {},
+    /// Shows the presale, and how much the given address may still spend in it
+    Presale { address: String },
+    /// Shows a poll and its tallies so far
+    Poll { poll_id: u64 },
+    /// Lists the polls, oldest first
+    Polls {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the contracts notified of trades. Returns HooksResponse
+    Hooks {},
     /// Returns the reserve and supply quantities, as well as the spot price to buy 1 token
     CurveInfo {},
+    /// Returns the spot price as of the given height, i.e. after the last trade at or before it
+    SpotPriceAtHeight { height: u64 },
+    /// Returns the time-weighted average spot price over the last window_seconds,
+    /// or since the first trade if that is more recent
+    Twap { window_seconds: u64 },
+    /// Returns trading volume and participation, split into buys, sells, bonds and unbonds
+    Stats {},
+    /// Returns how much reserve address has ever spent buying or bonding,
+    /// and the highest patron tier that reaches
+    PatronTier { address: String },
+    /// Returns the patron tiers, by ascending threshold
+    PatronTiers {},
+    /// Returns where NFT receipts are minted and from what size of buy, if anywhere
+    NftReceipts {},
+    /// Returns up to limit of the latest exchange rate snapshots, newest first
+    RateHistory { limit: Option<u32> },
+    /// Returns how far the reserve has come towards the funding goal, if one was set
+    FundingProgress {},
+    /// Returns which optional features this instance was set up with
+    Capabilities {},
 
     /// Implements CW20. Returns the current balance of the given address, 0 if unset.
     Balance { address: String },
+    /// Returns the balance of the given address at the start of the block at height,
+    /// for use as voting power. Returns cw20::BalanceResponse
+    BalanceAt { address: String, height: u64 },
+    /// Returns the total supply at the start of the block at height
+    TotalSupplyAt { height: u64 },
     /// Implements CW20. Returns metadata on the contract - name, decimals, supply, etc.
     TokenInfo {},
+    /// Returns the standard token info along with the artist and work metadata
+    TokenInfoWithMeta {},
     /// Implements CW20 "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     Allowance { owner: String, spender: String },
+    /// Implements CW20 "marketing" extension.
+    /// Returns more metadata on the contract to display in the client:
+    /// - description, logo, project url, etc.
+    MarketingInfo {},
+    /// Implements CW20 "marketing" extension.
+    /// Downloads the embedded logo data (if stored on chain). Errors if no logo data is stored
+    DownloadLogo {},
+    /// Implements CW20 "enumerable" extension.
+    /// Returns all allowances this owner has approved. Supports pagination.
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Implements CW20 "enumerable" extension.
+    /// Returns all accounts that have balances. Supports pagination.
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
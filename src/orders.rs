@@ -0,0 +1,352 @@
+use crate::curves::CurveFn;
+use cosmwasm_std::{
+    coins, BankMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Uint128,
+};
+use cw0::{must_pay, nonpayable, Expiration};
+use cw20_base::contract::execute_transfer;
+use cw_storage_plus::Bound;
+
+use crate::bonding::{do_buy, sell_on_curve, send_reserve};
+use crate::error::ContractError;
+use crate::freeze::assert_not_frozen;
+use crate::hooks::trade_hooks;
+use crate::msg::{CustomMsg, TradeDirection};
+use crate::query::{BuyOrderResponse, BuyOrdersResponse, SellOrderResponse, SellOrdersResponse};
+use crate::rewards::settle_accounts;
+use crate::snapshot::snapshot_balances;
+use crate::state::{
+    BuyOrder, SellOrder, BUY_ORDERS, CURVE_STATE, ESCROWED_RESERVE, KEEPER_FEE, ORDER_COUNT,
+    SELL_ORDERS,
+};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// the keeper fee if none is configured, 0.5%
+pub fn default_keeper_fee() -> Decimal {
+    Decimal::permille(5)
+}
+
+fn next_order_id(deps: &mut DepsMut) -> StdResult<u64> {
+    let id = ORDER_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    ORDER_COUNT.save(deps.storage, &id)?;
+    Ok(id)
+}
+
+fn current_spot_price(deps: Deps, curve_fn: &CurveFn) -> StdResult<Decimal> {
+    let state = CURVE_STATE.load(deps.storage)?;
+    Ok(curve_fn(state.decimals).spot_price(state.supply))
+}
+
+/// escrows the reserve sent until a keeper buys with it at or below max_spot_price
+pub fn place_buy_order<C: CustomMsg>(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    max_spot_price: Decimal,
+) -> Result<Response<C>, ContractError> {
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let reserve = must_pay(&info, &reserve_denom)?;
+
+    let id = next_order_id(&mut deps)?;
+    BUY_ORDERS.save(
+        deps.storage,
+        id.into(),
+        &BuyOrder {
+            owner: info.sender.clone(),
+            reserve,
+            max_spot_price,
+        },
+    )?;
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed + reserve)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "place_buy_order")
+        .add_attribute("order", id.to_string())
+        .add_attribute("from", info.sender)
+        .add_attribute("reserve", reserve)
+        .add_attribute("max_spot_price", max_spot_price.to_string());
+    Ok(res)
+}
+
+/// refunds the escrowed reserve of an open order to its owner
+pub fn cancel_buy_order<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let order = BUY_ORDERS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::OrderNotFound { id })?;
+    if info.sender != order.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    BUY_ORDERS.remove(deps.storage, id.into());
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(order.reserve)?)
+    })?;
+
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: order.owner.to_string(),
+            amount: coins(order.reserve.u128(), reserve_denom),
+        })
+        .add_attribute("action", "cancel_buy_order")
+        .add_attribute("order", id.to_string());
+    Ok(res)
+}
+
+/// anyone can fill an order once the curve is at or below its limit,
+/// taking the keeper fee out of the escrowed reserve for their trouble
+pub fn execute_buy_order<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let order = BUY_ORDERS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::OrderNotFound { id })?;
+
+    let spot_price = current_spot_price(deps.as_ref(), &curve_fn)?;
+    if spot_price > order.max_spot_price {
+        return Err(ContractError::LimitNotReached {
+            spot_price,
+            limit: order.max_spot_price,
+        });
+    }
+
+    BUY_ORDERS.remove(deps.storage, id.into());
+    ESCROWED_RESERVE.update(deps.storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(order.reserve)?)
+    })?;
+
+    let fee = order.reserve * KEEPER_FEE.load(deps.storage)?;
+    let payment = order.reserve.checked_sub(fee).map_err(StdError::overflow)?;
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+
+    let mut res = do_buy(deps, env, order.owner, payment, curve_fn, None)?
+        .add_attribute("order", id.to_string())
+        .add_attribute("keeper", info.sender.as_str())
+        .add_attribute("keeper_fee", fee);
+    if !fee.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(fee.u128(), reserve_denom),
+        });
+    }
+    Ok(res)
+}
+
+/// escrows amount of the sender's tokens until a keeper sells them at or above min_spot_price.
+/// the order can't be filled once it expires
+pub fn place_sell_order<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    min_spot_price: Decimal,
+    expires: Option<Expiration>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::OrderExpired {});
+    }
+
+    // hold the tokens in the contract's own balance while the order is open
+    let contract = env.contract.address.to_string();
+    let height = env.block.height;
+    let accounts = [info.sender.to_string(), contract.clone()];
+    assert_not_frozen(deps.storage, deps.api, &accounts)?;
+    settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+    let transfer = execute_transfer(deps.branch(), env, info.clone(), contract, amount)?;
+    snapshot_balances(deps.storage, deps.api, height, &accounts)?;
+
+    let id = next_order_id(&mut deps)?;
+    SELL_ORDERS.save(
+        deps.storage,
+        id.into(),
+        &SellOrder {
+            owner: info.sender.clone(),
+            amount,
+            min_spot_price,
+            expires,
+        },
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "place_sell_order")
+        .add_attribute("order", id.to_string())
+        .add_attribute("from", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("min_spot_price", min_spot_price.to_string())
+        .add_attributes(transfer.attributes);
+    Ok(res)
+}
+
+/// returns the escrowed tokens of a sell order to its owner. The owner can cancel at any
+/// time, anyone else only once the order has expired
+pub fn cancel_sell_order<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let order = SELL_ORDERS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::OrderNotFound { id })?;
+    if info.sender != order.owner && !order.expires.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized {});
+    }
+    SELL_ORDERS.remove(deps.storage, id.into());
+
+    let sub_info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let owner = order.owner.to_string();
+    let height = env.block.height;
+    let accounts = [sub_info.sender.to_string(), owner.clone()];
+    settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+    let transfer = execute_transfer(deps.branch(), env, sub_info, owner, order.amount)?;
+    snapshot_balances(deps.storage, deps.api, height, &accounts)?;
+
+    let res = Response::new()
+        .add_attribute("action", "cancel_sell_order")
+        .add_attribute("order", id.to_string())
+        .add_attributes(transfer.attributes);
+    Ok(res)
+}
+
+/// anyone can fill an unexpired order once the curve is at or above its limit,
+/// taking the keeper fee out of the released reserve
+pub fn execute_sell_order<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    id: u64,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let order = SELL_ORDERS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::OrderNotFound { id })?;
+    if order.expires.is_expired(&env.block) {
+        return Err(ContractError::OrderExpired {});
+    }
+
+    let spot_price = current_spot_price(deps.as_ref(), &curve_fn)?;
+    if spot_price < order.min_spot_price {
+        return Err(ContractError::LimitNotReached {
+            spot_price,
+            limit: order.min_spot_price,
+        });
+    }
+    SELL_ORDERS.remove(deps.storage, id.into());
+
+    // the escrowed tokens are burned from the contract's balance
+    let sub_info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let keeper_fee = KEEPER_FEE.load(deps.storage)?;
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &order.owner,
+        order.amount,
+        TradeDirection::Sell,
+    )?;
+    let (released, reserve_denom, burn) =
+        sell_on_curve(deps.branch(), env, sub_info, curve_fn, order.amount)?;
+    let fee = released * keeper_fee;
+    let proceeds = released.checked_sub(fee).map_err(StdError::overflow)?;
+
+    let mut res = Response::new()
+        .add_message(send_reserve(
+            deps.storage,
+            order.owner.to_string(),
+            proceeds,
+            reserve_denom.clone(),
+        )?)
+        .add_submessages(hooks)
+        .add_attribute("action", "execute_sell_order")
+        .add_attribute("order", id.to_string())
+        .add_attribute("from", order.owner)
+        .add_attribute("supply", order.amount)
+        .add_attribute("reserve", released)
+        .add_attribute("keeper", info.sender.as_str())
+        .add_attribute("keeper_fee", fee)
+        .add_attributes(burn);
+    if !fee.is_zero() {
+        res = res.add_message(send_reserve(
+            deps.storage,
+            info.sender.to_string(),
+            fee,
+            reserve_denom,
+        )?);
+    }
+    Ok(res)
+}
+
+pub fn query_buy_orders(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BuyOrdersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let orders = BUY_ORDERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, order) = item?;
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&key);
+            Ok(BuyOrderResponse {
+                id: u64::from_be_bytes(id_bytes),
+                owner: order.owner.into(),
+                reserve: order.reserve,
+                max_spot_price: order.max_spot_price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(BuyOrdersResponse { orders })
+}
+
+pub fn query_sell_orders(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SellOrdersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let orders = SELL_ORDERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, order) = item?;
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&key);
+            Ok(SellOrderResponse {
+                id: u64::from_be_bytes(id_bytes),
+                owner: order.owner.into(),
+                amount: order.amount,
+                min_spot_price: order.min_spot_price,
+                expires: order.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SellOrdersResponse { orders })
+}
@@ -0,0 +1,74 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, MessageInfo, Response, StdResult, Storage, Uint128};
+use cw0::nonpayable;
+
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, PatronTier};
+use crate::query::{PatronTierResponse, PatronTiersResponse};
+use crate::state::{LIFETIME_CONTRIBUTIONS, PATRON_TIERS};
+
+/// add reserve spent buying or bonding to supporter's lifetime contribution
+pub fn record_support(
+    storage: &mut dyn Storage,
+    supporter: &Addr,
+    reserve: Uint128,
+) -> StdResult<()> {
+    LIFETIME_CONTRIBUTIONS.update(storage, supporter, |c| -> StdResult<_> {
+        Ok(c.unwrap_or_default() + reserve)
+    })?;
+    Ok(())
+}
+
+/// take back the support of a bond whose delegation failed
+pub fn unrecord_support(
+    storage: &mut dyn Storage,
+    supporter: &Addr,
+    reserve: Uint128,
+) -> StdResult<()> {
+    LIFETIME_CONTRIBUTIONS.update(storage, supporter, |c| -> StdResult<_> {
+        Ok(c.unwrap_or_default().checked_sub(reserve)?)
+    })?;
+    Ok(())
+}
+
+/// replaces the patron tiers. Creator only
+pub fn set_patron_tiers<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    tiers: Vec<PatronTier>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    let mut floor = Uint128::zero();
+    for tier in tiers.iter() {
+        if tier.name.is_empty() || tier.threshold <= floor {
+            return Err(ContractError::InvalidPatronTiers {});
+        }
+        floor = tier.threshold;
+    }
+    PATRON_TIERS.save(deps.storage, &tiers)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_patron_tiers")
+        .add_attribute("tiers", tiers.len().to_string());
+    Ok(res)
+}
+
+pub fn query_patron_tier(deps: Deps, address: String) -> StdResult<PatronTierResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let contributed = LIFETIME_CONTRIBUTIONS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let tiers = PATRON_TIERS.may_load(deps.storage)?.unwrap_or_default();
+    let reached = tiers.iter().filter(|t| t.threshold <= contributed).count();
+    Ok(PatronTierResponse {
+        contributed,
+        tier: reached.checked_sub(1).map(|i| tiers[i].clone()),
+        next_tier: tiers.get(reached).cloned(),
+    })
+}
+
+pub fn query_patron_tiers(deps: Deps) -> StdResult<PatronTiersResponse> {
+    let tiers = PATRON_TIERS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(PatronTiersResponse { tiers })
+}
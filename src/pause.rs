@@ -0,0 +1,117 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult, Storage};
+use cw0::nonpayable;
+
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, ExecuteMsg};
+use crate::query::PausedResponse;
+use crate::state::{HALTS, INVESTMENT, PAUSED};
+use crate::wind_down::assert_not_winding_down;
+
+/// the messages the circuit breaker stops. Claims (and everything else) keep working,
+/// so nobody is locked out of what has already matured
+pub fn is_pausable(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::Buy { .. }
+            | ExecuteMsg::BuyExact { .. }
+            | ExecuteMsg::CommitBuy { .. }
+            | ExecuteMsg::RevealBuy { .. }
+            | ExecuteMsg::LaunchBid {}
+            | ExecuteMsg::BuyWithSwap { .. }
+            | ExecuteMsg::Purchase {}
+            | ExecuteMsg::Bond { .. }
+            | ExecuteMsg::Unbond { .. }
+            | ExecuteMsg::UnbondFrom { .. }
+            | ExecuteMsg::InstantUnbond { .. }
+            | ExecuteMsg::Transfer { .. }
+            | ExecuteMsg::TransferFrom { .. }
+            | ExecuteMsg::Send { .. }
+            | ExecuteMsg::SendFrom { .. }
+            | ExecuteMsg::TransferClaim { .. }
+    )
+}
+
+pub fn assert_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PAUSED.may_load(storage)?.unwrap_or(false) {
+        return Err(ContractError::ContractPaused {});
+    }
+    Ok(())
+}
+
+pub fn assert_buys_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    assert_not_winding_down(storage)?;
+    if HALTS.may_load(storage)?.unwrap_or_default().buys {
+        return Err(ContractError::BuysHalted {});
+    }
+    Ok(())
+}
+
+pub fn assert_sells_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    assert_not_winding_down(storage)?;
+    if HALTS.may_load(storage)?.unwrap_or_default().sells {
+        return Err(ContractError::SellsHalted {});
+    }
+    Ok(())
+}
+
+pub fn pause<C: CustomMsg>(deps: DepsMut, info: MessageInfo) -> Result<Response<C>, ContractError> {
+    set_paused(deps, info, true)
+}
+
+pub fn unpause<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    set_paused(deps, info, false)
+}
+
+// only the owner can flip the switch
+fn set_paused<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    PAUSED.save(deps.storage, &paused)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new().add_attribute("action", action))
+}
+
+/// halts or reopens either side of the market. Owner only
+pub fn set_halts<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    buys: Option<bool>,
+    sells: Option<bool>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut halts = HALTS.may_load(deps.storage)?.unwrap_or_default();
+    halts.buys = buys.unwrap_or(halts.buys);
+    halts.sells = sells.unwrap_or(halts.sells);
+    HALTS.save(deps.storage, &halts)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_halts")
+        .add_attribute("buys_halted", halts.buys.to_string())
+        .add_attribute("sells_halted", halts.sells.to_string());
+    Ok(res)
+}
+
+pub fn query_paused(deps: Deps) -> StdResult<PausedResponse> {
+    let paused = PAUSED.may_load(deps.storage)?.unwrap_or(false);
+    let halts = HALTS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(PausedResponse {
+        paused,
+        buys_halted: halts.buys,
+        sells_halted: halts.sells,
+    })
+}
@@ -0,0 +1,136 @@
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128};
+use cw0::{nonpayable, Duration};
+use cw_storage_plus::Bound;
+
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::milestones::assert_milestone_poll;
+use crate::msg::CustomMsg;
+use crate::query::{PollResponse, PollsResponse};
+use crate::snapshot::query_balance_at;
+use crate::state::{Ballot, Poll, BALLOTS, POLLS, POLL_COUNT};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// opens a poll for holders on a question of the creator's. Votes are weighted by the
+/// balances at the start of this block, so tokens can't be bought to sway it. Creator only
+pub fn create_poll<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    description: String,
+    options: Vec<String>,
+    voting_period: Duration,
+    milestone: Option<u32>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    if options.len() < 2 {
+        return Err(ContractError::PollNeedsOptions {});
+    }
+    if let Some(milestone) = milestone {
+        assert_milestone_poll(deps.storage, milestone, &voting_period)?;
+    }
+
+    let id = POLL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    POLL_COUNT.save(deps.storage, &id)?;
+    let end = voting_period.after(&env.block);
+    let poll = Poll {
+        description,
+        tallies: vec![Uint128::zero(); options.len()],
+        options,
+        start_height: env.block.height,
+        end,
+        milestone,
+    };
+    POLLS.save(deps.storage, id.into(), &poll)?;
+
+    let res = Response::new()
+        .add_attribute("action", "create_poll")
+        .add_attribute("poll", id.to_string())
+        .add_attribute("end", end.to_string());
+    Ok(res)
+}
+
+/// votes all of the sender's balance from when the poll opened. One vote per holder
+pub fn cast_vote<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: u64,
+    option: u32,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut poll = POLLS
+        .may_load(deps.storage, poll_id.into())?
+        .ok_or(ContractError::PollNotFound { id: poll_id })?;
+    if poll.end.is_expired(&env.block) {
+        return Err(ContractError::PollClosed { id: poll_id });
+    }
+    if option as usize >= poll.options.len() {
+        return Err(ContractError::InvalidPollOption { option });
+    }
+    let key = (poll_id.into(), &info.sender);
+    if BALLOTS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let weight =
+        query_balance_at(deps.as_ref(), info.sender.to_string(), poll.start_height)?.balance;
+    if weight.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+
+    poll.tallies[option as usize] += weight;
+    POLLS.save(deps.storage, poll_id.into(), &poll)?;
+    BALLOTS.save(deps.storage, key, &Ballot { option, weight })?;
+
+    let res = Response::new()
+        .add_attribute("action", "cast_vote")
+        .add_attribute("poll", poll_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("option", option.to_string())
+        .add_attribute("weight", weight);
+    Ok(res)
+}
+
+fn poll_response(env: &Env, id: u64, poll: Poll) -> PollResponse {
+    PollResponse {
+        id,
+        open: !poll.end.is_expired(&env.block),
+        description: poll.description,
+        options: poll.options,
+        tallies: poll.tallies,
+        start_height: poll.start_height,
+        end: poll.end,
+        milestone: poll.milestone,
+    }
+}
+
+pub fn query_poll(deps: Deps, env: Env, poll_id: u64) -> StdResult<PollResponse> {
+    let poll = POLLS.load(deps.storage, poll_id.into())?;
+    Ok(poll_response(&env, poll_id, poll))
+}
+
+pub fn query_polls(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PollsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let polls = POLLS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, poll) = item?;
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&key);
+            Ok(poll_response(&env, u64::from_be_bytes(id_bytes), poll))
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PollsResponse { polls })
+}
@@ -0,0 +1,73 @@
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+use cw0::{nonpayable, Expiration};
+
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, WhitelistEntry};
+use crate::query::PresaleResponse;
+use crate::state::{INVESTMENT, PRESALE_ALLOWANCES, PRESALE_END};
+
+/// while the presale runs, only whitelisted buyers get tokens, and only up to their allowance
+pub fn spend_presale_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    buyer: &Addr,
+    payment: Uint128,
+) -> Result<(), ContractError> {
+    if !presale_active(storage, block)? {
+        return Ok(());
+    }
+    let remaining = PRESALE_ALLOWANCES
+        .may_load(storage, buyer)?
+        .ok_or(ContractError::NotWhitelisted {})?;
+    let remaining = remaining
+        .checked_sub(payment)
+        .map_err(|_| ContractError::PresaleCapExceeded { remaining })?;
+    PRESALE_ALLOWANCES.save(storage, buyer, &remaining)?;
+    Ok(())
+}
+
+fn presale_active(storage: &dyn Storage, block: &BlockInfo) -> StdResult<bool> {
+    let active = match PRESALE_END.may_load(storage)? {
+        Some(end) => !end.is_expired(block),
+        None => false,
+    };
+    Ok(active)
+}
+
+/// sets when the presale ends and how much reserve each whitelisted address may still
+/// spend until then. Existing allowances not in whitelist are kept. Owner only
+pub fn set_presale<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    end: Expiration,
+    whitelist: Vec<WhitelistEntry>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PRESALE_END.save(deps.storage, &end)?;
+    for entry in whitelist.iter() {
+        let addr = deps.api.addr_validate(&entry.address)?;
+        PRESALE_ALLOWANCES.save(deps.storage, &addr, &entry.cap)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_presale")
+        .add_attribute("end", end.to_string())
+        .add_attribute("whitelisted", whitelist.len().to_string());
+    Ok(res)
+}
+
+pub fn query_presale(deps: Deps, env: Env, address: String) -> StdResult<PresaleResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    Ok(PresaleResponse {
+        end: PRESALE_END.may_load(deps.storage)?,
+        active: presale_active(deps.storage, &env.block)?,
+        allowance: PRESALE_ALLOWANCES.may_load(deps.storage, &addr)?,
+    })
+}
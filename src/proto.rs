@@ -0,0 +1,117 @@
+//! Just enough protobuf to make the few Stargate queries we need, whose requests and
+//! responses are made only of strings, nested messages and integers
+
+use cosmwasm_std::{
+    to_vec, Binary, ContractResult, Empty, QuerierWrapper, QueryRequest, StdError, StdResult,
+    SystemResult,
+};
+
+pub enum Field {
+    Varint(u64),
+    Bytes(Vec<u8>),
+}
+
+/// runs a Stargate query, returning None if the chain does not answer it
+pub fn query_stargate(
+    querier: &QuerierWrapper,
+    path: &str,
+    data: Vec<u8>,
+) -> StdResult<Option<Vec<u8>>> {
+    let request: QueryRequest<Empty> = QueryRequest::Stargate {
+        path: path.to_string(),
+        data: Binary(data),
+    };
+    match querier.raw_query(&to_vec(&request)?) {
+        SystemResult::Ok(ContractResult::Ok(response)) => Ok(Some(response.0)),
+        _ => Ok(None),
+    }
+}
+
+/// finds field in a message, if it is set
+pub fn field(data: &[u8], number: u64) -> StdResult<Option<Field>> {
+    let found = decode_fields(data)?
+        .into_iter()
+        .find(|(n, _)| *n == number)
+        .map(|(_, field)| field);
+    Ok(found)
+}
+
+/// finds a nested message or string field, defaulting to empty like protobuf does
+pub fn bytes_field(data: &[u8], number: u64) -> StdResult<Vec<u8>> {
+    match field(data, number)? {
+        Some(Field::Bytes(bytes)) => Ok(bytes),
+        Some(Field::Varint(_)) => Err(StdError::parse_err("protobuf", "expected bytes")),
+        None => Ok(vec![]),
+    }
+}
+
+/// every occurrence of a repeated nested message or string field, in order
+pub fn repeated_bytes_field(data: &[u8], number: u64) -> StdResult<Vec<Vec<u8>>> {
+    decode_fields(data)?
+        .into_iter()
+        .filter(|(n, _)| *n == number)
+        .map(|(_, field)| match field {
+            Field::Bytes(bytes) => Ok(bytes),
+            Field::Varint(_) => Err(StdError::parse_err("protobuf", "expected bytes")),
+        })
+        .collect()
+}
+
+/// finds an integer field, defaulting to 0 like protobuf does
+pub fn varint_field(data: &[u8], number: u64) -> StdResult<u64> {
+    match field(data, number)? {
+        Some(Field::Varint(value)) => Ok(value),
+        Some(Field::Bytes(_)) => Err(StdError::parse_err("protobuf", "expected varint")),
+        None => Ok(0),
+    }
+}
+
+pub fn encode_bytes(number: u64, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    encode_varint(&mut out, number << 3 | 2);
+    encode_varint(&mut out, value.len() as u64);
+    out.extend_from_slice(value);
+    out
+}
+
+pub fn encode_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_fields(mut data: &[u8]) -> StdResult<Vec<(u64, Field)>> {
+    let mut fields = vec![];
+    while !data.is_empty() {
+        let key = decode_varint(&mut data)?;
+        let field = match key & 7 {
+            0 => Field::Varint(decode_varint(&mut data)?),
+            2 => {
+                let len = decode_varint(&mut data)? as usize;
+                if len > data.len() {
+                    return Err(StdError::parse_err("protobuf", "truncated field"));
+                }
+                let bytes = data[..len].to_vec();
+                data = &data[len..];
+                Field::Bytes(bytes)
+            }
+            _ => return Err(StdError::parse_err("protobuf", "unexpected wire type")),
+        };
+        fields.push((key >> 3, field));
+    }
+    Ok(fields)
+}
+
+fn decode_varint(data: &mut &[u8]) -> StdResult<u64> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(StdError::parse_err("protobuf", "invalid varint"))
+}
@@ -1,10 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Coin, Decimal, Uint128};
+use cosmwasm_std::{Coin, Decimal, Timestamp, Uint128};
 
-use cw20::TokenInfoResponse;
-pub use cw_controllers::ClaimsResponse;
+use cw0::Duration;
+use cw20::{Cw20Coin, Expiration, TokenInfoResponse};
+
+use crate::msg::{
+    ExitTaxPayout, FeeConfig, MilestoneUnlock, NftReceipts, PatronTier, Payee, RewardStrategy,
+    TaxBracket, ValidatorWeight, VestingSchedule,
+};
+use cw_controllers::Claim;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct TokenInfoResponseWithMeta {
@@ -25,13 +31,47 @@ pub struct InvestmentResponse {
     pub nominal_value: Decimal,
     /// owner created the contract and takes a cut
     pub owner: String,
+    /// set while a transfer of ownership is waiting to be accepted
+    pub pending_owner: Option<String>,
     /// this is how much the owner takes as a cut when someone unbonds
     pub exit_tax: Decimal,
-    /// All tokens are bonded to this validator
+    /// the owner can't raise exit_tax above this
+    pub max_exit_tax: Decimal,
+    /// share of an instant unbond kept back for the remaining holders
+    pub instant_unbond_penalty: Decimal,
+    /// share of a claim paid to a keeper who pushes it out with ClaimFor
+    pub claim_incentive: Decimal,
+    /// marginal exit tax rates for larger unbonds
+    pub exit_tax_brackets: Vec<TaxBracket>,
+    /// Tokens are bonded to this validator unless the bonder picks another
     pub validator: String,
+    /// Validators that bonders may choose instead of the default one
+    pub allowed_validators: Vec<String>,
+    /// How stake should be spread across validators
+    pub target_weights: Vec<ValidatorWeight>,
+    /// Where Failover moves the stake if the validator leaves the active set
+    pub fallback_validators: Vec<String>,
+    /// How long unbonds are batched for before being undelegated together, if at all
+    pub unbond_epoch: Option<Duration>,
+    /// whether the exit tax is minted to the owner or paid out of the unbond
+    pub exit_tax_payout: ExitTaxPayout,
+    /// where reinvested rewards go
+    pub reward_strategy: RewardStrategy,
     /// This is the minimum amount we will pull out to reinvest, as well as a minimum
     /// that can be unbonded (to avoid needless staking tx)
     pub min_withdrawal: Uint128,
+    /// share of reinvested rewards that goes to the funding pool
+    pub reward_skim: Decimal,
+    /// how far stored and queried delegations may differ
+    pub bond_tolerance: Uint128,
+    /// skimmed rewards waiting to be withdrawn
+    pub funding_pool: Coin,
+    /// where staking rewards are paid, if not to the contract
+    pub reward_withdraw_address: Option<String>,
+    /// how long a matured claim may go unclaimed, if there is a limit
+    pub claim_expiry: Option<Duration>,
+    /// where expired claims go, if not back into the reserve
+    pub claim_recovery_address: Option<String>,
 }
 
 // might need to provide this in order to return claims info
@@ -44,4 +84,513 @@ pub struct CurveInfoResponse {
     pub spot_price: Decimal,
     pub reserve_denom: String,
     pub claims: Uint128,
+    /// how much is delegated, which the curve reserve need not match
+    pub staked_balance: Uint128,
+    /// where the reserve came from, if it is an ibc/... voucher
+    pub reserve_trace: Option<DenomTrace>,
+}
+
+/// The ibc transfer path (eg. transfer/channel-0) a voucher took, and its denom at the source
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomTrace {
+    pub path: String,
+    pub base_denom: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorDelegation {
+    pub validator: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<ValidatorDelegation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HealthResponse {
+    /// the reserve as stored
+    pub reserve: Uint128,
+    /// what is delegated, leaving out an epoch's batch waiting to be undelegated.
+    /// Zero if the reserve isn't staked
+    pub staked: Uint128,
+    /// our balance of the reserve
+    pub liquid: Uint128,
+    /// all claims outstanding, and those that can be paid out now
+    pub claims: Uint128,
+    pub matured_claims: Uint128,
+    /// the funding pool, escrow, donations, protocol fees and the creator escrow
+    pub held_aside: Uint128,
+    /// the supply the curve is at, and the cw20 total of all balances
+    pub supply: Uint128,
+    pub total_supply: Uint128,
+    /// whether the reserve is all delegated (or held, if not staked), and the liquid
+    /// balance covers matured claims and everything held aside
+    pub solvent: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidBufferResponse {
+    pub liquid_ratio: Decimal,
+    /// what reinvest keeps undelegated at the current reserve
+    pub target: Uint128,
+    /// what is undelegated now, beyond claims and everything held aside
+    pub liquid: Uint128,
+}
+
+/// A claim, with the id to page on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedClaim {
+    pub id: u64,
+    pub amount: Uint128,
+    pub release_at: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimsResponse {
+    pub claims: Vec<QueuedClaim>,
+}
+
+/// A claim, with when the undelegation behind it is expected back from the chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimEstimate {
+    pub id: u64,
+    pub amount: Uint128,
+    pub release_at: Expiration,
+    /// when the chain should return the undelegated funds, if they are still out
+    pub undelegation_completes: Option<Timestamp>,
+    /// the later of release_at and undelegation_completes
+    pub estimated_payout: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimEstimatesResponse {
+    pub claims: Vec<ClaimEstimate>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressClaims {
+    pub address: String,
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllClaimsResponse {
+    pub claims: Vec<AddressClaims>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondEpochResponse {
+    /// None if unbonds are undelegated straight away
+    pub epoch_length: Option<Duration>,
+    pub id: u64,
+    /// what has been unbonded this epoch, still to be undelegated
+    pub pending: Uint128,
+    /// when the batch can be undelegated, if anything is pending
+    pub ends: Option<Expiration>,
+    /// whether ProcessEpoch would undelegate the batch now
+    pub ready: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalClaimsResponse {
+    /// everything owed to those who unbonded
+    pub claims: Uint128,
+    /// what can be claimed now, and so must be held liquid
+    pub matured: Uint128,
+    /// what is still waiting out the unbonding period
+    pub locked: Uint128,
+    /// false if there were too many claimants to look at them all, in which case
+    /// matured may be short and locked over
+    pub complete: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReinvestPreviewResponse {
+    /// rewards accrued across our delegations, not yet withdrawn
+    pub pending_rewards: Uint128,
+    /// our balance less claims and everything held aside
+    pub liquid: Uint128,
+    /// what Reinvest would delegate, after keeping the liquid buffer back
+    pub to_bond: Uint128,
+    /// whether to_bond reaches min_withdrawal, below which Reinvest is a no-op
+    pub worthwhile: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorChangeResponse {
+    pub approvers: Vec<String>,
+    pub threshold: u64,
+    /// the validator proposed to take over all stake, if any
+    pub proposed_validator: Option<String>,
+    pub approvals: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftReceiptsResponse {
+    /// None if no receipts are minted
+    pub receipts: Option<NftReceipts>,
+    pub minted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PatronTierResponse {
+    pub contributed: Uint128,
+    /// None below the lowest tier
+    pub tier: Option<PatronTier>,
+    /// the tier above, if any, to show what is left to reach it
+    pub next_tier: Option<PatronTier>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PatronTiersResponse {
+    pub tiers: Vec<PatronTier>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollResponse {
+    pub id: u64,
+    pub description: String,
+    pub options: Vec<String>,
+    /// the voting weight behind each option, in the same order
+    pub tallies: Vec<Uint128>,
+    /// votes are weighted by balances at the start of this block
+    pub start_height: u64,
+    pub end: Expiration,
+    /// whether votes are still accepted
+    pub open: bool,
+    /// the creator escrow milestone this poll can release
+    pub milestone: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollsResponse {
+    pub polls: Vec<PollResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorHealthResponse {
+    pub validator: String,
+    /// whether the validator is in the active set right now
+    pub healthy: bool,
+    /// the height a reinvest first found it out of the active set, if one has
+    pub unhealthy_since: Option<u64>,
+    pub fallback_validators: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LaunchResponse {
+    pub tranche: Uint128,
+    pub start_price: Decimal,
+    pub end_price: Decimal,
+    /// what a bid pays per token right now
+    pub current_price: Decimal,
+    /// reserve bid so far
+    pub committed: Uint128,
+    pub bidding_open: bool,
+    /// what every bidder pays, once settled
+    pub clearing_price: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BuyCommitmentResponse {
+    /// reserve escrowed until the reveal, zero if nothing is committed
+    pub reserve: Uint128,
+    /// the block committed in, if anything is committed
+    pub height: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BuyOrderResponse {
+    pub id: u64,
+    pub owner: String,
+    /// reserve escrowed to buy with, before the keeper fee
+    pub reserve: Uint128,
+    pub max_spot_price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BuyOrdersResponse {
+    pub orders: Vec<BuyOrderResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SellOrderResponse {
+    pub id: u64,
+    pub owner: String,
+    /// tokens escrowed to sell
+    pub amount: Uint128,
+    pub min_spot_price: Decimal,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SellOrdersResponse {
+    pub orders: Vec<SellOrderResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MilestoneStatus {
+    pub share: Decimal,
+    pub unlock: MilestoneUnlock,
+    pub released: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorEscrowResponse {
+    /// the share of each buy escrowed
+    pub share: Decimal,
+    pub accrued: Uint128,
+    pub paid: Uint128,
+    /// what the milestones released so far would pay the creator now
+    pub releasable: Uint128,
+    pub milestones: Vec<MilestoneStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorVestingResponse {
+    pub amount: Uint128,
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    /// vested but not yet claimed
+    pub claimable: Uint128,
+    /// still held back by the schedule
+    pub locked: Uint128,
+    pub schedule: VestingSchedule,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerFeesResponse {
+    /// how the exit tax is paid now
+    pub payout: ExitTaxPayout,
+    /// exit tax minted to the owner as tokens, which is paid as it's charged
+    pub tokens_minted: Uint128,
+    /// exit tax paid out of unbonds
+    pub native_accrued: Coin,
+    /// native exit tax not yet withdrawn, matured or not
+    pub native_unclaimed: Coin,
+    /// the part of native_unclaimed WithdrawOwnerFees would pay now
+    pub native_withdrawable: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PayeesResponse {
+    pub payees: Vec<Payee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolFeesResponse {
+    /// None if no protocol fee is taken
+    pub fee_config: Option<FeeConfig>,
+    /// held for the collector until CollectFees
+    pub accrued: Uint128,
+    pub collected: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeExemptionsResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrozenResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HooksResponse {
+    pub hooks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DcaPlanResponse {
+    pub id: u64,
+    pub owner: String,
+    pub amount_per_period: Uint128,
+    pub period: Duration,
+    pub deposits_left: u64,
+    pub next: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DcaPlansResponse {
+    pub plans: Vec<DcaPlanResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryResponse {
+    /// donations of the reserve denom, plus any other native coins the contract holds
+    pub native: Vec<Coin>,
+    pub cw20: Vec<Cw20Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorResponse {
+    pub creator: String,
+    /// set while a transfer of the role is waiting to be accepted
+    pub pending_creator: Option<String>,
+}
+
+/// The exchange rate as it stood after a bond, unbond or reinvest
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSnapshot {
+    pub height: u64,
+    pub reserve: Uint128,
+    pub supply: Uint128,
+    /// spot price, written down by any slashing
+    pub nominal_value: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateHistoryResponse {
+    /// newest first
+    pub snapshots: Vec<RateSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub bought_reserve: Uint128,
+    pub sold_reserve: Uint128,
+    pub bonded: Uint128,
+    /// reserve released to claims by unbonds, instant or not
+    pub unbonded: Uint128,
+    /// unique addresses that have bought or bonded
+    pub buyers: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub bonds: u64,
+    pub unbonds: u64,
+}
+
+/// Where a campaign stands relative to its funding goal. Only Closed restricts trading
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingPhase {
+    /// still raising towards the goal
+    Hatch,
+    /// the goal has been reached, or there is none
+    Open,
+    /// the deadline passed without reaching the goal
+    Refund,
+    /// the goal has been reached in crowdfund mode, so Buy and Bond are shut
+    Closed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FundingProgressResponse {
+    pub goal: Option<Uint128>,
+    /// reserve currently held by the curve
+    pub raised: Uint128,
+    /// raised as a share of the goal, which can go above 100%
+    pub percent_complete: Option<Decimal>,
+    pub deadline: Option<Expiration>,
+    pub phase: FundingPhase,
+}
+
+/// Which optional features are enabled, so frontends needn't probe for them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CapabilitiesResponse {
+    /// bonders can pick from validators besides the default
+    pub multi_validator: bool,
+    /// stake can be rebalanced towards target weights
+    pub rebalance: bool,
+    /// a share of rewards is skimmed into the creator's funding pool
+    pub reward_skim: bool,
+    /// switching validators needs more than one approval
+    pub validator_quorum: bool,
+    /// large unbonds pay marginal exit tax brackets
+    pub exit_tax_brackets: bool,
+    /// buying and selling in other denoms through a DEX router
+    pub swap_router: bool,
+    /// a funding goal is tracked
+    pub funding_goal: bool,
+    /// keeper-executed limit buy and sell orders
+    pub limit_orders: bool,
+    /// the treasury accepts native and cw20 donations
+    pub donations: bool,
+    /// marketing info or a logo has been set through the cw20 marketing extension
+    pub marketing: bool,
+    /// hook contracts are notified of trades
+    pub hooks: bool,
+    /// buying is limited to a whitelist until the presale ends
+    pub presale: bool,
+    /// staked unbonds can be paid out at once, from liquid funds, for a penalty
+    pub instant_unbond: bool,
+    /// the reserve is a cw20 token rather than a native denom
+    pub cw20_reserve: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpotPriceAtHeightResponse {
+    /// height of the checkpoint the price was taken from
+    pub height: u64,
+    pub spot_price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    pub twap: Decimal,
+    /// the seconds actually averaged over, shorter than asked if trading started since
+    pub window_seconds: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PausedResponse {
+    pub paused: bool,
+    pub buys_halted: bool,
+    pub sells_halted: bool,
+}
+
+/// Like cw2981's RoyaltiesInfoResponse, with the rate it was worked out from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+    pub royalty_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondCapacityResponse {
+    /// what can still be unbonded this block, if there is a per block limit
+    pub remaining_in_block: Option<Uint128>,
+    /// what can still be unbonded today, if there is a per day limit
+    pub remaining_in_day: Option<Uint128>,
+    /// the lower of the two, if either is set
+    pub remaining: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WindDownResponse {
+    pub winding_down: bool,
+    /// when holders can start to Settle
+    pub settles_at: Option<Expiration>,
+    pub settlement_open: bool,
+    /// what is left to share between the holders
+    pub settleable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PresaleResponse {
+    pub end: Option<Expiration>,
+    pub active: bool,
+    /// reserve the address may still spend in the presale, None if not whitelisted
+    pub allowance: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimableRewardsResponse {
+    pub claimable: Coin,
+    /// rewards distributed per token so far
+    pub rewards_per_token: Decimal,
 }
@@ -0,0 +1,103 @@
+use cosmwasm_std::{
+    to_binary, Addr, Api, Deps, DepsMut, MessageInfo, Response, StdResult, Storage, SubMsg,
+    Uint128, WasmMsg,
+};
+use cw0::nonpayable;
+
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, Cw721ExecuteMsg, NftReceipts, ReceiptMetadata, ReceiptMintMsg};
+use crate::query::NftReceiptsResponse;
+use crate::state::{CURVE_STATE, NFT_RECEIPTS, RECEIPT_COUNT, TOKEN_INFO_WITH_META};
+
+/// reply id for minting a receipt, so a collection that won't mint doesn't fail the buy
+pub const RECEIPT_REPLY_ID: u64 = 2;
+
+/// checks the collection address and that there is a threshold to reach
+pub fn validate_nft_receipts(
+    api: &dyn Api,
+    receipts: NftReceipts,
+) -> Result<NftReceipts, ContractError> {
+    if receipts.min_reserve.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    Ok(NftReceipts {
+        collection: api.addr_validate(&receipts.collection)?.into_string(),
+        min_reserve: receipts.min_reserve,
+    })
+}
+
+/// the message minting buyer a receipt, if a buy of reserve is big enough to get one
+pub fn receipt_msg<C: CustomMsg>(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    reserve: Uint128,
+    tokens: Uint128,
+) -> StdResult<Option<SubMsg<C>>> {
+    let receipts = match NFT_RECEIPTS.may_load(storage)? {
+        Some(receipts) if reserve >= receipts.min_reserve => receipts,
+        _ => return Ok(None),
+    };
+    let id = RECEIPT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    RECEIPT_COUNT.save(storage, &id)?;
+
+    let meta = TOKEN_INFO_WITH_META.load(storage)?;
+    let mint = Cw721ExecuteMsg::Mint(ReceiptMintMsg {
+        token_id: id.to_string(),
+        owner: buyer.to_string(),
+        token_uri: meta.asset_uri,
+        extension: ReceiptMetadata {
+            creator: meta.creator,
+            work: meta.work,
+            description: meta.description,
+            external_permalink_uri: meta.external_permalink_uri,
+            reserve,
+            reserve_denom: CURVE_STATE.load(storage)?.reserve_denom,
+            tokens,
+        },
+    });
+    let msg = WasmMsg::Execute {
+        contract_addr: receipts.collection,
+        msg: to_binary(&mint)?,
+        funds: vec![],
+    };
+    Ok(Some(SubMsg::reply_on_error(msg, RECEIPT_REPLY_ID)))
+}
+
+/// the buy stands without its receipt
+pub fn receipt_failed<C: CustomMsg>(err: String) -> Response<C> {
+    Response::new()
+        .add_attribute("action", "receipt_failed")
+        .add_attribute("error", err)
+}
+
+/// changes or, with None, stops NFT receipts. Creator only
+pub fn set_nft_receipts<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    receipts: Option<NftReceipts>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    let res = Response::new().add_attribute("action", "set_nft_receipts");
+    let res = match receipts {
+        Some(receipts) => {
+            let receipts = validate_nft_receipts(deps.api, receipts)?;
+            NFT_RECEIPTS.save(deps.storage, &receipts)?;
+            res.add_attribute("collection", receipts.collection)
+                .add_attribute("min_reserve", receipts.min_reserve)
+        }
+        None => {
+            NFT_RECEIPTS.remove(deps.storage);
+            res
+        }
+    };
+    Ok(res)
+}
+
+pub fn query_nft_receipts(deps: Deps) -> StdResult<NftReceiptsResponse> {
+    Ok(NftReceiptsResponse {
+        receipts: NFT_RECEIPTS.may_load(deps.storage)?,
+        minted: RECEIPT_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,26 @@
+use cosmwasm_std::{to_binary, Addr, Env, StdResult, Storage, WasmMsg};
+
+use crate::msg::{BondcampRegistration, RegistryExecuteMsg};
+use crate::state::{CREATOR, CURVE_STATE, CURVE_TYPE, TOKEN_INFO_WITH_META};
+
+/// lists this token with a catalog contract, so frontends can find every bondcamp token
+/// on the chain. Built from what instantiate stored, so it must come last
+pub fn register_with(storage: &dyn Storage, env: &Env, registry: &Addr) -> StdResult<WasmMsg> {
+    let meta = TOKEN_INFO_WITH_META.load(storage)?;
+    let curve_state = CURVE_STATE.load(storage)?;
+    let registration = BondcampRegistration {
+        address: env.contract.address.to_string(),
+        creator: CREATOR.load(storage)?.into(),
+        work: meta.work,
+        name: meta.token_info.name,
+        symbol: meta.token_info.symbol,
+        curve_type: CURVE_TYPE.load(storage)?,
+        reserve_denom: curve_state.reserve_denom,
+        decimals: meta.token_info.decimals,
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: registry.to_string(),
+        msg: to_binary(&RegistryExecuteMsg::Register(registration))?,
+        funds: vec![],
+    })
+}
@@ -0,0 +1,187 @@
+use cosmwasm_std::{
+    attr, coin, Addr, Api, Attribute, BankMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Storage, Uint128,
+};
+use cw0::nonpayable;
+use cw20_base::state::BALANCES;
+
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, RewardStrategy};
+use crate::query::ClaimableRewardsResponse;
+use crate::snapshot::seed_balance;
+use crate::state::{
+    RewardAccount, FUNDING_POOL, HOLDER_REWARDS, INVESTMENT, REWARD_ACCOUNTS, TOKEN_INFO_WITH_META,
+};
+
+const BPS: u16 = 10_000;
+
+/// defaults to compounding, and the creator can't be split more than all of it
+pub fn validate_reward_strategy(
+    strategy: Option<RewardStrategy>,
+) -> Result<RewardStrategy, ContractError> {
+    match strategy {
+        Some(RewardStrategy::SplitWithCreator { bps }) if bps > BPS => {
+            Err(ContractError::InvalidRewardStrategy {})
+        }
+        Some(strategy) => Ok(strategy),
+        None => Ok(RewardStrategy::Compound {}),
+    }
+}
+
+/// what is owed to holders and not yet claimed, which reinvest keeps liquid
+pub fn holder_rewards_held(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(HOLDER_REWARDS
+        .may_load(storage)?
+        .unwrap_or_default()
+        .unclaimed)
+}
+
+/// sends rewards where the strategy says, returning the part left to compound.
+/// The creator's part joins the funding pool, and the holders' part raises how much
+/// each token has earned. Tokens the contract holds itself (open sell orders, unvested
+/// allocations) earn nothing, and rounding dust compounds
+pub fn route_rewards(
+    storage: &mut dyn Storage,
+    contract: &Addr,
+    strategy: &RewardStrategy,
+    rewards: Uint128,
+) -> StdResult<(Uint128, Vec<Attribute>)> {
+    match strategy {
+        RewardStrategy::Compound {} => Ok((rewards, vec![])),
+        RewardStrategy::SplitWithCreator { bps } => {
+            let to_creator = rewards.multiply_ratio(*bps, BPS);
+            let pool = FUNDING_POOL.may_load(storage)?.unwrap_or_default();
+            FUNDING_POOL.save(storage, &(pool + to_creator))?;
+            Ok((rewards - to_creator, vec![attr("to_creator", to_creator)]))
+        }
+        RewardStrategy::DistributeToHolders {} => {
+            let supply = TOKEN_INFO_WITH_META
+                .load(storage)?
+                .token_info
+                .total_supply
+                .checked_sub(BALANCES.may_load(storage, contract)?.unwrap_or_default())?;
+            if supply.is_zero() {
+                return Ok((rewards, vec![]));
+            }
+            let per_token = Decimal::from_ratio(rewards, supply);
+            let to_holders = supply * per_token;
+            let mut holder_rewards = HOLDER_REWARDS.may_load(storage)?.unwrap_or_default();
+            holder_rewards.per_token = holder_rewards.per_token + per_token;
+            holder_rewards.unclaimed += to_holders;
+            HOLDER_REWARDS.save(storage, &holder_rewards)?;
+            Ok((
+                rewards - to_holders,
+                vec![
+                    attr("to_holders", to_holders),
+                    attr("rewards_per_token", holder_rewards.per_token.to_string()),
+                ],
+            ))
+        }
+    }
+}
+
+// what balance has earned since the account was last settled
+fn earned_since(account: &RewardAccount, balance: Uint128, per_token: Decimal) -> Uint128 {
+    account.earned + balance * (per_token - account.per_token)
+}
+
+/// books what address has earned at its current balance. This has to run before
+/// every change to the balance, so tokens only earn for the time they were held.
+/// For the same reason it seeds the balance snapshots
+pub fn settle_rewards(storage: &mut dyn Storage, contract: &Addr, address: &Addr) -> StdResult<()> {
+    seed_balance(storage, address)?;
+    let per_token = HOLDER_REWARDS
+        .may_load(storage)?
+        .unwrap_or_default()
+        .per_token;
+    let account = REWARD_ACCOUNTS
+        .may_load(storage, address)?
+        .unwrap_or_default();
+    if address == contract || account.per_token == per_token {
+        return Ok(());
+    }
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let account = RewardAccount {
+        per_token,
+        earned: earned_since(&account, balance, per_token),
+    };
+    REWARD_ACCOUNTS.save(storage, address, &account)
+}
+
+/// settle_rewards for accounts cw20-base is about to move tokens between
+pub fn settle_accounts(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    contract: &Addr,
+    accounts: &[String],
+) -> StdResult<()> {
+    for account in accounts {
+        let addr = api.addr_validate(account)?;
+        settle_rewards(storage, contract, &addr)?;
+    }
+    Ok(())
+}
+
+/// pays out the sender's share of the rewards distributed to holders
+pub fn claim_rewards<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    settle_rewards(deps.storage, &env.contract.address, &info.sender)?;
+    let mut account = REWARD_ACCOUNTS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let amount = account.earned;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    account.earned = Uint128::zero();
+    REWARD_ACCOUNTS.save(deps.storage, &info.sender, &account)?;
+    let mut holder_rewards = HOLDER_REWARDS.load(deps.storage)?;
+    holder_rewards.unclaimed = holder_rewards
+        .unclaimed
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    HOLDER_REWARDS.save(deps.storage, &holder_rewards)?;
+
+    let invest = INVESTMENT.load(deps.storage)?;
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(amount.u128(), &invest.bond_denom)],
+        })
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("from", info.sender)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn query_claimable_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<ClaimableRewardsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let per_token = HOLDER_REWARDS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .per_token;
+    let claimable = if address == env.contract.address {
+        Uint128::zero()
+    } else {
+        let account = REWARD_ACCOUNTS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        let balance = BALANCES
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        earned_since(&account, balance, per_token)
+    };
+    Ok(ClaimableRewardsResponse {
+        claimable: coin(claimable.u128(), &invest.bond_denom),
+        rewards_per_token: per_token,
+    })
+}
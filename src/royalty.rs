@@ -0,0 +1,52 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult, Uint128};
+use cw0::nonpayable;
+
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::{CheckRoyaltiesResponse, RoyaltyInfoResponse};
+use crate::state::{CREATOR, ROYALTY_BPS};
+
+const BPS: u16 = 10_000;
+
+pub fn validate_royalty(bps: u16) -> Result<(), ContractError> {
+    if bps > BPS {
+        return Err(ContractError::InvalidRoyalty {});
+    }
+    Ok(())
+}
+
+/// sets the royalty marketplaces are asked to pay the creator. Zero turns it off.
+/// Creator only
+pub fn set_royalty<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: u16,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    validate_royalty(bps)?;
+    ROYALTY_BPS.save(deps.storage, &bps)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_royalty")
+        .add_attribute("bps", bps.to_string());
+    Ok(res)
+}
+
+/// what a secondary sale for sale_price owes the creator, as cw2981 has it
+pub fn query_royalty_info(deps: Deps, sale_price: Uint128) -> StdResult<RoyaltyInfoResponse> {
+    let bps = ROYALTY_BPS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(RoyaltyInfoResponse {
+        address: CREATOR.load(deps.storage)?.into(),
+        royalty_bps: bps,
+        royalty_amount: sale_price.multiply_ratio(bps, BPS),
+    })
+}
+
+pub fn query_check_royalties(deps: Deps) -> StdResult<CheckRoyaltiesResponse> {
+    let bps = ROYALTY_BPS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(CheckRoyaltiesResponse {
+        royalty_payments: bps > 0,
+    })
+}
@@ -0,0 +1,80 @@
+use cosmwasm_std::{Addr, Api, Deps, StdResult, Storage, Uint128};
+use cw20::BalanceResponse;
+use cw20_base::state::BALANCES;
+
+use crate::query::TotalSupplyResponse;
+use crate::state::{BALANCE_SNAPSHOTS, SUPPLY_SNAPSHOTS, TOKEN_INFO_WITH_META};
+
+/// gives a balance held since before snapshots began (on a contract migrated from v0.1.0)
+/// a snapshot at height 0, so the first change to it doesn't make earlier heights read 0.
+/// Has to run before the balance changes
+pub fn seed_balance(storage: &mut dyn Storage, addr: &Addr) -> StdResult<()> {
+    let balance = BALANCES.may_load(storage, addr)?.unwrap_or_default();
+    if balance.is_zero() || BALANCE_SNAPSHOTS.may_load(storage, addr)?.is_some() {
+        return Ok(());
+    }
+    BALANCE_SNAPSHOTS.save(storage, addr, &balance, 0)
+}
+
+/// as seed_balance, for the total supply
+pub fn seed_supply(storage: &mut dyn Storage) -> StdResult<()> {
+    let supply = TOKEN_INFO_WITH_META.load(storage)?.token_info.total_supply;
+    if SUPPLY_SNAPSHOTS.may_load(storage)?.is_some() {
+        return Ok(());
+    }
+    SUPPLY_SNAPSHOTS.save(storage, &supply, 0)
+}
+
+/// copies addr's balance into the snapshots, after it was changed at height
+pub fn snapshot_balance(storage: &mut dyn Storage, addr: &Addr, height: u64) -> StdResult<()> {
+    let balance = BALANCES.may_load(storage, addr)?.unwrap_or_default();
+    BALANCE_SNAPSHOTS.save(storage, addr, &balance, height)
+}
+
+/// snapshot_balance for accounts cw20-base moved tokens between
+pub fn snapshot_balances(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    height: u64,
+    accounts: &[String],
+) -> StdResult<()> {
+    for account in accounts {
+        let addr = api.addr_validate(account)?;
+        snapshot_balance(storage, &addr, height)?;
+    }
+    Ok(())
+}
+
+/// copies the total supply into the snapshots, after it was changed at height
+pub fn snapshot_supply(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    let supply = TOKEN_INFO_WITH_META.load(storage)?.token_info.total_supply;
+    SUPPLY_SNAPSHOTS.save(storage, &supply, height)
+}
+
+/// the balance at the start of the block at height, for use as voting power.
+/// Balances not touched since snapshots began are taken as unchanged
+pub fn query_balance_at(deps: Deps, address: String, height: u64) -> StdResult<BalanceResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let balance = match BALANCE_SNAPSHOTS.may_load_at_height(deps.storage, &addr, height)? {
+        Some(balance) => balance,
+        // created after height
+        None if BALANCE_SNAPSHOTS.may_load(deps.storage, &addr)?.is_some() => Uint128::zero(),
+        None => BALANCES.may_load(deps.storage, &addr)?.unwrap_or_default(),
+    };
+    Ok(BalanceResponse { balance })
+}
+
+/// the total supply at the start of the block at height
+pub fn query_total_supply_at(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    let total_supply = match SUPPLY_SNAPSHOTS.may_load_at_height(deps.storage, height)? {
+        Some(supply) => supply,
+        None if SUPPLY_SNAPSHOTS.may_load(deps.storage)?.is_some() => Uint128::zero(),
+        None => {
+            TOKEN_INFO_WITH_META
+                .load(deps.storage)?
+                .token_info
+                .total_supply
+        }
+    };
+    Ok(TotalSupplyResponse { total_supply })
+}
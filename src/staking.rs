@@ -1,20 +1,72 @@
+use crate::curves::CurveFn;
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, Deps, DepsMut, DistributionMsg, Env, MessageInfo,
-    QuerierWrapper, Response, StakingMsg, StdError, StdResult, Uint128, WasmMsg,
+    attr, coin, to_binary, Addr, Attribute, BankMsg, BlockInfo, Decimal, Deps, DepsMut,
+    DistributionMsg, Empty, Env, Event, Fraction, MessageInfo, Order, QuerierWrapper, Response,
+    StakingMsg, StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
-use cw20_bonding::msg::CurveFn;
+use cw0::{must_pay, nonpayable, Duration, Expiration};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use cw20_base::allowances::deduct_allowance;
 
-use crate::bonding::{execute_burn, execute_mint};
+use crate::bonding::{
+    assert_curve_open, assert_native_reserve, checkpoint_spot_price, execute_burn, execute_buy,
+    execute_mint, goal_reached_event, is_refunding, record_contribution, take_contribution,
+    unrecord_contribution,
+};
+use crate::claims::{
+    claim_tokens, create_claim, due_claimants, query_total_claims, remove_expired_claims,
+};
+use crate::cooldown::{assert_cooled_down, start_cooldown};
+use crate::creator::assert_creator;
+use crate::epoch::{pending_undelegation, queue_undelegation};
 use crate::error::ContractError;
+use crate::fees::{fees_accrued, is_fee_exempt};
+use crate::freeze::assert_not_frozen;
+use crate::hooks::trade_hooks;
+use crate::milestones::creator_escrow_held;
 use crate::msg::ExecuteMsg;
-use crate::query::InvestmentResponse;
-use crate::state::{CurveState, CLAIMS, CURVE_STATE, CURVE_TYPE, INVESTMENT};
+use crate::msg::ValidatorWeight;
+use crate::msg::{CustomMsg, ExitTaxPayout, TradeDirection};
+use crate::patrons::{record_support, unrecord_support};
+use crate::pause::{assert_buys_open, assert_sells_open};
+use crate::presale::spend_presale_allowance;
+use crate::proto::{bytes_field, encode_bytes, query_stargate, repeated_bytes_field, varint_field};
+use crate::query::{
+    DelegationsResponse, HealthResponse, InvestmentResponse, LiquidBufferResponse,
+    RateHistoryResponse, RateSnapshot, ReinvestPreviewResponse, ValidatorChangeResponse,
+    ValidatorDelegation, ValidatorHealthResponse,
+};
+use crate::rewards::{holder_rewards_held, route_rewards};
+use crate::state::{
+    CurveState, InvestmentInfo, PendingBond, ValidatorChange, BACKING, CURVE_STATE, DELEGATIONS,
+    ESCROWED_RESERVE, FUNDING_POOL, INVESTMENT, PENDING_BOND, PENDING_OWNER,
+    PENDING_VALIDATOR_CHANGE, PURCHASE_ONLY, RATE_HISTORY, RATE_HISTORY_SIZE, RATE_SNAPSHOTS,
+    REDELEGATED_TO, REDELEGATIONS, RESERVE_DONATIONS, RESERVE_TOKEN, REWARD_WITHDRAW_ADDRESS,
+    STAKED_BALANCE, TOKEN_INFO_WITH_META, UNHEALTHY_SINCE, VALIDATOR_APPROVERS, WIND_DOWN,
+};
+use crate::stats::{record_trade, unrecord_bond};
+use crate::tax::{accrue_owner_fee, matured_owner_fees, record_owner_tokens, split_tax};
+use crate::unbond_limits::use_unbond_capacity;
+use crate::wind_down::assert_not_winding_down;
+
+/// reply id for the delegation made when bonding
+pub const BOND_REPLY_ID: u64 = 1;
+
+// how many snapshots RateHistory returns unless asked for more
+const DEFAULT_HISTORY_LIMIT: u32 = 10;
+
+// how many claimants one SweepClaims settles
+const DEFAULT_SWEEP_LIMIT: u32 = 10;
+const MAX_SWEEP_LIMIT: u32 = 30;
+
+// the staking module caps open redelegations per (delegator, src, dst)
+const MAX_REDELEGATION_ENTRIES: usize = 7;
 
 // const FALLBACK_RATIO: Decimal = Decimal::one();
 
 // get_bonded returns the total amount of delegations from contract
 // it ensures they are all the same denom
-fn get_bonded(querier: &QuerierWrapper, contract: &Addr) -> Result<Uint128, ContractError> {
+pub fn get_bonded(querier: &QuerierWrapper, contract: &Addr) -> Result<Uint128, ContractError> {
     let bonds = querier.query_all_delegations(contract)?;
     if bonds.is_empty() {
         return Ok(Uint128::zero());
@@ -33,10 +85,17 @@ fn get_bonded(querier: &QuerierWrapper, contract: &Addr) -> Result<Uint128, Cont
     })
 }
 
-fn assert_bonds(curve_state: &CurveState, bonded: Uint128) -> Result<(), ContractError> {
-    if curve_state.reserve != bonded {
+// assert_bonds lets stored and queried differ by up to tolerance, to allow for
+// rounding dust and in-flight undelegations
+fn assert_bonds(stored: Uint128, bonded: Uint128, tolerance: Uint128) -> Result<(), ContractError> {
+    let difference = if stored > bonded {
+        stored - bonded
+    } else {
+        bonded - stored
+    };
+    if difference > tolerance {
         Err(ContractError::BondedMismatch {
-            stored: curve_state.reserve,
+            stored,
             queried: bonded,
         })
     } else {
@@ -44,14 +103,484 @@ fn assert_bonds(curve_state: &CurveState, bonded: Uint128) -> Result<(), Contrac
     }
 }
 
-pub fn bond(
+// a slash lowers what each token is worth, the curve itself is unchanged
+fn nominal_value(spot_price: Decimal, backing: Decimal) -> Decimal {
+    Decimal::from_ratio(
+        Uint128::new(spot_price.numerator()) * backing,
+        spot_price.denominator(),
+    )
+}
+
+/// appends to the rate history, overwriting the oldest snapshot once it is full
+fn record_rate(
+    storage: &mut dyn Storage,
+    height: u64,
+    curve_state: &CurveState,
+    spot_price: Decimal,
+) -> StdResult<()> {
+    let taken = RATE_SNAPSHOTS.may_load(storage)?.unwrap_or_default();
+    let snapshot = RateSnapshot {
+        height,
+        reserve: curve_state.curve_reserve,
+        supply: curve_state.supply,
+        nominal_value: nominal_value(spot_price, backing(storage)?),
+    };
+    RATE_HISTORY.save(storage, (taken % RATE_HISTORY_SIZE).into(), &snapshot)?;
+    RATE_SNAPSHOTS.save(storage, &(taken + 1))
+}
+
+pub fn query_rate_history(deps: Deps, limit: Option<u32>) -> StdResult<RateHistoryResponse> {
+    let taken = RATE_SNAPSHOTS.may_load(deps.storage)?.unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT) as u64;
+    let snapshots = (0..taken.min(limit).min(RATE_HISTORY_SIZE))
+        .map(|i| RATE_HISTORY.load(deps.storage, ((taken - 1 - i) % RATE_HISTORY_SIZE).into()))
+        .collect::<StdResult<_>>()?;
+    Ok(RateHistoryResponse { snapshots })
+}
+
+pub fn backing(storage: &dyn Storage) -> StdResult<Decimal> {
+    Ok(BACKING.may_load(storage)?.unwrap_or_else(Decimal::one))
+}
+
+/// undoes the write-down (or mark-up), giving the reserve the curve is evaluated on
+pub fn to_nominal(reserve: Uint128, backing: Decimal) -> Uint128 {
+    if backing.is_zero() {
+        return Uint128::zero();
+    }
+    reserve.multiply_ratio(backing.denominator(), backing.numerator())
+}
+
+/// raises the backing for added reserve, joining reserve, that nothing was minted for
+pub fn add_to_backing(
+    storage: &mut dyn Storage,
+    reserve: Uint128,
+    added: Uint128,
+) -> StdResult<Decimal> {
+    let old_backing = backing(storage)?;
+    let new_backing = Decimal::from_ratio(
+        Uint128::new(old_backing.numerator()).multiply_ratio(reserve + added, reserve),
+        old_backing.denominator(),
+    );
+    BACKING.save(storage, &new_backing)?;
+    Ok(new_backing)
+}
+
+// reconcile_slashing checks the staked balance against what is actually delegated.
+// If we were slashed, the loss comes out of the curve reserve, the per-validator records
+// are written down to match, and the loss is spread across all holders by lowering the backing
+fn reconcile_slashing(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    contract: &Addr,
+    curve_state: &mut CurveState,
+) -> Result<Option<Event>, ContractError> {
+    // a reserve other than the staking denom is held rather than staked, so nothing is slashed
+    let invest = INVESTMENT.load(storage)?;
+    if curve_state.reserve_denom != invest.bond_denom {
+        return Ok(None);
+    }
+    let bonded = get_bonded(querier, contract)?;
+    let staked = staked_balance(storage)?;
+    let tolerance = invest.bond_tolerance;
+    if bonded + tolerance >= staked {
+        assert_bonds(staked, bonded, tolerance)?;
+        return Ok(None);
+    }
+
+    let lost = staked - bonded;
+    let reserve = curve_state.curve_reserve;
+    let left = reserve.saturating_sub(lost);
+    let old_backing = backing(storage)?;
+    let new_backing = if reserve.is_zero() {
+        old_backing
+    } else {
+        Decimal::from_ratio(
+            Uint128::new(old_backing.numerator()).multiply_ratio(left, reserve),
+            old_backing.denominator(),
+        )
+    };
+    curve_state.curve_reserve = left;
+    BACKING.save(storage, &new_backing)?;
+
+    for (validator, _) in delegations(storage)? {
+        DELEGATIONS.save(storage, &validator, &Uint128::zero())?;
+    }
+    STAKED_BALANCE.save(storage, &Uint128::zero())?;
+    for delegation in querier.query_all_delegations(contract)? {
+        add_delegation(storage, &delegation.validator, delegation.amount.amount)?;
+    }
+
+    let event = Event::new("slashing")
+        .add_attribute("lost", lost)
+        .add_attribute("backing", new_backing.to_string());
+    Ok(Some(event))
+}
+
+/// the staking module's gRPC query for its params, which include the unbonding time
+pub const STAKING_PARAMS_PATH: &str = "/cosmos.staking.v1beta1.Query/Params";
+
+/// the chain's unbonding time in whole seconds (rounded up), if it answers the query
+pub fn query_chain_unbonding_time(querier: &QuerierWrapper) -> StdResult<Option<u64>> {
+    let response = match query_stargate(querier, STAKING_PARAMS_PATH, vec![])? {
+        Some(response) => response,
+        None => return Ok(None),
+    };
+    // QueryParamsResponse { params = 1 }, Params { unbonding_time = 1 },
+    // Duration { seconds = 1, nanos = 2 }
+    let params = bytes_field(&response, 1)?;
+    let unbonding_time = bytes_field(&params, 1)?;
+    let seconds = varint_field(&unbonding_time, 1)?;
+    let nanos = varint_field(&unbonding_time, 2)?;
+    Ok(Some(if nanos > 0 { seconds + 1 } else { seconds }))
+}
+
+/// the staking module's gRPC query for a delegator's undelegations still in flight
+pub const UNBONDING_DELEGATIONS_PATH: &str =
+    "/cosmos.staking.v1beta1.Query/DelegatorUnbondingDelegations";
+
+/// when each of contract's undelegations completes and how much it brings back,
+/// soonest first, if the chain answers the query
+pub fn query_unbonding_entries(
+    querier: &QuerierWrapper,
+    contract: &Addr,
+) -> StdResult<Option<Vec<(Timestamp, Uint128)>>> {
+    let request = encode_bytes(1, contract.as_bytes());
+    let response = match query_stargate(querier, UNBONDING_DELEGATIONS_PATH, request)? {
+        Some(response) => response,
+        None => return Ok(None),
+    };
+    // QueryDelegatorUnbondingDelegationsResponse { unbonding_responses = 1 },
+    // UnbondingDelegation { entries = 3 }, UnbondingDelegationEntry { completion_time = 2,
+    // balance = 4 }, Timestamp { seconds = 1, nanos = 2 }
+    let mut entries = vec![];
+    for unbonding in repeated_bytes_field(&response, 1)? {
+        for entry in repeated_bytes_field(&unbonding, 3)? {
+            let completion = bytes_field(&entry, 2)?;
+            let seconds = varint_field(&completion, 1)?;
+            let nanos = varint_field(&completion, 2)?;
+            let balance = String::from_utf8(bytes_field(&entry, 4)?)?;
+            entries.push((
+                Timestamp::from_seconds(seconds).plus_nanos(nanos),
+                balance.parse::<Uint128>()?,
+            ));
+        }
+    }
+    entries.sort();
+    Ok(Some(entries))
+}
+
+/// claims must not mature before the undelegated funds come back, so the unbonding period
+/// has to cover the chain's. If the chain cannot tell us, we can only insist it is counted
+/// in time like the chain's, and go with what we were given
+pub fn validate_unbonding_period(
+    querier: &QuerierWrapper,
+    period: &Duration,
+) -> Result<(), ContractError> {
+    let min = match query_chain_unbonding_time(querier)? {
+        Some(min) => min,
+        None => {
+            return match period {
+                Duration::Time(_) => Ok(()),
+                Duration::Height(_) => Err(ContractError::UnbondingPeriodNotTime {}),
+            }
+        }
+    };
+    match period {
+        Duration::Time(seconds) if *seconds >= min => Ok(()),
+        _ => Err(ContractError::UnbondingPeriodTooShort { min }),
+    }
+}
+
+/// errors unless the reserve is the staking denom, for everything that stakes it
+fn assert_staked_reserve(storage: &dyn Storage) -> Result<(), ContractError> {
+    assert_native_reserve(storage)?;
+    let reserve_denom = CURVE_STATE.load(storage)?.reserve_denom;
+    if reserve_denom != INVESTMENT.load(storage)?.bond_denom {
+        return Err(ContractError::ReserveNotStaked {});
+    }
+    Ok(())
+}
+
+/// Buy and Bond are turned off when the contract only takes Purchase
+pub fn assert_split_purchases(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PURCHASE_ONLY.may_load(storage)?.unwrap_or(false) {
+        return Err(ContractError::UsePurchase {});
+    }
+    Ok(())
+}
+
+/// the one way in, whatever the reserve: bonds it when the reserve is staked,
+/// otherwise buys with it
+pub fn purchase<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+) -> Result<Response<C>, ContractError> {
+    if assert_staked_reserve(deps.storage).is_ok() {
+        bond(deps, env, info, curve_fn, None, None)
+    } else {
+        execute_buy(deps, env, info, curve_fn, None, None)
+    }
+}
+
+/// writes down the reserve if any of our delegations were slashed. bond and unbond
+/// do this too, so this is only needed to bring queries up to date
+pub fn reconcile<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    let slashing = reconcile_slashing(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &mut curve_state,
+    )?;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+
+    let res = Response::new()
+        .add_events(slashing)
+        .add_attribute("action", "reconcile")
+        .add_attribute("reserve", curve_state.curve_reserve);
+    Ok(res)
+}
+
+/// adds the reserve sent to the backing without minting anything, so every token
+/// redeems for more. A staked reserve is delegated like a bond
+pub fn donate_to_reserve<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+) -> Result<Response<C>, ContractError> {
+    assert_native_reserve(deps.storage)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    let amount = must_pay(&info, &curve_state.reserve_denom)?;
+    assert_not_winding_down(deps.storage)?;
+    // with nothing issued, there are no holders for it to go to
+    if curve_state.curve_reserve.is_zero() {
+        return Err(ContractError::NothingToBack {});
+    }
+
+    let new_backing = add_to_backing(deps.storage, curve_state.curve_reserve, amount)?;
+    curve_state.curve_reserve += amount;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+    let curve = curve_fn(curve_state.decimals);
+    let spot_price = curve.spot_price(curve_state.supply);
+    record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "donate_to_reserve")
+        .add_attribute("from", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("backing", new_backing.to_string());
+    if curve_state.reserve_denom == invest.bond_denom {
+        add_delegation(deps.storage, &invest.validator, amount)?;
+        res = res.add_message(StakingMsg::Delegate {
+            validator: invest.validator,
+            amount: coin(amount.u128(), &invest.bond_denom),
+        });
+    }
+    Ok(res)
+}
+
+/// destroys amount of the sender's tokens and leaves the reserve where it is, so the
+/// tokens left are each backed by more
+pub fn burn_without_refund<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    let curve = curve_fn(curve_state.decimals);
+    let old_nominal = curve.reserve(curve_state.supply);
+    curve_state.supply = curve_state
+        .supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    let new_nominal = curve.reserve(curve_state.supply);
+    if new_nominal.is_zero() {
+        return Err(ContractError::BurnWouldOrphanReserve {});
+    }
+
+    take_contribution(deps.storage, &info.sender, amount)?;
+    let burn = execute_burn::<C>(deps.branch(), env.clone(), info.clone(), amount)?;
+
+    let old_backing = backing(deps.storage)?;
+    let new_backing = Decimal::from_ratio(
+        Uint128::new(old_backing.numerator()).multiply_ratio(old_nominal, new_nominal),
+        old_backing.denominator(),
+    );
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+    BACKING.save(deps.storage, &new_backing)?;
+    let spot_price = curve.spot_price(curve_state.supply);
+    checkpoint_spot_price(deps.storage, &env.block, spot_price)?;
+    record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+
+    let res = Response::new()
+        .add_attribute("action", "burn_without_refund")
+        .add_attribute("from", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("backing", new_backing.to_string())
+        .add_attributes(burn.attributes);
+    Ok(res)
+}
+
+// exit_tax charges the base rate up to the first bracket, and each bracket's
+// rate on the slice of the unbond between its threshold and the next one.
+// thresholds are fractions of the total supply, so larger exits pay more
+fn exit_tax(invest: &InvestmentInfo, amount: Uint128, supply: Uint128) -> Uint128 {
+    let mut tax = Uint128::zero();
+    let mut rate = invest.exit_tax;
+    let mut taxed = Uint128::zero();
+    for bracket in invest.exit_tax_brackets.iter() {
+        let bound = std::cmp::min(supply * bracket.threshold, amount);
+        if bound > taxed {
+            tax += (bound - taxed) * rate;
+            taxed = bound;
+        }
+        rate = bracket.rate;
+    }
+    tax + (amount - taxed) * rate
+}
+
+/// everything delegated across all validators. This is what the staking module holds for us,
+/// and can differ from the curve reserve by the liquid buffer and anything not yet undelegated
+pub fn staked_balance(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(STAKED_BALANCE.may_load(storage)?.unwrap_or_default())
+}
+
+fn add_delegation(storage: &mut dyn Storage, validator: &str, amount: Uint128) -> StdResult<()> {
+    DELEGATIONS.update(storage, validator, |bonded| -> StdResult<_> {
+        Ok(bonded.unwrap_or_default() + amount)
+    })?;
+    let staked = staked_balance(storage)? + amount;
+    STAKED_BALANCE.save(storage, &staked)
+}
+
+fn remove_delegation(storage: &mut dyn Storage, validator: &str, amount: Uint128) -> StdResult<()> {
+    DELEGATIONS.update(storage, validator, |bonded| -> StdResult<_> {
+        Ok(bonded.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    let staked = staked_balance(storage)?.checked_sub(amount)?;
+    STAKED_BALANCE.save(storage, &staked)
+}
+
+/// fills in the staked balance from the per-validator records, for contracts that
+/// predate it. Once it is there this leaves it alone, so it can run on every migration
+pub fn migrate_staked_balance(storage: &mut dyn Storage) -> StdResult<()> {
+    if STAKED_BALANCE.may_load(storage)?.is_none() {
+        let staked = delegations(storage)?
+            .into_iter()
+            .map(|(_, bonded)| bonded)
+            .sum();
+        STAKED_BALANCE.save(storage, &staked)?;
+    }
+    Ok(())
+}
+
+fn delegations(storage: &dyn Storage) -> StdResult<Vec<(String, Uint128)>> {
+    DELEGATIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (validator, bonded) = item?;
+            Ok((String::from_utf8(validator)?, bonded))
+        })
+        .collect()
+}
+
+// undelegate pulls amount out of the default validator first, then the allowed ones,
+// so stake directed at other validators is only touched when it has to be
+pub(crate) fn undelegate(
+    storage: &mut dyn Storage,
+    invest: &InvestmentInfo,
+    amount: Uint128,
+) -> StdResult<Vec<StakingMsg>> {
+    let mut msgs = vec![];
+    let mut remaining = amount;
+    let validators = std::iter::once(&invest.validator).chain(invest.allowed_validators.iter());
+    for validator in validators {
+        if remaining.is_zero() {
+            break;
+        }
+        let bonded = DELEGATIONS
+            .may_load(storage, validator)?
+            .unwrap_or_default();
+        let to_undelegate = std::cmp::min(bonded, remaining);
+        if to_undelegate.is_zero() {
+            continue;
+        }
+        remove_delegation(storage, validator, to_undelegate)?;
+        remaining -= to_undelegate;
+        msgs.push(StakingMsg::Undelegate {
+            validator: validator.clone(),
+            amount: coin(to_undelegate.u128(), &invest.bond_denom),
+        });
+    }
+    // this only happens if our books are out of sync with the chain
+    // let the staking module reject it rather than paying out less than the curve says
+    if !remaining.is_zero() {
+        msgs.push(StakingMsg::Undelegate {
+            validator: invest.validator.clone(),
+            amount: coin(remaining.u128(), &invest.bond_denom),
+        });
+    }
+    Ok(msgs)
+}
+
+/// undelegates everything we have staked, from every validator
+pub(crate) fn undelegate_all(
+    storage: &mut dyn Storage,
+    invest: &InvestmentInfo,
+) -> StdResult<Vec<StakingMsg>> {
+    let mut msgs = vec![];
+    for (validator, bonded) in delegations(storage)? {
+        if bonded.is_zero() {
+            continue;
+        }
+        remove_delegation(storage, &validator, bonded)?;
+        msgs.push(StakingMsg::Undelegate {
+            validator,
+            amount: coin(bonded.u128(), &invest.bond_denom),
+        });
+    }
+    Ok(msgs)
+}
+
+pub fn bond<C: CustomMsg>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     curve_fn: CurveFn,
-) -> Result<Response, ContractError> {
+    validator: Option<String>,
+    recipient: Option<String>,
+) -> Result<Response<C>, ContractError> {
+    assert_staked_reserve(deps.storage)?;
     // ensure we have the proper denom
     let invest = INVESTMENT.load(deps.storage)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    let accounts = [info.sender.to_string(), recipient.to_string()];
+    assert_not_frozen(deps.storage, deps.api, &accounts)?;
+    assert_buys_open(deps.storage)?;
+
+    // bonders may direct their stake to any of the allowed validators
+    let validator = match validator {
+        Some(v) if v != invest.validator && !invest.allowed_validators.contains(&v) => {
+            return Err(ContractError::ValidatorNotAllowed { validator: v })
+        }
+        Some(v) => v,
+        None => invest.validator.clone(),
+    };
     // payment finds the proper coin (or throws an error)
     let payment = info
         .funds
@@ -61,22 +590,32 @@ pub fn bond(
             denom: invest.bond_denom.clone(),
         })?;
 
-    // bonded is the total number of tokens we have delegated from this address
-    let bonded = get_bonded(&deps.querier, &env.contract.address)?;
-
     // calculate to_mint and update total supply
     let mut curve_state = CURVE_STATE.load(deps.storage)?;
 
     // TODO: this is just a safety assertion - do we keep it, or remove caching?
     // in the end supply is just there to cache the (expected) results of get_bonded() so we don't
     // have expensive queries everywhere
-    assert_bonds(&curve_state, bonded)?;
+    let slashing = reconcile_slashing(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &mut curve_state,
+    )?;
+
+    assert_curve_open(deps.storage, &env.block, curve_state.curve_reserve)?;
+    let goal_reached = goal_reached_event(
+        deps.storage,
+        curve_state.curve_reserve,
+        curve_state.curve_reserve + payment.amount,
+    )?;
 
     let curve = curve_fn(curve_state.decimals);
-    curve_state.reserve += payment.amount;
+    curve_state.curve_reserve += payment.amount;
 
     // curve.supply() calculates native -> CW20
-    let new_supply = curve.supply(curve_state.reserve);
+    let backing = backing(deps.storage)?;
+    let new_supply = curve.supply(to_nominal(curve_state.curve_reserve, backing));
 
     let minted = new_supply
         .checked_sub(curve_state.supply)
@@ -84,6 +623,39 @@ pub fn bond(
     curve_state.supply = new_supply;
 
     CURVE_STATE.save(deps.storage, &curve_state)?;
+    let spot_price = curve.spot_price(curve_state.supply);
+    checkpoint_spot_price(deps.storage, &env.block, spot_price)?;
+    record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+    spend_presale_allowance(deps.storage, &env.block, &recipient, payment.amount)?;
+    record_trade(
+        deps.storage,
+        &recipient,
+        TradeDirection::Bond,
+        payment.amount,
+    )?;
+    record_support(deps.storage, &recipient, payment.amount)?;
+    record_contribution(deps.storage, &recipient, payment.amount)?;
+    start_cooldown(deps.storage, &env.block, &recipient)?;
+    add_delegation(deps.storage, &validator, payment.amount)?;
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &recipient,
+        minted,
+        TradeDirection::Bond,
+    )?;
+
+    // keep what we did, in case the delegation fails and we have to undo it
+    PENDING_BOND.save(
+        deps.storage,
+        &PendingBond {
+            bonder: info.sender.clone(),
+            recipient: recipient.clone(),
+            validator: validator.clone(),
+            amount: payment.amount,
+            minted,
+        },
+    )?;
 
     // call into cw20-base to mint the token, call as self as no one else is allowed
     let sub_info = MessageInfo {
@@ -91,175 +663,1069 @@ pub fn bond(
         funds: vec![],
     };
 
-    execute_mint(deps, env, sub_info, info.sender.to_string(), minted)?;
+    let mint = execute_mint::<C>(deps, env, sub_info, recipient.to_string(), minted)?;
 
     // bond them to the validator
+    let delegate = StakingMsg::Delegate {
+        validator,
+        amount: payment.clone(),
+    };
+    // hooks go first, so that if the delegation fails they hear of the bond before its undoing
     let res = Response::new()
-        .add_message(StakingMsg::Delegate {
-            validator: invest.validator,
-            amount: payment.clone(),
-        })
+        .add_submessages(hooks)
+        .add_submessage(SubMsg::reply_on_error(delegate, BOND_REPLY_ID))
+        .add_events(slashing)
+        .add_events(goal_reached)
         .add_attribute("action", "bond")
         .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
         .add_attribute("bonded", payment.amount)
-        .add_attribute("minted", minted);
+        .add_attribute("minted", minted)
+        .add_attributes(mint.attributes);
     Ok(res)
 }
 
-pub fn unbond(
+/// undoes a bond whose delegation failed: the minted tokens are burned,
+/// the curve and our records put back, and the payment refunded
+pub fn bond_failed<C: CustomMsg>(
     mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    err: String,
     curve_fn: CurveFn,
-    amount: Uint128,
-) -> Result<Response, ContractError> {
-    let invest = INVESTMENT.load(deps.storage)?;
-    // ensure it is big enough to care
-    if amount < invest.min_withdrawal {
-        return Err(ContractError::UnbondTooSmall {
-            min_bonded: invest.min_withdrawal,
-            denom: invest.bond_denom,
-        });
-    }
-    // calculate tax and remainer to unbond
-    let tax = amount * invest.exit_tax;
-
-    // burn from the original caller
-    execute_burn(deps.branch(), env.clone(), info.clone(), amount)?;
-    if tax > Uint128::zero() {
-        let sub_info = MessageInfo {
-            sender: env.contract.address.clone(),
-            funds: vec![],
-        };
-        // call into cw20-base to mint tokens to owner, call as self as no one else is allowed
-        execute_mint(
-            deps.branch(),
-            env.clone(),
-            sub_info,
-            invest.owner.to_string(),
-            tax,
-        )?;
-    }
+) -> Result<Response<C>, ContractError> {
+    let pending = PENDING_BOND.load(deps.storage)?;
+    PENDING_BOND.remove(deps.storage);
 
-    // re-calculate bonded to ensure we have real values
-    // bonded is the total number of tokens we have delegated from this address
-    let bonded = get_bonded(&deps.querier, &env.contract.address)?;
+    let recipient_info = MessageInfo {
+        sender: pending.recipient.clone(),
+        funds: vec![],
+    };
+    let burn = execute_burn::<C>(deps.branch(), env.clone(), recipient_info, pending.minted)?;
 
-    // calculate how many native tokens this is worth from curve
-    // to do this, first we load curve state
     let mut curve_state = CURVE_STATE.load(deps.storage)?;
-
-    // TODO: this is just a safety assertion - do we keep it, or remove caching?
-    // in the end supply is just there to cache the (expected) results of get_bonded() so we don't
-    // have expensive queries everywhere
-    assert_bonds(&curve_state, bonded)?;
-
-    // unbond the amount minus tax
-    let amount_minus_tax = amount.checked_sub(tax).map_err(StdError::overflow)?;
-    let curve = curve_fn(curve_state.decimals);
+    curve_state.curve_reserve = curve_state
+        .curve_reserve
+        .checked_sub(pending.amount)
+        .map_err(StdError::overflow)?;
     curve_state.supply = curve_state
         .supply
-        .checked_sub(amount_minus_tax)
-        .map_err(StdError::overflow)?;
-
-    // curve.reserve() calculates CW20 -> native
-    // we've just updated total supply of CW20
-    // so we use that to calc the total reserve
-    // unbond is old reserve minus new reserve
-    // giving the amount of native tokens being unbonded
-    let new_reserve = curve.reserve(curve_state.supply);
-    let unbond = curve_state
-        .reserve
-        .checked_sub(new_reserve)
+        .checked_sub(pending.minted)
         .map_err(StdError::overflow)?;
-    curve_state.reserve = new_reserve;
-    curve_state.claims += unbond;
     CURVE_STATE.save(deps.storage, &curve_state)?;
-
-    CLAIMS.create_claim(
+    let curve = curve_fn(curve_state.decimals);
+    checkpoint_spot_price(
         deps.storage,
-        &info.sender,
-        unbond,
-        invest.unbonding_period.after(&env.block),
+        &env.block,
+        curve.spot_price(curve_state.supply),
     )?;
+    unrecord_bond(deps.storage, pending.amount)?;
+    unrecord_support(deps.storage, &pending.recipient, pending.amount)?;
+    unrecord_contribution(deps.storage, &pending.recipient, pending.amount)?;
+    remove_delegation(deps.storage, &pending.validator, pending.amount)?;
 
-    // unbond them
-    let res = Response::new()
-        .add_message(StakingMsg::Undelegate {
-            validator: invest.validator,
-            amount: coin(unbond.u128(), &invest.bond_denom),
-        })
-        .add_attribute("action", "unbond")
-        .add_attribute("to", info.sender)
-        .add_attribute("unbonded", unbond)
-        .add_attribute("burnt", amount);
-    Ok(res)
-}
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &pending.recipient,
+        pending.minted,
+        TradeDirection::Unbond,
+    )?;
 
-pub fn claim(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    // find how many tokens the contract has
     let invest = INVESTMENT.load(deps.storage)?;
-    let mut balance = deps
-        .querier
-        .query_balance(&env.contract.address, &invest.bond_denom)?;
-    if balance.amount < invest.min_withdrawal {
-        return Err(ContractError::BalanceTooSmall {});
-    }
-
-    // check how much to send - min(balance, claims[sender]), and reduce the claim
-    // Ensure we have enough balance to cover this and only send some claims if that is all we can cover
-    let to_send =
-        CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, Some(balance.amount))?;
-    if to_send == Uint128::zero() {
-        return Err(ContractError::NothingToClaim {});
-    }
-
-    // update total supply (lower claim)
-    CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
-        curve_state.claims = curve_state.claims.checked_sub(to_send)?;
-        Ok(curve_state)
-    })?;
-
-    // transfer tokens to the sender
-    balance.amount = to_send;
     let res = Response::new()
         .add_message(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![balance],
+            to_address: pending.bonder.to_string(),
+            amount: vec![coin(pending.amount.u128(), invest.bond_denom)],
         })
-        .add_attribute("action", "claim")
-        .add_attribute("from", info.sender)
-        .add_attribute("amount", to_send);
+        .add_submessages(hooks)
+        .add_attribute("action", "bond_failed")
+        .add_attribute("to", pending.bonder)
+        .add_attribute("refunded", pending.amount)
+        .add_attribute("error", err)
+        .add_attributes(burn.attributes);
     Ok(res)
 }
 
-/// reinvest will withdraw all pending rewards,
-/// then issue a callback to itself via _bond_all_tokens
-/// to reinvest the new earnings (and anything else that accumulated)
-pub fn reinvest(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
-    let contract_addr = env.contract.address;
-    let invest = INVESTMENT.load(deps.storage)?;
-    let msg = to_binary(&ExecuteMsg::_BondAllTokens {})?;
+pub fn unbond<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    let claimant = info.sender.clone();
+    do_unbond(deps, env, info, curve_fn, claimant, amount)
+}
+
+/// unbonds an owner's tokens as an approved spender. The claim goes to the owner,
+/// unless claim_to_spender is set
+pub fn unbond_from<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    owner: String,
+    amount: Uint128,
+    claim_to_spender: bool,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = info.sender.clone();
+
+    // deduct allowance before doing anything else have enough allowance
+    deduct_allowance(deps.storage, &owner_addr, &spender_addr, &env.block, amount)?;
+
+    let claimant = if claim_to_spender {
+        spender_addr.clone()
+    } else {
+        owner_addr.clone()
+    };
+    let owner_info = MessageInfo {
+        sender: owner_addr,
+        funds: info.funds,
+    };
+    let res = do_unbond(deps, env, owner_info, curve_fn, claimant, amount)?;
+    Ok(res.add_attribute("by", spender_addr))
+}
+
+/// burns the derivative tokens of info.sender and creates a claim for claimant
+fn do_unbond<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    claimant: Addr,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    assert_not_frozen(deps.storage, deps.api, &[claimant.to_string()])?;
+    let (unbond, owner_fee, slashing, burn) =
+        unbond_on_curve(deps.branch(), &env, &info, curve_fn, amount)?;
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &info.sender,
+        amount,
+        TradeDirection::Unbond,
+    )?;
+
+    // unbond them, now or at the end of the epoch
+    let (release_at, msgs) =
+        queue_undelegation(deps.storage, &env.block, &invest, unbond + owner_fee)?;
+    create_claim(deps.storage, &claimant, unbond, release_at)?;
+    accrue_owner_fee(deps.storage, owner_fee, release_at)?;
+    CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
+        curve_state.claims += unbond;
+        Ok(curve_state)
+    })?;
 
-    // and bond them to the validator
     let res = Response::new()
-        .add_message(DistributionMsg::WithdrawDelegatorReward {
-            validator: invest.validator,
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_events(slashing)
+        .add_attribute("action", "unbond")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", claimant)
+        .add_attribute("unbonded", unbond)
+        .add_attribute("burnt", amount)
+        .add_attributes(burn);
+    Ok(res)
+}
+
+/// pays out an unbond straight away from the liquid reserve, less the instant unbond penalty.
+/// The undelegated stake comes back to the liquid reserve once it matures, and the
+/// penalty with it is reinvested for the remaining holders
+pub fn instant_unbond<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+    max_penalty: Option<Decimal>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let penalty_rate = invest.instant_unbond_penalty;
+    if let Some(max) = max_penalty {
+        if penalty_rate > max {
+            return Err(ContractError::PenaltyExceeded {
+                penalty: penalty_rate,
+                max,
+            });
+        }
+    }
+
+    // refunds go through Unbond, at cost basis and with no penalty
+    let reserve = CURVE_STATE.load(deps.storage)?.curve_reserve;
+    if is_refunding(deps.storage, &env.block, reserve)? {
+        return Err(ContractError::FundingRefund {});
+    }
+
+    // what is neither owed to claims nor held aside for others is free to front the payout
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?
+        .amount;
+    let claims = CURVE_STATE.load(deps.storage)?.claims;
+    let liquid = balance.saturating_sub(claims + held_aside(deps.storage)?);
+
+    let (unbond, owner_fee, slashing, burn) =
+        unbond_on_curve(deps.branch(), &env, &info, curve_fn, amount)?;
+    let penalty = unbond * penalty_rate;
+    let payout = unbond - penalty;
+    if payout > liquid {
+        return Err(ContractError::InsufficientLiquidity { available: liquid });
+    }
+    let hooks = trade_hooks(
+        deps.storage,
+        &env.contract.address,
+        &info.sender,
+        amount,
+        TradeDirection::Unbond,
+    )?;
+
+    let (release_at, msgs) =
+        queue_undelegation(deps.storage, &env.block, &invest, unbond + owner_fee)?;
+    accrue_owner_fee(deps.storage, owner_fee, release_at)?;
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(payout.u128(), invest.bond_denom)],
+        })
+        .add_submessages(hooks)
+        .add_events(slashing)
+        .add_attribute("action", "instant_unbond")
+        .add_attribute("from", info.sender)
+        .add_attribute("unbonded", unbond)
+        .add_attribute("penalty", penalty)
+        .add_attribute("burnt", amount)
+        .add_attributes(burn);
+    Ok(res)
+}
+
+/// burns amount of info.sender's tokens, less exit tax, down the curve and returns
+/// how much reserve that releases for unbonding and the native exit tax owed to the owner
+/// on top of it, with the burn's (and tax mint's) attributes
+fn unbond_on_curve(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    curve_fn: CurveFn,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128, Option<Event>, Vec<Attribute>), ContractError> {
+    assert_staked_reserve(deps.storage)?;
+    assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+    let reserve = CURVE_STATE.load(deps.storage)?.curve_reserve;
+    if is_refunding(deps.storage, &env.block, reserve)? {
+        return unbond_refund(deps, env, info, amount);
+    }
+    assert_sells_open(deps.storage)?;
+    assert_cooled_down(deps.storage, &env.block, &info.sender)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    // ensure it is big enough to care
+    if amount < invest.min_withdrawal {
+        return Err(ContractError::UnbondTooSmall {
+            min_bonded: invest.min_withdrawal,
+            denom: invest.bond_denom,
+        });
+    }
+    use_unbond_capacity(deps.storage, &env.block, amount)?;
+    // calculate tax and remainer to unbond
+    // brackets are relative to the supply before this unbond
+    let supply = CURVE_STATE.load(deps.storage)?.supply;
+    let tax = if is_fee_exempt(deps.storage, &info.sender) {
+        Uint128::zero()
+    } else {
+        exit_tax(&invest, amount, supply)
+    };
+
+    // burn from the original caller
+    take_contribution(deps.storage, &info.sender, amount)?;
+    let mut attributes =
+        execute_burn::<Empty>(deps.branch(), env.clone(), info.clone(), amount)?.attributes;
+    let native_tax = invest.exit_tax_payout == ExitTaxPayout::Native {};
+    if tax > Uint128::zero() && !native_tax {
+        let sub_info = MessageInfo {
+            sender: env.contract.address.clone(),
+            funds: vec![],
+        };
+        // call into cw20-base to mint tokens to the payees, call as self as no one else is allowed
+        for (payee, part) in split_tax(deps.storage, tax)? {
+            let mint =
+                execute_mint::<Empty>(deps.branch(), env.clone(), sub_info.clone(), payee, part)?;
+            attributes.extend(mint.attributes);
+        }
+        record_owner_tokens(deps.storage, tax)?;
+    }
+
+    // calculate how many native tokens this is worth from curve
+    // to do this, first we load curve state
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+
+    // re-calculate bonded to ensure we have real values, writing down any slashing
+    // TODO: this is just a safety assertion - do we keep it, or remove caching?
+    // in the end supply is just there to cache the (expected) results of get_bonded() so we don't
+    // have expensive queries everywhere
+    let slashing = reconcile_slashing(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &mut curve_state,
+    )?;
+
+    // unbond the amount minus tax, unless the tax is paid out of what it releases
+    let amount_minus_tax = if native_tax {
+        amount
+    } else {
+        amount.checked_sub(tax).map_err(StdError::overflow)?
+    };
+    let curve = curve_fn(curve_state.decimals);
+    curve_state.supply = curve_state
+        .supply
+        .checked_sub(amount_minus_tax)
+        .map_err(StdError::overflow)?;
+
+    // curve.reserve() calculates CW20 -> native
+    // we've just updated total supply of CW20
+    // so we use that to calc the total reserve
+    // unbond is old reserve minus new reserve
+    // giving the amount of native tokens being unbonded
+    let new_reserve = curve.reserve(curve_state.supply) * backing(deps.storage)?;
+    let released = curve_state
+        .curve_reserve
+        .checked_sub(new_reserve)
+        .map_err(StdError::overflow)?;
+    curve_state.curve_reserve = new_reserve;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+    let spot_price = curve.spot_price(curve_state.supply);
+    checkpoint_spot_price(deps.storage, &env.block, spot_price)?;
+    record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+
+    let owner_fee = if native_tax {
+        released.multiply_ratio(tax, amount)
+    } else {
+        Uint128::zero()
+    };
+    if !owner_fee.is_zero() {
+        attributes.push(attr("owner_fee", owner_fee));
+    }
+    let unbond = released - owner_fee;
+    record_trade(deps.storage, &info.sender, TradeDirection::Unbond, unbond)?;
+
+    Ok((unbond, owner_fee, slashing, attributes))
+}
+
+// in refund mode an unbond releases what the holder paid for amount, as Burn does,
+// rather than the curve price, and no exit tax is taken
+fn unbond_refund(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128, Option<Event>, Vec<Attribute>), ContractError> {
+    let refund = take_contribution(deps.storage, &info.sender, amount)?;
+    let attributes =
+        execute_burn::<Empty>(deps.branch(), env.clone(), info.clone(), amount)?.attributes;
+
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    let slashing = reconcile_slashing(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &mut curve_state,
+    )?;
+    let refund = refund.min(curve_state.curve_reserve);
+    curve_state.supply = curve_state
+        .supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    curve_state.curve_reserve -= refund;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+    record_trade(deps.storage, &info.sender, TradeDirection::Unbond, refund)?;
+
+    Ok((refund, Uint128::zero(), slashing, attributes))
+}
+
+// held_aside is the reserve the contract holds liquid on behalf of others:
+// the funding pool, buy order escrow, donations, protocol fees and the creator escrow.
+// none of it is staked or claimable
+pub fn held_aside(storage: &dyn Storage) -> StdResult<Uint128> {
+    let pool = FUNDING_POOL.may_load(storage)?.unwrap_or_default();
+    let escrowed = ESCROWED_RESERVE.may_load(storage)?.unwrap_or_default();
+    let donated = RESERVE_DONATIONS.may_load(storage)?.unwrap_or_default();
+    let fees = fees_accrued(storage)?;
+    let holders = holder_rewards_held(storage)?;
+    Ok(pool + escrowed + donated + fees + holders + creator_escrow_held(storage)?)
+}
+
+pub fn claim<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    let owner = info.sender.clone();
+    do_claim(deps, env, info, owner)
+}
+
+/// releases address's matured claims to address, for whoever sends it.
+/// A keeper other than the owner is paid the claim incentive out of the payout
+pub fn claim_for<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response<C>, ContractError> {
+    let owner = deps.api.addr_validate(&address)?;
+    do_claim(deps, env, info, owner)
+}
+
+fn do_claim<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Addr,
+) -> Result<Response<C>, ContractError> {
+    // find how many tokens the contract has
+    let invest = INVESTMENT.load(deps.storage)?;
+    let mut balance = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?;
+    // the funding pool, order escrow and donations are held liquid too,
+    // but aren't ours to pay claims with
+    balance.amount = balance.amount.saturating_sub(held_aside(deps.storage)?);
+    if balance.amount < invest.min_withdrawal {
+        return Err(ContractError::BalanceTooSmall {});
+    }
+
+    // check how much to send - min(balance, claims[sender]), and reduce the claim
+    // Ensure we have enough balance to cover this and only send some claims if that is all we can cover
+    let to_send = claim_tokens(deps.storage, &owner, &env.block, Some(balance.amount))?;
+    if to_send == Uint128::zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    // update total supply (lower claim)
+    CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
+        curve_state.claims = curve_state.claims.checked_sub(to_send)?;
+        Ok(curve_state)
+    })?;
+
+    // transfer tokens to the owner, less any incentive for a keeper
+    let incentive = if info.sender == owner {
+        Uint128::zero()
+    } else {
+        to_send * invest.claim_incentive
+    };
+    let mut res = Response::new();
+    if !incentive.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(incentive.u128(), &invest.bond_denom)],
+        });
+    }
+    balance.amount = to_send - incentive;
+    let res = res
+        .add_message(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: vec![balance],
         })
+        .add_attribute("action", "claim")
+        .add_attribute("from", owner)
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", to_send)
+        .add_attribute("incentive", incentive);
+    Ok(res)
+}
+
+/// takes back up to limit claims left unclaimed for claim_expiry after maturing, so the
+/// liquidity set aside for them is not stranded. It goes to the recovery address if there
+/// is one, else back into the reserve, raising the backing like a donation
+pub fn recycle_expired_claims<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+    curve_fn: CurveFn,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let expiry = invest
+        .claim_expiry
+        .ok_or(ContractError::ClaimsDoNotExpire {})?;
+    let limit = limit.unwrap_or(DEFAULT_SWEEP_LIMIT).min(MAX_SWEEP_LIMIT) as usize;
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    // with nothing issued, there are no holders for it to go back to
+    if invest.claim_recovery.is_none() && curve_state.curve_reserve.is_zero() {
+        return Err(ContractError::NothingToBack {});
+    }
+
+    let recycled = remove_expired_claims(deps.storage, &env.block, &expiry, limit)?;
+    if recycled.is_zero() {
+        return Err(ContractError::NoExpiredClaims {});
+    }
+    curve_state.claims = curve_state
+        .claims
+        .checked_sub(recycled)
+        .map_err(StdError::overflow)?;
+
+    let res = Response::new()
+        .add_attribute("action", "recycle_expired_claims")
+        .add_attribute("recycled", recycled);
+    let res = match invest.claim_recovery {
+        Some(recovery) => {
+            CURVE_STATE.save(deps.storage, &curve_state)?;
+            res.add_attribute("to", &recovery)
+                .add_message(BankMsg::Send {
+                    to_address: recovery.into(),
+                    amount: vec![coin(recycled.u128(), &invest.bond_denom)],
+                })
+        }
+        // left liquid, for the next reinvest to bond with the rest of the reserve
+        None => {
+            let new_backing = add_to_backing(deps.storage, curve_state.curve_reserve, recycled)?;
+            curve_state.curve_reserve += recycled;
+            CURVE_STATE.save(deps.storage, &curve_state)?;
+            let spot_price = curve_fn(curve_state.decimals).spot_price(curve_state.supply);
+            record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+            res.add_attribute("backing", new_backing.to_string())
+        }
+    };
+    Ok(res)
+}
+
+/// settles everyone whose claims have matured, a page of limit addresses at a time
+pub fn sweep_claims<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+    start_after: Option<String>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_SWEEP_LIMIT)
+        .clamp(1, MAX_SWEEP_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let mut available = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?
+        .amount
+        .saturating_sub(held_aside(deps.storage)?);
+
+    let (due, next_start_after) = due_claimants(deps.storage, &env.block, start_after, limit)?;
+
+    let mut msgs = vec![];
+    let mut total = Uint128::zero();
+    let mut incentive = Uint128::zero();
+    for owner in due {
+        let to_send = claim_tokens(deps.storage, &owner, &env.block, Some(available))?;
+        if to_send.is_zero() {
+            continue;
+        }
+        available -= to_send;
+        total += to_send;
+        let cut = if owner == info.sender {
+            Uint128::zero()
+        } else {
+            to_send * invest.claim_incentive
+        };
+        incentive += cut;
+        msgs.push(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: vec![coin((to_send - cut).u128(), &invest.bond_denom)],
+        });
+    }
+    // with claims still to look at, a page with nothing due just moves the keeper on
+    if total.is_zero() && next_start_after.is_none() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    if !incentive.is_zero() {
+        msgs.push(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(incentive.u128(), &invest.bond_denom)],
+        });
+    }
+
+    CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
+        curve_state.claims = curve_state.claims.checked_sub(total)?;
+        Ok(curve_state)
+    })?;
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "sweep_claims")
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", total)
+        .add_attribute("incentive", incentive);
+    let res = match next_start_after {
+        Some(addr) => res.add_attribute("next_start_after", addr),
+        None => res,
+    };
+    Ok(res)
+}
+
+fn pending(entries: Option<Vec<Expiration>>, block: &BlockInfo) -> Vec<Expiration> {
+    entries
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|release_at| !release_at.is_expired(block))
+        .collect()
+}
+
+// a redelegation is only possible if the source isn't holding stake that is itself
+// still being redelegated, and the pair has a free entry
+fn can_redelegate(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    src: &str,
+    dst: &str,
+) -> StdResult<bool> {
+    let incoming = pending(REDELEGATED_TO.may_load(storage, src)?, block);
+    let open = pending(REDELEGATIONS.may_load(storage, (src, dst))?, block);
+    Ok(incoming.is_empty() && open.len() < MAX_REDELEGATION_ENTRIES)
+}
+
+fn record_redelegation(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    invest: &InvestmentInfo,
+    src: &str,
+    dst: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let release_at = invest.unbonding_period.after(block);
+    let mut open = pending(REDELEGATIONS.may_load(storage, (src, dst))?, block);
+    open.push(release_at);
+    REDELEGATIONS.save(storage, (src, dst), &open)?;
+    let mut incoming = pending(REDELEGATED_TO.may_load(storage, dst)?, block);
+    incoming.push(release_at);
+    REDELEGATED_TO.save(storage, dst, &incoming)?;
+
+    remove_delegation(storage, src, amount)?;
+    add_delegation(storage, dst, amount)
+}
+
+/// rebalance moves stake from validators above their target weight to those below it.
+/// Anything below min_withdrawal isn't worth a redelegation, and pairs that have hit
+/// the staking module's limits are skipped until their entries mature
+pub fn rebalance<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let total_weight: u64 = invest.target_weights.iter().map(|w| w.weight).sum();
+    if total_weight == 0 {
+        return Err(ContractError::NoTargetWeights {});
+    }
+
+    let current = delegations(deps.storage)?;
+    let total_bonded = current
+        .iter()
+        .fold(Uint128::zero(), |acc, (_, bonded)| acc + *bonded);
+
+    // validators without a weight should end up with nothing
+    let mut surplus: Vec<(String, Uint128)> = current
+        .iter()
+        .filter(|(validator, bonded)| {
+            !bonded.is_zero()
+                && !invest
+                    .target_weights
+                    .iter()
+                    .any(|w| &w.validator == validator)
+        })
+        .cloned()
+        .collect();
+    let mut deficit: Vec<(String, Uint128)> = vec![];
+    for target_weight in invest.target_weights.iter() {
+        let target = total_bonded.multiply_ratio(target_weight.weight, total_weight);
+        let bonded = current
+            .iter()
+            .find(|(validator, _)| validator == &target_weight.validator)
+            .map(|(_, bonded)| *bonded)
+            .unwrap_or_default();
+        if bonded > target {
+            surplus.push((target_weight.validator.clone(), bonded - target));
+        } else if target > bonded {
+            deficit.push((target_weight.validator.clone(), target - bonded));
+        }
+    }
+
+    let mut msgs = vec![];
+    let mut redelegated = Uint128::zero();
+    for (src, excess) in surplus.iter_mut() {
+        for (dst, missing) in deficit.iter_mut() {
+            let amount = std::cmp::min(*excess, *missing);
+            if amount.is_zero()
+                || amount < invest.min_withdrawal
+                || !can_redelegate(deps.storage, &env.block, src, dst)?
+            {
+                continue;
+            }
+            record_redelegation(deps.storage, &env.block, &invest, src, dst, amount)?;
+            *excess -= amount;
+            *missing -= amount;
+            redelegated += amount;
+            msgs.push(StakingMsg::Redelegate {
+                src_validator: src.clone(),
+                dst_validator: dst.clone(),
+                amount: coin(amount.u128(), &invest.bond_denom),
+            });
+        }
+    }
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "rebalance")
+        .add_attribute("redelegated", redelegated);
+    Ok(res)
+}
+
+pub fn assert_in_validator_set(
+    querier: &QuerierWrapper,
+    validator: &str,
+) -> Result<(), ContractError> {
+    let vals = querier.query_all_validators()?;
+    if !vals.iter().any(|v| v.address == validator) {
+        return Err(ContractError::NotInValidatorSet {
+            validator: validator.into(),
+        });
+    }
+    Ok(())
+}
+
+// switch_validator redelegates everything to validator and makes it the default.
+// any target weight of the old default is carried over to the new one
+fn switch_validator(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    mut invest: InvestmentInfo,
+    validator: String,
+) -> StdResult<Vec<StakingMsg>> {
+    let mut msgs = vec![];
+    for (src, bonded) in delegations(storage)? {
+        if src == validator || bonded.is_zero() {
+            continue;
+        }
+        record_redelegation(storage, block, &invest, &src, &validator, bonded)?;
+        msgs.push(StakingMsg::Redelegate {
+            src_validator: src,
+            dst_validator: validator.clone(),
+            amount: coin(bonded.u128(), &invest.bond_denom),
+        });
+    }
+
+    let old = invest.validator.clone();
+    let old_weight: u64 = invest
+        .target_weights
+        .iter()
+        .filter(|w| w.validator == old)
+        .map(|w| w.weight)
+        .sum();
+    invest.target_weights.retain(|w| w.validator != old);
+    if old_weight > 0 {
+        match invest
+            .target_weights
+            .iter_mut()
+            .find(|w| w.validator == validator)
+        {
+            Some(w) => w.weight += old_weight,
+            None => invest.target_weights.push(ValidatorWeight {
+                validator: validator.clone(),
+                weight: old_weight,
+            }),
+        }
+    }
+    invest.validator = validator;
+    INVESTMENT.save(storage, &invest)?;
+    Ok(msgs)
+}
+
+// record an approval, and carry out the change once there are enough of them
+fn approve<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    mut change: ValidatorChange,
+    approver: Addr,
+) -> Result<Response<C>, ContractError> {
+    let approvers = VALIDATOR_APPROVERS.load(deps.storage)?;
+    if !approvers.addrs.contains(&approver) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if change.approvals.contains(&approver) {
+        return Err(ContractError::AlreadyApproved {});
+    }
+    change.approvals.push(approver.clone());
+
+    if (change.approvals.len() as u64) < approvers.threshold {
+        PENDING_VALIDATOR_CHANGE.save(deps.storage, &change)?;
+        let res = Response::new()
+            .add_attribute("action", "approve_validator_change")
+            .add_attribute("validator", change.validator)
+            .add_attribute("approver", approver);
+        return Ok(res);
+    }
+
+    PENDING_VALIDATOR_CHANGE.remove(deps.storage);
+    let invest = INVESTMENT.load(deps.storage)?;
+    let msgs = switch_validator(deps.storage, &env.block, invest, change.validator.clone())?;
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "change_validator")
+        .add_attribute("validator", change.validator)
+        .add_attribute("approver", approver);
+    Ok(res)
+}
+
+pub fn propose_validator_change<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+) -> Result<Response<C>, ContractError> {
+    assert_in_validator_set(&deps.querier, &validator)?;
+    let change = ValidatorChange {
+        validator,
+        approvals: vec![],
+    };
+    approve(deps, env, change, info.sender)
+}
+
+pub fn approve_validator_change<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+) -> Result<Response<C>, ContractError> {
+    // approvers must name the validator, so nobody signs off on a swapped proposal
+    let change = PENDING_VALIDATOR_CHANGE
+        .may_load(deps.storage)?
+        .filter(|change| change.validator == validator)
+        .ok_or(ContractError::NoValidatorChangeProposed { validator })?;
+    approve(deps, env, change, info.sender)
+}
+
+/// lets the owner move all stake alone when the default validator has dropped out of the
+/// active set, as there may be no time to gather approvals
+pub fn rebond_all_tokens<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator_address: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if assert_in_validator_set(&deps.querier, &invest.validator).is_ok() {
+        return Err(ContractError::ValidatorStillActive {
+            validator: invest.validator,
+        });
+    }
+    assert_in_validator_set(&deps.querier, &validator_address)?;
+
+    // the emergency supersedes whatever was being voted on
+    PENDING_VALIDATOR_CHANGE.remove(deps.storage);
+    let msgs = switch_validator(deps.storage, &env.block, invest, validator_address.clone())?;
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "rebond_all_tokens")
+        .add_attribute("validator", validator_address);
+    Ok(res)
+}
+
+/// moves all bonded tokens to a new validator at the chain's request (gov or an end-blocker),
+/// without going through the approvers
+pub fn sudo_switch_validator<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    validator: String,
+) -> Result<Response<C>, ContractError> {
+    assert_in_validator_set(&deps.querier, &validator)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+
+    PENDING_VALIDATOR_CHANGE.remove(deps.storage);
+    let msgs = switch_validator(deps.storage, &env.block, invest, validator.clone())?;
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "sudo_switch_validator")
+        .add_attribute("validator", validator);
+    Ok(res)
+}
+
+pub fn query_validator_change(deps: Deps) -> StdResult<ValidatorChangeResponse> {
+    let approvers = VALIDATOR_APPROVERS.load(deps.storage)?;
+    let change = PENDING_VALIDATOR_CHANGE.may_load(deps.storage)?;
+    let res = ValidatorChangeResponse {
+        approvers: approvers.addrs.iter().map(|a| a.to_string()).collect(),
+        threshold: approvers.threshold,
+        proposed_validator: change.as_ref().map(|c| c.validator.clone()),
+        approvals: change
+            .map(|c| c.approvals.iter().map(|a| a.to_string()).collect())
+            .unwrap_or_default(),
+    };
+    Ok(res)
+}
+
+// withdraw rewards from every validator we have stake with
+fn withdraw_rewards(storage: &dyn Storage) -> StdResult<Vec<DistributionMsg>> {
+    let withdrawals = delegations(storage)?
+        .into_iter()
+        .filter(|(_, bonded)| !bonded.is_zero())
+        .map(|(validator, _)| DistributionMsg::WithdrawDelegatorReward { validator })
+        .collect();
+    Ok(withdrawals)
+}
+
+/// reinvest will withdraw all pending rewards,
+/// then issue a callback to itself via _bond_all_tokens
+/// to reinvest the new earnings (and anything else that accumulated).
+/// With a reward withdraw address set, the rewards go there instead, and only
+/// reserve waiting to be staked is bonded
+pub fn reinvest<C: CustomMsg>(deps: DepsMut, env: Env) -> Result<Response<C>, ContractError> {
+    assert_staked_reserve(deps.storage)?;
+    let healthy = check_validator_health(deps.storage, &deps.querier, env.block.height)?;
+    let contract_addr = env.contract.address;
+    let msg = to_binary(&ExecuteMsg::_BondAllTokens {})?;
+    let withdrawals = withdraw_rewards(deps.storage)?;
+    let rewards_to = REWARD_WITHDRAW_ADDRESS
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| contract_addr.clone());
+
+    // and bond them to the validator
+    let res = Response::new()
+        .add_messages(withdrawals)
         .add_message(WasmMsg::Execute {
             contract_addr: contract_addr.to_string(),
             msg,
             funds: vec![],
+        })
+        .add_attribute("validator_healthy", healthy.to_string())
+        .add_attribute("rewards_to", rewards_to);
+    Ok(res)
+}
+
+/// routes staking rewards to address rather than the contract, or back to the
+/// contract if None
+pub fn set_reward_withdraw_address<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = match address {
+        Some(address) => {
+            let address = deps.api.addr_validate(&address)?;
+            REWARD_WITHDRAW_ADDRESS.save(deps.storage, &address)?;
+            address
+        }
+        None => {
+            REWARD_WITHDRAW_ADDRESS.remove(deps.storage);
+            env.contract.address
+        }
+    };
+    let res = Response::new()
+        .add_message(DistributionMsg::SetWithdrawAddress {
+            address: address.to_string(),
+        })
+        .add_attribute("action", "set_reward_withdraw_address")
+        .add_attribute("address", address);
+    Ok(res)
+}
+
+// notes when the validator first turned up outside the active set, or clears that once
+// it is back, so keepers can see a failover is due
+fn check_validator_health(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    height: u64,
+) -> Result<bool, ContractError> {
+    let invest = INVESTMENT.load(storage)?;
+    let healthy = assert_in_validator_set(querier, &invest.validator).is_ok();
+    if healthy {
+        UNHEALTHY_SINCE.remove(storage);
+    } else if UNHEALTHY_SINCE.may_load(storage)?.is_none() {
+        UNHEALTHY_SINCE.save(storage, &height)?;
+    }
+    Ok(healthy)
+}
+
+/// moves all stake to the first fallback validator still in the active set, once the
+/// configured one has left it. Unlike RebondAllTokens anyone can do this, as the
+/// owner already chose where the stake may go
+pub fn failover<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if assert_in_validator_set(&deps.querier, &invest.validator).is_ok() {
+        return Err(ContractError::ValidatorStillActive {
+            validator: invest.validator,
         });
+    }
+    let fallback = invest
+        .fallback_validators
+        .iter()
+        .find(|v| assert_in_validator_set(&deps.querier, v).is_ok())
+        .cloned()
+        .ok_or(ContractError::NoHealthyFallback {})?;
+
+    // the emergency supersedes whatever was being voted on
+    PENDING_VALIDATOR_CHANGE.remove(deps.storage);
+    UNHEALTHY_SINCE.remove(deps.storage);
+    let old = invest.validator.clone();
+    let msgs = switch_validator(deps.storage, &env.block, invest, fallback.clone())?;
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "failover")
+        .add_attribute("from", old)
+        .add_attribute("validator", fallback);
     Ok(res)
 }
 
-pub fn _bond_all_tokens(
+pub fn query_validator_health(deps: Deps) -> StdResult<ValidatorHealthResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    Ok(ValidatorHealthResponse {
+        healthy: assert_in_validator_set(&deps.querier, &invest.validator).is_ok(),
+        validator: invest.validator,
+        unhealthy_since: UNHEALTHY_SINCE.may_load(deps.storage)?,
+        fallback_validators: invest.fallback_validators,
+    })
+}
+
+/// collects rewards without bonding them, leaving them as liquid balance until
+/// the next reinvest
+pub fn withdraw_rewards_only<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let withdrawals = withdraw_rewards(deps.storage)?;
+    let res = Response::new()
+        .add_messages(withdrawals)
+        .add_attribute("action", "withdraw_rewards");
+    Ok(res)
+}
+
+pub fn _bond_all_tokens<C: CustomMsg>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     curve_fn: CurveFn,
-) -> Result<Response, ContractError> {
+) -> Result<Response<C>, ContractError> {
     // this is just meant as a call-back to ourself
     if info.sender != env.contract.address {
         return Err(ContractError::Unauthorized {});
@@ -271,60 +1737,366 @@ pub fn _bond_all_tokens(
         .querier
         .query_balance(&env.contract.address, &invest.bond_denom)?;
 
-    // we deduct pending claims from our account balance before reinvesting.
-    // if there is not enough funds, we just return a no-op
-    match CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
-        balance.amount = balance.amount.checked_sub(curve_state.claims)?;
-        // this just triggers the "no op" case if we don't have min_withdrawal left to reinvest
-        balance.amount.checked_sub(invest.min_withdrawal)?;
-
-        // TODO: think about this some more.
-        // need coffee and a full night of sleep cos moderately certain
-        // that this ain't right like
-        curve_state.reserve += balance.amount;
-        // now let's mint the derived tokens
-        // off of this reward
-        let curve = curve_fn(curve_state.decimals);
-        // reserve -> token and increment
-        let new_supply = curve.supply(curve_state.reserve);
-        curve_state.supply = new_supply;
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    // with nothing issued, there are no holders for the rewards to go to yet, and
+    // winding down, nothing more is staked
+    if curve_state.curve_reserve.is_zero() || WIND_DOWN.may_load(deps.storage)?.is_some() {
+        return Ok(Response::default());
+    }
+    // what we hold beyond pending claims and everything held aside is either reserve that
+    // isn't staked yet (like buys), or rewards. An epoch's batch is still delegated, but
+    // already out of the reserve
+    let free = match balance
+        .amount
+        .checked_sub(curve_state.claims + held_aside(deps.storage)?)
+    {
+        Ok(free) => free,
+        Err(_) => return Ok(Response::default()),
+    };
+    let unstaked = (curve_state.curve_reserve + pending_undelegation(deps.storage)?)
+        .saturating_sub(staked_balance(deps.storage)?);
+    let rewards = free.saturating_sub(unstaked);
 
-        Ok(curve_state)
-    }) {
-        Ok(_) => {}
-        // if it is below the minimum, we do a no-op (do not revert other state from withdrawal)
-        Err(StdError::Overflow { .. }) => return Ok(Response::default()),
-        Err(e) => return Err(ContractError::Std(e)),
+    // the creator's share of the rewards stays liquid, as does whatever the reward strategy
+    // sends to the creator or holders. The rest joins the curve reserve through the backing.
+    // Nothing is minted for it: supply, and where we are on the curve, stay put while
+    // every token redeems for more
+    let skimmed = rewards * invest.reward_skim;
+    let (kept, routed) = route_rewards(
+        deps.storage,
+        &env.contract.address,
+        &invest.reward_strategy,
+        rewards - skimmed,
+    )?;
+    let set_aside = rewards - kept;
+
+    // everything but the liquid buffer is delegated, adding to the staked balance.
+    // Below the minimum it all stays liquid, and without rewards to book that's a no-op
+    let buffer = curve_state.curve_reserve * invest.liquid_ratio;
+    balance.amount = (free - set_aside).saturating_sub(buffer);
+    if balance.amount < invest.min_withdrawal {
+        if rewards.is_zero() {
+            return Ok(Response::default());
+        }
+        balance.amount = Uint128::zero();
     }
+    let new_backing = add_to_backing(deps.storage, curve_state.curve_reserve, kept)?;
+    curve_state.curve_reserve += kept;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+    let spot_price = curve_fn(curve_state.decimals).spot_price(curve_state.supply);
+    add_delegation(deps.storage, &invest.validator, balance.amount)?;
+    checkpoint_spot_price(deps.storage, &env.block, spot_price)?;
+    record_rate(deps.storage, env.block.height, &curve_state, spot_price)?;
+    let pool = FUNDING_POOL.may_load(deps.storage)?.unwrap_or_default();
+    FUNDING_POOL.save(deps.storage, &(pool + skimmed))?;
 
     // and bond them to the validator
-    let res = Response::new()
-        .add_message(StakingMsg::Delegate {
+    let mut res = Response::new();
+    if !balance.amount.is_zero() {
+        res = res.add_message(StakingMsg::Delegate {
             validator: invest.validator,
             amount: balance.clone(),
-        })
+        });
+    }
+    let res = res
         .add_attribute("action", "reinvest")
-        .add_attribute("bonded", balance.amount);
+        .add_attribute("bonded", balance.amount)
+        .add_attribute("rewards", rewards)
+        .add_attribute("skimmed", skimmed)
+        .add_attribute("buffer", buffer)
+        .add_attribute("backing", new_backing.to_string())
+        .add_attributes(routed);
+    Ok(res)
+}
+
+pub fn withdraw_funding_pool<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
+    assert_creator(deps.as_ref(), &info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+
+    let pool = FUNDING_POOL.may_load(deps.storage)?.unwrap_or_default();
+    let amount = amount.unwrap_or(pool);
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let remaining = pool
+        .checked_sub(amount)
+        .map_err(|_| ContractError::FundingPoolTooSmall { available: pool })?;
+    FUNDING_POOL.save(deps.storage, &remaining)?;
+
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(amount.u128(), &invest.bond_denom)],
+        })
+        .add_attribute("action", "withdraw_funding_pool")
+        .add_attribute("to", info.sender)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+/// lets the owner retune the exit tax, within the cap set at instantiate,
+/// and the minimum withdrawal. None leaves a value as it is
+pub fn update_staking_params<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    exit_tax: Option<Decimal>,
+    min_withdrawal: Option<Uint128>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let mut invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(exit_tax) = exit_tax {
+        if exit_tax > invest.max_exit_tax {
+            return Err(ContractError::ExitTaxTooHigh {
+                max: invest.max_exit_tax,
+            });
+        }
+        invest.exit_tax = exit_tax;
+    }
+    if let Some(min_withdrawal) = min_withdrawal {
+        invest.min_withdrawal = min_withdrawal;
+    }
+    INVESTMENT.save(deps.storage, &invest)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_staking_params")
+        .add_attribute("exit_tax", invest.exit_tax.to_string())
+        .add_attribute("min_withdrawal", invest.min_withdrawal);
+    Ok(res)
+}
+
+/// the first step of handing over ownership (and with it the exit tax), which the new
+/// owner must accept. proposing again replaces any pending transfer
+pub fn transfer_ownership<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+    let res = Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", new_owner);
     Ok(res)
 }
 
-pub fn query_investment(deps: Deps) -> StdResult<InvestmentResponse> {
+pub fn accept_ownership<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let pending = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if info.sender != pending {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    INVESTMENT.update(deps.storage, |mut invest| -> StdResult<_> {
+        invest.owner = pending.clone();
+        Ok(invest)
+    })?;
+    PENDING_OWNER.remove(deps.storage);
+
+    let res = Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", pending);
+    Ok(res)
+}
+
+pub fn query_investment(deps: Deps, curve_fn: CurveFn) -> StdResult<InvestmentResponse> {
     let invest = INVESTMENT.load(deps.storage)?;
     let curve_state = CURVE_STATE.load(deps.storage)?;
+    let pool = FUNDING_POOL.may_load(deps.storage)?.unwrap_or_default();
 
-    let curve_type = CURVE_TYPE.load(deps.storage)?;
-    let curve_fn = curve_type.to_curve_fn();
     let curve = curve_fn(curve_state.decimals);
-    let spot_price = curve.spot_price(curve_state.supply);
+    let spot_price = nominal_value(curve.spot_price(curve_state.supply), backing(deps.storage)?);
 
     let res = InvestmentResponse {
         owner: invest.owner.to_string(),
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?.map(Into::into),
         exit_tax: invest.exit_tax,
+        max_exit_tax: invest.max_exit_tax,
+        instant_unbond_penalty: invest.instant_unbond_penalty,
+        claim_incentive: invest.claim_incentive,
+        exit_tax_brackets: invest.exit_tax_brackets,
         validator: invest.validator,
+        allowed_validators: invest.allowed_validators,
+        target_weights: invest.target_weights,
+        fallback_validators: invest.fallback_validators,
+        unbond_epoch: invest.unbond_epoch,
+        exit_tax_payout: invest.exit_tax_payout,
+        reward_strategy: invest.reward_strategy,
         min_withdrawal: invest.min_withdrawal,
+        reward_skim: invest.reward_skim,
+        bond_tolerance: invest.bond_tolerance,
+        funding_pool: coin(pool.u128(), &invest.bond_denom),
+        reward_withdraw_address: REWARD_WITHDRAW_ADDRESS
+            .may_load(deps.storage)?
+            .map(Into::into),
+        claim_expiry: invest.claim_expiry,
+        claim_recovery_address: invest.claim_recovery.map(Into::into),
         token_supply: curve_state.supply,
-        staked_tokens: coin(curve_state.reserve.u128(), &invest.bond_denom),
+        staked_tokens: coin(staked_balance(deps.storage)?.u128(), &invest.bond_denom),
         nominal_value: spot_price,
     };
     Ok(res)
 }
+
+pub fn query_liquid_buffer(deps: Deps, env: Env) -> StdResult<LiquidBufferResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let curve_state = CURVE_STATE.load(deps.storage)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?
+        .amount;
+    let liquid = balance.saturating_sub(curve_state.claims + held_aside(deps.storage)?);
+    Ok(LiquidBufferResponse {
+        liquid_ratio: invest.liquid_ratio,
+        target: curve_state.curve_reserve * invest.liquid_ratio,
+        liquid,
+    })
+}
+
+/// what the contract holds of the reserve, whether that's a native denom or a cw20
+pub fn reserve_balance(deps: Deps, env: &Env) -> StdResult<Uint128> {
+    match RESERVE_TOKEN.may_load(deps.storage)? {
+        Some(token) => {
+            let balance: BalanceResponse = deps.querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            Ok(balance.balance)
+        }
+        None => {
+            let curve_state = CURVE_STATE.load(deps.storage)?;
+            Ok(deps
+                .querier
+                .query_balance(&env.contract.address, &curve_state.reserve_denom)?
+                .amount)
+        }
+    }
+}
+
+/// checks the books against what we actually hold. The reserve has to be delegated, or
+/// held liquid if it isn't staked, and matured claims and everything held aside paid
+/// out of the liquid balance. Claims still unbonding are in transit, so only count once
+/// they mature
+pub fn query_health(deps: Deps, env: Env) -> StdResult<HealthResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let curve_state = CURVE_STATE.load(deps.storage)?;
+    let staked_reserve = curve_state.reserve_denom == invest.bond_denom;
+
+    let delegated = if staked_reserve {
+        deps.querier
+            .query_all_delegations(&env.contract.address)?
+            .into_iter()
+            .filter(|d| d.amount.denom == invest.bond_denom)
+            .map(|d| d.amount.amount)
+            .sum::<Uint128>()
+    } else {
+        Uint128::zero()
+    };
+    // an epoch's batch is still delegated, but already out of the reserve
+    let pending = pending_undelegation(deps.storage)?;
+    let staked = delegated.saturating_sub(pending);
+    let liquid = reserve_balance(deps, &env)?;
+    let matured_claims =
+        matured_owner_fees(deps.storage, &env.block)? + query_total_claims(deps, env)?.matured;
+    let held_aside = held_aside(deps.storage)?;
+
+    let owed_liquid = matured_claims + held_aside;
+    let solvent = if staked_reserve {
+        // reserve that isn't delegated yet has to be on hand
+        let staked_balance = staked_balance(deps.storage)?;
+        let unstaked = (curve_state.curve_reserve + pending).saturating_sub(staked_balance);
+        delegated + invest.bond_tolerance >= staked_balance && liquid >= owed_liquid + unstaked
+    } else {
+        liquid >= curve_state.curve_reserve + owed_liquid
+    };
+    let total_supply = TOKEN_INFO_WITH_META
+        .load(deps.storage)?
+        .token_info
+        .total_supply;
+
+    Ok(HealthResponse {
+        reserve: curve_state.curve_reserve,
+        staked,
+        liquid,
+        claims: curve_state.claims,
+        matured_claims,
+        held_aside,
+        supply: curve_state.supply,
+        total_supply,
+        solvent,
+    })
+}
+
+pub fn query_reinvest_preview(deps: Deps, env: Env) -> StdResult<ReinvestPreviewResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let curve_state = CURVE_STATE.load(deps.storage)?;
+
+    let mut pending_rewards = Uint128::zero();
+    for (validator, bonded) in delegations(deps.storage)? {
+        if bonded.is_zero() {
+            continue;
+        }
+        if let Some(delegation) = deps
+            .querier
+            .query_delegation(&env.contract.address, validator)?
+        {
+            pending_rewards += delegation
+                .accumulated_rewards
+                .iter()
+                .filter(|c| c.denom == invest.bond_denom)
+                .map(|c| c.amount)
+                .sum::<Uint128>();
+        }
+    }
+
+    // the same sums as _bond_all_tokens, with the rewards already withdrawn
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?
+        .amount;
+    let liquid = balance.saturating_sub(curve_state.claims + held_aside(deps.storage)?);
+    let buffer = curve_state.curve_reserve * invest.liquid_ratio;
+    // rewards paid elsewhere never reach us to be bonded
+    let incoming = if REWARD_WITHDRAW_ADDRESS.may_load(deps.storage)?.is_some() {
+        Uint128::zero()
+    } else {
+        pending_rewards
+    };
+    let to_bond = (liquid + incoming).saturating_sub(buffer);
+    Ok(ReinvestPreviewResponse {
+        pending_rewards,
+        liquid,
+        to_bond,
+        worthwhile: to_bond >= invest.min_withdrawal,
+    })
+}
+
+pub fn query_delegations(deps: Deps) -> StdResult<DelegationsResponse> {
+    let delegations = delegations(deps.storage)?
+        .into_iter()
+        .map(|(validator, amount)| ValidatorDelegation { validator, amount })
+        .collect();
+    Ok(DelegationsResponse { delegations })
+}
@@ -1,24 +1,31 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Binary, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy, U64Key};
 
-use crate::msg::CurveType;
+use crate::msg::{
+    CurveType, ExitTaxPayout, FeeConfig, FundingGoal, Milestone, NftReceipts, PatronTier, Payee,
+    RewardStrategy, TaxBracket, UnbondLimits, ValidatorWeight, VestingSchedule,
+};
+use crate::query::{DenomTrace, RateSnapshot};
 use cw20_bonding::curves::DecimalPlaces;
 
 use cw20_base::state::TokenInfo;
 
-use cw0::Duration;
-use cw_controllers::Claims;
+use cw0::{Duration, Expiration};
+use cw_controllers::{Claim, Hooks};
 
 type ValidatorAddress = String;
 
 /// Supply is dynamic and tracks the current supply of staked and cw20 tokens.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
 pub struct CurveState {
-    /// reserve is how many native tokens exist bonded to the validator
-    pub reserve: Uint128,
+    /// curve_reserve is how many native tokens back the curve. Buys and bonds add what
+    /// they pay, while rewards and donations join it through the backing, never moving supply.
+    /// What is actually delegated is tracked apart from this, in STAKED_BALANCE
+    #[serde(alias = "reserve")]
+    pub curve_reserve: Uint128,
     /// supply is how many tokens this contract has issued
     pub supply: Uint128,
 
@@ -35,7 +42,7 @@ pub struct CurveState {
 impl CurveState {
     pub fn new(reserve_denom: String, decimals: DecimalPlaces) -> Self {
         CurveState {
-            reserve: Uint128::new(0),
+            curve_reserve: Uint128::new(0),
             supply: Uint128::new(0),
             reserve_denom,
             decimals,
@@ -73,20 +80,477 @@ pub struct InvestmentInfo {
     pub unbonding_period: Duration,
     /// This is how much the owner takes as a cut when someone unbonds
     pub exit_tax: Decimal,
+    /// Marginal rates applied to larger unbonds, relative to total supply
+    pub exit_tax_brackets: Vec<TaxBracket>,
     /// All tokens are bonded to this validator
     /// FIXME: address validation doesn't work for validator addresses
     pub validator: ValidatorAddress,
+    /// Bonders may pick one of these validators instead of the default one
+    pub allowed_validators: Vec<ValidatorAddress>,
+    /// Target spread of stake across validators, used by rebalance
+    pub target_weights: Vec<ValidatorWeight>,
     /// This is the minimum amount we will pull out to reinvest, as well as a minimum
     /// that can be unbonded (to avoid needless staking tx)
     pub min_withdrawal: Uint128,
+    /// This is the share of reinvested rewards that goes to the funding pool
+    pub reward_skim: Decimal,
+    /// How far the stored reserve may drift from the queried delegations before we error
+    pub bond_tolerance: Uint128,
+    /// The owner can change exit_tax, but never above this
+    pub max_exit_tax: Decimal,
+    /// Share of an instant unbond kept back for the remaining holders
+    pub instant_unbond_penalty: Decimal,
+    /// Reinvest keeps this share of the reserve undelegated, to pay claims and instant exits
+    pub liquid_ratio: Decimal,
+    /// Share of a claim paid to whoever pushes it out with ClaimFor
+    pub claim_incentive: Decimal,
+    /// Where Failover moves the stake if the validator leaves the active set, in order
+    pub fallback_validators: Vec<ValidatorAddress>,
+    /// Unbonds are batched into one undelegation per epoch of this length, if set
+    pub unbond_epoch: Option<Duration>,
+    /// Whether the exit tax is minted to the owner or paid out of the unbond
+    pub exit_tax_payout: ExitTaxPayout,
+    /// Whether rewards compound, go to holders, or are split with the creator
+    pub reward_strategy: RewardStrategy,
+    /// How long a matured claim may go unclaimed before it can be recycled, if ever
+    pub claim_expiry: Option<Duration>,
+    /// Where recycled claims go, if not back into the reserve
+    pub claim_recovery: Option<Addr>,
 }
 
-pub const CLAIMS: Claims = Claims::new("claims");
+/// InvestmentInfo as stored by v0.1.0, before validator sets and tax brackets.
+/// Only read when migrating
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvestmentInfoV0_1 {
+    pub owner: Addr,
+    pub bond_denom: String,
+    pub unbonding_period: Duration,
+    pub exit_tax: Decimal,
+    pub validator: ValidatorAddress,
+    pub min_withdrawal: Uint128,
+}
+
+pub const INVESTMENT_V0_1: Item<InvestmentInfoV0_1> = Item::new("invest");
+
+/// A bond whose delegation hasn't gone through yet, so it can be undone if that fails
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingBond {
+    pub bonder: Addr,
+    pub recipient: Addr,
+    pub validator: ValidatorAddress,
+    pub amount: Uint128,
+    pub minted: Uint128,
+}
+
+pub const PENDING_BOND: Item<PendingBond> = Item::new("pending_bond");
+
+/// Claims of those who unbonded, one entry per claim under (owner, id), so a long
+/// queue can be paged through rather than loaded whole
+pub const CLAIMS: Map<(&Addr, U64Key), Claim> = Map::new("indexed_claims");
+/// Unbonds waiting for the end of the epoch to be undelegated together
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct UnbondEpoch {
+    pub id: u64,
+    /// what has been unbonded this epoch, still to be undelegated
+    pub pending: Uint128,
+    pub ends: Expiration,
+}
+
+pub const UNBOND_EPOCH: Item<UnbondEpoch> = Item::new("unbond_epoch");
+
+/// The last claim id handed out
+pub const CLAIM_COUNT: Item<u64> = Item::new("claim_count");
+/// Claims as kept before they were indexed, one list per address. Only read to migrate
+pub const LEGACY_CLAIMS: Map<&Addr, Vec<Claim>> = Map::new("claims");
 
 pub const INVESTMENT: Item<InvestmentInfo> = Item::new("invest");
+/// An ownership handover waiting to be accepted
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+
+/// How many native tokens the contract has delegated to each validator
+pub const DELEGATIONS: Map<&str, Uint128> = Map::new("delegations");
+/// The sum of DELEGATIONS. It follows the staking module, and only a slash (written down
+/// through the backing) lets it change the curve reserve
+pub const STAKED_BALANCE: Item<Uint128> = Item::new("staked_balance");
+
+/// How much of the curve's reserve is still backed by delegations.
+/// One until a slash is reconciled, after which every holder shares the loss
+pub const BACKING: Item<Decimal> = Item::new("backing");
+
+/// Where the staking module pays our rewards, if not to the contract itself
+pub const REWARD_WITHDRAW_ADDRESS: Item<Addr> = Item::new("reward_withdraw_address");
+
+/// When each open redelegation from one validator to another completes.
+/// The staking module only allows a handful of these per pair at any time
+pub const REDELEGATIONS: Map<(&str, &str), Vec<Expiration>> = Map::new("redelegations");
+
+/// When redelegations into a validator complete. Until then, that stake can't be
+/// redelegated again
+pub const REDELEGATED_TO: Map<&str, Vec<Expiration>> = Map::new("redelegated_to");
+
+/// Who must sign off on switching the validator, since it controls all funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approvers {
+    pub addrs: Vec<Addr>,
+    pub threshold: u64,
+}
+
+/// A validator switch waiting on approvals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorChange {
+    pub validator: ValidatorAddress,
+    pub approvals: Vec<Addr>,
+}
+
+pub const VALIDATOR_APPROVERS: Item<Approvers> = Item::new("validator_approvers");
+
+/// Set by the first reinvest to find the validator out of the active set, to the height
+/// it did so. Cleared once it is back or we failed over
+pub const UNHEALTHY_SINCE: Item<u64> = Item::new("unhealthy_since");
+
+pub const PENDING_VALIDATOR_CHANGE: Item<ValidatorChange> = Item::new("pending_validator_change");
+
+/// The creator administers the work's metadata and funding pool, separately from the owner
+pub const CREATOR: Item<Addr> = Item::new("creator");
+/// (optional) the royalty on secondary sales marketplaces are asked to pay the creator
+pub const ROYALTY_BPS: Item<u16> = Item::new("royalty_bps");
+/// A creator handover waiting to be accepted
+pub const PENDING_CREATOR: Item<Addr> = Item::new("pending_creator");
+/// Once set, the metadata of the work can never change again
+pub const METADATA_LOCKED: Item<bool> = Item::new("metadata_locked");
+
+/// Native tokens skimmed from staking rewards, held liquid for the creator
+pub const FUNDING_POOL: Item<Uint128> = Item::new("funding_pool");
 
 pub const CURVE_STATE: Item<CurveState> = Item::new("curve_state");
 
+/// Spot price after each change to the curve, keyed by block height
+pub const SPOT_PRICES: Map<U64Key, Decimal> = Map::new("spot_prices");
+
+/// The running sum of spot price times seconds up to a change to the curve,
+/// and the spot price from then on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceObservation {
+    /// in Decimal atomics (10^-18) times seconds
+    pub cumulative: Uint128,
+    pub spot_price: Decimal,
+}
+
+/// A PriceObservation at each change to the curve, keyed by block time in seconds
+pub const PRICE_OBSERVATIONS: Map<U64Key, PriceObservation> = Map::new("price_observations");
+
+/// Trading volume and participation, split by how tokens came and went
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Stats {
+    /// reserve paid in by buys, and released by sells
+    pub bought_reserve: Uint128,
+    pub sold_reserve: Uint128,
+    /// reserve paid in by bonds, and released to unbonders' claims
+    pub bonded: Uint128,
+    pub unbonded: Uint128,
+    /// how many addresses have received tokens from a buy or bond
+    pub buyers: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub bonds: u64,
+    pub unbonds: u64,
+}
+
+pub const STATS: Item<Stats> = Item::new("stats");
+/// Everyone counted in Stats.buyers
+pub const TRADERS: Map<&Addr, Empty> = Map::new("traders");
+
+/// How many snapshots RATE_HISTORY keeps before overwriting the oldest
+pub const RATE_HISTORY_SIZE: u64 = 100;
+/// A ring buffer of exchange rate snapshots, keyed by snapshot number modulo RATE_HISTORY_SIZE
+pub const RATE_HISTORY: Map<U64Key, RateSnapshot> = Map::new("rate_history");
+/// How many snapshots have ever been taken
+pub const RATE_SNAPSHOTS: Item<u64> = Item::new("rate_snapshots");
+
+/// A limit buy, escrowing reserve until the curve is at or below max_spot_price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BuyOrder {
+    pub owner: Addr,
+    pub reserve: Uint128,
+    pub max_spot_price: Decimal,
+}
+
+/// The last order id handed out
+pub const ORDER_COUNT: Item<u64> = Item::new("order_count");
+pub const BUY_ORDERS: Map<U64Key, BuyOrder> = Map::new("buy_orders");
+/// A limit sell, escrowing tokens until the curve is at or above min_spot_price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SellOrder {
+    pub owner: Addr,
+    pub amount: Uint128,
+    pub min_spot_price: Decimal,
+    pub expires: Expiration,
+}
+
+pub const SELL_ORDERS: Map<U64Key, SellOrder> = Map::new("sell_orders");
+/// A recurring buy, escrowing reserve for the deposits still to be made
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DcaPlan {
+    pub owner: Addr,
+    pub amount_per_period: Uint128,
+    pub period: Duration,
+    pub deposits_left: u64,
+    /// when the next buy can be made
+    pub next: Expiration,
+}
+
+/// The last DCA plan id handed out
+pub const DCA_COUNT: Item<u64> = Item::new("dca_count");
+pub const DCA_PLANS: Map<U64Key, DcaPlan> = Map::new("dca_plans");
+/// Reserve a buyer has committed to spend, before revealing the salt behind hash
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BuyCommitment {
+    pub hash: Binary,
+    pub reserve: Uint128,
+    /// the reveal has to come in a later block
+    pub height: u64,
+}
+
+pub const BUY_COMMITMENTS: Map<&Addr, BuyCommitment> = Map::new("buy_commitments");
+/// A descending-price auction of the first tranche, run before the curve opens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Launch {
+    pub tranche: Uint128,
+    pub start_price: Decimal,
+    pub end_price: Decimal,
+    pub duration: Duration,
+    pub start_height: u64,
+    pub start_time: Timestamp,
+    /// reserve bid so far
+    pub committed: Uint128,
+    pub phase: LaunchPhase,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchPhase {
+    /// taking bids, with the curve closed
+    Bidding {},
+    /// the curve is open, and bidders claim their tokens at clearing_price
+    Settled { clearing_price: Decimal },
+}
+
+pub const LAUNCH: Item<Launch> = Item::new("launch");
+/// Reserve each bidder has put into the launch, until they claim their tokens
+pub const LAUNCH_BIDS: Map<&Addr, Uint128> = Map::new("launch_bids");
+/// Reserve held for open buy orders and commitments, which mustn't be staked or paid out
+/// as claims
+pub const ESCROWED_RESERVE: Item<Uint128> = Item::new("escrowed_reserve");
+/// The share of an order a keeper takes for filling it
+pub const KEEPER_FEE: Item<Decimal> = Item::new("keeper_fee");
+
+/// A question the creator put to holders, with the votes so far for each option
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Poll {
+    pub description: String,
+    pub options: Vec<String>,
+    pub tallies: Vec<Uint128>,
+    /// votes are weighted by balances at the start of this block
+    pub start_height: u64,
+    pub end: Expiration,
+    /// the creator escrow milestone this poll can release
+    pub milestone: Option<u32>,
+}
+
+/// How a holder voted, and with what weight
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ballot {
+    pub option: u32,
+    pub weight: Uint128,
+}
+
+/// The last poll id handed out
+pub const POLL_COUNT: Item<u64> = Item::new("poll_count");
+pub const POLLS: Map<U64Key, Poll> = Map::new("polls");
+pub const BALLOTS: Map<(U64Key, &Addr), Ballot> = Map::new("ballots");
+
+/// Reserve denom donated through Donate, kept out of the curve
+pub const RESERVE_DONATIONS: Item<Uint128> = Item::new("reserve_donations");
+/// cw20 tokens donated through Send, by token contract
+pub const CW20_DONATIONS: Map<&Addr, Uint128> = Map::new("cw20_donations");
+/// Other native denoms donated through Donate, kept from Sweep
+pub const NATIVE_DONATIONS: Map<&str, Uint128> = Map::new("native_donations");
+
+/// (optional) the campaign's funding goal
+pub const FUNDING_GOAL: Item<FundingGoal> = Item::new("funding_goal");
+/// (optional) Buy and Bond only work once sale_start has passed and before sale_end does
+pub const SALE_START: Item<Expiration> = Item::new("sale_start");
+pub const SALE_END: Item<Expiration> = Item::new("sale_end");
+
+/// (optional) until this passes, only whitelisted addresses can Buy or Bond
+pub const PRESALE_END: Item<Expiration> = Item::new("presale_end");
+/// reserve each whitelisted address may still spend in the presale
+pub const PRESALE_ALLOWANCES: Map<&Addr, Uint128> = Map::new("presale_allowances");
+
+/// reserve each buyer has paid in and not yet taken out, their cost basis for refunds
+pub const CONTRIBUTIONS: Map<&Addr, Uint128> = Map::new("contributions");
+/// reserve each address has ever spent buying or bonding, which nothing takes back
+pub const LIFETIME_CONTRIBUTIONS: Map<&Addr, Uint128> = Map::new("lifetime_contributions");
+/// the creator's patron tiers, by ascending threshold
+pub const PATRON_TIERS: Item<Vec<PatronTier>> = Item::new("patron_tiers");
+
+/// where large buys get an NFT receipt minted, with the collection validated
+pub const NFT_RECEIPTS: Item<NftReceipts> = Item::new("nft_receipts");
+/// how many receipts have been minted, which numbers the next one
+pub const RECEIPT_COUNT: Item<u64> = Item::new("receipt_count");
+
+/// the share of each buy and sell left in the reserve, raising the backing
+pub const SPREAD: Item<Decimal> = Item::new("spread");
+
+/// the protocol fee, with the collector validated
+pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+/// protocol fees held for the collector until CollectFees
+pub const FEES_ACCRUED: Item<Uint128> = Item::new("fees_accrued");
+/// protocol fees sent to the collector so far
+pub const FEES_COLLECTED: Item<Uint128> = Item::new("fees_collected");
+/// The creator's share of buys, held until milestones release it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorEscrow {
+    pub share: Decimal,
+    pub milestones: Vec<Milestone>,
+    /// which milestones have been reached, by index
+    pub released: Vec<bool>,
+    pub accrued: Uint128,
+    pub paid: Uint128,
+    /// polls already used to approve a milestone
+    pub polls_used: Vec<u64>,
+}
+
+pub const CREATOR_ESCROW: Item<CreatorEscrow> = Item::new("creator_escrow");
+
+/// The creator's pre-minted allocation, held by the contract while it vests
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorVesting {
+    pub amount: Uint128,
+    pub claimed: Uint128,
+    pub schedule: VestingSchedule,
+    pub start_height: u64,
+    pub start_time: Timestamp,
+}
+
+pub const CREATOR_VESTING: Item<CreatorVesting> = Item::new("creator_vesting");
+
+/// The owner's exit tax: what was minted as tokens, and what is paid out of unbonds
+/// under ExitTaxPayout::Native
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct OwnerFees {
+    pub tokens_minted: Uint128,
+    /// waiting on undelegations to mature, one entry per release time
+    pub pending: Vec<Claim>,
+    pub accrued: Uint128,
+    pub withdrawn: Uint128,
+}
+
+pub const OWNER_FEES: Item<OwnerFees> = Item::new("owner_fees");
+
+/// (optional) who the exit tax is split between. Without it, the owner takes it all
+pub const TAX_PAYEES: Item<Vec<Payee>> = Item::new("tax_payees");
+
+/// addresses that pay no exit tax or protocol fees
+pub const FEE_EXEMPT: Map<&Addr, Empty> = Map::new("fee_exempt");
+
+/// addresses the owner has blocked from moving or trading tokens
+pub const FROZEN: Map<&Addr, Empty> = Map::new("frozen");
+
+/// (optional) the cw20 used as the reserve. When set, reserve_denom holds its address
+pub const RESERVE_TOKEN: Item<Addr> = Item::new("reserve_token");
+
+/// (optional) the trace of an ibc/... reserve denom, looked up at instantiate
+pub const RESERVE_TRACE: Item<DenomTrace> = Item::new("reserve_trace");
+
+/// (optional) DEX router used to swap other denoms into the reserve
+pub const SWAP_ROUTER: Item<Addr> = Item::new("swap_router");
+
 pub const CURVE_TYPE: Item<CurveType> = Item::new("curve_type");
 
 pub const TOKEN_INFO_WITH_META: Item<TokenInfoWithMeta> = Item::new("token_info_with_meta");
+
+/// Contracts notified after every buy, sell, bond and unbond
+pub const HOOKS: Hooks = Hooks::new("hooks");
+
+/// Balances and total supply as of each height, kept alongside cw20-base's BALANCES
+/// so the token can be used as voting power
+pub const BALANCE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance_snapshots",
+    "balance_snapshots__checkpoints",
+    "balance_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+pub const SUPPLY_SNAPSHOTS: SnapshotItem<Uint128> = SnapshotItem::new(
+    "supply_snapshots",
+    "supply_snapshots__checkpoints",
+    "supply_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+
+/// While set, the owner has stopped buying, bonding, unbonding and transfers
+pub const PAUSED: Item<bool> = Item::new("paused");
+
+/// One side of the market halted by the owner, short of a full pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Halts {
+    /// no buying or bonding
+    pub buys: bool,
+    /// no selling or unbonding
+    pub sells: bool,
+}
+
+pub const HALTS: Item<Halts> = Item::new("halts");
+
+/// The project is being closed, with settlement open once the stake is back
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WindDown {
+    pub settles_at: Expiration,
+}
+
+pub const WIND_DOWN: Item<WindDown> = Item::new("wind_down");
+
+/// Buy and Bond are turned off in favour of Purchase
+pub const PURCHASE_ONLY: Item<bool> = Item::new("purchase_only");
+
+/// (optional) the wait between buying and selling
+pub const TRADE_COOLDOWN: Item<Duration> = Item::new("trade_cooldown");
+/// when each address that has bought may next sell
+pub const COOLDOWN_ENDS: Map<&Addr, Expiration> = Map::new("cooldown_ends");
+
+/// (optional) caps on unbonding per block and per day
+pub const UNBOND_LIMITS: Item<UnbondLimits> = Item::new("unbond_limits");
+
+/// What has been unbonded in the last block and day that saw an unbond
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct UnbondUsage {
+    pub height: u64,
+    pub in_block: Uint128,
+    /// days since the unix epoch
+    pub day: u64,
+    pub in_day: Uint128,
+}
+
+pub const UNBOND_USAGE: Item<UnbondUsage> = Item::new("unbond_usage");
+
+/// Rewards set aside for holders under RewardStrategy::DistributeToHolders
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HolderRewards {
+    /// rewards distributed per token, ever
+    pub per_token: Decimal,
+    /// distributed and not yet claimed, kept liquid
+    pub unclaimed: Uint128,
+}
+
+pub const HOLDER_REWARDS: Item<HolderRewards> = Item::new("holder_rewards");
+
+/// A holder's share of the distributed rewards, as of the last time their balance changed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct RewardAccount {
+    /// HolderRewards.per_token when this was last settled
+    pub per_token: Decimal,
+    /// earned up to then and not yet claimed
+    pub earned: Uint128,
+}
+
+pub const REWARD_ACCOUNTS: Map<&Addr, RewardAccount> = Map::new("reward_accounts");
@@ -0,0 +1,63 @@
+use cosmwasm_std::{Addr, Deps, Empty, StdResult, Storage, Uint128};
+
+use crate::msg::TradeDirection;
+use crate::query::StatsResponse;
+use crate::state::{STATS, TRADERS};
+
+/// count a trade of reserve in direction. Whoever receives the tokens of their first
+/// buy or bond is counted as a new buyer
+pub fn record_trade(
+    storage: &mut dyn Storage,
+    trader: &Addr,
+    direction: TradeDirection,
+    reserve: Uint128,
+) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    match direction {
+        TradeDirection::Buy => {
+            stats.buys += 1;
+            stats.bought_reserve += reserve;
+        }
+        TradeDirection::Sell => {
+            stats.sells += 1;
+            stats.sold_reserve += reserve;
+        }
+        TradeDirection::Bond => {
+            stats.bonds += 1;
+            stats.bonded += reserve;
+        }
+        TradeDirection::Unbond => {
+            stats.unbonds += 1;
+            stats.unbonded += reserve;
+        }
+    }
+    let buying = matches!(direction, TradeDirection::Buy | TradeDirection::Bond);
+    if buying && !TRADERS.has(storage, trader) {
+        TRADERS.save(storage, trader, &Empty {})?;
+        stats.buyers += 1;
+    }
+    STATS.save(storage, &stats)
+}
+
+/// take back a bond whose delegation failed. The bonder stays counted as a buyer
+pub fn unrecord_bond(storage: &mut dyn Storage, reserve: Uint128) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    stats.bonds = stats.bonds.saturating_sub(1);
+    stats.bonded = stats.bonded.checked_sub(reserve)?;
+    STATS.save(storage, &stats)
+}
+
+pub fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(StatsResponse {
+        bought_reserve: stats.bought_reserve,
+        sold_reserve: stats.sold_reserve,
+        bonded: stats.bonded,
+        unbonded: stats.unbonded,
+        buyers: stats.buyers,
+        buys: stats.buys,
+        sells: stats.sells,
+        bonds: stats.bonds,
+        unbonds: stats.unbonds,
+    })
+}
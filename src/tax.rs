@@ -0,0 +1,191 @@
+use cosmwasm_std::{
+    coin, Addr, BankMsg, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+    Uint128,
+};
+use cw0::{nonpayable, Expiration};
+use cw_controllers::Claim;
+
+use crate::error::ContractError;
+use crate::msg::{CustomMsg, Payee};
+use crate::query::{OwnerFeesResponse, PayeesResponse};
+use crate::staking::held_aside;
+use crate::state::{CURVE_STATE, INVESTMENT, OWNER_FEES, TAX_PAYEES};
+
+/// who the exit tax is split between, the owner alone unless payees are set
+pub fn tax_payees(storage: &dyn Storage) -> StdResult<Vec<Payee>> {
+    match TAX_PAYEES.may_load(storage)? {
+        Some(payees) => Ok(payees),
+        None => Ok(vec![Payee {
+            address: INVESTMENT.load(storage)?.owner.into_string(),
+            weight: 1,
+        }]),
+    }
+}
+
+/// divides amount between the payees by weight. Rounding dust goes to the first payee,
+/// so the parts always add up to amount
+pub fn split_tax(storage: &dyn Storage, amount: Uint128) -> StdResult<Vec<(String, Uint128)>> {
+    let payees = tax_payees(storage)?;
+    let total: u64 = payees.iter().map(|p| p.weight).sum();
+    let mut parts: Vec<_> = payees
+        .into_iter()
+        .map(|p| (p.address, amount.multiply_ratio(p.weight, total)))
+        .collect();
+    let split: Uint128 = parts.iter().map(|(_, part)| *part).sum();
+    parts[0].1 += amount - split;
+    Ok(parts
+        .into_iter()
+        .filter(|(_, part)| !part.is_zero())
+        .collect())
+}
+
+pub fn update_payees<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    payees: Vec<Payee>,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if payees.is_empty() || payees.iter().any(|p| p.weight == 0) {
+        return Err(ContractError::InvalidPayees {});
+    }
+    let mut addresses = vec![];
+    for payee in payees.iter() {
+        let addr = deps.api.addr_validate(&payee.address)?;
+        if addresses.contains(&addr) {
+            return Err(ContractError::InvalidPayees {});
+        }
+        addresses.push(addr);
+    }
+    TAX_PAYEES.save(deps.storage, &payees)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_payees")
+        .add_attribute(
+            "payees",
+            addresses
+                .iter()
+                .map(Addr::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    Ok(res)
+}
+
+/// owes the payees amount of native exit tax once release_at has passed. It is booked
+/// with the claims, so the unbonded reserve coming in is kept for it
+pub fn accrue_owner_fee(
+    storage: &mut dyn Storage,
+    amount: Uint128,
+    release_at: Expiration,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let mut fees = OWNER_FEES.may_load(storage)?.unwrap_or_default();
+    // unbonds batched into the same epoch mature together
+    match fees.pending.last_mut() {
+        Some(last) if last.release_at == release_at => last.amount += amount,
+        _ => fees.pending.push(Claim { amount, release_at }),
+    }
+    fees.accrued += amount;
+    OWNER_FEES.save(storage, &fees)?;
+    CURVE_STATE.update(storage, |mut curve_state| -> StdResult<_> {
+        curve_state.claims += amount;
+        Ok(curve_state)
+    })?;
+    Ok(())
+}
+
+/// counts exit tax minted to the owner as tokens
+pub fn record_owner_tokens(storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+    let mut fees = OWNER_FEES.may_load(storage)?.unwrap_or_default();
+    fees.tokens_minted += amount;
+    OWNER_FEES.save(storage, &fees)
+}
+
+/// native exit tax the owner could withdraw now
+pub fn matured_owner_fees(storage: &dyn Storage, block: &BlockInfo) -> StdResult<Uint128> {
+    Ok(OWNER_FEES
+        .may_load(storage)?
+        .unwrap_or_default()
+        .pending
+        .iter()
+        .filter(|fee| fee.release_at.is_expired(block))
+        .map(|fee| fee.amount)
+        .sum())
+}
+
+/// pays the payees the native exit tax whose undelegations have matured. Owner only
+pub fn withdraw_owner_fees<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut fees = OWNER_FEES.may_load(deps.storage)?.unwrap_or_default();
+    let (matured, pending): (Vec<_>, Vec<_>) = fees
+        .pending
+        .into_iter()
+        .partition(|fee| fee.release_at.is_expired(&env.block));
+    let amount: Uint128 = matured.iter().map(|fee| fee.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    // as with claims, what is held aside for others can't pay it
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &invest.bond_denom)?
+        .amount
+        .saturating_sub(held_aside(deps.storage)?);
+    if balance < amount {
+        return Err(ContractError::BalanceTooSmall {});
+    }
+
+    fees.pending = pending;
+    fees.withdrawn += amount;
+    OWNER_FEES.save(deps.storage, &fees)?;
+    CURVE_STATE.update(deps.storage, |mut curve_state| -> StdResult<_> {
+        curve_state.claims = curve_state.claims.checked_sub(amount)?;
+        Ok(curve_state)
+    })?;
+
+    let msgs = split_tax(deps.storage, amount)?
+        .into_iter()
+        .map(|(to_address, part)| BankMsg::Send {
+            to_address,
+            amount: vec![coin(part.u128(), &invest.bond_denom)],
+        });
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "withdraw_owner_fees")
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn query_owner_fees(deps: Deps, env: Env) -> StdResult<OwnerFeesResponse> {
+    let invest = INVESTMENT.load(deps.storage)?;
+    let fees = OWNER_FEES.may_load(deps.storage)?.unwrap_or_default();
+    let withdrawable = matured_owner_fees(deps.storage, &env.block)?;
+    let denom = &invest.bond_denom;
+    Ok(OwnerFeesResponse {
+        payout: invest.exit_tax_payout,
+        tokens_minted: fees.tokens_minted,
+        native_accrued: coin(fees.accrued.u128(), denom),
+        native_unclaimed: coin((fees.accrued - fees.withdrawn).u128(), denom),
+        native_withdrawable: coin(withdrawable.u128(), denom),
+    })
+}
+
+pub fn query_payees(deps: Deps) -> StdResult<PayeesResponse> {
+    Ok(PayeesResponse {
+        payees: tax_payees(deps.storage)?,
+    })
+}
@@ -0,0 +1,284 @@
+use cosmwasm_std::{
+    coins, to_binary, BankMsg, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
+    Uint128, WasmMsg,
+};
+use cw0::{nonpayable, PaymentError};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::CustomMsg;
+use crate::query::TreasuryResponse;
+use crate::state::{
+    CURVE_STATE, CW20_DONATIONS, INVESTMENT, NATIVE_DONATIONS, RESERVE_DONATIONS, RESERVE_TOKEN,
+};
+use crate::wind_down::assert_not_winding_down;
+
+/// accepts any native coins as a donation. The reserve denom is set aside from the curve,
+/// other denoms are never touched by it anyway
+pub fn donate<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    assert_not_winding_down(deps.storage)?;
+    if info.funds.is_empty() {
+        return Err(ContractError::Payment(PaymentError::NoFunds {}));
+    }
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    for coin in info.funds.iter() {
+        if coin.denom == reserve_denom {
+            RESERVE_DONATIONS.update(deps.storage, |donated| -> StdResult<_> {
+                Ok(donated + coin.amount)
+            })?;
+        } else {
+            NATIVE_DONATIONS.update(deps.storage, &coin.denom, |donated| -> StdResult<_> {
+                Ok(donated.unwrap_or_default() + coin.amount)
+            })?;
+        }
+    }
+
+    let funds: Vec<String> = info.funds.iter().map(|c| c.to_string()).collect();
+    let res = Response::new()
+        .add_attribute("action", "donate")
+        .add_attribute("from", info.sender)
+        .add_attribute("funds", funds.join(","));
+    Ok(res)
+}
+
+/// any cw20 sent to us is kept as a donation, except our own token
+pub fn receive_cw20<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_not_winding_down(deps.storage)?;
+    if info.sender == env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    CW20_DONATIONS.update(deps.storage, &info.sender, |donated| -> StdResult<_> {
+        Ok(donated.unwrap_or_default() + wrapper.amount)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "donate")
+        .add_attribute("from", wrapper.sender)
+        .add_attribute("token", info.sender)
+        .add_attribute("amount", wrapper.amount);
+    Ok(res)
+}
+
+/// sends native donations to the creator. Reserve donations are limited to what was donated
+/// through Donate, other denoms to whatever the contract holds
+pub fn withdraw_native_donation<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    if denom == reserve_denom {
+        let donated = RESERVE_DONATIONS.load(deps.storage)?;
+        let remaining = donated
+            .checked_sub(amount)
+            .map_err(|_| ContractError::DonationTooSmall { available: donated })?;
+        RESERVE_DONATIONS.save(deps.storage, &remaining)?;
+    } else {
+        let available = deps
+            .querier
+            .query_balance(&env.contract.address, &denom)?
+            .amount;
+        if amount > available {
+            return Err(ContractError::DonationTooSmall { available });
+        }
+        let donated = NATIVE_DONATIONS
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+        NATIVE_DONATIONS.save(deps.storage, &denom, &donated.saturating_sub(amount))?;
+    }
+
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.u128(), &denom),
+        })
+        .add_attribute("action", "withdraw_donation")
+        .add_attribute("to", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("denom", denom);
+    Ok(res)
+}
+
+/// sends cw20 donations received through Send to the creator
+pub fn withdraw_cw20_donation<C: CustomMsg>(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: String,
+    amount: Uint128,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+
+    let token = deps.api.addr_validate(&token)?;
+    let donated = CW20_DONATIONS
+        .may_load(deps.storage, &token)?
+        .unwrap_or_default();
+    let remaining = donated
+        .checked_sub(amount)
+        .map_err(|_| ContractError::DonationTooSmall { available: donated })?;
+    if remaining.is_zero() {
+        CW20_DONATIONS.remove(deps.storage, &token);
+    } else {
+        CW20_DONATIONS.save(deps.storage, &token, &remaining)?;
+    }
+
+    let transfer = Cw20ExecuteMsg::Transfer {
+        recipient: info.sender.to_string(),
+        amount,
+    };
+    let res = Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&transfer)?,
+            funds: vec![],
+        })
+        .add_attribute("action", "withdraw_donation")
+        .add_attribute("to", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("token", token);
+    Ok(res)
+}
+
+/// sends the owner the contract's balance of a native denom sent here by mistake.
+/// The reserve and bond denoms back the curve, so they can never be swept, and
+/// donations stay with the creator
+pub fn sweep<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    if denom == reserve_denom || denom == invest.bond_denom {
+        return Err(ContractError::CannotSweep { denom });
+    }
+    let donated = NATIVE_DONATIONS
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount
+        .saturating_sub(donated);
+    if balance.is_zero() {
+        return Err(ContractError::NothingToSweep { denom });
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "sweep")
+        .add_attribute("to", info.sender.as_str())
+        .add_attribute("amount", balance)
+        .add_attribute("denom", denom.as_str())
+        .add_message(BankMsg::Send {
+            to_address: info.sender.into_string(),
+            amount: coins(balance.u128(), denom),
+        });
+    Ok(res)
+}
+
+/// sends amount of a cw20 transferred here directly, rather than sent as a donation, to
+/// recipient. Our own token and a cw20 reserve can't be recovered, and donations stay
+/// with the creator
+pub fn recover_cw20<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    if info.sender != INVESTMENT.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let token = deps.api.addr_validate(&token)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let reserve_token = RESERVE_TOKEN.may_load(deps.storage)?;
+    if token == env.contract.address || Some(&token) == reserve_token.as_ref() {
+        return Err(ContractError::CannotRecover {
+            token: token.into_string(),
+        });
+    }
+
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &token,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    let donated = CW20_DONATIONS
+        .may_load(deps.storage, &token)?
+        .unwrap_or_default();
+    let available = balance.balance.saturating_sub(donated);
+    if amount > available {
+        return Err(ContractError::RecoverTooMuch { available });
+    }
+
+    let transfer = Cw20ExecuteMsg::Transfer {
+        recipient: recipient.to_string(),
+        amount,
+    };
+    let res = Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&transfer)?,
+            funds: vec![],
+        })
+        .add_attribute("action", "recover_cw20")
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("token", token);
+    Ok(res)
+}
+
+pub fn query_treasury(deps: Deps, env: Env) -> StdResult<TreasuryResponse> {
+    let reserve_denom = CURVE_STATE.load(deps.storage)?.reserve_denom;
+    let donated = RESERVE_DONATIONS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let mut native: Vec<Coin> = deps
+        .querier
+        .query_all_balances(&env.contract.address)?
+        .into_iter()
+        .filter(|c| c.denom != reserve_denom)
+        .collect();
+    if !donated.is_zero() {
+        native.push(Coin {
+            denom: reserve_denom,
+            amount: donated,
+        });
+    }
+
+    let cw20 = CW20_DONATIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (token, amount) = item?;
+            Ok(Cw20Coin {
+                address: String::from_utf8(token)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TreasuryResponse { native, cw20 })
+}
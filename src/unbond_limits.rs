@@ -0,0 +1,96 @@
+use cosmwasm_std::{BlockInfo, Deps, Env, StdResult, Storage, Timestamp, Uint128};
+use cw0::Expiration;
+
+use crate::error::ContractError;
+use crate::msg::UnbondLimits;
+use crate::query::UnbondCapacityResponse;
+use crate::state::{UnbondUsage, UNBOND_LIMITS, UNBOND_USAGE};
+
+const DAY_SECONDS: u64 = 86_400;
+
+/// a zero limit would stop unbonding altogether, which is what halting sells is for
+pub fn validate_unbond_limits(limits: &UnbondLimits) -> Result<(), ContractError> {
+    let zero = |limit: Option<Uint128>| matches!(limit, Some(l) if l.is_zero());
+    if zero(limits.per_block) || zero(limits.per_day) {
+        return Err(ContractError::InvalidUnbondLimits {});
+    }
+    Ok(())
+}
+
+/// counts amount against the unbond limits, failing with how much fits and when to
+/// try again if it doesn't
+pub fn use_unbond_capacity(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let limits = match UNBOND_LIMITS.may_load(storage)? {
+        Some(limits) => limits,
+        None => return Ok(()),
+    };
+    let mut usage = current_usage(storage, block)?;
+    let (in_block, in_day) = remaining(&limits, &usage);
+    let over_day = matches!(in_day, Some(left) if amount > left);
+    if over_day || matches!(in_block, Some(left) if amount > left) {
+        let available = in_block.into_iter().chain(in_day).min().unwrap_or_default();
+        // a day's limit only resets with the day, not the next block
+        let retry_at = if over_day {
+            let next_day = (usage.day + 1) * DAY_SECONDS;
+            Expiration::AtTime(Timestamp::from_seconds(next_day))
+        } else {
+            Expiration::AtHeight(block.height + 1)
+        };
+        return Err(ContractError::RateLimited {
+            available,
+            retry_at,
+        });
+    }
+    usage.in_block += amount;
+    usage.in_day += amount;
+    UNBOND_USAGE.save(storage, &usage)?;
+    Ok(())
+}
+
+// the usage so far, started afresh for a new block or day
+fn current_usage(storage: &dyn Storage, block: &BlockInfo) -> StdResult<UnbondUsage> {
+    let mut usage = UNBOND_USAGE.may_load(storage)?.unwrap_or_default();
+    if usage.height != block.height {
+        usage.height = block.height;
+        usage.in_block = Uint128::zero();
+    }
+    let day = block.time.seconds() / DAY_SECONDS;
+    if usage.day != day {
+        usage.day = day;
+        usage.in_day = Uint128::zero();
+    }
+    Ok(usage)
+}
+
+fn remaining(limits: &UnbondLimits, usage: &UnbondUsage) -> (Option<Uint128>, Option<Uint128>) {
+    (
+        limits
+            .per_block
+            .map(|max| max.saturating_sub(usage.in_block)),
+        limits.per_day.map(|max| max.saturating_sub(usage.in_day)),
+    )
+}
+
+pub fn query_unbond_capacity(deps: Deps, env: Env) -> StdResult<UnbondCapacityResponse> {
+    let limits = match UNBOND_LIMITS.may_load(deps.storage)? {
+        Some(limits) => limits,
+        None => {
+            return Ok(UnbondCapacityResponse {
+                remaining_in_block: None,
+                remaining_in_day: None,
+                remaining: None,
+            })
+        }
+    };
+    let usage = current_usage(deps.storage, &env.block)?;
+    let (in_block, in_day) = remaining(&limits, &usage);
+    Ok(UnbondCapacityResponse {
+        remaining_in_block: in_block,
+        remaining_in_day: in_day,
+        remaining: in_block.into_iter().chain(in_day).min(),
+    })
+}
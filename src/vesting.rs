@@ -0,0 +1,126 @@
+use cosmwasm_std::{
+    BlockInfo, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128,
+};
+use cw0::{nonpayable, Duration};
+use cw20_base::contract::execute_transfer;
+
+use crate::bonding::execute_mint;
+use crate::creator::assert_creator;
+use crate::error::ContractError;
+use crate::msg::{CreatorAllocation, CustomMsg, VestingSchedule};
+use crate::query::CreatorVestingResponse;
+use crate::rewards::settle_accounts;
+use crate::snapshot::snapshot_balances;
+use crate::state::{CreatorVesting, CREATOR, CREATOR_VESTING};
+
+/// mints the allocation to the contract itself, where it stays until it vests.
+/// It sits outside the curve, so it isn't backed by any reserve
+pub fn start_creator_vesting(
+    mut deps: DepsMut,
+    env: &Env,
+    allocation: CreatorAllocation,
+) -> Result<(), ContractError> {
+    let duration = match allocation.vesting_schedule {
+        VestingSchedule::Linear { duration } | VestingSchedule::Cliff { duration } => duration,
+    };
+    let no_time = matches!(duration, Duration::Height(0) | Duration::Time(0));
+    if allocation.amount.is_zero() || no_time {
+        return Err(ContractError::InvalidVesting {});
+    }
+
+    let contract = env.contract.address.clone();
+    let sub_info = MessageInfo {
+        sender: contract.clone(),
+        funds: vec![],
+    };
+    execute_mint::<Empty>(
+        deps.branch(),
+        env.clone(),
+        sub_info,
+        contract.into_string(),
+        allocation.amount,
+    )?;
+    let vesting = CreatorVesting {
+        amount: allocation.amount,
+        claimed: Uint128::zero(),
+        schedule: allocation.vesting_schedule,
+        start_height: env.block.height,
+        start_time: env.block.time,
+    };
+    CREATOR_VESTING.save(deps.storage, &vesting)?;
+    Ok(())
+}
+
+// how much of the allocation has vested by this block, claimed or not
+fn vested(vesting: &CreatorVesting, block: &BlockInfo) -> Uint128 {
+    let (linear, duration) = match &vesting.schedule {
+        VestingSchedule::Linear { duration } => (true, duration),
+        VestingSchedule::Cliff { duration } => (false, duration),
+    };
+    let (elapsed, total) = match duration {
+        Duration::Height(blocks) => (block.height.saturating_sub(vesting.start_height), *blocks),
+        Duration::Time(secs) => (
+            block
+                .time
+                .seconds()
+                .saturating_sub(vesting.start_time.seconds()),
+            *secs,
+        ),
+    };
+    if elapsed >= total {
+        vesting.amount
+    } else if linear {
+        vesting.amount.multiply_ratio(elapsed, total)
+    } else {
+        Uint128::zero()
+    }
+}
+
+/// sends the creator whatever has vested and they haven't claimed. Creator only
+pub fn claim_vested<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    assert_creator(deps.as_ref(), &info)?;
+    let mut vesting = CREATOR_VESTING
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoVesting {})?;
+    let amount = vested(&vesting, &env.block) - vesting.claimed;
+    if amount.is_zero() {
+        return Err(ContractError::NothingVested {});
+    }
+    vesting.claimed += amount;
+    CREATOR_VESTING.save(deps.storage, &vesting)?;
+
+    let creator = CREATOR.load(deps.storage)?;
+    let height = env.block.height;
+    let sub_info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let accounts = [sub_info.sender.to_string(), creator.to_string()];
+    settle_accounts(deps.storage, deps.api, &env.contract.address, &accounts)?;
+    execute_transfer(deps.branch(), env, sub_info, creator.to_string(), amount)?;
+    snapshot_balances(deps.storage, deps.api, height, &accounts)?;
+
+    let res = Response::new()
+        .add_attribute("action", "claim_vested")
+        .add_attribute("to", creator)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn query_creator_vesting(deps: Deps, env: Env) -> StdResult<CreatorVestingResponse> {
+    let vesting = CREATOR_VESTING.load(deps.storage)?;
+    let vested = vested(&vesting, &env.block);
+    Ok(CreatorVestingResponse {
+        amount: vesting.amount,
+        vested,
+        claimed: vesting.claimed,
+        claimable: vested - vesting.claimed,
+        locked: vesting.amount - vested,
+        schedule: vesting.schedule,
+    })
+}
@@ -0,0 +1,135 @@
+use cosmwasm_std::{
+    Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw0::nonpayable;
+use cw20_base::state::BALANCES;
+
+use crate::bonding::{execute_burn, send_reserve};
+use crate::error::ContractError;
+use crate::freeze::assert_not_frozen;
+use crate::msg::CustomMsg;
+use crate::query::WindDownResponse;
+use crate::staking::{held_aside, reserve_balance, undelegate_all};
+use crate::state::{WindDown, CURVE_STATE, INVESTMENT, UNBOND_EPOCH, WIND_DOWN};
+
+/// closes the project for good: buying and selling stop, every delegation is undelegated,
+/// and once the unbonding period is over holders Settle for their share of what is left.
+/// Owner only
+pub fn initiate_wind_down<C: CustomMsg>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let invest = INVESTMENT.load(deps.storage)?;
+    if info.sender != invest.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if WIND_DOWN.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::WindingDown {});
+    }
+
+    // whatever an unfinished epoch was waiting on goes out with the rest
+    let msgs = undelegate_all(deps.storage, &invest)?;
+    if let Some(mut epoch) = UNBOND_EPOCH.may_load(deps.storage)? {
+        epoch.pending = Uint128::zero();
+        UNBOND_EPOCH.save(deps.storage, &epoch)?;
+    }
+    let settles_at = invest.unbonding_period.after(&env.block);
+    WIND_DOWN.save(deps.storage, &WindDown { settles_at })?;
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "initiate_wind_down")
+        .add_attribute("settles_at", settles_at.to_string());
+    Ok(res)
+}
+
+pub fn assert_not_winding_down(storage: &dyn Storage) -> Result<(), ContractError> {
+    if WIND_DOWN.may_load(storage)?.is_some() {
+        return Err(ContractError::WindingDown {});
+    }
+    Ok(())
+}
+
+/// burns all the sender's tokens for their share of everything the contract holds that
+/// is owed to no one else. Only once a wind down has got the stake back
+pub fn settle<C: CustomMsg>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<C>, ContractError> {
+    nonpayable(&info)?;
+    let wind_down = WIND_DOWN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotWindingDown {})?;
+    if !wind_down.settles_at.is_expired(&env.block) {
+        return Err(ContractError::SettlementNotOpen {
+            at: wind_down.settles_at,
+        });
+    }
+    assert_not_frozen(deps.storage, deps.api, &[info.sender.to_string()])?;
+    let amount = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToSettle {});
+    }
+
+    let mut curve_state = CURVE_STATE.load(deps.storage)?;
+    let payout = settleable(deps.as_ref(), &env)?.multiply_ratio(amount, curve_state.supply);
+    // don't burn anyone's tokens for nothing
+    if payout.is_zero() {
+        return Err(ContractError::NothingToSettle {});
+    }
+    let burn = execute_burn::<C>(deps.branch(), env, info.clone(), amount)?;
+    let supply = curve_state
+        .supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    curve_state.curve_reserve = curve_state
+        .curve_reserve
+        .multiply_ratio(supply, curve_state.supply);
+    curve_state.supply = supply;
+    CURVE_STATE.save(deps.storage, &curve_state)?;
+
+    let msg = send_reserve(
+        deps.storage,
+        info.sender.to_string(),
+        payout,
+        curve_state.reserve_denom,
+    )?;
+    let res = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "settle")
+        .add_attribute("from", &info.sender)
+        .add_attribute("payout", payout)
+        .add_attributes(burn.attributes);
+    Ok(res)
+}
+
+// the reserve, along with any rewards that came in since, less claims and what
+// is held aside for others
+fn settleable(deps: Deps, env: &Env) -> StdResult<Uint128> {
+    let curve_state = CURVE_STATE.load(deps.storage)?;
+    let balance = reserve_balance(deps, env)?;
+    Ok(balance.saturating_sub(curve_state.claims + held_aside(deps.storage)?))
+}
+
+pub fn query_wind_down(deps: Deps, env: Env) -> StdResult<WindDownResponse> {
+    let res = match WIND_DOWN.may_load(deps.storage)? {
+        Some(wind_down) => WindDownResponse {
+            winding_down: true,
+            settles_at: Some(wind_down.settles_at),
+            settlement_open: wind_down.settles_at.is_expired(&env.block),
+            settleable: settleable(deps, &env)?,
+        },
+        None => WindDownResponse {
+            winding_down: false,
+            settles_at: None,
+            settlement_open: false,
+            settleable: Uint128::zero(),
+        },
+    };
+    Ok(res)
+}